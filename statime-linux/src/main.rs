@@ -1,12 +1,18 @@
+use std::{net::IpAddr, path::PathBuf};
+
 use clap::Parser;
 use fern::colors::Color;
 use statime::{
-    BasicFilter, ClockIdentity, DefaultDS, DelayMechanism, Port, PortDS, PortIdentity, PtpInstance,
-    SdoId, TimePropertiesDS, TimeSource,
+    BasicFilter, ClockIdentity, DefaultDS, DelayMechanism, DesiredState, Duration, NetworkPort,
+    NetworkProtocol, Port, PortAddress, PortDS, PortIdentity, PtpInstance, SdoId,
+    SecurityAssociation, TimePropertiesDS, TimeSource,
 };
 use statime_linux::{
     clock::{LinuxClock, LinuxTimer, RawLinuxClock},
-    network::linux::{get_clock_id, InterfaceDescriptor, LinuxRuntime, TimestampingMode},
+    network::{
+        ethernet::{EthernetDestination, EthernetRuntime},
+        linux::{get_clock_id, InterfaceDescriptor, LinuxRuntime, TimestampingMode},
+    },
 };
 
 #[derive(Clone, Copy)]
@@ -46,6 +52,68 @@ impl clap::builder::TypedValueParser for SdoIdParser {
     }
 }
 
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+enum DelayType {
+    /// End to end delay mechanism
+    E2E,
+    /// Peer to peer delay mechanism
+    P2P,
+}
+
+impl From<DelayType> for DelayMechanism {
+    fn from(value: DelayType) -> Self {
+        match value {
+            DelayType::E2E => DelayMechanism::E2E,
+            DelayType::P2P => DelayMechanism::P2P,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+enum TransportType {
+    /// UDP over IPv4 or IPv6
+    Udp,
+    /// IEEE 802.3 ethernet frames
+    Ethernet,
+}
+
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+enum EthernetDestinationType {
+    /// 01-1B-19-00-00-00, forwarded by bridges that are not PTP aware
+    Forwardable,
+    /// 01-80-C2-00-00-0E, not forwarded beyond the link
+    NonForwardable,
+}
+
+impl From<EthernetDestinationType> for EthernetDestination {
+    fn from(value: EthernetDestinationType) -> Self {
+        match value {
+            EthernetDestinationType::Forwardable => EthernetDestination::Forwardable,
+            EthernetDestinationType::NonForwardable => EthernetDestination::NonForwardable,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+enum PortStateType {
+    /// Act as master on the link
+    Master,
+    /// Synchronize to the master on the link
+    Slave,
+    /// Neither act as master nor synchronize
+    Passive,
+}
+
+impl From<PortStateType> for DesiredState {
+    fn from(value: PortStateType) -> Self {
+        match value {
+            PortStateType::Master => DesiredState::Master,
+            PortStateType::Slave => DesiredState::Slave,
+            PortStateType::Passive => DesiredState::Passive,
+        }
+    }
+}
+
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
 struct Args {
@@ -57,6 +125,16 @@ struct Args {
     #[clap(short, long)]
     interface: InterfaceDescriptor,
 
+    /// Network transport of the PTP messages, ethernet requires an interface
+    /// name, see: annex C, D and F
+    #[clap(long, value_enum, default_value_t = TransportType::Udp)]
+    transport: TransportType,
+
+    /// Multicast address to send messages to with the ethernet transport, peer
+    /// delay messages always use the non-forwardable address, see: F.3
+    #[clap(long, value_enum, default_value_t = EthernetDestinationType::Forwardable)]
+    ethernet_destination: EthernetDestinationType,
+
     /// The SDO id of the desired ptp domain
     #[clap(long, default_value_t = SdoId::default(), value_parser = SdoIdParser)]
     sdo: SdoId,
@@ -89,6 +167,89 @@ struct Args {
     #[clap(long, default_value_t = 3)]
     announce_receipt_timeout: u8,
 
+    /// The delay mechanism used to measure the delay to the master
+    #[clap(long, value_enum, default_value_t = DelayType::E2E)]
+    delay_mechanism: DelayType,
+
+    /// Log value of the minimum interval between Pdelay_Req messages, see:
+    /// 7.7.2.5. Only used with the peer to peer delay mechanism
+    /// Default init value is 0, see: A.9.4.2
+    #[clap(long, default_value_t = 0)]
+    log_min_pdelay_req_interval: i8,
+
+    /// Delay asymmetry of the link in nanoseconds, positive when the path from
+    /// the master is longer than the path to it, see: 16.8
+    #[clap(long, default_value_t = 0, allow_negative_numbers = true)]
+    delay_asymmetry: i64,
+
+    /// Latency in nanoseconds between the network connector and the point
+    /// where receive timestamps are taken, see: 8.2.16
+    #[clap(long, default_value_t = 0, allow_negative_numbers = true)]
+    ingress_latency: i64,
+
+    /// Latency in nanoseconds between the point where transmit timestamps are
+    /// taken and the network connector, see: 8.2.16
+    #[clap(long, default_value_t = 0, allow_negative_numbers = true)]
+    egress_latency: i64,
+
+    /// Send one-step Sync messages instead of Sync plus Follow_Up
+    #[clap(long)]
+    one_step: bool,
+
+    /// Negotiate unicast transmission with the other PTP nodes, see: 16.1
+    #[clap(long)]
+    unicast_negotiation: bool,
+
+    /// Duration in seconds of the unicast transmission grants to request
+    #[clap(long, default_value_t = 300)]
+    unicast_grant_duration: u32,
+
+    /// Maximum number of unicast transmission grants to give to slaves
+    #[clap(long, default_value_t = 32)]
+    max_unicast_grants: usize,
+
+    /// Address of a master to synchronize to over unicast, can be given
+    /// multiple times. Without --unicast-negotiation, messages are exchanged
+    /// with these masters using static unicast, see: 17.5
+    #[clap(long)]
+    unicast_master: Vec<IpAddr>,
+
+    /// Never become master, only synchronize to other clocks, see: 9.2.2.1
+    #[clap(long, conflicts_with = "master_only")]
+    slave_only: bool,
+
+    /// Never synchronize to other clocks, only act as master, see: 9.2.2.2
+    #[clap(long)]
+    master_only: bool,
+
+    /// Put the port in this state instead of selecting a master with the
+    /// best master clock algorithm, see: 17.6
+    #[clap(long, value_enum, conflicts_with_all = ["slave_only", "master_only"])]
+    port_state: Option<PortStateType>,
+
+    /// Also follow the master of this domain number, without steering the
+    /// clock by it, to monitor the offset between the domains
+    #[clap(long)]
+    monitor_domain: Option<u8>,
+
+    /// Address of a monitoring node to send the timing of the messages from
+    /// the master to while slave, see: 16.11
+    #[clap(long)]
+    slave_event_monitoring: Option<IpAddr>,
+
+    /// File with the pre-shared key to authenticate all messages with, see:
+    /// 16.14
+    #[clap(long)]
+    authentication_key_file: Option<PathBuf>,
+
+    /// Key id of the authentication key
+    #[clap(long, default_value_t = 1)]
+    authentication_key_id: u32,
+
+    /// Security parameter pointer of the authentication security association
+    #[clap(long, default_value_t = 0)]
+    authentication_spp: u8,
+
     /// Use hardware clock
     #[clap(long, short = 'c')]
     hardware_clock: Option<String>,
@@ -118,6 +279,19 @@ fn setup_logger(level: log::LevelFilter) -> Result<(), fern::InitError> {
     Ok(())
 }
 
+fn port_address(address: &IpAddr) -> PortAddress {
+    match address {
+        IpAddr::V4(ip) => PortAddress {
+            network_protocol: NetworkProtocol::UdpIPv4,
+            address: ip.octets().into_iter().collect(),
+        },
+        IpAddr::V6(ip) => PortAddress {
+            network_protocol: NetworkProtocol::UdpIPv6,
+            address: ip.octets().into_iter().collect(),
+        },
+    }
+}
+
 #[tokio::main]
 async fn main() {
     let args = Args::parse();
@@ -143,7 +317,6 @@ async fn main() {
         TimestampingMode::Software
     };
 
-    let mut network_runtime = LinuxRuntime::new(timestamping_mode, local_clock.clone());
     let clock_identity = ClockIdentity(get_clock_id().expect("Could not get clock identity"));
 
     let default_ds = DefaultDS::new_ordinary_clock(
@@ -151,31 +324,131 @@ async fn main() {
         args.priority_1,
         args.priority_2,
         args.domain,
-        false,
+        args.slave_only,
         args.sdo,
-    );
+    )
+    .with_external_port_configuration(args.port_state.is_some());
     let time_properties_ds =
         TimePropertiesDS::new_arbitrary_time(false, false, TimeSource::InternalOscillator);
-    let port_ds = PortDS::new(
-        PortIdentity {
-            clock_identity,
-            port_number: 1,
-        },
-        1,
-        args.log_announce_interval,
-        args.announce_receipt_timeout,
-        args.log_sync_interval,
-        DelayMechanism::E2E,
-        1,
-    );
-    let port = Port::new(port_ds, &mut network_runtime, args.interface).await;
+    let unicast_master_table: Vec<_> = args.unicast_master.iter().map(port_address).collect();
+    let new_port_ds = || {
+        let port_ds = PortDS::new(
+            PortIdentity {
+                clock_identity,
+                port_number: 1,
+            },
+            1,
+            args.log_announce_interval,
+            args.announce_receipt_timeout,
+            args.log_sync_interval,
+            args.delay_mechanism.into(),
+            args.log_min_pdelay_req_interval,
+        )
+        .with_one_step_sync(args.one_step)
+        .with_delay_asymmetry(Duration::from_nanos(args.delay_asymmetry))
+        .with_ingress_latency(Duration::from_nanos(args.ingress_latency))
+        .with_egress_latency(Duration::from_nanos(args.egress_latency))
+        .with_unicast_master_table(&unicast_master_table);
+        let port_ds = match &args.slave_event_monitoring {
+            Some(address) => port_ds.with_slave_event_monitoring(port_address(address)),
+            None => port_ds,
+        };
+        if args.unicast_negotiation {
+            port_ds.with_unicast_negotiation(args.unicast_grant_duration, args.max_unicast_grants)
+        } else {
+            port_ds
+        }
+    };
+    let mut port_ds = new_port_ds().with_master_only(args.master_only);
+    if let Some(port_state) = args.port_state {
+        port_ds = port_ds.with_desired_state(port_state.into());
+    }
+    // The monitored domain never gets this clock as master
+    let monitor = args.monitor_domain.map(|domain| {
+        let monitor_default_ds =
+            DefaultDS::new_ordinary_clock(clock_identity, 255, 255, domain, true, args.sdo);
+        (monitor_default_ds, new_port_ds())
+    });
+
+    match args.transport {
+        TransportType::Udp => {
+            // Unicast ports only talk to the nodes they know of or negotiated with
+            let multicast = args.unicast_master.is_empty() && !args.unicast_negotiation;
+            let mut network_runtime =
+                LinuxRuntime::new(timestamping_mode, local_clock.clone()).with_multicast(multicast);
+            let port = Port::new(port_ds, &mut network_runtime, args.interface.clone()).await;
+            run(
+                &args,
+                port,
+                default_ds,
+                time_properties_ds,
+                monitor,
+                local_clock,
+            )
+            .await
+        }
+        TransportType::Ethernet => {
+            let mut network_runtime = EthernetRuntime::new(timestamping_mode, local_clock.clone())
+                .with_destination(args.ethernet_destination.into());
+            let port = Port::new(port_ds, &mut network_runtime, args.interface.clone()).await;
+            run(
+                &args,
+                port,
+                default_ds,
+                time_properties_ds,
+                monitor,
+                local_clock,
+            )
+            .await
+        }
+    }
+}
+
+/// Run an ordinary clock on the port, whatever its network transport
+async fn run<P: NetworkPort>(
+    args: &Args,
+    port: Port<P>,
+    default_ds: DefaultDS,
+    time_properties_ds: TimePropertiesDS,
+    monitor: Option<(DefaultDS, PortDS)>,
+    local_clock: LinuxClock,
+) -> ! {
+    let mut port = port.with_max_domains::<2>();
+    if let Some(path) = &args.authentication_key_file {
+        let key = std::fs::read(path).expect("Could not read authentication key");
+        let key = key.strip_suffix(b"\n").unwrap_or(&key);
+        port = port.with_security_association(SecurityAssociation::new(
+            args.authentication_spp,
+            args.authentication_key_id,
+            key,
+        ));
+    }
     let mut instance = PtpInstance::new_ordinary_clock(
         default_ds,
-        time_properties_ds,
+        time_properties_ds.clone(),
         port,
         local_clock,
         BasicFilter::new(0.25),
     );
+    let Some((monitor_default_ds, monitor_port_ds)) = monitor else {
+        instance.run(&LinuxTimer).await
+    };
+    instance = instance.with_domain(
+        monitor_default_ds,
+        time_properties_ds,
+        [monitor_port_ds],
+        BasicFilter::new(0.25),
+    );
 
-    instance.run(&LinuxTimer).await;
+    // The monitored domain doesn't steer the clock, report how far off it is
+    instance
+        .run_observed(&LinuxTimer, |instance| {
+            let current_ds = instance.current_ds(1);
+            log::info!(
+                "Monitored domain offset from master: {}, mean delay: {}",
+                current_ds.offset_from_master(),
+                current_ds.mean_delay()
+            );
+        })
+        .await
 }
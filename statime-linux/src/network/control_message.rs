@@ -1,5 +1,9 @@
 use std::marker::PhantomData;
 
+/// The type of the control messages with the send timestamps of packet sockets
+/// (linux/if_packet.h)
+const PACKET_TX_TIMESTAMP: libc::c_int = 16;
+
 pub const fn control_message_space<T>() -> usize {
     // Safety: CMSG_SPACE is safe to call
     (unsafe { libc::CMSG_SPACE((std::mem::size_of::<T>()) as _) }) as usize
@@ -91,12 +95,15 @@ impl<'a> Iterator for ControlMessageIterator<'a> {
                 ControlMessage::Timestamping(timespec)
             }
 
-            (libc::SOL_IP, libc::IP_RECVERR) | (libc::SOL_IPV6, libc::IPV6_RECVERR) => {
+            (libc::SOL_IP, libc::IP_RECVERR)
+            | (libc::SOL_IPV6, libc::IPV6_RECVERR)
+            | (libc::SOL_PACKET, PACKET_TX_TIMESTAMP) => {
                 // this is part of how timestamps are reported.
                 // Safety:
                 // current_msg was constructed from a pointer that pointed to a valid
                 // control message.
-                // IP*_RECVERR always has a sock_extended_err in the data
+                // IP*_RECVERR and PACKET_TX_TIMESTAMP always have a sock_extended_err in
+                // the data
                 let error = unsafe {
                     let ptr = libc::CMSG_DATA(current_msg) as *const libc::sock_extended_err;
                     std::ptr::read_unaligned(ptr)
@@ -0,0 +1,311 @@
+#![forbid(unsafe_code)]
+
+//! Implementation of the abstract network types for PTP directly over
+//! ethernet (IEEE1588-2019 annex F) on the linux platform
+
+use std::{io, io::ErrorKind, os::unix::prelude::RawFd};
+
+use statime::{Instant, NetworkPacket, NetworkPort, NetworkProtocol, NetworkRuntime, PortAddress};
+use tokio::io::{unix::AsyncFd, Interest};
+
+use crate::{
+    clock::LinuxClock,
+    network::{
+        control_message::{control_message_space, ControlMessage},
+        interface::InterfaceDescriptor,
+        linux::{is_pdelay_message, libc_timestamp_to_instant, NetworkError, TimestampingMode},
+        raw_ethernet_socket::{receive_packet, MacAddress, RawEthernetSocket},
+        raw_udp_socket::{exceptional_condition_fd, set_timestamping_options},
+        timestamped_udp_socket::{fetch_send_timestamp, LibcTimestamp},
+    },
+};
+
+/// The multicast address for all PTP messages except peer delay messages,
+/// forwarded by bridges that are not PTP aware
+const FORWARDABLE_ADDRESS: MacAddress = [0x01, 0x1b, 0x19, 0x00, 0x00, 0x00];
+/// The multicast address for peer delay messages, never forwarded by bridges
+const NON_FORWARDABLE_ADDRESS: MacAddress = [0x01, 0x80, 0xc2, 0x00, 0x00, 0x0e];
+
+/// The multicast address PTP messages other than peer delay messages are sent
+/// to (IEEE1588-2019 annex F.3)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EthernetDestination {
+    /// 01-1B-19-00-00-00, forwarded by bridges that are not PTP aware
+    #[default]
+    Forwardable,
+    /// 01-80-C2-00-00-0E, never forwarded beyond the link, as used by
+    /// gPTP
+    NonForwardable,
+}
+
+impl EthernetDestination {
+    /// The multicast address to send a message to, peer delay messages are
+    /// not forwarded beyond the link (IEEE1588-2019 annex F.3)
+    fn address_for(self, data: &[u8]) -> MacAddress {
+        match self {
+            _ if is_pdelay_message(data) => NON_FORWARDABLE_ADDRESS,
+            EthernetDestination::Forwardable => FORWARDABLE_ADDRESS,
+            EthernetDestination::NonForwardable => NON_FORWARDABLE_ADDRESS,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct EthernetRuntime {
+    timestamping_mode: TimestampingMode,
+    clock: LinuxClock,
+    destination: EthernetDestination,
+}
+
+impl EthernetRuntime {
+    pub fn new(timestamping_mode: TimestampingMode, clock: LinuxClock) -> Self {
+        EthernetRuntime {
+            timestamping_mode,
+            clock,
+            destination: EthernetDestination::default(),
+        }
+    }
+
+    /// The multicast address to send PTP messages to, peer delay messages
+    /// always go to the non-forwardable address
+    pub fn with_destination(mut self, destination: EthernetDestination) -> Self {
+        self.destination = destination;
+        self
+    }
+}
+
+impl NetworkRuntime for EthernetRuntime {
+    type InterfaceDescriptor = InterfaceDescriptor;
+    type NetworkPort = EthernetNetworkPort;
+    type Error = NetworkError;
+
+    async fn open(
+        &mut self,
+        interface: Self::InterfaceDescriptor,
+    ) -> Result<<EthernetRuntime as NetworkRuntime>::NetworkPort, NetworkError> {
+        let Some(interface_name) = interface.interface_name else {
+            log::error!("an interface name is required for the ethernet transport");
+            return Err(NetworkError::InterfaceDoesNotExist);
+        };
+
+        log::info!("Opening ethernet network port on '{}'", interface_name);
+
+        // Every message arrives on the time critical socket, the non time critical
+        // socket is only used to send messages without fetching their timestamp
+        let tc_socket = RawEthernetSocket::new(interface_name, true)?;
+        tc_socket.join_multicast(FORWARDABLE_ADDRESS)?;
+        tc_socket.join_multicast(NON_FORWARDABLE_ADDRESS)?;
+        set_timestamping_options(&tc_socket, self.timestamping_mode)?;
+
+        let ntc_socket = RawEthernetSocket::new(interface_name, false)?;
+
+        Ok(EthernetNetworkPort {
+            exceptional_condition: exceptional_condition_fd(&tc_socket)?,
+            tc_socket: AsyncFd::new(tc_socket)?,
+            ntc_socket: AsyncFd::new(ntc_socket)?,
+            send_counter: 0,
+            destination: self.destination,
+            clock: self.clock.clone(),
+        })
+    }
+}
+
+pub struct EthernetNetworkPort {
+    tc_socket: AsyncFd<RawEthernetSocket>,
+    exceptional_condition: AsyncFd<RawFd>,
+    ntc_socket: AsyncFd<RawEthernetSocket>,
+    send_counter: u32,
+    destination: EthernetDestination,
+    clock: LinuxClock,
+}
+
+impl EthernetNetworkPort {
+    async fn send_ntc(&mut self, data: &[u8], destination: MacAddress) -> io::Result<()> {
+        let sender = |inner: &RawEthernetSocket| inner.send_to(data, destination);
+        self.ntc_socket.async_io(Interest::WRITABLE, sender).await?;
+
+        Ok(())
+    }
+
+    async fn send_tc(
+        &mut self,
+        data: &[u8],
+        destination: MacAddress,
+    ) -> io::Result<Option<Instant>> {
+        let sender = |inner: &RawEthernetSocket| inner.send_to(data, destination);
+        self.tc_socket.async_io(Interest::WRITABLE, sender).await?;
+
+        let expected_counter = self.send_counter;
+        self.send_counter = self.send_counter.wrapping_add(1);
+
+        let opt_libc_ts = fetch_send_timestamp(
+            self.tc_socket.get_ref(),
+            &self.exceptional_condition,
+            expected_counter,
+        )
+        .await?;
+
+        Ok(opt_libc_ts.map(libc_timestamp_to_instant))
+    }
+}
+
+/// The PTP port address of a MAC address
+fn port_address(address: MacAddress) -> PortAddress {
+    PortAddress {
+        network_protocol: NetworkProtocol::IEEE802_3,
+        address: address.into_iter().collect(),
+    }
+}
+
+/// The MAC address of a PTP port address
+fn mac_address(address: &PortAddress) -> io::Result<MacAddress> {
+    match address.network_protocol {
+        NetworkProtocol::IEEE802_3 => address.address.as_slice().try_into().ok(),
+        _ => None,
+    }
+    .ok_or_else(|| io::Error::new(ErrorKind::InvalidInput, "invalid port address"))
+}
+
+fn recv_with_timestamp(
+    socket: &RawEthernetSocket,
+    clock: &LinuxClock,
+    read_buf: &mut [u8],
+) -> io::Result<(usize, MacAddress, LibcTimestamp)> {
+    let mut control_buf = [0; control_message_space::<[libc::timespec; 3]>()];
+
+    let (bytes_read, control_messages, source_address) =
+        receive_packet(socket, read_buf, &mut control_buf)?;
+
+    // fallback receive timestamp, for when the kernel gives none
+    let mut timestamp = LibcTimestamp::from_timespec(clock.timespec()?);
+
+    for msg in control_messages {
+        match msg {
+            ControlMessage::Timestamping(timespec) => {
+                timestamp = LibcTimestamp::from_timespec(timespec);
+            }
+
+            ControlMessage::ReceiveError(_error) => {
+                log::warn!("unexpected error message on the MSG_ERRQUEUE");
+            }
+
+            ControlMessage::Other(msg) => {
+                log::warn!(
+                    "unexpected message on the MSG_ERRQUEUE (level = {}, type = {})",
+                    msg.cmsg_level,
+                    msg.cmsg_type,
+                );
+            }
+        }
+    }
+
+    Ok((bytes_read, source_address, timestamp))
+}
+
+impl NetworkPort for EthernetNetworkPort {
+    type Error = std::io::Error;
+
+    async fn send(&mut self, data: &[u8]) -> Result<(), std::io::Error> {
+        log::trace!("Send NTC");
+
+        self.send_ntc(data, self.destination.address_for(data))
+            .await
+    }
+
+    async fn send_time_critical(
+        &mut self,
+        data: &[u8],
+    ) -> Result<Option<statime::Instant>, std::io::Error> {
+        log::trace!("Send TC");
+
+        self.send_tc(data, self.destination.address_for(data)).await
+    }
+
+    async fn send_to(&mut self, data: &[u8], address: &PortAddress) -> Result<(), std::io::Error> {
+        log::trace!("Send NTC to {:?}", address);
+
+        self.send_ntc(data, mac_address(address)?).await
+    }
+
+    async fn send_time_critical_to(
+        &mut self,
+        data: &[u8],
+        address: &PortAddress,
+    ) -> Result<Option<statime::Instant>, std::io::Error> {
+        log::trace!("Send TC to {:?}", address);
+
+        self.send_tc(data, mac_address(address)?).await
+    }
+
+    async fn recv(&mut self) -> Result<NetworkPacket, std::io::Error> {
+        loop {
+            // Frames can carry more than a PTP message can be long
+            let mut buffer = [0; 1500];
+            let (received_len, source_address, timestamp) = self
+                .tc_socket
+                .async_io(Interest::READABLE, |inner| {
+                    recv_with_timestamp(inner, &self.clock, &mut buffer)
+                })
+                .await?;
+            log::trace!("Recv");
+
+            // Short messages are padded to the minimum frame size, which the
+            // message length in the header accounts for
+            let Ok(data) = buffer[..received_len].try_into() else {
+                log::debug!("Ignoring frame of {} bytes, too long", received_len);
+                continue;
+            };
+
+            return Ok(NetworkPacket {
+                data,
+                timestamp: libc_timestamp_to_instant(timestamp),
+                source_address: Some(port_address(source_address)),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pdelay_messages_use_non_forwardable_address() {
+        // Sync and Pdelay_Req
+        let forwardable = EthernetDestination::Forwardable;
+        assert_eq!(forwardable.address_for(&[0x0, 0x02]), FORWARDABLE_ADDRESS);
+        assert_eq!(
+            forwardable.address_for(&[0x2, 0x02]),
+            NON_FORWARDABLE_ADDRESS
+        );
+
+        // Announce and Pdelay_Resp
+        let non_forwardable = EthernetDestination::NonForwardable;
+        assert_eq!(
+            non_forwardable.address_for(&[0xb, 0x02]),
+            NON_FORWARDABLE_ADDRESS
+        );
+        assert_eq!(
+            non_forwardable.address_for(&[0x3, 0x02]),
+            NON_FORWARDABLE_ADDRESS
+        );
+    }
+
+    #[test]
+    fn port_address_conversion() {
+        let address = [0x00, 0x1b, 0x21, 0x12, 0x34, 0x56];
+        assert_eq!(mac_address(&port_address(address)).unwrap(), address);
+
+        let ipv4 = PortAddress {
+            network_protocol: NetworkProtocol::UdpIPv4,
+            address: [192, 168, 0, 25].into_iter().collect(),
+        };
+        assert!(mac_address(&ipv4).is_err());
+
+        let truncated = PortAddress {
+            network_protocol: NetworkProtocol::IEEE802_3,
+            address: [0x00, 0x1b, 0x21].into_iter().collect(),
+        };
+        assert!(mac_address(&truncated).is_err());
+    }
+}
@@ -8,7 +8,10 @@ use std::{
     net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
 };
 
-use statime::{Clock, Instant, NetworkPacket, NetworkPort, NetworkRuntime, MAX_DATA_LEN};
+use statime::{
+    Clock, Instant, NetworkPacket, NetworkPort, NetworkProtocol, NetworkRuntime, PortAddress,
+    MAX_DATA_LEN,
+};
 use tokio::io::{unix::AsyncFd, Interest};
 
 pub use super::interface::InterfaceDescriptor;
@@ -36,6 +39,7 @@ pub enum TimestampingMode {
 pub struct LinuxRuntime {
     timestamping_mode: TimestampingMode,
     clock: LinuxClock,
+    multicast: bool,
 }
 
 impl LinuxRuntime {
@@ -43,15 +47,38 @@ impl LinuxRuntime {
         LinuxRuntime {
             timestamping_mode,
             clock,
+            multicast: true,
         }
     }
 
+    /// Whether opened ports join the PTP multicast groups
+    ///
+    /// Ports that only talk to a unicast master table, or that negotiate
+    /// unicast transmission, have no use for multicast messages.
+    pub fn with_multicast(mut self, multicast: bool) -> Self {
+        self.multicast = multicast;
+        self
+    }
+
     const IPV6_PRIMARY_MULTICAST: Ipv6Addr = Ipv6Addr::new(0xff, 0x0e, 0, 0, 0, 0, 0x01, 0x81);
     const IPV6_PDELAY_MULTICAST: Ipv6Addr = Ipv6Addr::new(0xff, 0x02, 0, 0, 0, 0, 0, 0x6b);
 
     const IPV4_PRIMARY_MULTICAST: Ipv4Addr = Ipv4Addr::new(224, 0, 1, 129);
     const IPV4_PDELAY_MULTICAST: Ipv4Addr = Ipv4Addr::new(224, 0, 0, 107);
 
+    /// The primary multicast address for the socket, without joining it
+    fn multicast_address(
+        interface: &InterfaceDescriptor,
+        socket: &std::net::UdpSocket,
+    ) -> Result<SocketAddr, NetworkError> {
+        let port = socket.local_addr()?.port();
+
+        match interface.get_address()? {
+            IpAddr::V4(_) => Ok((Self::IPV4_PRIMARY_MULTICAST, port).into()),
+            IpAddr::V6(_) => Ok((Self::IPV6_PRIMARY_MULTICAST, port).into()),
+        }
+    }
+
     fn join_multicast(
         interface: &InterfaceDescriptor,
         socket: &std::net::UdpSocket,
@@ -81,6 +108,15 @@ impl LinuxRuntime {
             }
         }
     }
+
+    /// The peer delay multicast address belonging to a primary multicast
+    /// address
+    fn pdelay_address(primary_address: SocketAddr) -> SocketAddr {
+        match primary_address.ip() {
+            IpAddr::V4(_) => (Self::IPV4_PDELAY_MULTICAST, primary_address.port()).into(),
+            IpAddr::V6(_) => (Self::IPV6_PDELAY_MULTICAST, primary_address.port()).into(),
+        }
+    }
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -131,8 +167,18 @@ impl NetworkRuntime for LinuxRuntime {
         let tc_socket = RawUdpSocket::new_into_std(tc_addr, interface.interface_name)?;
         let ntc_socket = RawUdpSocket::new_into_std(ntc_addr, interface.interface_name)?;
 
-        let tc_address = Self::join_multicast(&interface, &tc_socket)?;
-        let ntc_address = Self::join_multicast(&interface, &ntc_socket)?;
+        let (tc_address, ntc_address) = if self.multicast {
+            (
+                Self::join_multicast(&interface, &tc_socket)?,
+                Self::join_multicast(&interface, &ntc_socket)?,
+            )
+        } else {
+            log::info!("Not joining the PTP multicast groups");
+            (
+                Self::multicast_address(&interface, &tc_socket)?,
+                Self::multicast_address(&interface, &ntc_socket)?,
+            )
+        };
 
         let tc_socket = TimestampedUdpSocket::from_udp_socket(tc_socket, self.timestamping_mode)?;
         let ntc_socket = AsyncFd::new(ntc_socket)?;
@@ -142,6 +188,8 @@ impl NetworkRuntime for LinuxRuntime {
             ntc_socket,
             tc_address,
             ntc_address,
+            tc_pdelay_address: Self::pdelay_address(tc_address),
+            ntc_pdelay_address: Self::pdelay_address(ntc_address),
             clock: self.clock.clone(),
         })
     }
@@ -152,10 +200,50 @@ pub struct LinuxNetworkPort {
     ntc_socket: AsyncFd<std::net::UdpSocket>,
     tc_address: SocketAddr,
     ntc_address: SocketAddr,
+    tc_pdelay_address: SocketAddr,
+    ntc_pdelay_address: SocketAddr,
     clock: LinuxClock,
 }
 
-fn libc_timestamp_to_instant(ts: LibcTimestamp) -> Instant {
+/// Peer delay messages are sent to a separate multicast address, so they are
+/// not forwarded beyond the link (IEEE1588-2019 annex C and D)
+pub(crate) fn is_pdelay_message(data: &[u8]) -> bool {
+    // Pdelay_Req, Pdelay_Resp and Pdelay_Resp_Follow_Up
+    matches!(data.first().map(|b| b & 0x0f), Some(0x2 | 0x3 | 0xa))
+}
+
+/// The PTP port address of an IP socket address
+fn port_address(address: SocketAddr) -> PortAddress {
+    match address.ip() {
+        IpAddr::V4(ip) => PortAddress {
+            network_protocol: NetworkProtocol::UdpIPv4,
+            address: ip.octets().into_iter().collect(),
+        },
+        IpAddr::V6(ip) => PortAddress {
+            network_protocol: NetworkProtocol::UdpIPv6,
+            address: ip.octets().into_iter().collect(),
+        },
+    }
+}
+
+/// The socket address of a PTP port address on the given UDP port
+fn socket_address(address: &PortAddress, port: u16) -> io::Result<SocketAddr> {
+    let invalid_address = || io::Error::new(ErrorKind::InvalidInput, "invalid port address");
+
+    let ip: IpAddr = match address.network_protocol {
+        NetworkProtocol::UdpIPv4 => <[u8; 4]>::try_from(address.address.as_slice())
+            .map_err(|_| invalid_address())?
+            .into(),
+        NetworkProtocol::UdpIPv6 => <[u8; 16]>::try_from(address.address.as_slice())
+            .map_err(|_| invalid_address())?
+            .into(),
+        _ => return Err(invalid_address()),
+    };
+
+    Ok((ip, port).into())
+}
+
+pub(crate) fn libc_timestamp_to_instant(ts: LibcTimestamp) -> Instant {
     match ts {
         LibcTimestamp::TimeSpec { seconds, nanos } => {
             Instant::from_fixed_nanos(seconds as i128 * 1_000_000_000i128 + nanos as i128)
@@ -172,7 +260,13 @@ impl NetworkPort for LinuxNetworkPort {
     async fn send(&mut self, data: &[u8]) -> Result<(), <LinuxNetworkPort as NetworkPort>::Error> {
         log::trace!("Send NTC");
 
-        let sender = |inner: &std::net::UdpSocket| inner.send_to(data, self.ntc_address);
+        let address = if is_pdelay_message(data) {
+            self.ntc_pdelay_address
+        } else {
+            self.ntc_address
+        };
+
+        let sender = |inner: &std::net::UdpSocket| inner.send_to(data, address);
         self.ntc_socket.async_io(Interest::WRITABLE, sender).await?;
 
         Ok(())
@@ -184,7 +278,36 @@ impl NetworkPort for LinuxNetworkPort {
     ) -> Result<Option<statime::Instant>, std::io::Error> {
         log::trace!("Send TC");
 
-        let opt_libc_ts = self.tc_socket.send(data, self.tc_address).await?;
+        let address = if is_pdelay_message(data) {
+            self.tc_pdelay_address
+        } else {
+            self.tc_address
+        };
+
+        let opt_libc_ts = self.tc_socket.send(data, address).await?;
+
+        Ok(opt_libc_ts.map(libc_timestamp_to_instant))
+    }
+
+    async fn send_to(&mut self, data: &[u8], address: &PortAddress) -> Result<(), std::io::Error> {
+        log::trace!("Send NTC to {:?}", address);
+
+        let address = socket_address(address, NTC_PORT)?;
+        let sender = |inner: &std::net::UdpSocket| inner.send_to(data, address);
+        self.ntc_socket.async_io(Interest::WRITABLE, sender).await?;
+
+        Ok(())
+    }
+
+    async fn send_time_critical_to(
+        &mut self,
+        data: &[u8],
+        address: &PortAddress,
+    ) -> Result<Option<statime::Instant>, std::io::Error> {
+        log::trace!("Send TC to {:?}", address);
+
+        let address = socket_address(address, TC_PORT)?;
+        let opt_libc_ts = self.tc_socket.send(data, address).await?;
 
         Ok(opt_libc_ts.map(libc_timestamp_to_instant))
     }
@@ -198,6 +321,7 @@ impl NetworkPort for LinuxNetworkPort {
             let packet = NetworkPacket {
                 data: buf.into(),
                 timestamp: libc_timestamp_to_instant(recv_result.timestamp),
+                source_address: Some(port_address(recv_result.peer_address)),
             };
 
             log::trace!("Recv TC");
@@ -207,7 +331,7 @@ impl NetworkPort for LinuxNetworkPort {
 
         let non_time_critical_future = async {
             let mut buffer = [0; 2048];
-            let (received_len, _) = self
+            let (received_len, source_address) = self
                 .ntc_socket
                 .async_io(Interest::READABLE, |inner| inner.recv_from(&mut buffer))
                 .await?;
@@ -219,6 +343,7 @@ impl NetworkPort for LinuxNetworkPort {
             Ok(NetworkPacket {
                 data,
                 timestamp: self.clock.now(),
+                source_address: Some(port_address(source_address)),
             })
         };
 
@@ -250,6 +375,43 @@ mod tests {
     use super::*;
     use crate::network::interface::LinuxNetworkMode;
 
+    #[test]
+    fn pdelay_messages_use_pdelay_address() {
+        // Sync, Delay_Req, Follow_Up and Announce
+        for message_type in [0x0, 0x1, 0x8, 0xb] {
+            assert!(!is_pdelay_message(&[message_type, 0x02]));
+        }
+        // Pdelay_Req, Pdelay_Resp and Pdelay_Resp_Follow_Up, with a transport specific
+        // nibble set
+        for message_type in [0x2, 0x3, 0xa] {
+            assert!(is_pdelay_message(&[0x10 | message_type, 0x02]));
+        }
+
+        let address =
+            LinuxRuntime::pdelay_address((LinuxRuntime::IPV4_PRIMARY_MULTICAST, 319).into());
+        assert_eq!(address, (LinuxRuntime::IPV4_PDELAY_MULTICAST, 319).into());
+    }
+
+    #[test]
+    fn port_address_conversion() {
+        for address in [
+            SocketAddr::from((Ipv4Addr::new(192, 168, 0, 25), TC_PORT)),
+            SocketAddr::from((Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 1), NTC_PORT)),
+        ] {
+            let port_address = port_address(address);
+            assert_eq!(
+                socket_address(&port_address, address.port()).unwrap(),
+                address
+            );
+        }
+
+        let profinet = PortAddress {
+            network_protocol: NetworkProtocol::Profinet,
+            address: Default::default(),
+        };
+        assert!(socket_address(&profinet, TC_PORT).is_err());
+    }
+
     #[tokio::test]
     async fn port_setup_ipv4() -> Result<(), Box<dyn std::error::Error>> {
         let port = 9000;
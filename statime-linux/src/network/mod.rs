@@ -1,6 +1,8 @@
 mod control_message;
+pub mod ethernet;
 mod interface;
 pub mod linux;
+mod raw_ethernet_socket;
 mod raw_udp_socket;
 mod timestamped_udp_socket;
 
@@ -0,0 +1,177 @@
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+
+use super::{
+    cerr,
+    control_message::{zeroed_sockaddr_storage, ControlMessage, MessageQueue},
+    interface::InterfaceName,
+    raw_udp_socket::receive_message_from,
+};
+
+/// A 48-bit IEEE 802 MAC address
+pub type MacAddress = [u8; 6];
+
+/// The ethertype of PTP messages (IEEE1588-2019 annex F)
+const PTP_ETHERTYPE: u16 = libc::ETH_P_1588 as u16;
+
+/// A linux packet socket for PTP messages, sent and received without the
+/// ethernet header
+pub struct RawEthernetSocket {
+    fd: OwnedFd,
+    interface_index: libc::c_int,
+}
+
+impl RawEthernetSocket {
+    /// Open a packet socket on the interface. Only a socket that is `receiving`
+    /// gets the PTP messages arriving on the interface, others can only send.
+    pub fn new(interface_name: InterfaceName, receiving: bool) -> std::io::Result<Self> {
+        // # SAFETY
+        //
+        // The pointer is valid and null-terminated
+        let interface_index =
+            match unsafe { libc::if_nametoindex(interface_name.as_cstr().as_ptr()) } {
+                0 => return Err(std::io::Error::last_os_error()),
+                n => n as libc::c_int,
+            };
+
+        // Packets of protocol 0 are never delivered to the socket
+        let protocol = if receiving { PTP_ETHERTYPE } else { 0 };
+
+        let fd = cerr(unsafe {
+            libc::socket(
+                libc::AF_PACKET,
+                libc::SOCK_DGRAM,
+                protocol.to_be() as libc::c_int,
+            )
+        })?;
+
+        // Safety:
+        // the fd was just created, so it is open and owned by no one else
+        let socket = Self {
+            fd: unsafe { OwnedFd::from_raw_fd(fd) },
+            interface_index,
+        };
+
+        socket.bind(protocol)?;
+        socket.set_nonblocking(true)?;
+
+        Ok(socket)
+    }
+
+    fn sockaddr_ll(&self, protocol: u16, address: MacAddress) -> libc::sockaddr_ll {
+        let mut sll_addr = [0; 8];
+        sll_addr[..6].copy_from_slice(&address);
+
+        libc::sockaddr_ll {
+            sll_family: libc::AF_PACKET as _,
+            sll_protocol: protocol.to_be(),
+            sll_ifindex: self.interface_index,
+            sll_hatype: 0,
+            sll_pkttype: 0,
+            sll_halen: address.len() as _,
+            sll_addr,
+        }
+    }
+
+    /// Only receive the packets of the interface of the socket
+    fn bind(&self, protocol: u16) -> std::io::Result<()> {
+        let sockaddr_ll = self.sockaddr_ll(protocol, [0; 6]);
+
+        let address = &sockaddr_ll as *const _ as *const libc::sockaddr;
+        let address_len = std::mem::size_of_val(&sockaddr_ll) as libc::socklen_t;
+
+        cerr(unsafe { libc::bind(self.fd.as_raw_fd(), address, address_len) })?;
+
+        Ok(())
+    }
+
+    fn set_nonblocking(&self, nonblocking: bool) -> std::io::Result<()> {
+        let nonblocking = nonblocking as libc::c_int;
+        cerr(unsafe { libc::ioctl(self.fd.as_raw_fd(), libc::FIONBIO, &nonblocking) }).map(drop)
+    }
+
+    /// Receive the packets sent to the multicast `address` as well
+    pub fn join_multicast(&self, address: MacAddress) -> std::io::Result<()> {
+        let mut mr_address = [0; 8];
+        mr_address[..6].copy_from_slice(&address);
+
+        let request = libc::packet_mreq {
+            mr_ifindex: self.interface_index,
+            mr_type: libc::PACKET_MR_MULTICAST as _,
+            mr_alen: address.len() as _,
+            mr_address,
+        };
+
+        // Safety:
+        //
+        // the pointer argument is valid, the size is accurate
+        unsafe {
+            cerr(libc::setsockopt(
+                self.fd.as_raw_fd(),
+                libc::SOL_PACKET,
+                libc::PACKET_ADD_MEMBERSHIP,
+                &request as *const _ as *const libc::c_void,
+                std::mem::size_of_val(&request) as libc::socklen_t,
+            ))?;
+        }
+
+        Ok(())
+    }
+
+    pub fn send_to(&self, data: &[u8], destination: MacAddress) -> std::io::Result<usize> {
+        let sockaddr_ll = self.sockaddr_ll(PTP_ETHERTYPE, destination);
+
+        let address = &sockaddr_ll as *const _ as *const libc::sockaddr;
+        let address_len = std::mem::size_of_val(&sockaddr_ll) as libc::socklen_t;
+
+        // Safety:
+        //
+        // the data pointer and length describe a valid buffer, the address is a valid
+        // sockaddr_ll of the given length
+        let sent = cerr(unsafe {
+            libc::sendto(
+                self.fd.as_raw_fd(),
+                data.as_ptr().cast(),
+                data.len(),
+                0,
+                address,
+                address_len,
+            )
+        } as _)?;
+
+        Ok(sent as usize)
+    }
+}
+
+impl AsRawFd for RawEthernetSocket {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd.as_raw_fd()
+    }
+}
+
+/// Receive a packet from the socket, with its control messages and the MAC
+/// address of its sender
+pub fn receive_packet<'a>(
+    socket: &RawEthernetSocket,
+    packet_buf: &mut [u8],
+    control_buf: &'a mut [u8],
+) -> std::io::Result<(usize, impl Iterator<Item = ControlMessage> + 'a, MacAddress)> {
+    let mut addr = zeroed_sockaddr_storage();
+    let (received_bytes, control_messages) = receive_message_from(
+        socket,
+        packet_buf,
+        control_buf,
+        MessageQueue::Normal,
+        &mut addr,
+    )?;
+
+    // Safety:
+    //
+    // sockaddr_storage has enough space for a sockaddr_ll, and recvmsg filled it
+    // in as one for a packet socket
+    let sockaddr_ll: libc::sockaddr_ll =
+        unsafe { std::ptr::read_unaligned(&addr as *const _ as *const _) };
+    let mut source_address = [0; 6];
+    source_address.copy_from_slice(&sockaddr_ll.sll_addr[..6]);
+
+    Ok((received_bytes, control_messages, source_address))
+}
@@ -140,7 +140,7 @@ impl RawUdpSocket {
 }
 
 pub fn receive_message<'a>(
-    socket: &std::net::UdpSocket,
+    socket: &impl AsRawFd,
     packet_buf: &mut [u8],
     control_buf: &'a mut [u8],
     queue: MessageQueue,
@@ -149,8 +149,27 @@ pub fn receive_message<'a>(
     impl Iterator<Item = ControlMessage> + 'a,
     Option<SocketAddr>,
 )> {
-    let mut buf_slice = IoSliceMut::new(packet_buf);
     let mut addr = zeroed_sockaddr_storage();
+    let (received_bytes, control_messages) =
+        receive_message_from(socket, packet_buf, control_buf, queue, &mut addr)?;
+
+    Ok((
+        received_bytes,
+        control_messages,
+        sockaddr_storage_to_socket_addr(&addr),
+    ))
+}
+
+/// Like [`receive_message`], but leaves the source address in `addr` as is,
+/// for sockets of other families than IP
+pub fn receive_message_from<'a>(
+    socket: &impl AsRawFd,
+    packet_buf: &mut [u8],
+    control_buf: &'a mut [u8],
+    queue: MessageQueue,
+    addr: &mut libc::sockaddr_storage,
+) -> std::io::Result<(usize, impl Iterator<Item = ControlMessage> + 'a)> {
+    let mut buf_slice = IoSliceMut::new(packet_buf);
 
     let mut mhdr = empty_msghdr();
 
@@ -159,7 +178,7 @@ pub fn receive_message<'a>(
     mhdr.msg_iov = (&mut buf_slice as *mut IoSliceMut).cast::<libc::iovec>();
     mhdr.msg_iovlen = 1;
     mhdr.msg_flags = 0;
-    mhdr.msg_name = (&mut addr as *mut libc::sockaddr_storage).cast::<libc::c_void>();
+    mhdr.msg_name = (addr as *mut libc::sockaddr_storage).cast::<libc::c_void>();
     mhdr.msg_namelen = std::mem::size_of::<libc::sockaddr_storage>() as u32;
 
     let receive_flags = match queue {
@@ -207,11 +226,7 @@ pub fn receive_message<'a>(
     // recvmsg ensures that the control buffer contains
     // a set of valid control messages and that controllen is
     // the length these take up in the buffer.
-    Ok((
-        sent_bytes,
-        unsafe { ControlMessageIterator::new(mhdr) },
-        sockaddr_storage_to_socket_addr(&addr),
-    ))
+    Ok((sent_bytes, unsafe { ControlMessageIterator::new(mhdr) }))
 }
 
 mod set_timestamping_options {
@@ -221,7 +236,7 @@ mod set_timestamping_options {
     use crate::network::interface::InterfaceName;
 
     fn configure_timestamping_socket(
-        udp_socket: &std::net::UdpSocket,
+        udp_socket: &impl AsRawFd,
         options: u32,
     ) -> std::io::Result<libc::c_int> {
         // Documentation on the timestamping calls:
@@ -252,7 +267,7 @@ mod set_timestamping_options {
     }
 
     pub(crate) fn set_timestamping_options(
-        udp_socket: &std::net::UdpSocket,
+        udp_socket: &impl AsRawFd,
         timestamping_mode: TimestampingMode,
     ) -> std::io::Result<()> {
         // Setup timestamping
@@ -282,7 +297,7 @@ mod set_timestamping_options {
     }
 
     pub fn driver_enable_hardware_timestamping(
-        udp_socket: &std::net::UdpSocket,
+        udp_socket: &impl AsRawFd,
         interface: InterfaceName,
     ) {
         let mut tstamp_config = libc::hwtstamp_config {
@@ -316,7 +331,7 @@ mod exceptional_condition_fd {
     // this works around that by creating a epoll fd that becomes
     // ready to read when the underlying fd has an event on its error queue.
     pub(crate) fn exceptional_condition_fd(
-        socket_of_interest: &std::net::UdpSocket,
+        socket_of_interest: &impl AsRawFd,
     ) -> std::io::Result<AsyncFd<RawFd>> {
         // Safety:
         // epoll_create1 is safe to call without flags
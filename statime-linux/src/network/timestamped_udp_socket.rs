@@ -1,6 +1,11 @@
 #![forbid(unsafe_code)]
 
-use std::{io, net::SocketAddr, os::unix::prelude::RawFd, time::Duration};
+use std::{
+    io,
+    net::SocketAddr,
+    os::unix::prelude::{AsRawFd, RawFd},
+    time::Duration,
+};
 
 use tokio::io::{unix::AsyncFd, Interest};
 
@@ -60,55 +65,71 @@ impl TimestampedUdpSocket {
         &self,
         expected_counter: u32,
     ) -> std::io::Result<Option<LibcTimestamp>> {
-        // the send timestamp may never come set a very short timeout to prevent hanging
-        // forever. We automatically fall back to a less accurate timestamp when
-        // this function returns None
-        let timeout = Duration::from_millis(10);
-
-        let fetch = self.fetch_send_timestamp_help(expected_counter);
-        if let Ok(send_timestamp) = tokio::time::timeout(timeout, fetch).await {
-            Ok(Some(send_timestamp?))
-        } else {
-            log::warn!("Packet without timestamp (waiting for timestamp timed out)");
-            eprintln!("Packet without timestamp (waiting for timestamp timed out)");
-            Ok(None)
-        }
+        fetch_send_timestamp(
+            self.io.get_ref(),
+            &self.exceptional_condition,
+            expected_counter,
+        )
+        .await
     }
+}
 
-    async fn fetch_send_timestamp_help(
-        &self,
-        expected_counter: u32,
-    ) -> std::io::Result<LibcTimestamp> {
-        log::trace!("waiting for timestamp socket to become readable to fetch a send timestamp");
-
-        // Send timestamps are sent to the udp socket's error queue. Sadly, tokio does
-        // not currently support awaiting whether there is something in the
-        // error queue see https://github.com/tokio-rs/tokio/issues/4885.
-        //
-        // Therefore, we manually configure an extra file descriptor to listen for
-        // POLLPRI on the main udp socket. This `exceptional_condition` file
-        // descriptor becomes readable when there is something in the error
-        // queue.
-
-        loop {
-            let result = self
-                .exceptional_condition
-                .async_io(Interest::READABLE, |_| {
-                    fetch_send_timestamp_help(self.io.get_ref(), expected_counter)
-                })
-                .await;
-
-            match result {
-                Ok(Some(send_timestamp)) => {
-                    return Ok(send_timestamp);
-                }
-                Ok(None) => {
-                    continue;
-                }
-                Err(e) => {
-                    log::warn!("Error fetching timestamp: {e:?}");
-                    return Err(e);
-                }
+/// Fetch the send timestamp of the message with the `expected_counter` from
+/// the error queue of the `socket`, the `exceptional_condition` fd becomes
+/// readable when the error queue of the socket has a message
+pub(crate) async fn fetch_send_timestamp(
+    socket: &impl AsRawFd,
+    exceptional_condition: &AsyncFd<RawFd>,
+    expected_counter: u32,
+) -> std::io::Result<Option<LibcTimestamp>> {
+    // the send timestamp may never come set a very short timeout to prevent hanging
+    // forever. We automatically fall back to a less accurate timestamp when
+    // this function returns None
+    let timeout = Duration::from_millis(10);
+
+    let fetch = wait_for_send_timestamp(socket, exceptional_condition, expected_counter);
+    if let Ok(send_timestamp) = tokio::time::timeout(timeout, fetch).await {
+        Ok(Some(send_timestamp?))
+    } else {
+        log::warn!("Packet without timestamp (waiting for timestamp timed out)");
+        eprintln!("Packet without timestamp (waiting for timestamp timed out)");
+        Ok(None)
+    }
+}
+
+async fn wait_for_send_timestamp(
+    socket: &impl AsRawFd,
+    exceptional_condition: &AsyncFd<RawFd>,
+    expected_counter: u32,
+) -> std::io::Result<LibcTimestamp> {
+    log::trace!("waiting for timestamp socket to become readable to fetch a send timestamp");
+
+    // Send timestamps are sent to the udp socket's error queue. Sadly, tokio does
+    // not currently support awaiting whether there is something in the
+    // error queue see https://github.com/tokio-rs/tokio/issues/4885.
+    //
+    // Therefore, we manually configure an extra file descriptor to listen for
+    // POLLPRI on the main udp socket. This `exceptional_condition` file
+    // descriptor becomes readable when there is something in the error
+    // queue.
+
+    loop {
+        let result = exceptional_condition
+            .async_io(Interest::READABLE, |_| {
+                fetch_send_timestamp_help(socket, expected_counter)
+            })
+            .await;
+
+        match result {
+            Ok(Some(send_timestamp)) => {
+                return Ok(send_timestamp);
+            }
+            Ok(None) => {
+                continue;
+            }
+            Err(e) => {
+                log::warn!("Error fetching timestamp: {e:?}");
+                return Err(e);
             }
         }
     }
@@ -128,7 +149,7 @@ pub enum LibcTimestamp {
 }
 
 impl LibcTimestamp {
-    fn from_timespec(timespec: libc::timespec) -> Self {
+    pub(crate) fn from_timespec(timespec: libc::timespec) -> Self {
         Self::TimeSpec {
             seconds: timespec.tv_sec as _,
             nanos: timespec.tv_nsec as _,
@@ -218,7 +239,7 @@ fn recv_with_timestamp(
 }
 
 fn fetch_send_timestamp_help(
-    socket: &std::net::UdpSocket,
+    socket: &impl AsRawFd,
     expected_counter: u32,
 ) -> io::Result<Option<LibcTimestamp>> {
     // we get back two control messages: one with the timestamp (just like a receive
@@ -11,6 +11,7 @@ use crate::{
         messages::AnnounceMessage,
     },
     port::state::PortState,
+    profile::Profile,
     time::Instant,
 };
 
@@ -57,25 +58,31 @@ impl Bmca {
     }
 
     /// Takes the Erbest from this port
+    ///
+    /// The `local_priority` of the port is only used by the alternate BMCA of
+    /// some profiles.
     pub fn take_best_port_announce_message(
         &mut self,
         current_time: Timestamp,
+        profile: Profile,
+        local_priority: u8,
     ) -> Option<BestAnnounceMessage> {
         // Find the announce message we want to use from each foreign master that has
         // qualified messages
         let announce_messages = self
             .foreign_master_list
-            .take_qualified_announce_messages(current_time);
+            .take_qualified_announce_messages(current_time, profile.foreign_master_qualification());
 
         // The best of the foreign master messages is our erbest
-        let erbest =
-            Self::find_best_announce_message(announce_messages.map(|(message, timestamp)| {
-                BestAnnounceMessage {
-                    message,
-                    timestamp,
-                    identity: self.own_port_identity,
-                }
-            }));
+        let erbest = Self::find_best_announce_message(
+            announce_messages.map(|(message, timestamp)| BestAnnounceMessage {
+                message,
+                timestamp,
+                identity: self.own_port_identity,
+                local_priority,
+            }),
+            profile,
+        );
 
         if let Some(best) = &erbest {
             // All messages that were considered have been removed from the
@@ -92,10 +99,13 @@ impl Bmca {
     /// the announce message.
     pub fn find_best_announce_message(
         announce_messages: impl IntoIterator<Item = BestAnnounceMessage>,
+        profile: Profile,
     ) -> Option<BestAnnounceMessage> {
         announce_messages.into_iter().reduce(|left, right| {
-            match ComparisonDataset::from_announce_message(&left.message, &left.identity).compare(
-                &ComparisonDataset::from_announce_message(&right.message, &right.identity),
+            match Self::compare(
+                profile,
+                &left.comparison_dataset(),
+                &right.comparison_dataset(),
             ) {
                 DatasetOrdering::Better | DatasetOrdering::BetterByTopology => left,
                 // We get errors if two announce messages are (functionally) the same, in that case
@@ -136,21 +146,35 @@ impl Bmca {
         best_port_announce_message: Option<BestAnnounceMessage>,
         port_state: &PortState,
     ) -> Option<RecommendedState> {
+        let profile = own_data.profile;
         let d0 = ComparisonDataset::from_own_data(own_data);
         let ebest = best_global_announce_message
-            .map(|best| ComparisonDataset::from_announce_message(&best.message, &best.identity));
+            .as_ref()
+            .map(BestAnnounceMessage::comparison_dataset);
         let erbest = best_port_announce_message
-            .map(|best| ComparisonDataset::from_announce_message(&best.message, &best.identity));
+            .as_ref()
+            .map(BestAnnounceMessage::comparison_dataset);
 
         if best_global_announce_message.is_none() && matches!(port_state, PortState::Listening) {
             return None;
         }
 
+        // A slave only clock only ever synchronizes to the best master, all other
+        // ports keep listening (IEEE1588-2019 section 9.3.1)
+        if own_data.slave_only {
+            return match (best_global_announce_message, best_port_announce_message) {
+                (Some(global), Some(port)) if global.timestamp == port.timestamp => {
+                    Some(RecommendedState::S1(global.message))
+                }
+                _ => Some(RecommendedState::Listening),
+            };
+        }
+
         if (1..=127).contains(&own_data.clock_quality.clock_class) {
             return match erbest {
                 None => Some(RecommendedState::M1(*own_data)),
                 Some(erbest) => {
-                    if d0.compare(&erbest).is_better() {
+                    if Self::compare(profile, &d0, &erbest).is_better() {
                         Some(RecommendedState::M1(*own_data))
                     } else {
                         Some(RecommendedState::P1(
@@ -164,7 +188,7 @@ impl Bmca {
         match &ebest {
             None => return Some(RecommendedState::M2(*own_data)),
             Some(ebest) => {
-                if d0.compare(ebest).is_better() {
+                if Self::compare(profile, &d0, ebest).is_better() {
                     return Some(RecommendedState::M2(*own_data));
                 }
             }
@@ -181,7 +205,10 @@ impl Bmca {
 
                 if best_global_announce_message.timestamp == best_port_announce_message.timestamp {
                     Some(RecommendedState::S1(best_global_announce_message.message))
-                } else if matches!(ebest.compare(&erbest), DatasetOrdering::BetterByTopology) {
+                } else if matches!(
+                    Self::compare(profile, &ebest, &erbest),
+                    DatasetOrdering::BetterByTopology
+                ) {
                     Some(RecommendedState::P2(best_port_announce_message.message))
                 } else {
                     Some(RecommendedState::M3(best_global_announce_message.message))
@@ -189,13 +216,33 @@ impl Bmca {
             }
         }
     }
+
+    /// Compare datasets with the dataset comparison algorithm of the profile
+    fn compare(
+        profile: Profile,
+        left: &ComparisonDataset,
+        right: &ComparisonDataset,
+    ) -> DatasetOrdering {
+        if profile.alternate_bmca() {
+            left.compare_alternate(right)
+        } else {
+            left.compare(right)
+        }
+    }
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Clone, Debug)]
 pub struct BestAnnounceMessage {
-    message: AnnounceMessage,
+    pub(crate) message: AnnounceMessage,
     timestamp: Timestamp,
     identity: PortIdentity,
+    local_priority: u8,
+}
+
+impl BestAnnounceMessage {
+    fn comparison_dataset(&self) -> ComparisonDataset {
+        ComparisonDataset::from_announce_message(&self.message, &self.identity, self.local_priority)
+    }
 }
 
 #[derive(Debug)]
@@ -206,4 +253,7 @@ pub enum RecommendedState {
     P1(AnnounceMessage),
     P2(AnnounceMessage),
     S1(AnnounceMessage),
+    /// Wait for a master, used by slave only clocks instead of the master and
+    /// passive states
+    Listening,
 }
@@ -1,4 +1,5 @@
-//! Implementation of chapter 9.3.4 Data set comparison algorithm
+//! Implementation of chapter 9.3.4 Data set comparison algorithm, and of the
+//! alternate data set comparison of ITU-T G.8275.1 section 6.3.7
 
 use core::cmp::Ordering;
 
@@ -19,6 +20,7 @@ pub struct ComparisonDataset {
     gm_identity: ClockIdentity,
     gm_clock_quality: ClockQuality,
     gm_priority_2: u8,
+    local_priority: u8,
     steps_removed: u16,
     identity_of_senders: ClockIdentity,
     identity_of_receiver: PortIdentity,
@@ -26,16 +28,19 @@ pub struct ComparisonDataset {
 
 impl ComparisonDataset {
     /// Create a ComparisonDataset from the data in an announce message and the
-    /// port identity of the port that received the announce message
+    /// port identity and local priority of the port that received the announce
+    /// message
     pub fn from_announce_message(
         message: &AnnounceMessage,
         port_receiver_identity: &PortIdentity,
+        local_priority: u8,
     ) -> Self {
         Self {
             gm_priority_1: message.grandmaster_priority_1(),
             gm_identity: message.grandmaster_identity(),
             gm_clock_quality: message.grandmaster_clock_quality(),
             gm_priority_2: message.grandmaster_priority_2(),
+            local_priority,
             steps_removed: message.steps_removed(),
             identity_of_senders: message.header().source_port_identity().clock_identity,
             identity_of_receiver: *port_receiver_identity,
@@ -48,6 +53,7 @@ impl ComparisonDataset {
             gm_identity: data.clock_identity,
             gm_clock_quality: data.clock_quality,
             gm_priority_2: data.priority_2,
+            local_priority: data.local_priority,
             steps_removed: 0,
             identity_of_senders: data.clock_identity,
             identity_of_receiver: PortIdentity {
@@ -110,46 +116,90 @@ impl ComparisonDataset {
                 }
             }
             // Figure 35
-            true => {
-                let steps_removed_difference =
-                    self.steps_removed as i32 - other.steps_removed as i32;
-
-                match steps_removed_difference {
-                    2..=i32::MAX => DatasetOrdering::Worse,
-                    i32::MIN..=-2 => DatasetOrdering::Better,
-                    1 => match self
-                        .identity_of_receiver
-                        .clock_identity
-                        .cmp(&self.identity_of_senders)
-                    {
-                        Ordering::Less => DatasetOrdering::Better,
-                        Ordering::Equal => DatasetOrdering::Error1,
-                        Ordering::Greater => DatasetOrdering::BetterByTopology,
-                    },
-                    -1 => match other
-                        .identity_of_receiver
-                        .clock_identity
-                        .cmp(&other.identity_of_senders)
-                    {
-                        Ordering::Less => DatasetOrdering::Worse,
-                        Ordering::Equal => DatasetOrdering::Error1,
-                        Ordering::Greater => DatasetOrdering::WorseByTopology,
-                    },
-                    0 => match self.identity_of_senders.cmp(&other.identity_of_senders) {
-                        Ordering::Less => DatasetOrdering::BetterByTopology,
-                        Ordering::Equal => match self
-                            .identity_of_receiver
-                            .port_number
-                            .cmp(&other.identity_of_receiver.port_number)
-                        {
-                            Ordering::Less => DatasetOrdering::BetterByTopology,
-                            Ordering::Equal => DatasetOrdering::Error2,
-                            Ordering::Greater => DatasetOrdering::WorseByTopology,
-                        },
-                        Ordering::Greater => DatasetOrdering::WorseByTopology,
-                    },
-                }
+            true => self.compare_topology(other),
+        }
+    }
+
+    /// Returns the ordering of `self` in comparison to other, using the
+    /// alternate BMCA of ITU-T G.8275.1 (figure 4).
+    ///
+    /// Priority 1 is not used, and the local priorities decide between
+    /// grandmasters of equal quality.
+    pub fn compare_alternate(&self, other: &Self) -> DatasetOrdering {
+        let ordering = self
+            .gm_clock_quality
+            .clock_class
+            .cmp(&other.gm_clock_quality.clock_class)
+            // Reversed, the cmp function of the ClockAccuracy orders semantically
+            .then(
+                other
+                    .gm_clock_quality
+                    .clock_accuracy
+                    .cmp(&self.gm_clock_quality.clock_accuracy),
+            )
+            .then(
+                self.gm_clock_quality
+                    .offset_scaled_log_variance
+                    .cmp(&other.gm_clock_quality.offset_scaled_log_variance),
+            )
+            .then(self.gm_priority_2.cmp(&other.gm_priority_2))
+            .then(self.local_priority.cmp(&other.local_priority));
+
+        match ordering {
+            Ordering::Less => DatasetOrdering::Better,
+            Ordering::Greater => DatasetOrdering::Worse,
+            // Grandmasters of class 127 and below are equivalent, the closest one is
+            // preferred
+            Ordering::Equal if self.gm_clock_quality.clock_class <= 127 => {
+                self.compare_topology(other)
             }
+            Ordering::Equal => match self.gm_identity.cmp(&other.gm_identity) {
+                Ordering::Less => DatasetOrdering::Better,
+                Ordering::Equal => self.compare_topology(other),
+                Ordering::Greater => DatasetOrdering::Worse,
+            },
+        }
+    }
+
+    /// Compare datasets by their position in the network (IEEE1588-2019
+    /// figure 35)
+    fn compare_topology(&self, other: &Self) -> DatasetOrdering {
+        let steps_removed_difference = self.steps_removed as i32 - other.steps_removed as i32;
+
+        match steps_removed_difference {
+            2..=i32::MAX => DatasetOrdering::Worse,
+            i32::MIN..=-2 => DatasetOrdering::Better,
+            1 => match self
+                .identity_of_receiver
+                .clock_identity
+                .cmp(&self.identity_of_senders)
+            {
+                Ordering::Less => DatasetOrdering::Better,
+                Ordering::Equal => DatasetOrdering::Error1,
+                Ordering::Greater => DatasetOrdering::BetterByTopology,
+            },
+            -1 => match other
+                .identity_of_receiver
+                .clock_identity
+                .cmp(&other.identity_of_senders)
+            {
+                Ordering::Less => DatasetOrdering::Worse,
+                Ordering::Equal => DatasetOrdering::Error1,
+                Ordering::Greater => DatasetOrdering::WorseByTopology,
+            },
+            0 => match self.identity_of_senders.cmp(&other.identity_of_senders) {
+                Ordering::Less => DatasetOrdering::BetterByTopology,
+                Ordering::Equal => match self
+                    .identity_of_receiver
+                    .port_number
+                    .cmp(&other.identity_of_receiver.port_number)
+                {
+                    Ordering::Less => DatasetOrdering::BetterByTopology,
+                    Ordering::Equal => DatasetOrdering::Error2,
+                    Ordering::Greater => DatasetOrdering::WorseByTopology,
+                },
+                Ordering::Greater => DatasetOrdering::WorseByTopology,
+            },
         }
     }
 }
@@ -284,4 +334,63 @@ mod tests {
         assert_eq!(a.compare(&b), DatasetOrdering::Better);
         assert_eq!(b.compare(&a), DatasetOrdering::Worse);
     }
+
+    #[test]
+    fn g8275_1_figure_4() {
+        let (mut a, mut b) = get_default_test_pair();
+        a.gm_clock_quality.clock_class = 165;
+        b.gm_clock_quality.clock_class = 165;
+
+        // Grandmasters of class 128 and up are told apart by their identity
+        a.gm_identity = IDENTITY_A;
+        b.gm_identity = IDENTITY_B;
+        a.steps_removed = 3;
+
+        assert_eq!(a.compare_alternate(&b), DatasetOrdering::Better);
+        assert_eq!(b.compare_alternate(&a), DatasetOrdering::Worse);
+
+        // Grandmasters of class 127 and below by their distance
+        a.gm_clock_quality.clock_class = 6;
+        b.gm_clock_quality.clock_class = 6;
+
+        assert_eq!(a.compare_alternate(&b), DatasetOrdering::Worse);
+        assert_eq!(b.compare_alternate(&a), DatasetOrdering::Better);
+
+        a.local_priority = 1;
+        b.local_priority = 2;
+
+        assert_eq!(a.compare_alternate(&b), DatasetOrdering::Better);
+        assert_eq!(b.compare_alternate(&a), DatasetOrdering::Worse);
+
+        a.gm_priority_2 = 1;
+        b.gm_priority_2 = 0;
+
+        assert_eq!(a.compare_alternate(&b), DatasetOrdering::Worse);
+        assert_eq!(b.compare_alternate(&a), DatasetOrdering::Better);
+
+        a.gm_clock_quality.offset_scaled_log_variance = 0;
+        b.gm_clock_quality.offset_scaled_log_variance = 1;
+
+        assert_eq!(a.compare_alternate(&b), DatasetOrdering::Better);
+        assert_eq!(b.compare_alternate(&a), DatasetOrdering::Worse);
+
+        a.gm_clock_quality.clock_accuracy = ClockAccuracy::US1;
+        b.gm_clock_quality.clock_accuracy = ClockAccuracy::NS1;
+
+        assert_eq!(a.compare_alternate(&b), DatasetOrdering::Worse);
+        assert_eq!(b.compare_alternate(&a), DatasetOrdering::Better);
+
+        a.gm_clock_quality.clock_class = 6;
+        b.gm_clock_quality.clock_class = 7;
+
+        assert_eq!(a.compare_alternate(&b), DatasetOrdering::Better);
+        assert_eq!(b.compare_alternate(&a), DatasetOrdering::Worse);
+
+        // Priority 1 is not used
+        a.gm_priority_1 = 255;
+        b.gm_priority_1 = 0;
+
+        assert_eq!(a.compare_alternate(&b), DatasetOrdering::Better);
+        assert_eq!(b.compare_alternate(&a), DatasetOrdering::Worse);
+    }
 }
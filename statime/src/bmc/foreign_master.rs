@@ -26,17 +26,22 @@ const MAX_FOREIGN_MASTERS: usize = 8;
 
 pub struct ForeignMaster {
     foreign_master_port_identity: PortIdentity,
+    /// The most recent announce message, the ones before it only count
+    /// towards the qualification of the foreign master
+    announce_message: AnnounceMessage,
     // Must have a capacity of at least 2
-    announce_messages: ArrayVec<(AnnounceMessage, Timestamp), MAX_ANNOUNCE_MESSAGES>,
+    receipt_times: ArrayVec<Timestamp, MAX_ANNOUNCE_MESSAGES>,
 }
 
 impl ForeignMaster {
     fn new(announce_message: AnnounceMessage, current_time: Timestamp) -> Self {
-        let mut messages = ArrayVec::<_, MAX_ANNOUNCE_MESSAGES>::new();
-        messages.push((announce_message, current_time));
+        let foreign_master_port_identity = announce_message.header().source_port_identity();
+        let mut receipt_times = ArrayVec::<_, MAX_ANNOUNCE_MESSAGES>::new();
+        receipt_times.push(current_time);
         Self {
-            foreign_master_port_identity: announce_message.header().source_port_identity(),
-            announce_messages: messages,
+            foreign_master_port_identity,
+            announce_message,
+            receipt_times,
         }
     }
 
@@ -55,10 +60,10 @@ impl ForeignMaster {
     ) -> bool {
         let cutoff_time = Instant::from(current_time)
             - Duration::from(announce_interval) * FOREIGN_MASTER_TIME_WINDOW;
-        self.announce_messages
-            .retain(|(_, ts)| Instant::from(*ts) > cutoff_time);
+        self.receipt_times
+            .retain(|ts| Instant::from(*ts) > cutoff_time);
 
-        self.announce_messages.is_empty()
+        self.receipt_times.is_empty()
     }
 
     fn register_announce_message(
@@ -69,15 +74,11 @@ impl ForeignMaster {
     ) {
         self.purge_old_messages(current_time, announce_interval);
         // Try to add new message; otherwise remove the first message and then add
-        if self
-            .announce_messages
-            .try_push((announce_message, current_time))
-            .is_err()
-        {
-            self.announce_messages.remove(0);
-            self.announce_messages
-                .push((announce_message, current_time));
+        if self.receipt_times.is_full() {
+            self.receipt_times.remove(0);
         }
+        self.receipt_times.push(current_time);
+        self.announce_message = announce_message;
     }
 }
 
@@ -102,9 +103,13 @@ impl ForeignMasterList {
 
     /// Takes the qualified announce message of all foreign masters that have
     /// one
+    ///
+    /// Without `foreign_master_qualification`, the most recent announce message
+    /// of every foreign master is qualified, as in a gPTP domain.
     pub fn take_qualified_announce_messages(
         &mut self,
         current_time: Timestamp,
+        foreign_master_qualification: bool,
     ) -> impl Iterator<Item = (AnnounceMessage, Timestamp)> {
         let mut qualified_foreign_masters = ArrayVec::<_, MAX_FOREIGN_MASTERS>::new();
 
@@ -121,12 +126,16 @@ impl ForeignMasterList {
             // A foreign master must have at least FOREIGN_MASTER_THRESHOLD messages in the
             // last FOREIGN_MASTER_TIME_WINDOW to be qualified, so we filter out
             // any that don't have that
-            if self.foreign_masters[i].announce_messages.len() > FOREIGN_MASTER_THRESHOLD {
+            let foreign_master = &mut self.foreign_masters[i];
+            if !foreign_master_qualification
+                || foreign_master.receipt_times.len() > FOREIGN_MASTER_THRESHOLD
+            {
                 // Only the most recent announce message is qualified, so we remove that one
                 // from the list
-                let last_index = self.foreign_masters[i].announce_messages.len() - 1;
-                qualified_foreign_masters
-                    .push(self.foreign_masters[i].announce_messages.remove(last_index));
+                if let Some(receipt_time) = foreign_master.receipt_times.pop() {
+                    qualified_foreign_masters
+                        .push((foreign_master.announce_message.clone(), receipt_time));
+                }
                 continue;
             }
         }
@@ -152,7 +161,7 @@ impl ForeignMasterList {
         {
             // Yes, so add the announce message to it
             foreign_master.register_announce_message(
-                *announce_message,
+                announce_message.clone(),
                 current_time,
                 port_announce_interval,
             );
@@ -160,7 +169,7 @@ impl ForeignMasterList {
             // No, insert a new foreign master, if there is room in the array
             if self.foreign_masters.len() < MAX_FOREIGN_MASTERS {
                 self.foreign_masters
-                    .push(ForeignMaster::new(*announce_message, current_time));
+                    .push(ForeignMaster::new(announce_message.clone(), current_time));
             }
         }
     }
@@ -193,19 +202,17 @@ impl ForeignMasterList {
         // 2. The announce message must be newer than the one(s) we already have
         // We can check the sequence id for that (with some logic for u16 rollover)
         if let Some(foreign_master) = self.get_foreign_master(source_identity) {
-            if let Some((last_announce_message, _)) = foreign_master.announce_messages.last() {
-                let announce_sequence_id = announce_message.header().sequence_id();
-                let last_sequence_id = last_announce_message.header().sequence_id();
-
-                if last_sequence_id >= FOREIGN_MASTER_TIME_WINDOW {
-                    if announce_sequence_id < last_sequence_id {
-                        return false;
-                    }
-                } else if announce_sequence_id - last_sequence_id
-                    > u16::MAX - FOREIGN_MASTER_TIME_WINDOW
-                {
+            let announce_sequence_id = announce_message.header().sequence_id();
+            let last_sequence_id = foreign_master.announce_message.header().sequence_id();
+
+            if last_sequence_id >= FOREIGN_MASTER_TIME_WINDOW {
+                if announce_sequence_id < last_sequence_id {
                     return false;
                 }
+            } else if announce_sequence_id - last_sequence_id
+                > u16::MAX - FOREIGN_MASTER_TIME_WINDOW
+            {
+                return false;
             }
         }
 
@@ -218,7 +225,125 @@ impl ForeignMasterList {
         // than FOREIGN_MASTER_THRESHOLD, but that is handled in the
         // `take_qualified_announce_messages` method.
 
+        // 5. The announce message must not have passed through our own ptp instance
+        // already, using it would create a timing loop (IEEE1588-2019 section 16.2.3)
+        if announce_message
+            .path_trace()
+            .contains(&self.own_port_identity.clock_identity)
+        {
+            log::debug!(
+                "Ignoring announce from {:?}, its path trace contains our own identity",
+                source_identity
+            );
+            return false;
+        }
+
         // Otherwise, the announce message is qualified
         true
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datastructures::{
+        common::{ClockIdentity, ClockQuality, TimeSource},
+        messages::{Message, MessageBuilder},
+    };
+
+    fn identity(id: u8) -> PortIdentity {
+        PortIdentity {
+            clock_identity: ClockIdentity([id; 8]),
+            port_number: 1,
+        }
+    }
+
+    fn announce(sequence_id: u16, path_trace: &[ClockIdentity]) -> AnnounceMessage {
+        let message = MessageBuilder::new()
+            .source_port_identity(identity(2))
+            .sequence_id(sequence_id)
+            .announce_message(
+                Timestamp::default(),
+                37,
+                128,
+                ClockQuality::default(),
+                128,
+                identity(2).clock_identity,
+                0,
+                TimeSource::InternalOscillator,
+                path_trace.iter().copied().collect(),
+                Default::default(),
+                None,
+                None,
+                None,
+            );
+        match message {
+            Message::Announce(announce) => announce,
+            _ => unreachable!(),
+        }
+    }
+
+    fn qualified_count(path_trace: &[ClockIdentity]) -> usize {
+        let mut list = ForeignMasterList::new(Duration::from_secs(1).into(), identity(1));
+
+        for sequence_id in 0..4 {
+            let current_time = Instant::from_secs(10 + sequence_id as u64).into();
+            list.register_announce_message(&announce(sequence_id, path_trace), current_time);
+        }
+
+        list.take_qualified_announce_messages(Instant::from_secs(14).into(), true)
+            .count()
+    }
+
+    #[test]
+    fn test_path_trace_loop() {
+        assert_eq!(qualified_count(&[]), 1);
+        assert_eq!(qualified_count(&[ClockIdentity([2; 8])]), 1);
+        assert_eq!(
+            qualified_count(&[ClockIdentity([2; 8]), ClockIdentity([1; 8])]),
+            0
+        );
+    }
+
+    #[test]
+    fn test_most_recent_announce_qualified() {
+        let mut list = ForeignMasterList::new(Duration::from_secs(1).into(), identity(1));
+        for sequence_id in 0..4 {
+            let current_time = Instant::from_secs(10 + sequence_id as u64).into();
+            list.register_announce_message(&announce(sequence_id, &[]), current_time);
+        }
+
+        let qualified: std::vec::Vec<_> = list
+            .take_qualified_announce_messages(Instant::from_secs(14).into(), true)
+            .collect();
+        assert_eq!(
+            qualified,
+            [(announce(3, &[]), Instant::from_secs(13).into())]
+        );
+
+        // Older announce messages are not qualified once the most recent one is taken
+        assert_eq!(
+            list.take_qualified_announce_messages(Instant::from_secs(14).into(), true)
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_without_foreign_master_qualification() {
+        let mut list = ForeignMasterList::new(Duration::from_secs(1).into(), identity(1));
+        list.register_announce_message(&announce(0, &[]), Instant::from_secs(10).into());
+
+        let current_time = Instant::from_secs(11).into();
+        assert_eq!(
+            list.take_qualified_announce_messages(current_time, true)
+                .count(),
+            0
+        );
+        assert_eq!(
+            list.take_qualified_announce_messages(current_time, false)
+                .count(),
+            1
+        );
+    }
+}
@@ -23,6 +23,11 @@ pub trait Clock {
     fn now(&self) -> Instant;
 
     /// Get the quality of the clock
+    ///
+    /// This is queried every time the BMCA runs, and advertised while the
+    /// instance is grandmaster of a domain with
+    /// [holdover](crate::DefaultDS::with_holdover). A clock class of 127 or
+    /// below means the clock is locked to a primary reference.
     fn quality(&self) -> ClockQuality;
 
     /// Adjust the clock with the given time offset and frequency multiplier.
@@ -7,6 +7,11 @@ use crate::datastructures::{WireFormat, WireFormatError};
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, PartialOrd, Ord)]
 pub struct ClockIdentity(pub [u8; 8]);
 
+impl ClockIdentity {
+    /// The wildcard identity, used to address messages to all PTP instances
+    pub const ALL: Self = Self([0xff; 8]);
+}
+
 impl WireFormat for ClockIdentity {
     fn wire_size(&self) -> usize {
         8
@@ -7,6 +7,7 @@ mod instance_type;
 mod network_protocol;
 mod port_address;
 mod port_identity;
+mod ptp_text;
 mod time_interval;
 mod time_source;
 mod timestamp;
@@ -19,6 +20,7 @@ pub use instance_type::*;
 pub use network_protocol::*;
 pub use port_address::*;
 pub use port_identity::*;
+pub use ptp_text::*;
 pub use time_interval::*;
 pub use time_source::*;
 pub use timestamp::*;
@@ -1,3 +1,5 @@
+/// The network protocol of a [`PortAddress`](super::PortAddress)
+/// (IEEE1588-2019 section 7.4.1)
 #[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum NetworkProtocol {
     Reserved,
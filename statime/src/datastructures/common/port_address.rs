@@ -3,6 +3,10 @@ use arrayvec::ArrayVec;
 use super::network_protocol::NetworkProtocol;
 use crate::datastructures::{WireFormat, WireFormatError};
 
+/// The network address of a PTP port (IEEE1588-2019 section 5.3.6)
+///
+/// The format of the address depends on the network protocol, e.g. the four
+/// bytes of the IP address for [`NetworkProtocol::UdpIPv4`].
 #[derive(Default, Debug, Clone, PartialEq, Eq)]
 pub struct PortAddress {
     pub network_protocol: NetworkProtocol,
@@ -0,0 +1,84 @@
+use arrayvec::ArrayVec;
+
+use crate::datastructures::{WireFormat, WireFormatError};
+
+/// A text field as used in PTP messages (IEEE1588-2019 section 5.3.9)
+///
+/// The text is UTF-8 encoded and at most `CAPACITY` bytes long. By default,
+/// that is the longest text the standard allows for any field.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PtpText<const CAPACITY: usize = 128>(ArrayVec<u8, CAPACITY>);
+
+impl<const CAPACITY: usize> PtpText<CAPACITY> {
+    /// Create a new text, returns `None` when the text is too long
+    pub fn new(text: &str) -> Option<Self> {
+        let mut bytes = ArrayVec::new();
+        bytes.try_extend_from_slice(text.as_bytes()).ok()?;
+        Some(Self(bytes))
+    }
+
+    /// The text, or `None` if it is not valid UTF-8
+    pub fn as_str(&self) -> Option<&str> {
+        core::str::from_utf8(&self.0).ok()
+    }
+
+    /// The raw bytes of the text
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl<const CAPACITY: usize> WireFormat for PtpText<CAPACITY> {
+    fn wire_size(&self) -> usize {
+        1 + self.0.len()
+    }
+
+    fn serialize(&self, buffer: &mut [u8]) -> Result<(), WireFormatError> {
+        if buffer.len() < self.wire_size() {
+            return Err(WireFormatError::BufferTooShort);
+        }
+
+        buffer[0] = self.0.len() as u8;
+        buffer[1..][..self.0.len()].copy_from_slice(&self.0);
+
+        Ok(())
+    }
+
+    fn deserialize(buffer: &[u8]) -> Result<Self, WireFormatError> {
+        let length = *buffer.first().ok_or(WireFormatError::BufferTooShort)? as usize;
+        let text = buffer
+            .get(1..1 + length)
+            .ok_or(WireFormatError::BufferTooShort)?;
+
+        let mut bytes = ArrayVec::new();
+        bytes.try_extend_from_slice(text)?;
+        Ok(Self(bytes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ptp_text_wireformat() {
+        let text: PtpText = PtpText::new("statime").unwrap();
+
+        let mut buffer = [0; 8];
+        text.serialize(&mut buffer).unwrap();
+        assert_eq!(buffer, [7, b's', b't', b'a', b't', b'i', b'm', b'e']);
+
+        let deserialized = PtpText::deserialize(&buffer).unwrap();
+        assert_eq!(deserialized, text);
+        assert_eq!(deserialized.as_str(), Some("statime"));
+
+        assert!(matches!(
+            <PtpText>::deserialize(&[8, b'a']),
+            Err(WireFormatError::BufferTooShort)
+        ));
+    }
+}
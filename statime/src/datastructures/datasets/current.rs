@@ -1,8 +1,31 @@
 use crate::time::Duration;
 
+/// A concrete implementation of the PTP Current dataset (IEEE1588-2019 section
+/// 8.2.2)
+///
+/// This dataset describes the synchronization of the instance to its master in
+/// a domain, as last measured by its slave port.
 #[derive(Default, Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
 pub struct CurrentDS {
     pub(crate) steps_removed: u16,
     pub(crate) offset_from_master: Duration,
     pub(crate) mean_delay: Duration,
 }
+
+impl CurrentDS {
+    /// The number of communication paths between the grandmaster and this
+    /// instance
+    pub fn steps_removed(&self) -> u16 {
+        self.steps_removed
+    }
+
+    /// The offset of the local clock from the master, as last measured
+    pub fn offset_from_master(&self) -> Duration {
+        self.offset_from_master
+    }
+
+    /// The mean delay of the path to the master, as last measured
+    pub fn mean_delay(&self) -> Duration {
+        self.mean_delay
+    }
+}
@@ -1,9 +1,10 @@
 use crate::{
     datastructures::{
         common::{ClockIdentity, ClockQuality, InstanceType},
-        messages::SdoId,
+        messages::{EnhancedAccuracyMetricsTlv, SdoId},
     },
-    time::Instant,
+    profile::Profile,
+    time::{Duration, Instant},
 };
 
 /// A concrete implementation of the PTP Default dataset (IEEE1588-2019 section
@@ -16,18 +17,24 @@ use crate::{
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub struct DefaultDS {
     pub(crate) clock_identity: ClockIdentity,
-    number_ports: u16,
+    pub(crate) number_ports: u16,
     pub(crate) clock_quality: ClockQuality,
     pub(crate) priority_1: u8,
     pub(crate) priority_2: u8,
     pub(crate) domain_number: u8,
-    slave_only: bool,
+    pub(crate) slave_only: bool,
     pub(crate) sdo_id: SdoId,
     current_time: Instant,
     pub(crate) instance_enable: bool,
-    external_port_configuration_enabled: bool,
+    pub(crate) external_port_configuration_enabled: bool,
     max_steps_removed: u8,
     pub(crate) instance_type: InstanceType,
+    pub(crate) profile: Profile,
+    pub(crate) local_priority: u8,
+    pub(crate) holdover: Option<Holdover>,
+    pub(crate) grandmaster_id: u16,
+    pub(crate) time_inaccuracy: u32,
+    pub(crate) enhanced_accuracy_metrics: Option<EnhancedAccuracyMetricsTlv>,
 }
 
 impl DefaultDS {
@@ -49,6 +56,9 @@ impl DefaultDS {
     /// typically be derived from the mac address of one of the interfaces of
     /// the device running the PTP instance, as described in IEEE1588-2019
     /// section 7.5.2.2.
+    ///
+    /// A `slave_only` clock never becomes master, not even when it hears no
+    /// other masters, and advertises clock class 255.
     pub fn new_ordinary_clock(
         clock_identity: ClockIdentity,
         priority_1: u8,
@@ -60,7 +70,14 @@ impl DefaultDS {
         DefaultDS {
             clock_identity,
             number_ports: 1,
-            clock_quality: Default::default(),
+            clock_quality: if slave_only {
+                ClockQuality {
+                    clock_class: 255,
+                    ..Default::default()
+                }
+            } else {
+                Default::default()
+            },
             priority_1,
             priority_2,
             domain_number,
@@ -71,6 +88,12 @@ impl DefaultDS {
             external_port_configuration_enabled: false,
             max_steps_removed: 255,
             instance_type: InstanceType::OrdinaryClock,
+            profile: Profile::Default,
+            local_priority: 128,
+            holdover: None,
+            grandmaster_id: 0,
+            time_inaccuracy: 0,
+            enhanced_accuracy_metrics: None,
         }
     }
 
@@ -115,6 +138,216 @@ impl DefaultDS {
             external_port_configuration_enabled: false,
             max_steps_removed: 255,
             instance_type: InstanceType::BoundaryClock,
+            profile: Profile::Default,
+            local_priority: 128,
+            holdover: None,
+            grandmaster_id: 0,
+            time_inaccuracy: 0,
+            enhanced_accuracy_metrics: None,
+        }
+    }
+
+    /// Create a Default dataset for an end to end transparent clock
+    ///
+    /// A transparent clock does not take part in the selection of the primary
+    /// time source, it only forwards messages between its ports. The
+    /// `domain_number` and `sdo_id` set the domain the clock would syntonize
+    /// to, messages of all domains are forwarded.
+    ///
+    /// `clock_identity` should be the identifier for this clock. It should
+    /// typically be derived from the mac address of one of the interfaces of
+    /// the device running the PTP instance, as described in IEEE1588-2019
+    /// section 7.5.2.2.
+    pub fn new_e2e_transparent_clock(
+        clock_identity: ClockIdentity,
+        number_ports: u16,
+        domain_number: u8,
+        sdo_id: SdoId,
+    ) -> Self {
+        DefaultDS {
+            clock_identity,
+            number_ports,
+            clock_quality: Default::default(),
+            // Not applicable
+            priority_1: 255,
+            priority_2: 255,
+            domain_number,
+            // Not applicable
+            slave_only: false,
+            sdo_id,
+            current_time: Default::default(),
+            instance_enable: true,
+            external_port_configuration_enabled: false,
+            max_steps_removed: 255,
+            instance_type: InstanceType::E2ETransparentClock,
+            profile: Profile::Default,
+            local_priority: 128,
+            holdover: None,
+            grandmaster_id: 0,
+            time_inaccuracy: 0,
+            enhanced_accuracy_metrics: None,
         }
     }
+
+    /// Create a Default dataset for a peer to peer transparent clock
+    ///
+    /// A transparent clock does not take part in the selection of the primary
+    /// time source, it only forwards messages between its ports. The
+    /// `domain_number` and `sdo_id` set the domain the clock would syntonize
+    /// to, messages of all domains are forwarded.
+    ///
+    /// All ports of a peer to peer transparent clock should use the
+    /// [P2P](crate::DelayMechanism::P2P) delay mechanism.
+    ///
+    /// `clock_identity` should be the identifier for this clock. It should
+    /// typically be derived from the mac address of one of the interfaces of
+    /// the device running the PTP instance, as described in IEEE1588-2019
+    /// section 7.5.2.2.
+    pub fn new_p2p_transparent_clock(
+        clock_identity: ClockIdentity,
+        number_ports: u16,
+        domain_number: u8,
+        sdo_id: SdoId,
+    ) -> Self {
+        DefaultDS {
+            clock_identity,
+            number_ports,
+            clock_quality: Default::default(),
+            // Not applicable
+            priority_1: 255,
+            priority_2: 255,
+            domain_number,
+            // Not applicable
+            slave_only: false,
+            sdo_id,
+            current_time: Default::default(),
+            instance_enable: true,
+            external_port_configuration_enabled: false,
+            max_steps_removed: 255,
+            instance_type: InstanceType::P2PTransparentClock,
+            profile: Profile::Default,
+            local_priority: 128,
+            holdover: None,
+            grandmaster_id: 0,
+            time_inaccuracy: 0,
+            enhanced_accuracy_metrics: None,
+        }
+    }
+
+    /// Operate under the given PTP profile
+    ///
+    /// Profiles that prescribe an sdo id or priority 1, like
+    /// [gPTP](crate::Profile::Gptp), replace the values given when creating
+    /// the dataset.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the domain number is not allowed by the profile.
+    pub fn with_profile(mut self, profile: Profile) -> Self {
+        assert!(
+            profile.domain_numbers().contains(&self.domain_number),
+            "domain number {} is not allowed by profile {:?}",
+            self.domain_number,
+            profile
+        );
+
+        self.profile = profile;
+        if let Some(sdo_id) = profile.sdo_id() {
+            self.sdo_id = sdo_id;
+        }
+        if let Some(priority_1) = profile.priority_1() {
+            self.priority_1 = priority_1;
+        }
+        self
+    }
+
+    /// Set the local priority of the instance itself, used by the alternate
+    /// BMCA of the [G.8275.1](crate::Profile::G8275_1) and
+    /// [G.8275.2](crate::Profile::G8275_2) profiles to compare the instance to
+    /// masters of equal quality. A lower value is preferred, the
+    /// default is 128.
+    pub fn with_local_priority(mut self, local_priority: u8) -> Self {
+        self.local_priority = local_priority;
+        self
+    }
+
+    /// Set the state of every port from its [desired
+    /// state](crate::PortDS::with_desired_state) instead of the best master
+    /// clock algorithm (IEEE1588-2019 section 17.6)
+    ///
+    /// Ports still handle the messages of their state, so the instance follows
+    /// the announce messages of the master of its slave port. Every port of an
+    /// instance with external port configuration needs a desired state.
+    pub fn with_external_port_configuration(mut self, enabled: bool) -> Self {
+        self.external_port_configuration_enabled = enabled;
+        self
+    }
+
+    /// Advertise a holdover clock class when the instance loses its time
+    /// reference (ITU-T G.8275.1 section 6.4)
+    ///
+    /// With holdover, the instance advertises the [clock
+    /// quality](crate::Clock::quality) of the local clock instead of the one
+    /// in this dataset. It has a reference while that has a clock class of 127
+    /// or lower, or while one of its ports is synchronized to a master. Once
+    /// it loses that reference, the instance advertises the holdover clock
+    /// class instead of the clock class of the clock, until it gets a
+    /// reference again.
+    pub fn with_holdover(mut self, holdover: Holdover) -> Self {
+        self.holdover = Some(holdover);
+        self
+    }
+
+    /// Set the grandmaster id the instance advertises as grandmaster of a
+    /// [C37.238](crate::Profile::C37_238) domain
+    ///
+    /// Every grandmaster capable instance in the domain needs a unique id in
+    /// the range 3 to 254.
+    pub fn with_grandmaster_id(mut self, grandmaster_id: u16) -> Self {
+        self.grandmaster_id = grandmaster_id;
+        self
+    }
+
+    /// Set the worst case time inaccuracy in nanoseconds the instance adds, as
+    /// advertised in a [C37.238](crate::Profile::C37_238) domain
+    ///
+    /// As grandmaster, this is the inaccuracy of the time of its reference.
+    /// Boundary and transparent clocks add it to the total time inaccuracy of
+    /// the announce messages they pass on.
+    pub fn with_time_inaccuracy(mut self, time_inaccuracy: u32) -> Self {
+        self.time_inaccuracy = time_inaccuracy;
+        self
+    }
+
+    /// Send enhanced accuracy metrics with the announce messages of the
+    /// instance (IEEE1588-2019 section 16.12)
+    ///
+    /// As grandmaster, the metrics are sent as given, with a hop count of
+    /// zero. As boundary clock, all inaccuracies but that of the grandmaster
+    /// are added to the metrics received from the master, which are only
+    /// passed on when the master sends them.
+    pub fn with_enhanced_accuracy_metrics(
+        mut self,
+        enhanced_accuracy_metrics: EnhancedAccuracyMetricsTlv,
+    ) -> Self {
+        self.enhanced_accuracy_metrics = Some(enhanced_accuracy_metrics);
+        self
+    }
+}
+
+/// The clock classes of a PTP instance in holdover, see
+/// [`DefaultDS::with_holdover`]
+///
+/// A telecom grandmaster locked to a primary reference time clock has clock
+/// class 6, and uses 7 and 140 in holdover (for a category 1 oscillator). A
+/// telecom boundary clock uses 135 and 165.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Holdover {
+    /// The clock class while the clock is within the holdover specification
+    pub in_spec_class: u8,
+    /// The clock class once the clock is no longer within the holdover
+    /// specification
+    pub out_of_spec_class: u8,
+    /// How long the clock stays within the holdover specification
+    pub in_spec_duration: Duration,
 }
@@ -1,7 +1,7 @@
 pub use current::CurrentDS;
-pub use default::DefaultDS;
+pub use default::{DefaultDS, Holdover};
 pub use parent::ParentDS;
-pub use port::{DelayMechanism, PortDS};
+pub use port::{DelayMechanism, DesiredState, PortDS, MAX_UNICAST_MASTERS};
 pub use time_properties::TimePropertiesDS;
 
 mod current;
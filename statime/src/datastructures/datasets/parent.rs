@@ -1,4 +1,9 @@
-use crate::datastructures::common::{ClockIdentity, ClockQuality, PortAddress, PortIdentity};
+use arrayvec::ArrayVec;
+
+use crate::datastructures::{
+    common::{ClockIdentity, ClockQuality, PortAddress, PortIdentity},
+    messages::MAX_PATH_TRACE_LEN,
+};
 
 // TODO: Discuss moving this (and TimePropertiesDS, ...) to slave?
 #[derive(Default, Clone, Debug, Eq, PartialEq)]
@@ -12,4 +17,14 @@ pub struct ParentDS {
     pub(crate) grandmaster_priority_1: u8,
     pub(crate) grandmaster_priority_2: u8,
     pub(crate) protocol_address: PortAddress,
+    pub(crate) path_trace: ArrayVec<ClockIdentity, MAX_PATH_TRACE_LEN>,
+}
+
+impl ParentDS {
+    /// The clock identities of the boundary clocks between the grandmaster and
+    /// this instance, as carried by the announce messages of the parent
+    /// (IEEE1588-2019 section 16.2)
+    pub fn path_trace(&self) -> &[ClockIdentity] {
+        &self.path_trace
+    }
 }
@@ -1,15 +1,23 @@
 use core::{future::Future, pin::Pin};
 
+use arrayvec::ArrayVec;
+
 use crate::{
     bmc::bmca::RecommendedState,
-    datastructures::common::PortIdentity,
+    datastructures::{
+        common::{PortAddress, PortIdentity},
+        messages::PortDataSetTlv,
+    },
     port::{
         state::{MasterState, PortState, SlaveState},
-        Ticker,
+        Ticker, MAX_UNICAST_GRANTS,
     },
     time::Duration,
 };
 
+/// The maximum number of entries in the unicast master table of a port
+pub const MAX_UNICAST_MASTERS: usize = 16;
+
 /// A concrete implementation of the PTP port dataset (IEEE1588-2019 section
 /// 8.2.15)
 ///
@@ -21,23 +29,30 @@ pub struct PortDS {
     pub(crate) port_identity: PortIdentity,
     pub(crate) port_state: PortState,
     log_min_delay_req_interval: i8,
-    #[allow(unused)]
     mean_link_delay: Duration,
     log_announce_interval: i8,
     announce_receipt_timeout: u8,
     log_sync_interval: i8,
-    #[allow(unused)]
     delay_mechanism: DelayMechanism,
     log_min_p_delay_req_interval: i8,
-    #[allow(unused)]
     version_number: u8,
     #[allow(unused)]
     minor_version_number: u8,
-    #[allow(unused)]
     delay_asymmetry: Duration,
+    ingress_latency: Duration,
+    egress_latency: Duration,
     port_enable: bool,
-    #[allow(unused)]
     master_only: bool,
+    one_step_sync: bool,
+    unicast_master_table: ArrayVec<PortAddress, MAX_UNICAST_MASTERS>,
+    unicast_negotiation: bool,
+    unicast_grant_duration: u32,
+    max_unicast_grants: usize,
+    sync_receipt_timeout: Option<u8>,
+    neighbor_prop_delay_thresh: Duration,
+    local_priority: u8,
+    desired_state: Option<DesiredState>,
+    slave_event_monitoring: Option<PortAddress>,
 }
 
 impl PortDS {
@@ -61,18 +76,11 @@ impl PortDS {
         delay_mechanism: DelayMechanism,
         log_min_p_delay_req_interval: i8,
     ) -> Self {
-        let mean_link_delay = match delay_mechanism {
-            DelayMechanism::E2E | DelayMechanism::NoMechanism | DelayMechanism::Special => {
-                Duration::ZERO
-            }
-            DelayMechanism::P2P | DelayMechanism::CommonP2p => unimplemented!(),
-        };
-
         PortDS {
             port_identity,
-            port_state: PortState::Listening,
+            port_state: PortState::Initializing,
             log_min_delay_req_interval,
-            mean_link_delay,
+            mean_link_delay: Duration::ZERO,
             log_announce_interval,
             announce_receipt_timeout,
             log_sync_interval,
@@ -81,11 +89,308 @@ impl PortDS {
             version_number: 2,
             minor_version_number: 1,
             delay_asymmetry: Duration::ZERO,
+            ingress_latency: Duration::ZERO,
+            egress_latency: Duration::ZERO,
             port_enable: true,
             master_only: false,
+            one_step_sync: false,
+            unicast_master_table: ArrayVec::new(),
+            unicast_negotiation: false,
+            unicast_grant_duration: 300,
+            max_unicast_grants: 0,
+            sync_receipt_timeout: None,
+            neighbor_prop_delay_thresh: Duration::from_nanos(800),
+            local_priority: 128,
+            desired_state: None,
+            slave_event_monitoring: None,
         }
     }
 
+    /// Create a new Port dataset for a port in a [gPTP](crate::Profile::Gptp)
+    /// domain, with the default configuration of IEEE 802.1AS-2020
+    ///
+    /// The port uses the peer to peer delay mechanism, sends an announce and a
+    /// peer delay request every second, and eight sync messages per second.
+    /// Both the announce and sync receipt timeouts are three intervals.
+    pub fn new_gptp(port_identity: PortIdentity) -> Self {
+        PortDS::new(port_identity, 0, 0, 3, -3, DelayMechanism::P2P, 0).with_sync_receipt_timeout(3)
+    }
+
+    /// Create a new Port dataset for a port in a
+    /// [G.8275.1](crate::Profile::G8275_1) domain, with the default
+    /// configuration of ITU-T G.8275.1
+    ///
+    /// The port uses the end to end delay mechanism, sends eight announce
+    /// messages per second, and sixteen sync and delay request messages per
+    /// second. The announce receipt timeout is three intervals.
+    pub fn new_g8275_1(port_identity: PortIdentity) -> Self {
+        PortDS::new(port_identity, -4, -3, 3, -4, DelayMechanism::E2E, 0)
+    }
+
+    /// Create a new Port dataset for a port in a
+    /// [G.8275.2](crate::Profile::G8275_2) domain, with the default
+    /// configuration of ITU-T G.8275.2
+    ///
+    /// The port negotiates unicast messages with the masters in `masters`, for
+    /// grants of 300 seconds. It sends an announce message every second, and
+    /// sixteen sync and delay request messages per second. The announce
+    /// receipt timeout is three intervals.
+    ///
+    /// # Panics
+    ///
+    /// Panics when more than [`MAX_UNICAST_MASTERS`] addresses are given.
+    pub fn new_g8275_2(port_identity: PortIdentity, masters: &[PortAddress]) -> Self {
+        PortDS::new(port_identity, -4, 0, 3, -4, DelayMechanism::E2E, 0)
+            .with_unicast_master_table(masters)
+            .with_unicast_negotiation(300, MAX_UNICAST_GRANTS)
+    }
+
+    /// Create a new Port dataset for a port in a
+    /// [ST 2059-2](crate::Profile::St2059_2) domain, with the default
+    /// configuration of SMPTE ST 2059-2
+    ///
+    /// The port uses the end to end delay mechanism, sends four announce
+    /// messages per second, and eight sync and delay request messages per
+    /// second. The announce receipt timeout is three intervals.
+    pub fn new_st2059_2(port_identity: PortIdentity) -> Self {
+        PortDS::new(port_identity, -3, -2, 3, -3, DelayMechanism::E2E, 0)
+    }
+
+    /// Create a new Port dataset for a port in a
+    /// [C37.238](crate::Profile::C37_238) domain, with the default
+    /// configuration of IEEE C37.238-2017
+    ///
+    /// The port uses the peer to peer delay mechanism, and sends an announce,
+    /// a sync and a peer delay request every second. The announce receipt
+    /// timeout is three intervals.
+    pub fn new_c37_238(port_identity: PortIdentity) -> Self {
+        PortDS::new(port_identity, 0, 0, 3, 0, DelayMechanism::P2P, 0)
+    }
+
+    /// Send one-step Sync messages, carrying the origin timestamp in the Sync
+    /// itself, instead of following each Sync up with a Follow_Up message.
+    ///
+    /// Ports send two-step Sync messages by default. See
+    /// [`NetworkPort::send_one_step`](crate::NetworkPort::send_one_step) for
+    /// how network ports can insert the exact transmit time. One-step Sync
+    /// messages are not supported on ports with a
+    /// [security association](crate::Port::with_security_association).
+    pub fn with_one_step_sync(mut self, one_step_sync: bool) -> Self {
+        self.one_step_sync = one_step_sync;
+        self
+    }
+
+    /// Set the addresses of the masters this port may synchronize to
+    /// (IEEE1588-2019 section 17.5)
+    ///
+    /// A port with a unicast master table talks to other PTP nodes over unicast
+    /// only. Without [unicast
+    /// negotiation](PortDS::with_unicast_negotiation), the port uses static
+    /// unicast: it only considers Announce messages from the masters in the
+    /// table, and sends its own messages directly to the master it
+    /// synchronizes to. As a master, it sends Announce and Sync messages to all
+    /// addresses in the table.
+    ///
+    /// # Panics
+    ///
+    /// Panics when more than [`MAX_UNICAST_MASTERS`] addresses are given.
+    pub fn with_unicast_master_table(mut self, masters: &[PortAddress]) -> Self {
+        self.unicast_master_table = masters
+            .try_into()
+            .expect("too many entries in the unicast master table");
+        self
+    }
+
+    /// Use unicast negotiation (IEEE1588-2019 section 16.1) instead of
+    /// multicast.
+    ///
+    /// As a slave, the port requests Announce messages from every master in
+    /// its [unicast master table](PortDS::with_unicast_master_table), and Sync
+    /// and Delay_Resp messages from the master it synchronizes to. Requests are
+    /// for `grant_duration` seconds, and are renewed before they expire. As a
+    /// master, the port only sends messages to the slaves that requested them,
+    /// and grants at most `max_grants` requests at a time.
+    pub fn with_unicast_negotiation(mut self, grant_duration: u32, max_grants: usize) -> Self {
+        self.unicast_negotiation = true;
+        self.unicast_grant_duration = grant_duration;
+        self.max_unicast_grants = max_grants;
+        self
+    }
+
+    /// Never synchronize to a master through this port (IEEE1588-2019 section
+    /// 9.2.2.2), the notSlave attribute of ITU-T G.8275.1
+    ///
+    /// Announce messages received on the port are not used to select a
+    /// master, so the port never becomes slave.
+    pub fn with_master_only(mut self, master_only: bool) -> Self {
+        self.master_only = master_only;
+        self
+    }
+
+    /// Set the local priority of the port, used by the alternate BMCA of the
+    /// [G.8275.1](crate::Profile::G8275_1) and
+    /// [G.8275.2](crate::Profile::G8275_2) profiles to choose between masters
+    /// of equal quality. A lower value is preferred, the default is 128.
+    pub fn with_local_priority(mut self, local_priority: u8) -> Self {
+        self.local_priority = local_priority;
+        self
+    }
+
+    /// Set the state of the port when the instance uses [external port
+    /// configuration](crate::DefaultDS::with_external_port_configuration)
+    /// (IEEE1588-2019 section 17.6.5.2)
+    pub fn with_desired_state(mut self, desired_state: DesiredState) -> Self {
+        self.desired_state = Some(desired_state);
+        self
+    }
+
+    pub(crate) fn desired_state(&self) -> Option<DesiredState> {
+        self.desired_state
+    }
+
+    pub(crate) fn master_only(&self) -> bool {
+        self.master_only
+    }
+
+    pub(crate) fn local_priority(&self) -> u8 {
+        self.local_priority
+    }
+
+    /// Stop synchronizing to the master when no sync messages were received
+    /// from it for `sync_receipt_timeout` of its sync intervals, and become
+    /// master instead (IEEE 802.1AS-2020 section 10.7.3.1).
+    ///
+    /// By default, only the announce receipt timeout applies.
+    pub fn with_sync_receipt_timeout(mut self, sync_receipt_timeout: u8) -> Self {
+        self.sync_receipt_timeout = Some(sync_receipt_timeout);
+        self
+    }
+
+    /// Set the largest mean link delay at which a port in a
+    /// [gPTP](crate::Profile::Gptp) domain still takes part in
+    /// synchronization (IEEE 802.1AS-2020 section 11.2.13.5).
+    ///
+    /// The default of 800 ns suits copper links, longer links such as fiber
+    /// need a larger threshold.
+    pub fn with_neighbor_prop_delay_thresh(mut self, neighbor_prop_delay_thresh: Duration) -> Self {
+        self.neighbor_prop_delay_thresh = neighbor_prop_delay_thresh;
+        self
+    }
+
+    /// Correct for a link with a longer delay in one direction than in the
+    /// other (IEEE1588-2019 section 16.8)
+    ///
+    /// The `delay_asymmetry` is the amount by which the delay from the master
+    /// to the slave exceeds the mean path delay, so it is positive when the
+    /// master to slave direction is the slow one. It is applied to the Sync
+    /// and Delay_Req messages of the port as a slave, and can be changed at
+    /// runtime with a management SET of the port dataset.
+    pub fn with_delay_asymmetry(mut self, delay_asymmetry: Duration) -> Self {
+        self.delay_asymmetry = delay_asymmetry;
+        self
+    }
+
+    pub(crate) fn delay_asymmetry(&self) -> Duration {
+        self.delay_asymmetry
+    }
+
+    pub(crate) fn set_delay_asymmetry(&mut self, delay_asymmetry: Duration) {
+        self.delay_asymmetry = delay_asymmetry;
+    }
+
+    /// Set the latency between the reference plane of the port and the point
+    /// where received event messages are timestamped (IEEE1588-2019 section
+    /// 8.2.16)
+    ///
+    /// The latency is subtracted from the receive timestamp of every message
+    /// of the port before it is used.
+    pub fn with_ingress_latency(mut self, ingress_latency: Duration) -> Self {
+        self.ingress_latency = ingress_latency;
+        self
+    }
+
+    /// Set the latency between the point where sent event messages are
+    /// timestamped and the reference plane of the port (IEEE1588-2019 section
+    /// 8.2.16)
+    ///
+    /// The latency is added to the transmit timestamp of every event message
+    /// of the port before it is used.
+    pub fn with_egress_latency(mut self, egress_latency: Duration) -> Self {
+        self.egress_latency = egress_latency;
+        self
+    }
+
+    /// Send the events of the port while it is a slave to a monitoring node at
+    /// `address` (IEEE1588-2019 section 16.11)
+    ///
+    /// The timing of received Sync messages, the offsets computed from them and
+    /// the transmit times of Delay_Req messages are collected in batches, each
+    /// sent in a signaling message once full or when the port changes master.
+    pub fn with_slave_event_monitoring(mut self, address: PortAddress) -> Self {
+        self.slave_event_monitoring = Some(address);
+        self
+    }
+
+    pub(crate) fn slave_event_monitoring(&self) -> Option<&PortAddress> {
+        self.slave_event_monitoring.as_ref()
+    }
+
+    pub(crate) fn ingress_latency(&self) -> Duration {
+        self.ingress_latency
+    }
+
+    pub(crate) fn egress_latency(&self) -> Duration {
+        self.egress_latency
+    }
+
+    pub(crate) fn sync_receipt_timeout(&self) -> Option<u8> {
+        self.sync_receipt_timeout
+    }
+
+    pub(crate) fn neighbor_prop_delay_thresh(&self) -> Duration {
+        self.neighbor_prop_delay_thresh
+    }
+
+    pub(crate) fn unicast_master_table(&self) -> &[PortAddress] {
+        &self.unicast_master_table
+    }
+
+    pub(crate) fn set_unicast_master_table(
+        &mut self,
+        unicast_master_table: ArrayVec<PortAddress, MAX_UNICAST_MASTERS>,
+    ) {
+        self.unicast_master_table = unicast_master_table;
+    }
+
+    /// Whether the port talks to other PTP nodes over unicast only
+    pub(crate) fn unicast(&self) -> bool {
+        self.unicast_negotiation || !self.unicast_master_table.is_empty()
+    }
+
+    pub(crate) fn unicast_negotiation(&self) -> bool {
+        self.unicast_negotiation
+    }
+
+    pub(crate) fn set_unicast_negotiation(&mut self, unicast_negotiation: bool) {
+        self.unicast_negotiation = unicast_negotiation;
+    }
+
+    pub(crate) fn unicast_grant_duration(&self) -> u32 {
+        self.unicast_grant_duration
+    }
+
+    pub(crate) fn max_unicast_grants(&self) -> usize {
+        self.max_unicast_grants
+    }
+
+    pub(crate) fn log_announce_interval(&self) -> i8 {
+        self.log_announce_interval
+    }
+
+    pub(crate) fn log_sync_interval(&self) -> i8 {
+        self.log_sync_interval
+    }
+
     pub(crate) fn min_delay_req_interval(&self) -> i8 {
         self.log_min_delay_req_interval
     }
@@ -98,11 +403,51 @@ impl PortDS {
         Duration::from_log_interval(self.log_sync_interval)
     }
 
-    #[allow(unused)]
+    pub(crate) fn log_min_p_delay_req_interval(&self) -> i8 {
+        self.log_min_p_delay_req_interval
+    }
+
     pub(crate) fn min_p_delay_req_interval(&self) -> Duration {
         Duration::from_log_interval(self.log_min_p_delay_req_interval)
     }
 
+    pub(crate) fn set_log_announce_interval(&mut self, log_announce_interval: i8) {
+        self.log_announce_interval = log_announce_interval;
+    }
+
+    pub(crate) fn announce_receipt_timeout(&self) -> u8 {
+        self.announce_receipt_timeout
+    }
+
+    pub(crate) fn set_announce_receipt_timeout(&mut self, announce_receipt_timeout: u8) {
+        self.announce_receipt_timeout = announce_receipt_timeout;
+    }
+
+    pub(crate) fn set_log_sync_interval(&mut self, log_sync_interval: i8) {
+        self.log_sync_interval = log_sync_interval;
+    }
+
+    pub(crate) fn set_log_min_p_delay_req_interval(&mut self, log_min_p_delay_req_interval: i8) {
+        self.log_min_p_delay_req_interval = log_min_p_delay_req_interval;
+    }
+
+    pub(crate) fn one_step_sync(&self) -> bool {
+        self.one_step_sync
+    }
+
+    pub(crate) fn delay_mechanism(&self) -> DelayMechanism {
+        self.delay_mechanism
+    }
+
+    pub(crate) fn mean_link_delay(&self) -> Duration {
+        self.mean_link_delay
+    }
+
+    pub(crate) fn set_mean_link_delay(&mut self, mean_link_delay: Duration) {
+        self.mean_link_delay = mean_link_delay;
+        self.port_state.set_mean_link_delay(mean_link_delay);
+    }
+
     // TODO: Count the actual number of passed announce intervals, rather than this
     // approximation
     pub(crate) fn announce_receipt_interval(&self) -> Duration {
@@ -111,15 +456,14 @@ impl PortDS {
         )
     }
 
-    #[allow(unused)]
     pub(crate) fn disable(&mut self) {
         self.port_enable = false;
         self.set_forced_port_state(PortState::Disabled);
     }
 
-    #[allow(unused)]
     pub(crate) fn enable(&mut self) {
         self.port_enable = true;
+        // The network port is already open, so initialization is immediate
         if let PortState::Disabled = self.port_state {
             self.port_state = PortState::Listening;
         }
@@ -135,59 +479,164 @@ impl PortDS {
         self.port_state = state;
     }
 
+    /// Apply the state decision of the BMCA (IEEE1588-2019 section 9.2.5)
+    ///
+    /// `steps_removed` is the number of steps to the grandmaster the instance
+    /// had before this decision, which sets the qualification timeout of a
+    /// port that becomes pre master.
     pub(crate) fn set_recommended_port_state<F: Future>(
         &mut self,
         recommended_state: &RecommendedState,
+        steps_removed: u16,
         announce_receipt_timeout: &mut Pin<&mut Ticker<F, impl FnMut(Duration) -> F>>,
     ) {
+        // Ports that are not running don't take part in the BMCA
+        if matches!(
+            self.port_state,
+            PortState::Initializing | PortState::Faulty | PortState::Disabled
+        ) {
+            return;
+        }
+
         match recommended_state {
-            // TODO set things like steps_removed once they are added
-            // TODO make sure states are complete
             RecommendedState::S1(announce_message) => {
                 let remote_master = announce_message.header().source_port_identity();
-                let state = PortState::Slave(SlaveState::new(remote_master));
-
-                match &self.port_state {
-                    PortState::Listening | PortState::Master(_) | PortState::Passive => {
-                        self.set_forced_port_state(state);
-                        announce_receipt_timeout.reset();
-                    }
-                    PortState::Slave(old_state) => {
-                        if old_state.remote_master() != remote_master {
-                            self.set_forced_port_state(state);
-                            announce_receipt_timeout.reset();
-                        }
-                    }
-                    PortState::Disabled => (),
-                }
-            }
-            RecommendedState::M1(_) | RecommendedState::M2(_) | RecommendedState::M3(_) => {
-                match self.port_state {
-                    PortState::Listening | PortState::Slave(_) | PortState::Passive => {
-                        self.set_forced_port_state(PortState::Master(MasterState::new()))
-                    }
-                    PortState::Master(_) | PortState::Disabled => (),
+
+                // A port is uncalibrated until it synchronized to a new master
+                if self.port_state.remote_master() != Some(remote_master) {
+                    self.set_forced_port_state(PortState::Uncalibrated(SlaveState::new(
+                        remote_master,
+                        self.delay_mechanism,
+                    )));
+                    announce_receipt_timeout.reset();
                 }
             }
+            RecommendedState::M1(_) | RecommendedState::M2(_) => match self.port_state {
+                PortState::Master(_) => (),
+                _ => self.set_forced_port_state(PortState::Master(MasterState::new())),
+            },
+            RecommendedState::M3(_) => match self.port_state {
+                PortState::PreMaster(_) | PortState::Master(_) => (),
+                // Announce messages of a timing loop need time to die out before
+                // the port becomes master (IEEE1588-2019 section 9.2.6.11)
+                _ => self
+                    .set_forced_port_state(PortState::PreMaster(steps_removed.saturating_add(1))),
+            },
             RecommendedState::P1(_) | RecommendedState::P2(_) => match self.port_state {
-                PortState::Listening | PortState::Slave(_) | PortState::Master(_) => {
-                    self.set_forced_port_state(PortState::Passive)
+                PortState::Passive => (),
+                _ => self.set_forced_port_state(PortState::Passive),
+            },
+            RecommendedState::Listening => match self.port_state {
+                PortState::Listening => (),
+                _ => {
+                    self.set_forced_port_state(PortState::Listening);
+                    announce_receipt_timeout.reset();
                 }
-                PortState::Passive | PortState::Disabled => (),
             },
         }
     }
+
+    /// Move to a configured master or passive state, for instances with
+    /// external port configuration. A port configured as slave moves through
+    /// [`set_recommended_port_state`](Self::set_recommended_port_state) once
+    /// it heard from its master.
+    pub(crate) fn set_configured_port_state(&mut self, desired_state: DesiredState) {
+        if matches!(
+            self.port_state,
+            PortState::Initializing | PortState::Faulty | PortState::Disabled
+        ) {
+            return;
+        }
+
+        match (desired_state, &self.port_state) {
+            (DesiredState::Master, PortState::Master(_))
+            | (DesiredState::Passive, PortState::Passive)
+            | (DesiredState::Slave, _) => (),
+            (DesiredState::Master, _) => {
+                self.set_forced_port_state(PortState::Master(MasterState::new()))
+            }
+            (DesiredState::Passive, _) => self.set_forced_port_state(PortState::Passive),
+        }
+    }
+
+    /// Finish the qualification of a pre master port, see
+    /// [`set_recommended_port_state`](Self::set_recommended_port_state).
+    /// Should be called once every announce interval.
+    pub(crate) fn qualification_timeout(&mut self) {
+        if let PortState::PreMaster(intervals) = &mut self.port_state {
+            *intervals = intervals.saturating_sub(1);
+            if *intervals == 0 {
+                self.set_forced_port_state(PortState::Master(MasterState::new()));
+            }
+        }
+    }
+
+    /// Move between uncalibrated and slave, depending on whether the servo
+    /// converged on the time of the master
+    pub(crate) fn set_calibrated(&mut self, calibrated: bool) {
+        let state = core::mem::take(&mut self.port_state);
+        self.port_state = match state {
+            PortState::Uncalibrated(slave) if calibrated => {
+                log::info!(
+                    "new state for port {}: Uncalibrated -> Slave",
+                    self.port_identity.port_number
+                );
+                PortState::Slave(slave)
+            }
+            PortState::Slave(slave) if !calibrated => {
+                log::info!(
+                    "new state for port {}: Slave -> Uncalibrated",
+                    self.port_identity.port_number
+                );
+                PortState::Uncalibrated(slave)
+            }
+            state => state,
+        };
+    }
+}
+
+impl From<&PortDS> for PortDataSetTlv {
+    fn from(port_ds: &PortDS) -> Self {
+        PortDataSetTlv {
+            port_identity: port_ds.port_identity,
+            port_state: port_ds.port_state.to_primitive(),
+            log_min_delay_req_interval: port_ds.log_min_delay_req_interval,
+            peer_mean_path_delay: port_ds.mean_link_delay.into(),
+            log_announce_interval: port_ds.log_announce_interval,
+            announce_receipt_timeout: port_ds.announce_receipt_timeout,
+            log_sync_interval: port_ds.log_sync_interval,
+            delay_mechanism: port_ds.delay_mechanism as u8,
+            log_min_pdelay_req_interval: port_ds.log_min_p_delay_req_interval,
+            version_number: port_ds.version_number,
+            delay_asymmetry: Some(port_ds.delay_asymmetry.into()),
+        }
+    }
+}
+
+/// The state of a port of an instance with [external port
+/// configuration](crate::DefaultDS::with_external_port_configuration), see
+/// [`PortDS::with_desired_state`]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum DesiredState {
+    /// Send sync and announce messages to the other nodes on the link
+    Master,
+    /// Synchronize to the master that sends announce messages on the link
+    Slave,
+    /// Neither send nor synchronize to sync messages
+    Passive,
 }
 
 /// Which delay mechanism a port is using.
 ///
-/// Currently, statime only supports the end to end (E2E) delay mechanism.
+/// Currently, statime supports the end to end (E2E) and peer to peer (P2P)
+/// delay mechanisms.
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub enum DelayMechanism {
     /// End to end delay mechanism. Delay measurement is done directly to the
     /// chosen master, across potential transparent nodes in between.
     E2E = 0x01,
-    /// Not supported, needed for parsing
+    /// Peer to peer delay mechanism. Each port measures the delay of the link
+    /// to its direct neighbour, which is used as the delay to the master.
     P2P = 0x02,
     /// Not supported, needed for parsing
     NoMechanism = 0xfe,
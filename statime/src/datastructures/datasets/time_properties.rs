@@ -1,11 +1,23 @@
-use crate::datastructures::common::TimeSource;
+use arrayvec::ArrayVec;
+
+use crate::datastructures::{
+    common::TimeSource,
+    messages::{
+        AlternateTimeOffsetIndicatorTlv, EnhancedAccuracyMetricsTlv, PowerProfileTlv,
+        SmpteSyncMetadataTlv, MAX_ALTERNATE_TIME_OFFSETS,
+    },
+};
 
 /// A concrete implementation of the PTP Time Properties dataset (IEEE1588-2019
 /// section 8.2.4
 ///
 /// This dataset describes the timescale currently in use, as well as any
 /// upcoming leap seconds on that timescale.
-#[derive(Default, Copy, Clone, Debug, Eq, PartialEq)]
+///
+/// Slaves update it with what their grandmaster sends. While the instance
+/// runs, find the dataset of every domain through
+/// [`PtpInstance::time_properties_ds`](crate::PtpInstance::time_properties_ds).
+#[derive(Default, Clone, Debug, Eq, PartialEq)]
 pub struct TimePropertiesDS {
     pub(crate) current_utc_offset: i16,
     pub(crate) current_utc_offset_valid: bool,
@@ -15,6 +27,11 @@ pub struct TimePropertiesDS {
     pub(crate) frequency_traceable: bool,
     pub(crate) ptp_timescale: bool,
     pub(crate) time_source: TimeSource,
+    pub(crate) alternate_time_offsets:
+        ArrayVec<AlternateTimeOffsetIndicatorTlv, MAX_ALTERNATE_TIME_OFFSETS>,
+    pub(crate) smpte_sync_metadata: Option<SmpteSyncMetadataTlv>,
+    pub(crate) power_profile: Option<PowerProfileTlv>,
+    pub(crate) enhanced_accuracy_metrics: Option<EnhancedAccuracyMetricsTlv>,
 }
 
 impl TimePropertiesDS {
@@ -43,6 +60,10 @@ impl TimePropertiesDS {
             frequency_traceable,
             ptp_timescale: true,
             time_source,
+            alternate_time_offsets: ArrayVec::new(),
+            smpte_sync_metadata: None,
+            power_profile: None,
+            enhanced_accuracy_metrics: None,
         }
     }
 
@@ -68,6 +89,10 @@ impl TimePropertiesDS {
             frequency_traceable,
             ptp_timescale: false,
             time_source,
+            alternate_time_offsets: ArrayVec::new(),
+            smpte_sync_metadata: None,
+            power_profile: None,
+            enhanced_accuracy_metrics: None,
         }
     }
 
@@ -86,4 +111,64 @@ impl TimePropertiesDS {
     pub fn leap61(&self) -> bool {
         self.leap61
     }
+
+    /// Advertise an alternate timescale, such as a local time zone, when this
+    /// instance is the grandmaster (IEEE1588-2019 section 16.3)
+    ///
+    /// Slaves receive the alternate timescales of their grandmaster, and find
+    /// them through [alternate_time_offsets](Self::alternate_time_offsets). An
+    /// instance that becomes grandmaster keeps advertising the alternate
+    /// timescales it knows of.
+    ///
+    /// # Panics
+    ///
+    /// Panics when more than [`MAX_ALTERNATE_TIME_OFFSETS`] alternate
+    /// timescales are added.
+    pub fn with_alternate_time_offset(
+        mut self,
+        alternate_time_offset: AlternateTimeOffsetIndicatorTlv,
+    ) -> Self {
+        self.alternate_time_offsets.push(alternate_time_offset);
+        self
+    }
+
+    /// The alternate timescales of the grandmaster
+    pub fn alternate_time_offsets(&self) -> &[AlternateTimeOffsetIndicatorTlv] {
+        &self.alternate_time_offsets
+    }
+
+    /// Advertise the synchronization metadata of SMPTE ST 2059-2 when this
+    /// instance is the grandmaster
+    ///
+    /// Slaves receive the metadata of their grandmaster, and find it through
+    /// [smpte_sync_metadata](Self::smpte_sync_metadata). Like the alternate
+    /// timescales, an instance that becomes grandmaster keeps advertising the
+    /// metadata it knows of.
+    pub fn with_smpte_sync_metadata(mut self, smpte_sync_metadata: SmpteSyncMetadataTlv) -> Self {
+        self.smpte_sync_metadata = Some(smpte_sync_metadata);
+        self
+    }
+
+    /// The synchronization metadata of the grandmaster, if it sends any
+    pub fn smpte_sync_metadata(&self) -> Option<SmpteSyncMetadataTlv> {
+        self.smpte_sync_metadata
+    }
+
+    /// The grandmaster id and time inaccuracy of the grandmaster of a
+    /// [C37.238](crate::Profile::C37_238) domain
+    pub fn power_profile(&self) -> Option<PowerProfileTlv> {
+        self.power_profile
+    }
+
+    /// The inaccuracy budget of the path from the grandmaster, when the master
+    /// sends enhanced accuracy metrics (IEEE1588-2019 section 16.12)
+    ///
+    /// The [total inaccuracy](EnhancedAccuracyMetricsTlv::inaccuracy) tells
+    /// how far off the time received from the master may be. It doesn't
+    /// include the inaccuracy of this instance, which is only added to the
+    /// metrics it [passes on](crate::DefaultDS::with_enhanced_accuracy_metrics)
+    /// as a master.
+    pub fn enhanced_accuracy_metrics(&self) -> Option<EnhancedAccuracyMetricsTlv> {
+        self.enhanced_accuracy_metrics
+    }
 }
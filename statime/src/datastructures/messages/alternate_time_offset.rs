@@ -0,0 +1,143 @@
+use crate::datastructures::{
+    common::{PtpText, TlvType},
+    WireFormat, WireFormatError,
+};
+
+/// The maximum number of alternate timescales advertised in, or kept from,
+/// announce messages
+pub const MAX_ALTERNATE_TIME_OFFSETS: usize = 4;
+
+/// The maximum length in bytes of the display name of an alternate timescale
+pub const MAX_DISPLAY_NAME_LEN: usize = 10;
+
+/// An ALTERNATE_TIME_OFFSET_INDICATOR TLV (IEEE1588-2019 section 16.3.3)
+///
+/// Announces an alternate timescale, such as a local time zone, as an offset
+/// from the PTP timescale. `jump_seconds` is the change of the offset that
+/// happens at `time_of_next_jump` (in seconds of the PTP timescale, only the
+/// lower 48 bits are used), e.g. the start or end of daylight saving time.
+/// When no jump is scheduled, both are zero.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AlternateTimeOffsetIndicatorTlv {
+    pub key_field: u8,
+    pub current_offset: i32,
+    pub jump_seconds: i32,
+    pub time_of_next_jump: u64,
+    /// A short name of the timescale, e.g. `CEST`
+    pub display_name: PtpText<MAX_DISPLAY_NAME_LEN>,
+}
+
+impl AlternateTimeOffsetIndicatorTlv {
+    /// Length of the value of the TLV, which is padded to an even length
+    fn value_size(&self) -> usize {
+        let size = 15 + self.display_name.wire_size();
+        size + size % 2
+    }
+}
+
+impl WireFormat for AlternateTimeOffsetIndicatorTlv {
+    fn wire_size(&self) -> usize {
+        4 + self.value_size()
+    }
+
+    fn serialize(&self, buffer: &mut [u8]) -> Result<(), WireFormatError> {
+        if buffer.len() < self.wire_size() {
+            return Err(WireFormatError::BufferTooShort);
+        }
+
+        buffer[0..2].copy_from_slice(
+            &TlvType::AlternateTimeOffsetIndicator
+                .to_primitive()
+                .to_be_bytes(),
+        );
+        buffer[2..4].copy_from_slice(&(self.value_size() as u16).to_be_bytes());
+
+        let value = &mut buffer[4..self.wire_size()];
+        value[0] = self.key_field;
+        value[1..5].copy_from_slice(&self.current_offset.to_be_bytes());
+        value[5..9].copy_from_slice(&self.jump_seconds.to_be_bytes());
+        value[9..15].copy_from_slice(&self.time_of_next_jump.to_be_bytes()[2..8]);
+        self.display_name.serialize(&mut value[15..])?;
+        value[15 + self.display_name.wire_size()..].fill(0);
+
+        Ok(())
+    }
+
+    fn deserialize(buffer: &[u8]) -> Result<Self, WireFormatError> {
+        if buffer.len() < 4 {
+            return Err(WireFormatError::BufferTooShort);
+        }
+
+        let length = u16::from_be_bytes([buffer[2], buffer[3]]) as usize;
+        let value = buffer
+            .get(4..4 + length)
+            .ok_or(WireFormatError::BufferTooShort)?;
+        if value.len() < 16 {
+            return Err(WireFormatError::BufferTooShort);
+        }
+
+        let mut time_of_next_jump = [0; 8];
+        time_of_next_jump[2..8].copy_from_slice(&value[9..15]);
+
+        Ok(Self {
+            key_field: value[0],
+            current_offset: i32::from_be_bytes(value[1..5].try_into().unwrap()),
+            jump_seconds: i32::from_be_bytes(value[5..9].try_into().unwrap()),
+            time_of_next_jump: u64::from_be_bytes(time_of_next_jump),
+            display_name: PtpText::deserialize(&value[15..])?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alternate_time_offset_indicator_wireformat() {
+        let byte_representation = [
+            0x00, 0x09, 0x00, 0x14, 0x01, 0x00, 0x00, 0x1c, 0x20, 0xff, 0xff, 0xf1, 0xf0, 0x00,
+            0x00, 0x65, 0x3b, 0x1d, 0x90, 0x04, b'C', b'E', b'S', b'T',
+        ];
+        let object_representation = AlternateTimeOffsetIndicatorTlv {
+            key_field: 1,
+            current_offset: 7200,
+            jump_seconds: -3600,
+            time_of_next_jump: 1698373008,
+            display_name: PtpText::new("CEST").unwrap(),
+        };
+
+        // Test the serialization output
+        let mut serialization_buffer = [0; 24];
+        object_representation
+            .serialize(&mut serialization_buffer)
+            .unwrap();
+        assert_eq!(serialization_buffer, byte_representation);
+
+        // Test the deserialization output
+        let deserialized_data =
+            AlternateTimeOffsetIndicatorTlv::deserialize(&byte_representation).unwrap();
+        assert_eq!(deserialized_data, object_representation);
+    }
+
+    #[test]
+    fn display_name_is_padded() {
+        let indicator = AlternateTimeOffsetIndicatorTlv {
+            key_field: 0,
+            current_offset: 0,
+            jump_seconds: 0,
+            time_of_next_jump: 0,
+            display_name: PtpText::new("UTC").unwrap(),
+        };
+
+        let mut buffer = [0xff; 24];
+        indicator.serialize(&mut buffer).unwrap();
+        assert_eq!(indicator.wire_size(), 24);
+        assert_eq!(buffer[2..4], [0x00, 0x14]);
+        assert_eq!(buffer[23], 0);
+        assert_eq!(
+            AlternateTimeOffsetIndicatorTlv::deserialize(&buffer).unwrap(),
+            indicator
+        );
+    }
+}
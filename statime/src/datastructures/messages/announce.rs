@@ -1,13 +1,21 @@
+use arrayvec::ArrayVec;
 use getset::CopyGetters;
 
-use super::Header;
+use super::{
+    AlternateTimeOffsetIndicatorTlv, EnhancedAccuracyMetricsTlv, Header, PowerProfileTlv,
+    SmpteSyncMetadataTlv, MAX_ALTERNATE_TIME_OFFSETS,
+};
 use crate::datastructures::{
-    common::{ClockIdentity, ClockQuality, TimeSource, Timestamp},
+    common::{ClockIdentity, ClockQuality, TimeSource, Timestamp, TlvType},
     datasets::TimePropertiesDS,
     WireFormat, WireFormatError,
 };
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, CopyGetters)]
+/// The maximum number of clock identities in the path trace of an announce
+/// message
+pub const MAX_PATH_TRACE_LEN: usize = 16;
+
+#[derive(Debug, Clone, PartialEq, Eq, CopyGetters)]
 #[getset(get_copy = "pub")]
 pub struct AnnounceMessage {
     pub(crate) header: Header,
@@ -19,15 +27,70 @@ pub struct AnnounceMessage {
     pub(crate) grandmaster_identity: ClockIdentity,
     pub(crate) steps_removed: u16,
     pub(crate) time_source: TimeSource,
+    /// The clock identities of the boundary clocks the announce passed
+    /// through, carried in a PATH_TRACE TLV (IEEE1588-2019 section 16.2)
+    #[getset(skip)]
+    pub(crate) path_trace: ArrayVec<ClockIdentity, MAX_PATH_TRACE_LEN>,
+    /// The alternate timescales of the grandmaster (IEEE1588-2019 section
+    /// 16.3)
+    #[getset(skip)]
+    pub(crate) alternate_time_offsets:
+        ArrayVec<AlternateTimeOffsetIndicatorTlv, MAX_ALTERNATE_TIME_OFFSETS>,
+    /// The synchronization metadata of the grandmaster (SMPTE ST 2059-2
+    /// section 7.3)
+    #[getset(skip)]
+    pub(crate) smpte_sync_metadata: Option<SmpteSyncMetadataTlv>,
+    /// The IEEE_C37_238 TLV of the power profile
+    #[getset(skip)]
+    pub(crate) power_profile: Option<PowerProfileTlv>,
+    /// The inaccuracy budget of the path from the grandmaster (IEEE1588-2019
+    /// section 16.12)
+    #[getset(skip)]
+    pub(crate) enhanced_accuracy_metrics: Option<EnhancedAccuracyMetricsTlv>,
 }
 
 impl AnnounceMessage {
     pub fn content_size(&self) -> usize {
-        30
+        30 + self.path_trace_size()
+            + self
+                .alternate_time_offsets
+                .iter()
+                .map(|tlv| tlv.wire_size())
+                .sum::<usize>()
+            + self.smpte_sync_metadata.map_or(0, |tlv| tlv.wire_size())
+            + self.power_profile.map_or(0, |tlv| tlv.wire_size())
+            + self
+                .enhanced_accuracy_metrics
+                .map_or(0, |tlv| tlv.wire_size())
+    }
+
+    fn path_trace_size(&self) -> usize {
+        if self.path_trace.is_empty() {
+            0
+        } else {
+            4 + 8 * self.path_trace.len()
+        }
+    }
+
+    /// The path of clock identities the announce message traveled, starting at
+    /// the grandmaster
+    pub fn path_trace(&self) -> &[ClockIdentity] {
+        &self.path_trace
+    }
+
+    /// Leave out the last alternate time offsets until the content is at most
+    /// `max_content_size` bytes. Returns whether any were left out.
+    pub(crate) fn fit_alternate_time_offsets(&mut self, max_content_size: usize) -> bool {
+        let mut truncated = false;
+        while self.content_size() > max_content_size && !self.alternate_time_offsets.is_empty() {
+            self.alternate_time_offsets.pop();
+            truncated = true;
+        }
+        truncated
     }
 
     pub fn serialize_content(&self, buffer: &mut [u8]) -> Result<(), WireFormatError> {
-        if buffer.len() < 30 {
+        if buffer.len() < self.content_size() {
             return Err(WireFormatError::BufferTooShort);
         }
 
@@ -41,6 +104,35 @@ impl AnnounceMessage {
         buffer[27..29].copy_from_slice(&self.steps_removed.to_be_bytes());
         buffer[29] = self.time_source.to_primitive();
 
+        if !self.path_trace.is_empty() {
+            let tlv = &mut buffer[30..30 + self.path_trace_size()];
+            tlv[0..2].copy_from_slice(&TlvType::PathTrace.to_primitive().to_be_bytes());
+            tlv[2..4].copy_from_slice(&(8 * self.path_trace.len() as u16).to_be_bytes());
+            for (identity, chunk) in self.path_trace.iter().zip(tlv[4..].chunks_exact_mut(8)) {
+                identity.serialize(chunk)?;
+            }
+        }
+
+        let mut offset = 30 + self.path_trace_size();
+        for tlv in &self.alternate_time_offsets {
+            tlv.serialize(&mut buffer[offset..])?;
+            offset += tlv.wire_size();
+        }
+
+        if let Some(smpte_sync_metadata) = self.smpte_sync_metadata {
+            smpte_sync_metadata.serialize(&mut buffer[offset..])?;
+            offset += smpte_sync_metadata.wire_size();
+        }
+
+        if let Some(power_profile) = self.power_profile {
+            power_profile.serialize(&mut buffer[offset..])?;
+            offset += power_profile.wire_size();
+        }
+
+        if let Some(enhanced_accuracy_metrics) = self.enhanced_accuracy_metrics {
+            enhanced_accuracy_metrics.serialize(&mut buffer[offset..])?;
+        }
+
         Ok(())
     }
 
@@ -49,6 +141,52 @@ impl AnnounceMessage {
             return Err(WireFormatError::BufferTooShort);
         }
 
+        // Of the TLVs following the announce, only the path trace, alternate
+        // time offsets, SMPTE synchronization metadata, IEEE_C37_238 and
+        // enhanced accuracy metrics TLV are used
+        let mut path_trace = ArrayVec::new();
+        let mut alternate_time_offsets = ArrayVec::new();
+        let mut smpte_sync_metadata = None;
+        let mut power_profile = None;
+        let mut enhanced_accuracy_metrics = None;
+        let mut tlvs = &buffer[30..];
+        while tlvs.len() >= 4 {
+            let tlv_type = TlvType::from_primitive(u16::from_be_bytes([tlvs[0], tlvs[1]]));
+            let length = u16::from_be_bytes([tlvs[2], tlvs[3]]) as usize;
+            let value = tlvs
+                .get(4..4 + length)
+                .ok_or(WireFormatError::BufferTooShort)?;
+
+            if tlv_type == TlvType::PathTrace {
+                path_trace = value
+                    .chunks_exact(8)
+                    .take(MAX_PATH_TRACE_LEN)
+                    .map(ClockIdentity::deserialize)
+                    .collect::<Result<_, _>>()?;
+            } else if tlv_type == TlvType::AlternateTimeOffsetIndicator
+                && !alternate_time_offsets.is_full()
+            {
+                // An indicator with a display name that is too long shouldn't make us ignore
+                // the whole announce
+                match AlternateTimeOffsetIndicatorTlv::deserialize(tlvs) {
+                    Ok(indicator) => alternate_time_offsets.push(indicator),
+                    Err(WireFormatError::CapacityError) => {}
+                    Err(error) => return Err(error),
+                }
+            } else if tlv_type == TlvType::OrganizationExtension
+                && SmpteSyncMetadataTlv::matches(value)
+            {
+                smpte_sync_metadata = Some(SmpteSyncMetadataTlv::deserialize(tlvs)?);
+            } else if tlv_type == TlvType::OrganizationExtension && PowerProfileTlv::matches(value)
+            {
+                power_profile = Some(PowerProfileTlv::deserialize(tlvs)?);
+            } else if tlv_type == TlvType::EnhancedAccuracyMetrics {
+                enhanced_accuracy_metrics = Some(EnhancedAccuracyMetricsTlv::deserialize(tlvs)?);
+            }
+
+            tlvs = &tlvs[4 + length..];
+        }
+
         Ok(Self {
             header,
             origin_timestamp: Timestamp::deserialize(&buffer[0..10])?,
@@ -59,6 +197,11 @@ impl AnnounceMessage {
             grandmaster_identity: ClockIdentity::deserialize(&buffer[19..27])?,
             steps_removed: u16::from_be_bytes(buffer[27..29].try_into().unwrap()),
             time_source: TimeSource::from_primitive(buffer[29]),
+            path_trace,
+            alternate_time_offsets,
+            smpte_sync_metadata,
+            power_profile,
+            enhanced_accuracy_metrics,
         })
     }
 
@@ -72,6 +215,10 @@ impl AnnounceMessage {
             frequency_traceable: self.header.frequency_tracable,
             ptp_timescale: self.header.ptp_timescale,
             time_source: self.time_source,
+            alternate_time_offsets: self.alternate_time_offsets.clone(),
+            smpte_sync_metadata: self.smpte_sync_metadata,
+            power_profile: self.power_profile,
+            enhanced_accuracy_metrics: self.enhanced_accuracy_metrics,
         }
     }
 }
@@ -79,7 +226,7 @@ impl AnnounceMessage {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::datastructures::common::ClockAccuracy;
+    use crate::datastructures::common::{ClockAccuracy, PtpText};
 
     #[test]
     fn announce_wireformat() {
@@ -108,6 +255,11 @@ mod tests {
                 ]),
                 steps_removed: 128,
                 time_source: TimeSource::Unknown(0x80),
+                path_trace: ArrayVec::new(),
+                alternate_time_offsets: ArrayVec::new(),
+                smpte_sync_metadata: None,
+                power_profile: None,
+                enhanced_accuracy_metrics: None,
             },
         )];
 
@@ -126,4 +278,102 @@ mod tests {
             assert_eq!(deserialized_data, object_representation);
         }
     }
+
+    #[test]
+    fn path_trace_wireformat() {
+        let mut byte_representation = [0; 50];
+        byte_representation[30..34].copy_from_slice(&[0x00, 0x08, 0x00, 0x10]);
+        byte_representation[34..42].copy_from_slice(&[1; 8]);
+        byte_representation[42..50].copy_from_slice(&[2; 8]);
+
+        let message =
+            AnnounceMessage::deserialize_content(Header::default(), &byte_representation).unwrap();
+        assert_eq!(
+            message.path_trace(),
+            [ClockIdentity([1; 8]), ClockIdentity([2; 8])]
+        );
+        assert_eq!(message.content_size(), 50);
+
+        let mut serialization_buffer = [0; 50];
+        message
+            .serialize_content(&mut serialization_buffer)
+            .unwrap();
+        assert_eq!(serialization_buffer, byte_representation);
+
+        // Other TLVs are skipped, truncated ones rejected
+        byte_representation[31] = 0x01;
+        let message =
+            AnnounceMessage::deserialize_content(Header::default(), &byte_representation).unwrap();
+        assert!(message.path_trace().is_empty());
+        assert!(AnnounceMessage::deserialize_content(
+            Header::default(),
+            &byte_representation[..40]
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn alternate_time_offsets_wireformat() {
+        let mut message =
+            AnnounceMessage::deserialize_content(Header::default(), &[0; 30]).unwrap();
+        for key_field in 0..=MAX_ALTERNATE_TIME_OFFSETS as u8 {
+            let indicator = AlternateTimeOffsetIndicatorTlv {
+                key_field,
+                current_offset: 3600,
+                jump_seconds: 0,
+                time_of_next_jump: 0,
+                display_name: PtpText::new("CET").unwrap(),
+            };
+            let _ = message.alternate_time_offsets.try_push(indicator);
+        }
+        message.path_trace.push(ClockIdentity([1; 8]));
+
+        let mut buffer = [0; 255];
+        message.serialize_content(&mut buffer).unwrap();
+        let deserialized = AnnounceMessage::deserialize_content(
+            Header::default(),
+            &buffer[..message.content_size()],
+        )
+        .unwrap();
+        assert_eq!(deserialized, message);
+        assert_eq!(
+            deserialized.time_properties().alternate_time_offsets(),
+            message.alternate_time_offsets.as_slice()
+        );
+
+        // Offsets are left out until the message fits
+        assert!(message.fit_alternate_time_offsets(100));
+        assert!(message.content_size() <= 100);
+        assert_eq!(message.alternate_time_offsets.len(), 2);
+        assert!(!message.fit_alternate_time_offsets(100));
+    }
+
+    #[test]
+    fn smpte_sync_metadata_wireformat() {
+        let mut message =
+            AnnounceMessage::deserialize_content(Header::default(), &[0; 30]).unwrap();
+        message.smpte_sync_metadata = Some(SmpteSyncMetadataTlv {
+            default_system_frame_rate_numerator: 25,
+            default_system_frame_rate_denominator: 1,
+            current_local_offset: 3600,
+            ..Default::default()
+        });
+        assert_eq!(message.content_size(), 82);
+
+        let mut buffer = [0; 82];
+        message.serialize_content(&mut buffer).unwrap();
+        let deserialized =
+            AnnounceMessage::deserialize_content(Header::default(), &buffer).unwrap();
+        assert_eq!(deserialized, message);
+        assert_eq!(
+            deserialized.time_properties().smpte_sync_metadata(),
+            message.smpte_sync_metadata
+        );
+
+        // Other organization extensions are skipped
+        buffer[34] = 0x00;
+        let deserialized =
+            AnnounceMessage::deserialize_content(Header::default(), &buffer).unwrap();
+        assert_eq!(deserialized.smpte_sync_metadata, None);
+    }
 }
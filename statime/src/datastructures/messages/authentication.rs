@@ -0,0 +1,108 @@
+use crate::datastructures::{common::TlvType, WireFormat, WireFormatError};
+
+/// The length of the integrity check value of HMAC-SHA256-128 (IEEE1588-2019
+/// annex P.2)
+pub const ICV_LEN: usize = 16;
+
+/// The secParamIndicator bit telling the TLV carries a sequence number
+const SEQUENCE_NO_PRESENT: u8 = 1 << 1;
+
+/// The AUTHENTICATION TLV (IEEE1588-2019 section 16.14.3)
+///
+/// Always the last TLV of an authenticated message. It names the security
+/// association and key the integrity check value is computed with, and carries
+/// the sequence number of the message to protect against replays. Delayed
+/// security processing (the disclosed key) is not supported, so the ICV is
+/// checked immediately on receipt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AuthenticationTlv {
+    /// The security parameter pointer, selecting the security association
+    pub spp: u8,
+    /// The key of the security association the ICV is computed with
+    pub key_id: u32,
+    /// The sequence number of the message within the security association
+    pub sequence_no: u64,
+    /// The integrity check value
+    pub icv: [u8; ICV_LEN],
+}
+
+impl AuthenticationTlv {
+    /// The number of bytes of the TLV before the ICV, these are covered by the
+    /// ICV like the rest of the message
+    pub(crate) const ICV_OFFSET: usize = 18;
+
+    /// Whether the TLV in `buffer` is an AUTHENTICATION TLV in the format
+    /// statime uses, only those should be deserialized as one
+    pub(crate) fn matches(buffer: &[u8]) -> bool {
+        buffer.len() == Self::ICV_OFFSET + ICV_LEN
+            && buffer[0..2] == TlvType::Authentication.to_primitive().to_be_bytes()
+            && buffer[2..4] == ((Self::ICV_OFFSET + ICV_LEN - 4) as u16).to_be_bytes()
+            && buffer[5] == SEQUENCE_NO_PRESENT
+    }
+}
+
+impl WireFormat for AuthenticationTlv {
+    fn wire_size(&self) -> usize {
+        Self::ICV_OFFSET + ICV_LEN
+    }
+
+    fn serialize(&self, buffer: &mut [u8]) -> Result<(), WireFormatError> {
+        if buffer.len() < self.wire_size() {
+            return Err(WireFormatError::BufferTooShort);
+        }
+
+        buffer[0..2].copy_from_slice(&TlvType::Authentication.to_primitive().to_be_bytes());
+        buffer[2..4].copy_from_slice(&((self.wire_size() - 4) as u16).to_be_bytes());
+        buffer[4] = self.spp;
+        buffer[5] = SEQUENCE_NO_PRESENT;
+        buffer[6..10].copy_from_slice(&self.key_id.to_be_bytes());
+        buffer[10..18].copy_from_slice(&self.sequence_no.to_be_bytes());
+        buffer[18..34].copy_from_slice(&self.icv);
+
+        Ok(())
+    }
+
+    fn deserialize(buffer: &[u8]) -> Result<Self, WireFormatError> {
+        let buffer = buffer
+            .get(0..Self::ICV_OFFSET + ICV_LEN)
+            .ok_or(WireFormatError::BufferTooShort)?;
+
+        Ok(Self {
+            spp: buffer[4],
+            key_id: u32::from_be_bytes(buffer[6..10].try_into().unwrap()),
+            sequence_no: u64::from_be_bytes(buffer[10..18].try_into().unwrap()),
+            icv: buffer[18..34].try_into().unwrap(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn authentication_wireformat() {
+        let byte_representation = [
+            0x80, 0x09, 0x00, 0x1e, 0x02, 0x02, 0x00, 0x00, 0x00, 0x07, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x01, 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a,
+            0x0b, 0x0c, 0x0d, 0x0e, 0x0f, 0x10,
+        ];
+        let object_representation = AuthenticationTlv {
+            spp: 2,
+            key_id: 7,
+            sequence_no: 256,
+            icv: [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16],
+        };
+
+        assert!(AuthenticationTlv::matches(&byte_representation));
+
+        let mut serialization_buffer = [0; 34];
+        object_representation
+            .serialize(&mut serialization_buffer)
+            .unwrap();
+        assert_eq!(serialization_buffer, byte_representation);
+
+        let deserialized_data = AuthenticationTlv::deserialize(&byte_representation).unwrap();
+        assert_eq!(deserialized_data, object_representation);
+    }
+}
@@ -0,0 +1,203 @@
+use core::ops::Add;
+
+use crate::datastructures::{common::TlvType, WireFormat, WireFormatError};
+
+/// An inaccuracy estimate: the maximum inaccuracy in nanoseconds and the
+/// variance of the inaccuracy in nanoseconds squared
+///
+/// Adding the estimates of independent sources of inaccuracy sums both their
+/// maxima and their variances.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Inaccuracy {
+    // Kept as the bits of the Float64 values on the wire, so the TLVs stay
+    // comparable
+    max: u64,
+    variance: u64,
+}
+
+impl Inaccuracy {
+    /// An inaccuracy of at most `max` nanoseconds, with a `variance` in
+    /// nanoseconds squared
+    pub fn new(max: f64, variance: f64) -> Self {
+        Self {
+            max: max.to_bits(),
+            variance: variance.to_bits(),
+        }
+    }
+
+    /// The maximum inaccuracy in nanoseconds
+    pub fn max(&self) -> f64 {
+        f64::from_bits(self.max)
+    }
+
+    /// The variance of the inaccuracy in nanoseconds squared
+    pub fn variance(&self) -> f64 {
+        f64::from_bits(self.variance)
+    }
+
+    fn serialize(&self, buffer: &mut [u8]) {
+        buffer[0..8].copy_from_slice(&self.max.to_be_bytes());
+        buffer[8..16].copy_from_slice(&self.variance.to_be_bytes());
+    }
+
+    fn deserialize(buffer: &[u8]) -> Self {
+        Self {
+            max: u64::from_be_bytes(buffer[0..8].try_into().unwrap()),
+            variance: u64::from_be_bytes(buffer[8..16].try_into().unwrap()),
+        }
+    }
+}
+
+impl Add for Inaccuracy {
+    type Output = Inaccuracy;
+
+    fn add(self, rhs: Inaccuracy) -> Self::Output {
+        Self::new(self.max() + rhs.max(), self.variance() + rhs.variance())
+    }
+}
+
+/// The ENHANCED_ACCURACY_METRICS TLV (IEEE1588-2019 section 16.12)
+///
+/// Carried by announce messages, it tells slaves how far off the time they
+/// receive may be. The grandmaster gives the inaccuracy of its reference, and
+/// it and every boundary clock on the way add their own inaccuracies, so the
+/// TLV received by a slave holds the inaccuracy budget of the whole path. See
+/// [`inaccuracy`](Self::inaccuracy) for the total.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct EnhancedAccuracyMetricsTlv {
+    /// The number of boundary clocks between the grandmaster and the receiver
+    /// of the TLV
+    pub bc_hop_count: u8,
+    /// The inaccuracy of the time of the grandmaster, relative to its
+    /// reference
+    pub gm_inaccuracy: Inaccuracy,
+    /// The inaccuracy of the time offset of the clocks while they are
+    /// settling, for example after a change of master
+    pub transient_inaccuracy: Inaccuracy,
+    /// The inaccuracy of the time offset of the clocks due to frequency
+    /// wander and noise, between synchronizations
+    pub dynamic_inaccuracy: Inaccuracy,
+    /// The constant inaccuracy of the time offset of the clocks, such as
+    /// uncompensated timestamp latencies
+    pub static_instance_inaccuracy: Inaccuracy,
+    /// The constant inaccuracy of the links, such as uncompensated delay
+    /// asymmetry
+    pub static_medium_inaccuracy: Inaccuracy,
+}
+
+impl EnhancedAccuracyMetricsTlv {
+    /// The total inaccuracy of the time at the receiver of the TLV
+    pub fn inaccuracy(&self) -> Inaccuracy {
+        self.gm_inaccuracy
+            + self.transient_inaccuracy
+            + self.dynamic_inaccuracy
+            + self.static_instance_inaccuracy
+            + self.static_medium_inaccuracy
+    }
+
+    /// The TLV as forwarded by a boundary clock that adds the inaccuracies of
+    /// `own`, except for the inaccuracy of the grandmaster
+    pub(crate) fn forwarded(self, own: &EnhancedAccuracyMetricsTlv) -> Self {
+        Self {
+            bc_hop_count: self.bc_hop_count.saturating_add(1),
+            gm_inaccuracy: self.gm_inaccuracy,
+            transient_inaccuracy: self.transient_inaccuracy + own.transient_inaccuracy,
+            dynamic_inaccuracy: self.dynamic_inaccuracy + own.dynamic_inaccuracy,
+            static_instance_inaccuracy: self.static_instance_inaccuracy
+                + own.static_instance_inaccuracy,
+            static_medium_inaccuracy: self.static_medium_inaccuracy + own.static_medium_inaccuracy,
+        }
+    }
+}
+
+impl WireFormat for EnhancedAccuracyMetricsTlv {
+    fn wire_size(&self) -> usize {
+        86
+    }
+
+    fn serialize(&self, buffer: &mut [u8]) -> Result<(), WireFormatError> {
+        if buffer.len() < self.wire_size() {
+            return Err(WireFormatError::BufferTooShort);
+        }
+
+        buffer[0..2].copy_from_slice(
+            &TlvType::EnhancedAccuracyMetrics
+                .to_primitive()
+                .to_be_bytes(),
+        );
+        buffer[2..4].copy_from_slice(&82u16.to_be_bytes());
+        buffer[4] = self.bc_hop_count;
+        buffer[5] = 0;
+        self.gm_inaccuracy.serialize(&mut buffer[6..22]);
+        self.transient_inaccuracy.serialize(&mut buffer[22..38]);
+        self.dynamic_inaccuracy.serialize(&mut buffer[38..54]);
+        self.static_instance_inaccuracy
+            .serialize(&mut buffer[54..70]);
+        self.static_medium_inaccuracy.serialize(&mut buffer[70..86]);
+
+        Ok(())
+    }
+
+    fn deserialize(buffer: &[u8]) -> Result<Self, WireFormatError> {
+        let buffer = buffer.get(0..86).ok_or(WireFormatError::BufferTooShort)?;
+
+        Ok(Self {
+            bc_hop_count: buffer[4],
+            gm_inaccuracy: Inaccuracy::deserialize(&buffer[6..22]),
+            transient_inaccuracy: Inaccuracy::deserialize(&buffer[22..38]),
+            dynamic_inaccuracy: Inaccuracy::deserialize(&buffer[38..54]),
+            static_instance_inaccuracy: Inaccuracy::deserialize(&buffer[54..70]),
+            static_medium_inaccuracy: Inaccuracy::deserialize(&buffer[70..86]),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn enhanced_accuracy_metrics_wireformat() {
+        let tlv = EnhancedAccuracyMetricsTlv {
+            bc_hop_count: 2,
+            gm_inaccuracy: Inaccuracy::new(100.0, 25.0),
+            transient_inaccuracy: Inaccuracy::new(10.0, 4.0),
+            dynamic_inaccuracy: Inaccuracy::new(20.0, 9.0),
+            static_instance_inaccuracy: Inaccuracy::new(30.0, 0.0),
+            static_medium_inaccuracy: Inaccuracy::new(40.0, 1.0),
+        };
+
+        let mut buffer = [0; 86];
+        tlv.serialize(&mut buffer).unwrap();
+        assert_eq!(buffer[0..6], [0x40, 0x01, 0x00, 0x52, 0x02, 0x00]);
+        assert_eq!(buffer[6..14], 100.0f64.to_be_bytes());
+        assert_eq!(buffer[78..86], 1.0f64.to_be_bytes());
+
+        assert_eq!(
+            EnhancedAccuracyMetricsTlv::deserialize(&buffer).unwrap(),
+            tlv
+        );
+    }
+
+    #[test]
+    fn enhanced_accuracy_metrics_forwarded() {
+        let received = EnhancedAccuracyMetricsTlv {
+            bc_hop_count: 1,
+            gm_inaccuracy: Inaccuracy::new(100.0, 25.0),
+            dynamic_inaccuracy: Inaccuracy::new(20.0, 9.0),
+            ..Default::default()
+        };
+        let own = EnhancedAccuracyMetricsTlv {
+            gm_inaccuracy: Inaccuracy::new(1000.0, 1000.0),
+            dynamic_inaccuracy: Inaccuracy::new(5.0, 7.0),
+            static_instance_inaccuracy: Inaccuracy::new(8.0, 0.0),
+            ..Default::default()
+        };
+
+        let forwarded = received.forwarded(&own);
+        assert_eq!(forwarded.bc_hop_count, 2);
+        assert_eq!(forwarded.gm_inaccuracy, received.gm_inaccuracy);
+        assert_eq!(forwarded.dynamic_inaccuracy, Inaccuracy::new(25.0, 16.0));
+        assert_eq!(forwarded.inaccuracy(), Inaccuracy::new(133.0, 41.0));
+    }
+}
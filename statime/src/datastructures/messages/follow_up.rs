@@ -1,23 +1,31 @@
 use getset::CopyGetters;
 
-use super::Header;
-use crate::datastructures::{common::Timestamp, WireFormat, WireFormatError};
+use super::{FollowUpInformationTlv, Header};
+use crate::datastructures::{
+    common::{Timestamp, TlvType},
+    WireFormat, WireFormatError,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, CopyGetters)]
 #[getset(get_copy = "pub")]
 pub struct FollowUpMessage {
     pub(crate) header: Header,
     pub(crate) precise_origin_timestamp: Timestamp,
+    /// Only present on follow ups of a gPTP domain
+    pub(crate) follow_up_information: Option<FollowUpInformationTlv>,
 }
 
 impl FollowUpMessage {
     pub fn content_size(&self) -> usize {
-        10
+        10 + self.follow_up_information.map_or(0, |tlv| tlv.wire_size())
     }
 
     pub fn serialize_content(&self, buffer: &mut [u8]) -> Result<(), WireFormatError> {
         self.precise_origin_timestamp
             .serialize(&mut buffer[0..10])?;
+        if let Some(follow_up_information) = self.follow_up_information {
+            follow_up_information.serialize(&mut buffer[10..])?;
+        }
 
         Ok(())
     }
@@ -26,9 +34,28 @@ impl FollowUpMessage {
         let slice = buffer.get(0..10).ok_or(WireFormatError::BufferTooShort)?;
         let precise_origin_timestamp = Timestamp::deserialize(slice)?;
 
+        // Of the TLVs following the follow up, only the follow up information is used
+        let mut follow_up_information = None;
+        let mut tlvs = &buffer[10..];
+        while tlvs.len() >= 4 {
+            let tlv_type = TlvType::from_primitive(u16::from_be_bytes([tlvs[0], tlvs[1]]));
+            let length = u16::from_be_bytes([tlvs[2], tlvs[3]]) as usize;
+            let value = tlvs
+                .get(4..4 + length)
+                .ok_or(WireFormatError::BufferTooShort)?;
+
+            if tlv_type == TlvType::OrganizationExtension && FollowUpInformationTlv::matches(value)
+            {
+                follow_up_information = Some(FollowUpInformationTlv::deserialize(tlvs)?);
+            }
+
+            tlvs = &tlvs[4 + length..];
+        }
+
         Ok(Self {
             header,
             precise_origin_timestamp,
+            follow_up_information,
         })
     }
 }
@@ -48,6 +75,7 @@ mod tests {
                         seconds: 1169232218,
                         nanos: 174389936,
                     },
+                    follow_up_information: None,
                 },
             ),
             (
@@ -58,6 +86,7 @@ mod tests {
                         seconds: 0x0000_0000_0002,
                         nanos: 0x0000_0001,
                     },
+                    follow_up_information: None,
                 },
             ),
         ];
@@ -77,4 +106,37 @@ mod tests {
             assert_eq!(deserialized_data, object_representation);
         }
     }
+
+    #[test]
+    fn follow_up_information_wireformat() {
+        let message = FollowUpMessage {
+            header: Header::default(),
+            precise_origin_timestamp: Timestamp {
+                seconds: 2,
+                nanos: 1,
+            },
+            follow_up_information: Some(FollowUpInformationTlv {
+                cumulative_scaled_rate_offset: -1,
+                ..Default::default()
+            }),
+        };
+
+        let mut buffer = [0; 42];
+        message.serialize_content(&mut buffer).unwrap();
+        assert_eq!(message.content_size(), 42);
+        assert_eq!(buffer[10..14], [0x00, 0x03, 0x00, 0x1c]);
+        assert_eq!(
+            FollowUpMessage::deserialize_content(Header::default(), &buffer).unwrap(),
+            message
+        );
+
+        // Other organization extensions are ignored
+        buffer[16] = 0x01;
+        assert_eq!(
+            FollowUpMessage::deserialize_content(Header::default(), &buffer)
+                .unwrap()
+                .follow_up_information,
+            None
+        );
+    }
 }
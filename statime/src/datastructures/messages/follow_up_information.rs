@@ -0,0 +1,118 @@
+use crate::datastructures::{common::TlvType, WireFormat, WireFormatError};
+
+/// The organization id (IEEE 802.1) and subtype of the follow up information
+/// TLV
+const ORGANIZATION_ID: [u8; 3] = [0x00, 0x80, 0xc2];
+const ORGANIZATION_SUB_TYPE: [u8; 3] = [0x00, 0x00, 0x01];
+
+/// The follow up information TLV of IEEE 802.1AS-2020 (section 11.4.4.3)
+///
+/// It is carried by every Follow_Up message of a gPTP domain, and tells the
+/// slave how the frequency of the grandmaster relates to that of the sender.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FollowUpInformationTlv {
+    /// The ratio of the frequency of the grandmaster to the frequency of the
+    /// sender, minus one, times 2^41
+    pub cumulative_scaled_rate_offset: i32,
+    /// Identifies the time base of the grandmaster, changes whenever the
+    /// grandmaster changes phase or frequency
+    pub gm_time_base_indicator: u16,
+    /// The most recent phase change of the grandmaster, in nanoseconds times
+    /// 2^16. Only the lower 96 bits are used.
+    pub last_gm_phase_change: i128,
+    /// The most recent frequency change of the grandmaster, as a fraction
+    /// times 2^41
+    pub scaled_last_gm_freq_change: i32,
+}
+
+impl FollowUpInformationTlv {
+    /// The ratio of the frequency of the grandmaster to the frequency of the
+    /// sender
+    pub fn cumulative_rate_ratio(&self) -> f64 {
+        1.0 + self.cumulative_scaled_rate_offset as f64 / (1u64 << 41) as f64
+    }
+
+    /// Whether an organization extension TLV with this `value` is a follow up
+    /// information TLV, only those should be deserialized as one
+    pub(crate) fn matches(value: &[u8]) -> bool {
+        value.get(0..3) == Some(&ORGANIZATION_ID) && value.get(3..6) == Some(&ORGANIZATION_SUB_TYPE)
+    }
+}
+
+impl WireFormat for FollowUpInformationTlv {
+    fn wire_size(&self) -> usize {
+        32
+    }
+
+    fn serialize(&self, buffer: &mut [u8]) -> Result<(), WireFormatError> {
+        if buffer.len() < self.wire_size() {
+            return Err(WireFormatError::BufferTooShort);
+        }
+
+        buffer[0..2].copy_from_slice(&TlvType::OrganizationExtension.to_primitive().to_be_bytes());
+        buffer[2..4].copy_from_slice(&28u16.to_be_bytes());
+        buffer[4..7].copy_from_slice(&ORGANIZATION_ID);
+        buffer[7..10].copy_from_slice(&ORGANIZATION_SUB_TYPE);
+        buffer[10..14].copy_from_slice(&self.cumulative_scaled_rate_offset.to_be_bytes());
+        buffer[14..16].copy_from_slice(&self.gm_time_base_indicator.to_be_bytes());
+        buffer[16..28].copy_from_slice(&self.last_gm_phase_change.to_be_bytes()[4..16]);
+        buffer[28..32].copy_from_slice(&self.scaled_last_gm_freq_change.to_be_bytes());
+
+        Ok(())
+    }
+
+    fn deserialize(buffer: &[u8]) -> Result<Self, WireFormatError> {
+        let buffer = buffer.get(0..32).ok_or(WireFormatError::BufferTooShort)?;
+
+        // Sign extend the 96 bit phase change
+        let mut last_gm_phase_change = [if buffer[16] & 0x80 != 0 { 0xff } else { 0 }; 16];
+        last_gm_phase_change[4..16].copy_from_slice(&buffer[16..28]);
+
+        Ok(Self {
+            cumulative_scaled_rate_offset: i32::from_be_bytes(buffer[10..14].try_into().unwrap()),
+            gm_time_base_indicator: u16::from_be_bytes([buffer[14], buffer[15]]),
+            last_gm_phase_change: i128::from_be_bytes(last_gm_phase_change),
+            scaled_last_gm_freq_change: i32::from_be_bytes(buffer[28..32].try_into().unwrap()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn follow_up_information_wireformat() {
+        let byte_representation = [
+            0x00, 0x03, 0x00, 0x1c, 0x00, 0x80, 0xc2, 0x00, 0x00, 0x01, 0x00, 0x00, 0x02, 0x00,
+            0x00, 0x05, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00,
+        ];
+        let object_representation = FollowUpInformationTlv {
+            cumulative_scaled_rate_offset: 512,
+            gm_time_base_indicator: 5,
+            last_gm_phase_change: -2 << 16,
+            scaled_last_gm_freq_change: 0,
+        };
+
+        // Test the serialization output
+        let mut serialization_buffer = [0; 32];
+        object_representation
+            .serialize(&mut serialization_buffer)
+            .unwrap();
+        assert_eq!(serialization_buffer, byte_representation);
+
+        // Test the deserialization output
+        let deserialized_data = FollowUpInformationTlv::deserialize(&byte_representation).unwrap();
+        assert_eq!(deserialized_data, object_representation);
+    }
+
+    #[test]
+    fn cumulative_rate_ratio() {
+        let tlv = FollowUpInformationTlv {
+            cumulative_scaled_rate_offset: 1 << 21,
+            ..Default::default()
+        };
+        assert_eq!(tlv.cumulative_rate_ratio(), 1.0 + 1.0 / (1u64 << 20) as f64);
+    }
+}
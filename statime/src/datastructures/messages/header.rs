@@ -37,6 +37,19 @@ pub struct DeserializedHeader {
     pub message_length: u16,
 }
 
+/// The location of the correction field in a serialized message
+pub(crate) const CORRECTION_FIELD: core::ops::Range<usize> = 8..16;
+
+/// Add `correction` to the correction field of the serialized `message` in
+/// place, leaving the rest of the message, its TLVs included, as it is
+pub(crate) fn add_to_correction_field(message: &mut [u8], correction: TimeInterval) {
+    if let Some(field) = message.get_mut(CORRECTION_FIELD) {
+        let corrected =
+            i64::from_be_bytes(field.try_into().unwrap()).wrapping_add(correction.to_bits());
+        field.copy_from_slice(&corrected.to_be_bytes());
+    }
+}
+
 impl Header {
     pub(super) fn new() -> Self {
         Self {
@@ -1,51 +1,47 @@
-use super::Header;
-use crate::datastructures::{
-    common::{PortIdentity, Tlv},
-    WireFormat, WireFormatError,
-};
+use super::{Header, ManagementTlv};
+use crate::datastructures::{common::PortIdentity, WireFormat, WireFormatError};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ManagementMessage {
-    pub(super) header: Header,
-    pub(super) target_port_identity: PortIdentity,
-    pub(super) starting_boundary_hops: u8,
-    pub(super) boundary_hops: u8,
-    pub(super) action: ManagementAction,
-    pub(super) management_tlv: Tlv,
+    pub(crate) header: Header,
+    pub(crate) target_port_identity: PortIdentity,
+    pub(crate) starting_boundary_hops: u8,
+    pub(crate) boundary_hops: u8,
+    pub(crate) action: ManagementAction,
+    pub(crate) management_tlv: ManagementTlv,
 }
 
 impl ManagementMessage {
     pub fn content_size(&self) -> usize {
-        10
+        14 + self.management_tlv.wire_size()
     }
 
-    pub fn serialize_content(
-        &self,
-        buffer: &mut [u8],
-    ) -> Result<(), crate::datastructures::WireFormatError> {
+    pub fn serialize_content(&self, buffer: &mut [u8]) -> Result<(), WireFormatError> {
+        if buffer.len() < self.content_size() {
+            return Err(WireFormatError::BufferTooShort);
+        }
+
         self.target_port_identity.serialize(&mut buffer[0..10])?;
-        buffer[11] = self.starting_boundary_hops;
-        buffer[12] = self.boundary_hops;
-        buffer[13] = self.action.to_primitive();
-        Tlv::serialize(&self.management_tlv, &mut buffer[14..])?;
+        buffer[10] = self.starting_boundary_hops;
+        buffer[11] = self.boundary_hops;
+        buffer[12] = self.action.to_primitive();
+        buffer[13] = 0;
+        self.management_tlv.serialize(&mut buffer[14..])?;
 
         Ok(())
     }
 
-    pub fn deserialize_content(
-        header: Header,
-        buffer: &[u8],
-    ) -> Result<Self, crate::datastructures::WireFormatError> {
+    pub fn deserialize_content(header: Header, buffer: &[u8]) -> Result<Self, WireFormatError> {
         if buffer.len() < 14 {
             return Err(WireFormatError::BufferTooShort);
         }
         Ok(Self {
             header,
             target_port_identity: PortIdentity::deserialize(&buffer[0..10])?,
-            starting_boundary_hops: buffer[11],
-            boundary_hops: buffer[12],
-            action: ManagementAction::from_primitive(buffer[13]),
-            management_tlv: Tlv::deserialize(&buffer[13..])?,
+            starting_boundary_hops: buffer[10],
+            boundary_hops: buffer[11],
+            action: ManagementAction::from_primitive(buffer[12] & 0x0f),
+            management_tlv: ManagementTlv::deserialize(&buffer[14..])?,
         })
     }
 }
@@ -85,3 +81,42 @@ impl ManagementAction {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datastructures::{common::ClockIdentity, messages::ManagementId};
+
+    #[test]
+    fn management_wireformat() {
+        let bytes = [
+            0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x03, 0x02, 0x01, 0x00,
+            0x00, 0x01, 0x00, 0x04, 0x20, 0x07, 0x05, 0x00,
+        ];
+        let message = ManagementMessage {
+            header: Header::default(),
+            target_port_identity: PortIdentity {
+                clock_identity: ClockIdentity([0xff; 8]),
+                port_number: 0xffff,
+            },
+            starting_boundary_hops: 3,
+            boundary_hops: 2,
+            action: ManagementAction::SET,
+            management_tlv: ManagementTlv::Domain(5),
+        };
+
+        assert_eq!(message.content_size(), bytes.len());
+
+        let mut buffer = [0; 22];
+        message.serialize_content(&mut buffer).unwrap();
+        assert_eq!(buffer, bytes);
+
+        let deserialized =
+            ManagementMessage::deserialize_content(Header::default(), &bytes).unwrap();
+        assert_eq!(deserialized, message);
+        assert_eq!(
+            deserialized.management_tlv.management_id(),
+            ManagementId::Domain
+        );
+    }
+}
@@ -0,0 +1,1973 @@
+use arrayvec::ArrayVec;
+
+use crate::datastructures::{
+    common::{
+        ClockAccuracy, ClockIdentity, ClockQuality, PortAddress, PortIdentity, PtpText,
+        TimeInterval, TimeSource, Timestamp, TlvType,
+    },
+    WireFormat, WireFormatError,
+};
+
+/// The maximum number of entries in the tables and lists carried by
+/// management TLVs
+pub const MAX_MANAGEMENT_TABLE_SIZE: usize = 16;
+
+/// The TLV carried by a management message (IEEE1588-2019 section 15.5)
+///
+/// GET requests and most COMMAND messages carry no data, and are represented
+/// by [`ManagementTlv::Empty`]. Every other variant corresponds to the data
+/// field of one management id of IEEE1588-2019 table 59, except for
+/// [`ManagementTlv::ErrorStatus`], which replaces the management TLV in
+/// responses to requests that could not be handled. TLVs with reserved or
+/// implementation specific management ids are kept as their raw data field in
+/// [`ManagementTlv::Other`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[allow(clippy::large_enum_variant)]
+pub enum ManagementTlv {
+    Empty(ManagementId),
+    ErrorStatus(ManagementErrorStatusTlv),
+    ClockDescription(ClockDescriptionTlv),
+    UserDescription(PtpText),
+    Initialize(u16),
+    FaultLog(FaultLogTlv),
+    DefaultDataSet(DefaultDataSetTlv),
+    CurrentDataSet(CurrentDataSetTlv),
+    ParentDataSet(ParentDataSetTlv),
+    TimePropertiesDataSet(TimePropertiesDataSetTlv),
+    PortDataSet(PortDataSetTlv),
+    Priority1(u8),
+    Priority2(u8),
+    Domain(u8),
+    SlaveOnly(bool),
+    LogAnnounceInterval(i8),
+    AnnounceReceiptTimeout(u8),
+    LogSyncInterval(i8),
+    VersionNumber(u8),
+    Time(Timestamp),
+    ClockAccuracy(ClockAccuracy),
+    UtcProperties(UtcPropertiesTlv),
+    TraceabilityProperties(TraceabilityPropertiesTlv),
+    TimescaleProperties(TimescalePropertiesTlv),
+    UnicastNegotiationEnable(bool),
+    PathTraceList(ArrayVec<ClockIdentity, MAX_MANAGEMENT_TABLE_SIZE>),
+    PathTraceEnable(bool),
+    GrandmasterClusterTable(PortAddressTableTlv),
+    UnicastMasterTable(PortAddressTableTlv),
+    UnicastMasterMaxTableSize(u16),
+    AcceptableMasterTable(ArrayVec<AcceptableMaster, MAX_MANAGEMENT_TABLE_SIZE>),
+    AcceptableMasterTableEnabled(bool),
+    AcceptableMasterMaxTableSize(u16),
+    AlternateMaster(AlternateMasterTlv),
+    AlternateTimeOffsetEnable(AlternateTimeOffsetEnableTlv),
+    AlternateTimeOffsetName(AlternateTimeOffsetNameTlv),
+    AlternateTimeOffsetMaxKey(u8),
+    AlternateTimeOffsetProperties(AlternateTimeOffsetPropertiesTlv),
+    ExternalPortConfigurationEnabled(bool),
+    MasterOnly(bool),
+    HoldoverUpgradeEnable(bool),
+    ExtPortConfigPortDataSet(u8),
+    TransparentClockDefaultDataSet(TransparentClockDefaultDataSetTlv),
+    TransparentClockPortDataSet(TransparentClockPortDataSetTlv),
+    PrimaryDomain(u8),
+    DelayMechanism(u8),
+    LogMinPdelayReqInterval(i8),
+    Other(ManagementId, ArrayVec<u8, { ManagementTlv::CAPACITY }>),
+}
+
+impl ManagementTlv {
+    /// The maximum size of the data field of a TLV we don't have a typed
+    /// representation for
+    const CAPACITY: usize = 128;
+
+    pub fn management_id(&self) -> ManagementId {
+        match self {
+            Self::Empty(id) | Self::Other(id, _) => *id,
+            Self::ErrorStatus(error) => error.management_id,
+            Self::ClockDescription(_) => ManagementId::ClockDescription,
+            Self::UserDescription(_) => ManagementId::UserDescription,
+            Self::Initialize(_) => ManagementId::Initialize,
+            Self::FaultLog(_) => ManagementId::FaultLog,
+            Self::DefaultDataSet(_) => ManagementId::DefaultDataSet,
+            Self::CurrentDataSet(_) => ManagementId::CurrentDataSet,
+            Self::ParentDataSet(_) => ManagementId::ParentDataSet,
+            Self::TimePropertiesDataSet(_) => ManagementId::TimePropertiesDataSet,
+            Self::PortDataSet(_) => ManagementId::PortDataSet,
+            Self::Priority1(_) => ManagementId::Priority1,
+            Self::Priority2(_) => ManagementId::Priority2,
+            Self::Domain(_) => ManagementId::Domain,
+            Self::SlaveOnly(_) => ManagementId::SlaveOnly,
+            Self::LogAnnounceInterval(_) => ManagementId::LogAnnounceInterval,
+            Self::AnnounceReceiptTimeout(_) => ManagementId::AnnounceReceiptTimeout,
+            Self::LogSyncInterval(_) => ManagementId::LogSyncInterval,
+            Self::VersionNumber(_) => ManagementId::VersionNumber,
+            Self::Time(_) => ManagementId::Time,
+            Self::ClockAccuracy(_) => ManagementId::ClockAccuracy,
+            Self::UtcProperties(_) => ManagementId::UtcProperties,
+            Self::TraceabilityProperties(_) => ManagementId::TraceabilityProperties,
+            Self::TimescaleProperties(_) => ManagementId::TimescaleProperties,
+            Self::UnicastNegotiationEnable(_) => ManagementId::UnicastNegotiationEnable,
+            Self::PathTraceList(_) => ManagementId::PathTraceList,
+            Self::PathTraceEnable(_) => ManagementId::PathTraceEnable,
+            Self::GrandmasterClusterTable(_) => ManagementId::GrandmasterClusterTable,
+            Self::UnicastMasterTable(_) => ManagementId::UnicastMasterTable,
+            Self::UnicastMasterMaxTableSize(_) => ManagementId::UnicastMasterMaxTableSize,
+            Self::AcceptableMasterTable(_) => ManagementId::AcceptableMasterTable,
+            Self::AcceptableMasterTableEnabled(_) => ManagementId::AcceptableMasterTableEnabled,
+            Self::AcceptableMasterMaxTableSize(_) => ManagementId::AcceptableMasterMaxTableSize,
+            Self::AlternateMaster(_) => ManagementId::AlternateMaster,
+            Self::AlternateTimeOffsetEnable(_) => ManagementId::AlternateTimeOffsetEnable,
+            Self::AlternateTimeOffsetName(_) => ManagementId::AlternateTimeOffsetName,
+            Self::AlternateTimeOffsetMaxKey(_) => ManagementId::AlternateTimeOffsetMaxKey,
+            Self::AlternateTimeOffsetProperties(_) => ManagementId::AlternateTimeOffsetProperties,
+            Self::ExternalPortConfigurationEnabled(_) => {
+                ManagementId::ExternalPortConfigurationEnabled
+            }
+            Self::MasterOnly(_) => ManagementId::MasterOnly,
+            Self::HoldoverUpgradeEnable(_) => ManagementId::HoldoverUpgradeEnable,
+            Self::ExtPortConfigPortDataSet(_) => ManagementId::ExtPortConfigPortDataSet,
+            Self::TransparentClockDefaultDataSet(_) => ManagementId::TransparentClockDefaultDataSet,
+            Self::TransparentClockPortDataSet(_) => ManagementId::TransparentClockPortDataSet,
+            Self::PrimaryDomain(_) => ManagementId::PrimaryDomain,
+            Self::DelayMechanism(_) => ManagementId::DelayMechanism,
+            Self::LogMinPdelayReqInterval(_) => ManagementId::LogMinPdelayReqInterval,
+        }
+    }
+
+    /// The size of the data field, including padding to an even length
+    fn data_size(&self) -> usize {
+        let size = match self {
+            Self::Empty(_) => 0,
+            Self::ErrorStatus(error) => error.wire_size(),
+            Self::ClockDescription(data) => data.wire_size(),
+            Self::UserDescription(text) => text.wire_size(),
+            Self::FaultLog(data) => data.wire_size(),
+            Self::DefaultDataSet(data) => data.wire_size(),
+            Self::CurrentDataSet(data) => data.wire_size(),
+            Self::ParentDataSet(data) => data.wire_size(),
+            Self::TimePropertiesDataSet(data) => data.wire_size(),
+            Self::PortDataSet(data) => data.wire_size(),
+            Self::Time(_) => 10,
+            Self::UtcProperties(data) => data.wire_size(),
+            Self::TraceabilityProperties(data) => data.wire_size(),
+            Self::TimescaleProperties(data) => data.wire_size(),
+            Self::PathTraceList(path) => 8 * path.len(),
+            Self::GrandmasterClusterTable(table) => table.wire_size(1),
+            Self::UnicastMasterTable(table) => table.wire_size(2),
+            Self::AcceptableMasterTable(table) => 2 + 11 * table.len(),
+            Self::AlternateMaster(data) => data.wire_size(),
+            Self::AlternateTimeOffsetEnable(data) => data.wire_size(),
+            Self::AlternateTimeOffsetName(data) => data.wire_size(),
+            Self::AlternateTimeOffsetProperties(data) => data.wire_size(),
+            Self::TransparentClockDefaultDataSet(data) => data.wire_size(),
+            Self::TransparentClockPortDataSet(data) => data.wire_size(),
+            Self::Initialize(_)
+            | Self::Priority1(_)
+            | Self::Priority2(_)
+            | Self::Domain(_)
+            | Self::SlaveOnly(_)
+            | Self::LogAnnounceInterval(_)
+            | Self::AnnounceReceiptTimeout(_)
+            | Self::LogSyncInterval(_)
+            | Self::VersionNumber(_)
+            | Self::ClockAccuracy(_)
+            | Self::UnicastNegotiationEnable(_)
+            | Self::PathTraceEnable(_)
+            | Self::UnicastMasterMaxTableSize(_)
+            | Self::AcceptableMasterTableEnabled(_)
+            | Self::AcceptableMasterMaxTableSize(_)
+            | Self::AlternateTimeOffsetMaxKey(_)
+            | Self::ExternalPortConfigurationEnabled(_)
+            | Self::MasterOnly(_)
+            | Self::HoldoverUpgradeEnable(_)
+            | Self::ExtPortConfigPortDataSet(_)
+            | Self::PrimaryDomain(_)
+            | Self::DelayMechanism(_)
+            | Self::LogMinPdelayReqInterval(_) => 2,
+            Self::Other(_, data) => data.len(),
+        };
+
+        size + size % 2
+    }
+
+    fn serialize_data(&self, buffer: &mut [u8]) -> Result<(), WireFormatError> {
+        // Takes care of reserved fields and padding
+        buffer[..self.data_size()].fill(0);
+
+        match self {
+            Self::Empty(_) => {}
+            Self::ErrorStatus(error) => error.serialize(buffer)?,
+            Self::ClockDescription(data) => data.serialize(buffer)?,
+            Self::UserDescription(text) => text.serialize(buffer)?,
+            Self::Initialize(key) => buffer[0..2].copy_from_slice(&key.to_be_bytes()),
+            Self::FaultLog(data) => data.serialize(buffer)?,
+            Self::DefaultDataSet(data) => data.serialize(buffer)?,
+            Self::CurrentDataSet(data) => data.serialize(buffer)?,
+            Self::ParentDataSet(data) => data.serialize(buffer)?,
+            Self::TimePropertiesDataSet(data) => data.serialize(buffer)?,
+            Self::PortDataSet(data) => data.serialize(buffer)?,
+            Self::Priority1(value)
+            | Self::Priority2(value)
+            | Self::Domain(value)
+            | Self::AnnounceReceiptTimeout(value)
+            | Self::AlternateTimeOffsetMaxKey(value)
+            | Self::ExtPortConfigPortDataSet(value)
+            | Self::PrimaryDomain(value)
+            | Self::DelayMechanism(value) => buffer[0] = *value,
+            Self::LogAnnounceInterval(value)
+            | Self::LogSyncInterval(value)
+            | Self::LogMinPdelayReqInterval(value) => buffer[0] = *value as u8,
+            Self::SlaveOnly(flag)
+            | Self::UnicastNegotiationEnable(flag)
+            | Self::PathTraceEnable(flag)
+            | Self::AcceptableMasterTableEnabled(flag)
+            | Self::ExternalPortConfigurationEnabled(flag)
+            | Self::MasterOnly(flag)
+            | Self::HoldoverUpgradeEnable(flag) => buffer[0] = *flag as u8,
+            Self::VersionNumber(version) => buffer[0] = version & 0x0f,
+            Self::Time(time) => time.serialize(&mut buffer[0..10])?,
+            Self::ClockAccuracy(accuracy) => buffer[0] = accuracy.to_primitive(),
+            Self::UtcProperties(data) => data.serialize(buffer)?,
+            Self::TraceabilityProperties(data) => data.serialize(buffer)?,
+            Self::TimescaleProperties(data) => data.serialize(buffer)?,
+            Self::PathTraceList(path) => {
+                for (identity, chunk) in path.iter().zip(buffer.chunks_exact_mut(8)) {
+                    identity.serialize(chunk)?;
+                }
+            }
+            Self::GrandmasterClusterTable(table) => table.serialize(buffer, 1)?,
+            Self::UnicastMasterTable(table) => table.serialize(buffer, 2)?,
+            Self::UnicastMasterMaxTableSize(size) | Self::AcceptableMasterMaxTableSize(size) => {
+                buffer[0..2].copy_from_slice(&size.to_be_bytes())
+            }
+            Self::AcceptableMasterTable(table) => {
+                buffer[0..2].copy_from_slice(&(table.len() as i16).to_be_bytes());
+                for (entry, chunk) in table.iter().zip(buffer[2..].chunks_exact_mut(11)) {
+                    entry.serialize(chunk)?;
+                }
+            }
+            Self::AlternateMaster(data) => data.serialize(buffer)?,
+            Self::AlternateTimeOffsetEnable(data) => data.serialize(buffer)?,
+            Self::AlternateTimeOffsetName(data) => data.serialize(buffer)?,
+            Self::AlternateTimeOffsetProperties(data) => data.serialize(buffer)?,
+            Self::TransparentClockDefaultDataSet(data) => data.serialize(buffer)?,
+            Self::TransparentClockPortDataSet(data) => data.serialize(buffer)?,
+            Self::Other(_, data) => buffer[..data.len()].copy_from_slice(data),
+        }
+
+        Ok(())
+    }
+
+    fn deserialize_data(id: ManagementId, buffer: &[u8]) -> Result<Self, WireFormatError> {
+        if buffer.is_empty() {
+            return Ok(Self::Empty(id));
+        }
+
+        // All fixed size data fields are at least two bytes
+        if buffer.len() < 2 {
+            return Err(WireFormatError::BufferTooShort);
+        }
+
+        Ok(match id {
+            ManagementId::ClockDescription => {
+                Self::ClockDescription(ClockDescriptionTlv::deserialize(buffer)?)
+            }
+            ManagementId::UserDescription => Self::UserDescription(PtpText::deserialize(buffer)?),
+            ManagementId::Initialize => {
+                Self::Initialize(u16::from_be_bytes([buffer[0], buffer[1]]))
+            }
+            ManagementId::FaultLog => Self::FaultLog(FaultLogTlv::deserialize(buffer)?),
+            ManagementId::DefaultDataSet => {
+                Self::DefaultDataSet(DefaultDataSetTlv::deserialize(buffer)?)
+            }
+            ManagementId::CurrentDataSet => {
+                Self::CurrentDataSet(CurrentDataSetTlv::deserialize(buffer)?)
+            }
+            ManagementId::ParentDataSet => {
+                Self::ParentDataSet(ParentDataSetTlv::deserialize(buffer)?)
+            }
+            ManagementId::TimePropertiesDataSet => {
+                Self::TimePropertiesDataSet(TimePropertiesDataSetTlv::deserialize(buffer)?)
+            }
+            ManagementId::PortDataSet => Self::PortDataSet(PortDataSetTlv::deserialize(buffer)?),
+            ManagementId::Priority1 => Self::Priority1(buffer[0]),
+            ManagementId::Priority2 => Self::Priority2(buffer[0]),
+            ManagementId::Domain => Self::Domain(buffer[0]),
+            ManagementId::SlaveOnly => Self::SlaveOnly(buffer[0] & 1 > 0),
+            ManagementId::LogAnnounceInterval => Self::LogAnnounceInterval(buffer[0] as i8),
+            ManagementId::AnnounceReceiptTimeout => Self::AnnounceReceiptTimeout(buffer[0]),
+            ManagementId::LogSyncInterval => Self::LogSyncInterval(buffer[0] as i8),
+            ManagementId::VersionNumber => Self::VersionNumber(buffer[0] & 0x0f),
+            ManagementId::Time => Self::Time(Timestamp::deserialize(
+                buffer.get(0..10).ok_or(WireFormatError::BufferTooShort)?,
+            )?),
+            ManagementId::ClockAccuracy => {
+                Self::ClockAccuracy(ClockAccuracy::from_primitive(buffer[0]))
+            }
+            ManagementId::UtcProperties => {
+                Self::UtcProperties(UtcPropertiesTlv::deserialize(buffer)?)
+            }
+            ManagementId::TraceabilityProperties => {
+                Self::TraceabilityProperties(TraceabilityPropertiesTlv::deserialize(buffer)?)
+            }
+            ManagementId::TimescaleProperties => {
+                Self::TimescaleProperties(TimescalePropertiesTlv::deserialize(buffer)?)
+            }
+            ManagementId::UnicastNegotiationEnable => {
+                Self::UnicastNegotiationEnable(buffer[0] & 1 > 0)
+            }
+            ManagementId::PathTraceList => {
+                let mut path = ArrayVec::new();
+                for chunk in buffer.chunks_exact(8) {
+                    path.try_push(ClockIdentity::deserialize(chunk)?)?;
+                }
+                Self::PathTraceList(path)
+            }
+            ManagementId::PathTraceEnable => Self::PathTraceEnable(buffer[0] & 1 > 0),
+            ManagementId::GrandmasterClusterTable => {
+                Self::GrandmasterClusterTable(PortAddressTableTlv::deserialize(buffer, 1)?)
+            }
+            ManagementId::UnicastMasterTable => {
+                Self::UnicastMasterTable(PortAddressTableTlv::deserialize(buffer, 2)?)
+            }
+            ManagementId::UnicastMasterMaxTableSize => {
+                Self::UnicastMasterMaxTableSize(u16::from_be_bytes([buffer[0], buffer[1]]))
+            }
+            ManagementId::AcceptableMasterTable => {
+                let table_size = i16::from_be_bytes([buffer[0], buffer[1]]).max(0) as usize;
+                let entries = buffer
+                    .get(2..2 + 11 * table_size)
+                    .ok_or(WireFormatError::BufferTooShort)?;
+
+                let mut table = ArrayVec::new();
+                for chunk in entries.chunks_exact(11) {
+                    table.try_push(AcceptableMaster::deserialize(chunk)?)?;
+                }
+                Self::AcceptableMasterTable(table)
+            }
+            ManagementId::AcceptableMasterTableEnabled => {
+                Self::AcceptableMasterTableEnabled(buffer[0] & 1 > 0)
+            }
+            ManagementId::AcceptableMasterMaxTableSize => {
+                Self::AcceptableMasterMaxTableSize(u16::from_be_bytes([buffer[0], buffer[1]]))
+            }
+            ManagementId::AlternateMaster => {
+                Self::AlternateMaster(AlternateMasterTlv::deserialize(buffer)?)
+            }
+            ManagementId::AlternateTimeOffsetEnable => {
+                Self::AlternateTimeOffsetEnable(AlternateTimeOffsetEnableTlv::deserialize(buffer)?)
+            }
+            ManagementId::AlternateTimeOffsetName => {
+                Self::AlternateTimeOffsetName(AlternateTimeOffsetNameTlv::deserialize(buffer)?)
+            }
+            ManagementId::AlternateTimeOffsetMaxKey => Self::AlternateTimeOffsetMaxKey(buffer[0]),
+            ManagementId::AlternateTimeOffsetProperties => Self::AlternateTimeOffsetProperties(
+                AlternateTimeOffsetPropertiesTlv::deserialize(buffer)?,
+            ),
+            ManagementId::ExternalPortConfigurationEnabled => {
+                Self::ExternalPortConfigurationEnabled(buffer[0] & 1 > 0)
+            }
+            ManagementId::MasterOnly => Self::MasterOnly(buffer[0] & 1 > 0),
+            ManagementId::HoldoverUpgradeEnable => Self::HoldoverUpgradeEnable(buffer[0] & 1 > 0),
+            ManagementId::ExtPortConfigPortDataSet => Self::ExtPortConfigPortDataSet(buffer[0]),
+            ManagementId::TransparentClockDefaultDataSet => Self::TransparentClockDefaultDataSet(
+                TransparentClockDefaultDataSetTlv::deserialize(buffer)?,
+            ),
+            ManagementId::TransparentClockPortDataSet => Self::TransparentClockPortDataSet(
+                TransparentClockPortDataSetTlv::deserialize(buffer)?,
+            ),
+            ManagementId::PrimaryDomain => Self::PrimaryDomain(buffer[0]),
+            ManagementId::DelayMechanism => Self::DelayMechanism(buffer[0]),
+            ManagementId::LogMinPdelayReqInterval => Self::LogMinPdelayReqInterval(buffer[0] as i8),
+            ManagementId::NullPtpManagement
+            | ManagementId::SaveInNonVolatileStorage
+            | ManagementId::ResetNonVolatileStorage
+            | ManagementId::FaultLogReset
+            | ManagementId::EnablePort
+            | ManagementId::DisablePort
+            | ManagementId::Reserved(_)
+            | ManagementId::Experimental(_) => {
+                let mut data = ArrayVec::new();
+                data.try_extend_from_slice(buffer)?;
+                Self::Other(id, data)
+            }
+        })
+    }
+}
+
+impl WireFormat for ManagementTlv {
+    fn wire_size(&self) -> usize {
+        match self {
+            // The error status TLV has no management id field of its own
+            Self::ErrorStatus(_) => 4 + self.data_size(),
+            _ => 6 + self.data_size(),
+        }
+    }
+
+    fn serialize(&self, buffer: &mut [u8]) -> Result<(), WireFormatError> {
+        if buffer.len() < self.wire_size() {
+            return Err(WireFormatError::BufferTooShort);
+        }
+
+        let (tlv_type, data) = match self {
+            Self::ErrorStatus(_) => (TlvType::ManagementErrorStatus, &mut buffer[4..]),
+            _ => {
+                buffer[4..6].copy_from_slice(&self.management_id().to_primitive().to_be_bytes());
+                (TlvType::Management, &mut buffer[6..])
+            }
+        };
+        self.serialize_data(data)?;
+
+        buffer[0..2].copy_from_slice(&tlv_type.to_primitive().to_be_bytes());
+        buffer[2..4].copy_from_slice(&((self.wire_size() - 4) as u16).to_be_bytes());
+
+        Ok(())
+    }
+
+    fn deserialize(buffer: &[u8]) -> Result<Self, WireFormatError> {
+        if buffer.len() < 6 {
+            return Err(WireFormatError::BufferTooShort);
+        }
+
+        let tlv_type = TlvType::from_primitive(u16::from_be_bytes([buffer[0], buffer[1]]));
+        let length = u16::from_be_bytes([buffer[2], buffer[3]]) as usize;
+        let value = buffer
+            .get(4..4 + length)
+            .ok_or(WireFormatError::BufferTooShort)?;
+
+        match tlv_type {
+            TlvType::Management => {
+                if value.len() < 2 {
+                    return Err(WireFormatError::BufferTooShort);
+                }
+
+                let id = ManagementId::from_primitive(u16::from_be_bytes([value[0], value[1]]));
+                Self::deserialize_data(id, &value[2..])
+            }
+            TlvType::ManagementErrorStatus => Ok(Self::ErrorStatus(
+                ManagementErrorStatusTlv::deserialize(value)?,
+            )),
+            _ => Err(WireFormatError::EnumConversionError),
+        }
+    }
+}
+
+/// A MANAGEMENT_ERROR_STATUS TLV (IEEE1588-2019 section 15.5.4)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManagementErrorStatusTlv {
+    pub management_error_id: ManagementErrorId,
+    pub management_id: ManagementId,
+    pub display_data: PtpText,
+}
+
+impl WireFormat for ManagementErrorStatusTlv {
+    fn wire_size(&self) -> usize {
+        // The display data is optional
+        match self.display_data.is_empty() {
+            true => 8,
+            false => 8 + self.display_data.wire_size(),
+        }
+    }
+
+    fn serialize(&self, buffer: &mut [u8]) -> Result<(), WireFormatError> {
+        buffer[0..2].copy_from_slice(&self.management_error_id.to_primitive().to_be_bytes());
+        buffer[2..4].copy_from_slice(&self.management_id.to_primitive().to_be_bytes());
+        buffer[4..8].fill(0);
+        if !self.display_data.is_empty() {
+            self.display_data.serialize(&mut buffer[8..])?;
+        }
+
+        Ok(())
+    }
+
+    fn deserialize(buffer: &[u8]) -> Result<Self, WireFormatError> {
+        if buffer.len() < 8 {
+            return Err(WireFormatError::BufferTooShort);
+        }
+
+        Ok(Self {
+            management_error_id: ManagementErrorId::from_primitive(u16::from_be_bytes([
+                buffer[0], buffer[1],
+            ])),
+            management_id: ManagementId::from_primitive(u16::from_be_bytes([buffer[2], buffer[3]])),
+            display_data: match buffer.len() {
+                8 => PtpText::default(),
+                _ => PtpText::deserialize(&buffer[8..])?,
+            },
+        })
+    }
+}
+
+/// See 15.5.4.1.4 / Table 109
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ManagementErrorId {
+    Reserved(u16),
+    ImplementationSpecific(u16),
+    ResponseTooBig,
+    NoSuchId,
+    WrongLength,
+    WrongValue,
+    NotSetable,
+    NotSupported,
+    Unpopulated,
+    GeneralError,
+}
+
+impl ManagementErrorId {
+    pub fn to_primitive(self) -> u16 {
+        match self {
+            Self::Reserved(value) | Self::ImplementationSpecific(value) => value,
+            Self::ResponseTooBig => 0x0001,
+            Self::NoSuchId => 0x0002,
+            Self::WrongLength => 0x0003,
+            Self::WrongValue => 0x0004,
+            Self::NotSetable => 0x0005,
+            Self::NotSupported => 0x0006,
+            Self::Unpopulated => 0x0007,
+            Self::GeneralError => 0xfffe,
+        }
+    }
+
+    pub fn from_primitive(value: u16) -> Self {
+        match value {
+            0x0001 => Self::ResponseTooBig,
+            0x0002 => Self::NoSuchId,
+            0x0003 => Self::WrongLength,
+            0x0004 => Self::WrongValue,
+            0x0005 => Self::NotSetable,
+            0x0006 => Self::NotSupported,
+            0x0007 => Self::Unpopulated,
+            0xfffe => Self::GeneralError,
+            0xc000..=0xdfff => Self::ImplementationSpecific(value),
+            _ => Self::Reserved(value),
+        }
+    }
+}
+
+/// The data of a CLOCK_DESCRIPTION management TLV (IEEE1588-2019 section
+/// 15.5.3.1.2)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClockDescriptionTlv {
+    pub clock_type: u16,
+    pub physical_layer_protocol: PtpText,
+    pub physical_address: ArrayVec<u8, 16>,
+    pub protocol_address: PortAddress,
+    pub manufacturer_identity: [u8; 3],
+    pub product_description: PtpText,
+    pub revision_data: PtpText,
+    pub user_description: PtpText,
+    pub profile_identity: [u8; 6],
+}
+
+impl WireFormat for ClockDescriptionTlv {
+    fn wire_size(&self) -> usize {
+        2 + self.physical_layer_protocol.wire_size()
+            + 2
+            + self.physical_address.len()
+            + self.protocol_address.wire_size()
+            + 4
+            + self.product_description.wire_size()
+            + self.revision_data.wire_size()
+            + self.user_description.wire_size()
+            + 6
+    }
+
+    fn serialize(&self, buffer: &mut [u8]) -> Result<(), WireFormatError> {
+        buffer[0..2].copy_from_slice(&self.clock_type.to_be_bytes());
+        let mut offset = 2;
+
+        self.physical_layer_protocol
+            .serialize(&mut buffer[offset..])?;
+        offset += self.physical_layer_protocol.wire_size();
+
+        buffer[offset..][..2].copy_from_slice(&(self.physical_address.len() as u16).to_be_bytes());
+        buffer[offset + 2..][..self.physical_address.len()].copy_from_slice(&self.physical_address);
+        offset += 2 + self.physical_address.len();
+
+        self.protocol_address.serialize(&mut buffer[offset..])?;
+        offset += self.protocol_address.wire_size();
+
+        buffer[offset..][..3].copy_from_slice(&self.manufacturer_identity);
+        buffer[offset + 3] = 0;
+        offset += 4;
+
+        for text in [
+            &self.product_description,
+            &self.revision_data,
+            &self.user_description,
+        ] {
+            text.serialize(&mut buffer[offset..])?;
+            offset += text.wire_size();
+        }
+
+        buffer[offset..][..6].copy_from_slice(&self.profile_identity);
+
+        Ok(())
+    }
+
+    fn deserialize(buffer: &[u8]) -> Result<Self, WireFormatError> {
+        let field = |offset: usize, length: usize| {
+            buffer
+                .get(offset..offset + length)
+                .ok_or(WireFormatError::BufferTooShort)
+        };
+        let rest = |offset: usize| buffer.get(offset..).ok_or(WireFormatError::BufferTooShort);
+
+        let clock_type = u16::from_be_bytes(field(0, 2)?.try_into().unwrap());
+        let mut offset = 2;
+
+        let physical_layer_protocol = PtpText::deserialize(rest(offset)?)?;
+        offset += physical_layer_protocol.wire_size();
+
+        let physical_address_length =
+            u16::from_be_bytes(field(offset, 2)?.try_into().unwrap()) as usize;
+        let mut physical_address = ArrayVec::new();
+        physical_address.try_extend_from_slice(field(offset + 2, physical_address_length)?)?;
+        offset += 2 + physical_address_length;
+
+        let protocol_address = deserialize_port_address(rest(offset)?)?;
+        offset += protocol_address.wire_size();
+
+        let manufacturer_identity = field(offset, 3)?.try_into().unwrap();
+        offset += 4;
+
+        let product_description = PtpText::deserialize(rest(offset)?)?;
+        offset += product_description.wire_size();
+        let revision_data = PtpText::deserialize(rest(offset)?)?;
+        offset += revision_data.wire_size();
+        let user_description = PtpText::deserialize(rest(offset)?)?;
+        offset += user_description.wire_size();
+
+        Ok(Self {
+            clock_type,
+            physical_layer_protocol,
+            physical_address,
+            protocol_address,
+            manufacturer_identity,
+            product_description,
+            revision_data,
+            user_description,
+            profile_identity: field(offset, 6)?.try_into().unwrap(),
+        })
+    }
+}
+
+/// Deserialize a port address, checking the length of the buffer and address
+/// first
+fn deserialize_port_address(buffer: &[u8]) -> Result<PortAddress, WireFormatError> {
+    if buffer.len() < 4 {
+        return Err(WireFormatError::BufferTooShort);
+    }
+
+    let length = u16::from_be_bytes([buffer[2], buffer[3]]) as usize;
+    if length > 16 {
+        return Err(WireFormatError::CapacityError);
+    }
+    if buffer.len() < 4 + length {
+        return Err(WireFormatError::BufferTooShort);
+    }
+
+    PortAddress::deserialize(buffer)
+}
+
+/// The data of a FAULT_LOG management TLV (IEEE1588-2019 section 15.5.3.2.1)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FaultLogTlv {
+    pub fault_records: ArrayVec<FaultRecord, { FaultLogTlv::CAPACITY }>,
+}
+
+impl FaultLogTlv {
+    /// The maximum number of fault records in a single TLV. Records carry
+    /// several texts, so few of them fit into a single message anyway.
+    pub const CAPACITY: usize = 2;
+}
+
+impl WireFormat for FaultLogTlv {
+    fn wire_size(&self) -> usize {
+        2 + self
+            .fault_records
+            .iter()
+            .map(|record| record.wire_size())
+            .sum::<usize>()
+    }
+
+    fn serialize(&self, buffer: &mut [u8]) -> Result<(), WireFormatError> {
+        buffer[0..2].copy_from_slice(&(self.fault_records.len() as u16).to_be_bytes());
+
+        let mut offset = 2;
+        for record in &self.fault_records {
+            record.serialize(&mut buffer[offset..])?;
+            offset += record.wire_size();
+        }
+
+        Ok(())
+    }
+
+    fn deserialize(buffer: &[u8]) -> Result<Self, WireFormatError> {
+        if buffer.len() < 2 {
+            return Err(WireFormatError::BufferTooShort);
+        }
+
+        let number_of_fault_records = u16::from_be_bytes([buffer[0], buffer[1]]);
+
+        let mut fault_records = ArrayVec::new();
+        let mut offset = 2;
+        for _ in 0..number_of_fault_records {
+            let record = FaultRecord::deserialize(&buffer[offset..])?;
+            offset += record.wire_size();
+            fault_records.try_push(record)?;
+        }
+
+        Ok(Self { fault_records })
+    }
+}
+
+/// A single record of the fault log (IEEE1588-2019 section 5.3.10)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FaultRecord {
+    pub fault_time: Timestamp,
+    pub severity_code: u8,
+    pub fault_name: PtpText,
+    pub fault_value: PtpText,
+    pub fault_description: PtpText,
+}
+
+impl WireFormat for FaultRecord {
+    fn wire_size(&self) -> usize {
+        13 + self.fault_name.wire_size()
+            + self.fault_value.wire_size()
+            + self.fault_description.wire_size()
+    }
+
+    fn serialize(&self, buffer: &mut [u8]) -> Result<(), WireFormatError> {
+        // The record length does not include the length field itself
+        buffer[0..2].copy_from_slice(&((self.wire_size() - 2) as u16).to_be_bytes());
+        self.fault_time.serialize(&mut buffer[2..12])?;
+        buffer[12] = self.severity_code;
+
+        let mut offset = 13;
+        for text in [&self.fault_name, &self.fault_value, &self.fault_description] {
+            text.serialize(&mut buffer[offset..])?;
+            offset += text.wire_size();
+        }
+
+        Ok(())
+    }
+
+    fn deserialize(buffer: &[u8]) -> Result<Self, WireFormatError> {
+        if buffer.len() < 2 {
+            return Err(WireFormatError::BufferTooShort);
+        }
+
+        let length = u16::from_be_bytes([buffer[0], buffer[1]]) as usize;
+        let buffer = buffer
+            .get(..2 + length)
+            .ok_or(WireFormatError::BufferTooShort)?;
+        if buffer.len() < 16 {
+            return Err(WireFormatError::BufferTooShort);
+        }
+
+        let fault_name = PtpText::deserialize(&buffer[13..])?;
+        let fault_value = PtpText::deserialize(&buffer[13 + fault_name.wire_size()..])?;
+        let fault_description =
+            PtpText::deserialize(&buffer[13 + fault_name.wire_size() + fault_value.wire_size()..])?;
+
+        Ok(Self {
+            fault_time: Timestamp::deserialize(&buffer[2..12])?,
+            severity_code: buffer[12],
+            fault_name,
+            fault_value,
+            fault_description,
+        })
+    }
+}
+/// The data of a DEFAULT_DATA_SET management TLV (IEEE1588-2019 section
+/// 15.5.3.3.1)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DefaultDataSetTlv {
+    pub two_step_flag: bool,
+    pub slave_only: bool,
+    pub number_ports: u16,
+    pub priority_1: u8,
+    pub clock_quality: ClockQuality,
+    pub priority_2: u8,
+    pub clock_identity: ClockIdentity,
+    pub domain_number: u8,
+}
+
+impl WireFormat for DefaultDataSetTlv {
+    fn wire_size(&self) -> usize {
+        20
+    }
+
+    fn serialize(&self, buffer: &mut [u8]) -> Result<(), WireFormatError> {
+        buffer[0] = self.two_step_flag as u8 | (self.slave_only as u8) << 1;
+        buffer[1] = 0;
+        buffer[2..4].copy_from_slice(&self.number_ports.to_be_bytes());
+        buffer[4] = self.priority_1;
+        self.clock_quality.serialize(&mut buffer[5..9])?;
+        buffer[9] = self.priority_2;
+        self.clock_identity.serialize(&mut buffer[10..18])?;
+        buffer[18] = self.domain_number;
+        buffer[19] = 0;
+
+        Ok(())
+    }
+
+    fn deserialize(buffer: &[u8]) -> Result<Self, WireFormatError> {
+        if buffer.len() < 20 {
+            return Err(WireFormatError::BufferTooShort);
+        }
+
+        Ok(Self {
+            two_step_flag: buffer[0] & (1 << 0) > 0,
+            slave_only: buffer[0] & (1 << 1) > 0,
+            number_ports: u16::from_be_bytes([buffer[2], buffer[3]]),
+            priority_1: buffer[4],
+            clock_quality: ClockQuality::deserialize(&buffer[5..9])?,
+            priority_2: buffer[9],
+            clock_identity: ClockIdentity::deserialize(&buffer[10..18])?,
+            domain_number: buffer[18],
+        })
+    }
+}
+
+/// The data of a CURRENT_DATA_SET management TLV (IEEE1588-2019 section
+/// 15.5.3.4.1)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CurrentDataSetTlv {
+    pub steps_removed: u16,
+    pub offset_from_master: TimeInterval,
+    pub mean_path_delay: TimeInterval,
+}
+
+impl WireFormat for CurrentDataSetTlv {
+    fn wire_size(&self) -> usize {
+        18
+    }
+
+    fn serialize(&self, buffer: &mut [u8]) -> Result<(), WireFormatError> {
+        buffer[0..2].copy_from_slice(&self.steps_removed.to_be_bytes());
+        self.offset_from_master.serialize(&mut buffer[2..10])?;
+        self.mean_path_delay.serialize(&mut buffer[10..18])?;
+
+        Ok(())
+    }
+
+    fn deserialize(buffer: &[u8]) -> Result<Self, WireFormatError> {
+        if buffer.len() < 18 {
+            return Err(WireFormatError::BufferTooShort);
+        }
+
+        Ok(Self {
+            steps_removed: u16::from_be_bytes([buffer[0], buffer[1]]),
+            offset_from_master: TimeInterval::deserialize(&buffer[2..10])?,
+            mean_path_delay: TimeInterval::deserialize(&buffer[10..18])?,
+        })
+    }
+}
+
+/// The data of a PARENT_DATA_SET management TLV (IEEE1588-2019 section
+/// 15.5.3.5.1)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParentDataSetTlv {
+    pub parent_port_identity: PortIdentity,
+    pub parent_stats: bool,
+    pub observed_parent_offset_scaled_log_variance: u16,
+    pub observed_parent_clock_phase_change_rate: u32,
+    pub grandmaster_priority_1: u8,
+    pub grandmaster_clock_quality: ClockQuality,
+    pub grandmaster_priority_2: u8,
+    pub grandmaster_identity: ClockIdentity,
+}
+
+impl WireFormat for ParentDataSetTlv {
+    fn wire_size(&self) -> usize {
+        32
+    }
+
+    fn serialize(&self, buffer: &mut [u8]) -> Result<(), WireFormatError> {
+        self.parent_port_identity.serialize(&mut buffer[0..10])?;
+        buffer[10] = self.parent_stats as u8;
+        buffer[11] = 0;
+        buffer[12..14].copy_from_slice(
+            &self
+                .observed_parent_offset_scaled_log_variance
+                .to_be_bytes(),
+        );
+        buffer[14..18].copy_from_slice(&self.observed_parent_clock_phase_change_rate.to_be_bytes());
+        buffer[18] = self.grandmaster_priority_1;
+        self.grandmaster_clock_quality
+            .serialize(&mut buffer[19..23])?;
+        buffer[23] = self.grandmaster_priority_2;
+        self.grandmaster_identity.serialize(&mut buffer[24..32])?;
+
+        Ok(())
+    }
+
+    fn deserialize(buffer: &[u8]) -> Result<Self, WireFormatError> {
+        if buffer.len() < 32 {
+            return Err(WireFormatError::BufferTooShort);
+        }
+
+        Ok(Self {
+            parent_port_identity: PortIdentity::deserialize(&buffer[0..10])?,
+            parent_stats: buffer[10] & (1 << 0) > 0,
+            observed_parent_offset_scaled_log_variance: u16::from_be_bytes([
+                buffer[12], buffer[13],
+            ]),
+            observed_parent_clock_phase_change_rate: u32::from_be_bytes(
+                buffer[14..18].try_into().unwrap(),
+            ),
+            grandmaster_priority_1: buffer[18],
+            grandmaster_clock_quality: ClockQuality::deserialize(&buffer[19..23])?,
+            grandmaster_priority_2: buffer[23],
+            grandmaster_identity: ClockIdentity::deserialize(&buffer[24..32])?,
+        })
+    }
+}
+
+/// The data of a TIME_PROPERTIES_DATA_SET management TLV (IEEE1588-2019
+/// section 15.5.3.6.1)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimePropertiesDataSetTlv {
+    pub current_utc_offset: i16,
+    pub leap61: bool,
+    pub leap59: bool,
+    pub current_utc_offset_valid: bool,
+    pub ptp_timescale: bool,
+    pub time_traceable: bool,
+    pub frequency_traceable: bool,
+    pub time_source: TimeSource,
+}
+
+impl WireFormat for TimePropertiesDataSetTlv {
+    fn wire_size(&self) -> usize {
+        4
+    }
+
+    fn serialize(&self, buffer: &mut [u8]) -> Result<(), WireFormatError> {
+        buffer[0..2].copy_from_slice(&self.current_utc_offset.to_be_bytes());
+        buffer[2] = self.leap61 as u8
+            | (self.leap59 as u8) << 1
+            | (self.current_utc_offset_valid as u8) << 2
+            | (self.ptp_timescale as u8) << 3
+            | (self.time_traceable as u8) << 4
+            | (self.frequency_traceable as u8) << 5;
+        buffer[3] = self.time_source.to_primitive();
+
+        Ok(())
+    }
+
+    fn deserialize(buffer: &[u8]) -> Result<Self, WireFormatError> {
+        if buffer.len() < 4 {
+            return Err(WireFormatError::BufferTooShort);
+        }
+
+        Ok(Self {
+            current_utc_offset: i16::from_be_bytes([buffer[0], buffer[1]]),
+            leap61: buffer[2] & (1 << 0) > 0,
+            leap59: buffer[2] & (1 << 1) > 0,
+            current_utc_offset_valid: buffer[2] & (1 << 2) > 0,
+            ptp_timescale: buffer[2] & (1 << 3) > 0,
+            time_traceable: buffer[2] & (1 << 4) > 0,
+            frequency_traceable: buffer[2] & (1 << 5) > 0,
+            time_source: TimeSource::from_primitive(buffer[3]),
+        })
+    }
+}
+
+/// The data of a PORT_DATA_SET management TLV (IEEE1588-2019 section
+/// 15.5.3.7.1)
+///
+/// The `port_state` and `delay_mechanism` fields hold the values of
+/// IEEE1588-2019 tables 20 and 21. The `delay_asymmetry` member of the port
+/// dataset follows the other fields, when present. Implementations that don't
+/// know it leave it out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PortDataSetTlv {
+    pub port_identity: PortIdentity,
+    pub port_state: u8,
+    pub log_min_delay_req_interval: i8,
+    pub peer_mean_path_delay: TimeInterval,
+    pub log_announce_interval: i8,
+    pub announce_receipt_timeout: u8,
+    pub log_sync_interval: i8,
+    pub delay_mechanism: u8,
+    pub log_min_pdelay_req_interval: i8,
+    pub version_number: u8,
+    pub delay_asymmetry: Option<TimeInterval>,
+}
+
+impl WireFormat for PortDataSetTlv {
+    fn wire_size(&self) -> usize {
+        match self.delay_asymmetry {
+            Some(_) => 34,
+            None => 26,
+        }
+    }
+
+    fn serialize(&self, buffer: &mut [u8]) -> Result<(), WireFormatError> {
+        if buffer.len() < self.wire_size() {
+            return Err(WireFormatError::BufferTooShort);
+        }
+
+        self.port_identity.serialize(&mut buffer[0..10])?;
+        buffer[10] = self.port_state;
+        buffer[11] = self.log_min_delay_req_interval as u8;
+        self.peer_mean_path_delay.serialize(&mut buffer[12..20])?;
+        buffer[20] = self.log_announce_interval as u8;
+        buffer[21] = self.announce_receipt_timeout;
+        buffer[22] = self.log_sync_interval as u8;
+        buffer[23] = self.delay_mechanism;
+        buffer[24] = self.log_min_pdelay_req_interval as u8;
+        buffer[25] = self.version_number & 0x0f;
+        if let Some(delay_asymmetry) = self.delay_asymmetry {
+            delay_asymmetry.serialize(&mut buffer[26..34])?;
+        }
+
+        Ok(())
+    }
+
+    fn deserialize(buffer: &[u8]) -> Result<Self, WireFormatError> {
+        if buffer.len() < 26 {
+            return Err(WireFormatError::BufferTooShort);
+        }
+
+        Ok(Self {
+            port_identity: PortIdentity::deserialize(&buffer[0..10])?,
+            port_state: buffer[10],
+            log_min_delay_req_interval: buffer[11] as i8,
+            peer_mean_path_delay: TimeInterval::deserialize(&buffer[12..20])?,
+            log_announce_interval: buffer[20] as i8,
+            announce_receipt_timeout: buffer[21],
+            log_sync_interval: buffer[22] as i8,
+            delay_mechanism: buffer[23],
+            log_min_pdelay_req_interval: buffer[24] as i8,
+            version_number: buffer[25] & 0x0f,
+            delay_asymmetry: buffer
+                .get(26..34)
+                .map(TimeInterval::deserialize)
+                .transpose()?,
+        })
+    }
+}
+
+/// The data of a UTC_PROPERTIES management TLV (IEEE1588-2019 section
+/// 15.5.3.6.5)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UtcPropertiesTlv {
+    pub current_utc_offset: i16,
+    pub leap61: bool,
+    pub leap59: bool,
+    pub current_utc_offset_valid: bool,
+}
+
+impl WireFormat for UtcPropertiesTlv {
+    fn wire_size(&self) -> usize {
+        4
+    }
+
+    fn serialize(&self, buffer: &mut [u8]) -> Result<(), WireFormatError> {
+        buffer[0..2].copy_from_slice(&self.current_utc_offset.to_be_bytes());
+        buffer[2] = self.leap61 as u8
+            | (self.leap59 as u8) << 1
+            | (self.current_utc_offset_valid as u8) << 2;
+        buffer[3] = 0;
+
+        Ok(())
+    }
+
+    fn deserialize(buffer: &[u8]) -> Result<Self, WireFormatError> {
+        if buffer.len() < 4 {
+            return Err(WireFormatError::BufferTooShort);
+        }
+
+        Ok(Self {
+            current_utc_offset: i16::from_be_bytes([buffer[0], buffer[1]]),
+            leap61: buffer[2] & (1 << 0) > 0,
+            leap59: buffer[2] & (1 << 1) > 0,
+            current_utc_offset_valid: buffer[2] & (1 << 2) > 0,
+        })
+    }
+}
+
+/// The data of a TRACEABILITY_PROPERTIES management TLV (IEEE1588-2019
+/// section 15.5.3.6.6)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TraceabilityPropertiesTlv {
+    pub time_traceable: bool,
+    pub frequency_traceable: bool,
+}
+
+impl WireFormat for TraceabilityPropertiesTlv {
+    fn wire_size(&self) -> usize {
+        2
+    }
+
+    fn serialize(&self, buffer: &mut [u8]) -> Result<(), WireFormatError> {
+        buffer[0] = (self.time_traceable as u8) << 4 | (self.frequency_traceable as u8) << 5;
+        buffer[1] = 0;
+
+        Ok(())
+    }
+
+    fn deserialize(buffer: &[u8]) -> Result<Self, WireFormatError> {
+        if buffer.len() < 2 {
+            return Err(WireFormatError::BufferTooShort);
+        }
+
+        Ok(Self {
+            time_traceable: buffer[0] & (1 << 4) > 0,
+            frequency_traceable: buffer[0] & (1 << 5) > 0,
+        })
+    }
+}
+
+/// The data of a TIMESCALE_PROPERTIES management TLV (IEEE1588-2019 section
+/// 15.5.3.6.7)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimescalePropertiesTlv {
+    pub ptp_timescale: bool,
+    pub time_source: TimeSource,
+}
+
+impl WireFormat for TimescalePropertiesTlv {
+    fn wire_size(&self) -> usize {
+        2
+    }
+
+    fn serialize(&self, buffer: &mut [u8]) -> Result<(), WireFormatError> {
+        buffer[0] = (self.ptp_timescale as u8) << 3;
+        buffer[1] = self.time_source.to_primitive();
+
+        Ok(())
+    }
+
+    fn deserialize(buffer: &[u8]) -> Result<Self, WireFormatError> {
+        if buffer.len() < 2 {
+            return Err(WireFormatError::BufferTooShort);
+        }
+
+        Ok(Self {
+            ptp_timescale: buffer[0] & (1 << 3) > 0,
+            time_source: TimeSource::from_primitive(buffer[1]),
+        })
+    }
+}
+
+/// The data of the GRANDMASTER_CLUSTER_TABLE and UNICAST_MASTER_TABLE
+/// management TLVs (IEEE1588-2019 sections 15.5.3.6.4 and 15.5.3.7.2)
+///
+/// The two only differ in the width of their table size field.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PortAddressTableTlv {
+    pub log_query_interval: i8,
+    pub port_addresses: ArrayVec<PortAddress, MAX_MANAGEMENT_TABLE_SIZE>,
+}
+
+impl PortAddressTableTlv {
+    fn wire_size(&self, size_width: usize) -> usize {
+        1 + size_width
+            + self
+                .port_addresses
+                .iter()
+                .map(|address| address.wire_size())
+                .sum::<usize>()
+    }
+
+    fn serialize(&self, buffer: &mut [u8], size_width: usize) -> Result<(), WireFormatError> {
+        buffer[0] = self.log_query_interval as u8;
+        let size = (self.port_addresses.len() as u16).to_be_bytes();
+        buffer[1..][..size_width].copy_from_slice(&size[2 - size_width..]);
+
+        let mut offset = 1 + size_width;
+        for address in &self.port_addresses {
+            address.serialize(&mut buffer[offset..])?;
+            offset += address.wire_size();
+        }
+
+        Ok(())
+    }
+
+    fn deserialize(buffer: &[u8], size_width: usize) -> Result<Self, WireFormatError> {
+        if buffer.len() < 1 + size_width {
+            return Err(WireFormatError::BufferTooShort);
+        }
+
+        let table_size = buffer[1..][..size_width]
+            .iter()
+            .fold(0usize, |size, byte| size << 8 | *byte as usize);
+
+        let mut port_addresses = ArrayVec::new();
+        let mut offset = 1 + size_width;
+        for _ in 0..table_size {
+            let address = deserialize_port_address(&buffer[offset..])?;
+            offset += address.wire_size();
+            port_addresses.try_push(address)?;
+        }
+
+        Ok(Self {
+            log_query_interval: buffer[0] as i8,
+            port_addresses,
+        })
+    }
+}
+
+/// An entry of the acceptable master table (IEEE1588-2019 section 17.6.3.4)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AcceptableMaster {
+    pub acceptable_port_identity: PortIdentity,
+    pub alternate_priority_1: u8,
+}
+
+impl WireFormat for AcceptableMaster {
+    fn wire_size(&self) -> usize {
+        11
+    }
+
+    fn serialize(&self, buffer: &mut [u8]) -> Result<(), WireFormatError> {
+        self.acceptable_port_identity
+            .serialize(&mut buffer[0..10])?;
+        buffer[10] = self.alternate_priority_1;
+
+        Ok(())
+    }
+
+    fn deserialize(buffer: &[u8]) -> Result<Self, WireFormatError> {
+        if buffer.len() < 11 {
+            return Err(WireFormatError::BufferTooShort);
+        }
+
+        Ok(Self {
+            acceptable_port_identity: PortIdentity::deserialize(&buffer[0..10])?,
+            alternate_priority_1: buffer[10],
+        })
+    }
+}
+
+/// The data of an ALTERNATE_MASTER management TLV (IEEE1588-2019 section
+/// 15.5.3.7.7)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AlternateMasterTlv {
+    pub transmit_alternate_multicast_sync: bool,
+    pub log_alternate_multicast_sync_interval: i8,
+    pub number_of_alternate_masters: u8,
+}
+
+impl WireFormat for AlternateMasterTlv {
+    fn wire_size(&self) -> usize {
+        4
+    }
+
+    fn serialize(&self, buffer: &mut [u8]) -> Result<(), WireFormatError> {
+        buffer[0] = self.transmit_alternate_multicast_sync as u8;
+        buffer[1] = self.log_alternate_multicast_sync_interval as u8;
+        buffer[2] = self.number_of_alternate_masters;
+        buffer[3] = 0;
+
+        Ok(())
+    }
+
+    fn deserialize(buffer: &[u8]) -> Result<Self, WireFormatError> {
+        if buffer.len() < 4 {
+            return Err(WireFormatError::BufferTooShort);
+        }
+
+        Ok(Self {
+            transmit_alternate_multicast_sync: buffer[0] & 1 > 0,
+            log_alternate_multicast_sync_interval: buffer[1] as i8,
+            number_of_alternate_masters: buffer[2],
+        })
+    }
+}
+
+/// The data of an ALTERNATE_TIME_OFFSET_ENABLE management TLV (IEEE1588-2019
+/// section 15.5.3.6.8)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AlternateTimeOffsetEnableTlv {
+    pub key_field: u8,
+    pub enable: bool,
+}
+
+impl WireFormat for AlternateTimeOffsetEnableTlv {
+    fn wire_size(&self) -> usize {
+        2
+    }
+
+    fn serialize(&self, buffer: &mut [u8]) -> Result<(), WireFormatError> {
+        buffer[0] = self.key_field;
+        buffer[1] = self.enable as u8;
+
+        Ok(())
+    }
+
+    fn deserialize(buffer: &[u8]) -> Result<Self, WireFormatError> {
+        if buffer.len() < 2 {
+            return Err(WireFormatError::BufferTooShort);
+        }
+
+        Ok(Self {
+            key_field: buffer[0],
+            enable: buffer[1] & 1 > 0,
+        })
+    }
+}
+
+/// The data of an ALTERNATE_TIME_OFFSET_NAME management TLV (IEEE1588-2019
+/// section 15.5.3.6.9)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AlternateTimeOffsetNameTlv {
+    pub key_field: u8,
+    pub display_name: PtpText,
+}
+
+impl WireFormat for AlternateTimeOffsetNameTlv {
+    fn wire_size(&self) -> usize {
+        1 + self.display_name.wire_size()
+    }
+
+    fn serialize(&self, buffer: &mut [u8]) -> Result<(), WireFormatError> {
+        buffer[0] = self.key_field;
+        self.display_name.serialize(&mut buffer[1..])
+    }
+
+    fn deserialize(buffer: &[u8]) -> Result<Self, WireFormatError> {
+        if buffer.len() < 2 {
+            return Err(WireFormatError::BufferTooShort);
+        }
+
+        Ok(Self {
+            key_field: buffer[0],
+            display_name: PtpText::deserialize(&buffer[1..])?,
+        })
+    }
+}
+
+/// The data of an ALTERNATE_TIME_OFFSET_PROPERTIES management TLV
+/// (IEEE1588-2019 section 15.5.3.6.11)
+///
+/// `time_of_next_jump` is a 48 bit number of seconds in the PTP timescale.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AlternateTimeOffsetPropertiesTlv {
+    pub key_field: u8,
+    pub current_offset: i32,
+    pub jump_seconds: i32,
+    pub time_of_next_jump: u64,
+}
+
+impl WireFormat for AlternateTimeOffsetPropertiesTlv {
+    fn wire_size(&self) -> usize {
+        16
+    }
+
+    fn serialize(&self, buffer: &mut [u8]) -> Result<(), WireFormatError> {
+        buffer[0] = self.key_field;
+        buffer[1..5].copy_from_slice(&self.current_offset.to_be_bytes());
+        buffer[5..9].copy_from_slice(&self.jump_seconds.to_be_bytes());
+        buffer[9..15].copy_from_slice(&self.time_of_next_jump.to_be_bytes()[2..8]);
+        buffer[15] = 0;
+
+        Ok(())
+    }
+
+    fn deserialize(buffer: &[u8]) -> Result<Self, WireFormatError> {
+        if buffer.len() < 16 {
+            return Err(WireFormatError::BufferTooShort);
+        }
+
+        let mut time_of_next_jump = [0; 8];
+        time_of_next_jump[2..8].copy_from_slice(&buffer[9..15]);
+
+        Ok(Self {
+            key_field: buffer[0],
+            current_offset: i32::from_be_bytes(buffer[1..5].try_into().unwrap()),
+            jump_seconds: i32::from_be_bytes(buffer[5..9].try_into().unwrap()),
+            time_of_next_jump: u64::from_be_bytes(time_of_next_jump),
+        })
+    }
+}
+
+/// The data of a TRANSPARENT_CLOCK_DEFAULT_DATA_SET management TLV
+/// (IEEE1588-2019 section 15.5.3.10.1)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransparentClockDefaultDataSetTlv {
+    pub clock_identity: ClockIdentity,
+    pub number_ports: u16,
+    pub delay_mechanism: u8,
+    pub primary_domain: u8,
+}
+
+impl WireFormat for TransparentClockDefaultDataSetTlv {
+    fn wire_size(&self) -> usize {
+        12
+    }
+
+    fn serialize(&self, buffer: &mut [u8]) -> Result<(), WireFormatError> {
+        self.clock_identity.serialize(&mut buffer[0..8])?;
+        buffer[8..10].copy_from_slice(&self.number_ports.to_be_bytes());
+        buffer[10] = self.delay_mechanism;
+        buffer[11] = self.primary_domain;
+
+        Ok(())
+    }
+
+    fn deserialize(buffer: &[u8]) -> Result<Self, WireFormatError> {
+        if buffer.len() < 12 {
+            return Err(WireFormatError::BufferTooShort);
+        }
+
+        Ok(Self {
+            clock_identity: ClockIdentity::deserialize(&buffer[0..8])?,
+            number_ports: u16::from_be_bytes([buffer[8], buffer[9]]),
+            delay_mechanism: buffer[10],
+            primary_domain: buffer[11],
+        })
+    }
+}
+
+/// The data of a TRANSPARENT_CLOCK_PORT_DATA_SET management TLV
+/// (IEEE1588-2019 section 15.5.3.11.1)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransparentClockPortDataSetTlv {
+    pub port_identity: PortIdentity,
+    pub faulty: bool,
+    pub log_min_pdelay_req_interval: i8,
+    pub peer_mean_path_delay: TimeInterval,
+}
+
+impl WireFormat for TransparentClockPortDataSetTlv {
+    fn wire_size(&self) -> usize {
+        20
+    }
+
+    fn serialize(&self, buffer: &mut [u8]) -> Result<(), WireFormatError> {
+        self.port_identity.serialize(&mut buffer[0..10])?;
+        buffer[10] = self.faulty as u8;
+        buffer[11] = self.log_min_pdelay_req_interval as u8;
+        self.peer_mean_path_delay.serialize(&mut buffer[12..20])?;
+
+        Ok(())
+    }
+
+    fn deserialize(buffer: &[u8]) -> Result<Self, WireFormatError> {
+        if buffer.len() < 20 {
+            return Err(WireFormatError::BufferTooShort);
+        }
+
+        Ok(Self {
+            port_identity: PortIdentity::deserialize(&buffer[0..10])?,
+            faulty: buffer[10] & 1 > 0,
+            log_min_pdelay_req_interval: buffer[11] as i8,
+            peer_mean_path_delay: TimeInterval::deserialize(&buffer[12..20])?,
+        })
+    }
+}
+
+/// See 15.5.2.3 / Table 59
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ManagementId {
+    Reserved(u16),
+    Experimental(u16),
+    NullPtpManagement,
+    ClockDescription,
+    UserDescription,
+    SaveInNonVolatileStorage,
+    ResetNonVolatileStorage,
+    Initialize,
+    FaultLog,
+    FaultLogReset,
+    DefaultDataSet,
+    CurrentDataSet,
+    ParentDataSet,
+    TimePropertiesDataSet,
+    PortDataSet,
+    Priority1,
+    Priority2,
+    Domain,
+    SlaveOnly,
+    LogAnnounceInterval,
+    AnnounceReceiptTimeout,
+    LogSyncInterval,
+    VersionNumber,
+    EnablePort,
+    DisablePort,
+    Time,
+    ClockAccuracy,
+    UtcProperties,
+    TraceabilityProperties,
+    TimescaleProperties,
+    UnicastNegotiationEnable,
+    PathTraceList,
+    PathTraceEnable,
+    GrandmasterClusterTable,
+    UnicastMasterTable,
+    UnicastMasterMaxTableSize,
+    AcceptableMasterTable,
+    AcceptableMasterTableEnabled,
+    AcceptableMasterMaxTableSize,
+    AlternateMaster,
+    AlternateTimeOffsetEnable,
+    AlternateTimeOffsetName,
+    AlternateTimeOffsetMaxKey,
+    AlternateTimeOffsetProperties,
+    ExternalPortConfigurationEnabled,
+    MasterOnly,
+    HoldoverUpgradeEnable,
+    ExtPortConfigPortDataSet,
+    TransparentClockDefaultDataSet,
+    TransparentClockPortDataSet,
+    PrimaryDomain,
+    DelayMechanism,
+    LogMinPdelayReqInterval,
+}
+
+impl ManagementId {
+    pub fn to_primitive(self) -> u16 {
+        match self {
+            Self::Reserved(value) | Self::Experimental(value) => value,
+            Self::NullPtpManagement => 0x0000,
+            Self::ClockDescription => 0x0001,
+            Self::UserDescription => 0x0002,
+            Self::SaveInNonVolatileStorage => 0x0003,
+            Self::ResetNonVolatileStorage => 0x0004,
+            Self::Initialize => 0x0005,
+            Self::FaultLog => 0x0006,
+            Self::FaultLogReset => 0x0007,
+            Self::DefaultDataSet => 0x2000,
+            Self::CurrentDataSet => 0x2001,
+            Self::ParentDataSet => 0x2002,
+            Self::TimePropertiesDataSet => 0x2003,
+            Self::PortDataSet => 0x2004,
+            Self::Priority1 => 0x2005,
+            Self::Priority2 => 0x2006,
+            Self::Domain => 0x2007,
+            Self::SlaveOnly => 0x2008,
+            Self::LogAnnounceInterval => 0x2009,
+            Self::AnnounceReceiptTimeout => 0x200a,
+            Self::LogSyncInterval => 0x200b,
+            Self::VersionNumber => 0x200c,
+            Self::EnablePort => 0x200d,
+            Self::DisablePort => 0x200e,
+            Self::Time => 0x200f,
+            Self::ClockAccuracy => 0x2010,
+            Self::UtcProperties => 0x2011,
+            Self::TraceabilityProperties => 0x2012,
+            Self::TimescaleProperties => 0x2013,
+            Self::UnicastNegotiationEnable => 0x2014,
+            Self::PathTraceList => 0x2015,
+            Self::PathTraceEnable => 0x2016,
+            Self::GrandmasterClusterTable => 0x2017,
+            Self::UnicastMasterTable => 0x2018,
+            Self::UnicastMasterMaxTableSize => 0x2019,
+            Self::AcceptableMasterTable => 0x201a,
+            Self::AcceptableMasterTableEnabled => 0x201b,
+            Self::AcceptableMasterMaxTableSize => 0x201c,
+            Self::AlternateMaster => 0x201d,
+            Self::AlternateTimeOffsetEnable => 0x201e,
+            Self::AlternateTimeOffsetName => 0x201f,
+            Self::AlternateTimeOffsetMaxKey => 0x2020,
+            Self::AlternateTimeOffsetProperties => 0x2021,
+            Self::ExternalPortConfigurationEnabled => 0x3000,
+            Self::MasterOnly => 0x3001,
+            Self::HoldoverUpgradeEnable => 0x3002,
+            Self::ExtPortConfigPortDataSet => 0x3003,
+            Self::TransparentClockDefaultDataSet => 0x4000,
+            Self::TransparentClockPortDataSet => 0x4001,
+            Self::PrimaryDomain => 0x4002,
+            Self::DelayMechanism => 0x6000,
+            Self::LogMinPdelayReqInterval => 0x6001,
+        }
+    }
+
+    pub fn from_primitive(value: u16) -> Self {
+        match value {
+            0x0000 => Self::NullPtpManagement,
+            0x0001 => Self::ClockDescription,
+            0x0002 => Self::UserDescription,
+            0x0003 => Self::SaveInNonVolatileStorage,
+            0x0004 => Self::ResetNonVolatileStorage,
+            0x0005 => Self::Initialize,
+            0x0006 => Self::FaultLog,
+            0x0007 => Self::FaultLogReset,
+            0x2000 => Self::DefaultDataSet,
+            0x2001 => Self::CurrentDataSet,
+            0x2002 => Self::ParentDataSet,
+            0x2003 => Self::TimePropertiesDataSet,
+            0x2004 => Self::PortDataSet,
+            0x2005 => Self::Priority1,
+            0x2006 => Self::Priority2,
+            0x2007 => Self::Domain,
+            0x2008 => Self::SlaveOnly,
+            0x2009 => Self::LogAnnounceInterval,
+            0x200a => Self::AnnounceReceiptTimeout,
+            0x200b => Self::LogSyncInterval,
+            0x200c => Self::VersionNumber,
+            0x200d => Self::EnablePort,
+            0x200e => Self::DisablePort,
+            0x200f => Self::Time,
+            0x2010 => Self::ClockAccuracy,
+            0x2011 => Self::UtcProperties,
+            0x2012 => Self::TraceabilityProperties,
+            0x2013 => Self::TimescaleProperties,
+            0x2014 => Self::UnicastNegotiationEnable,
+            0x2015 => Self::PathTraceList,
+            0x2016 => Self::PathTraceEnable,
+            0x2017 => Self::GrandmasterClusterTable,
+            0x2018 => Self::UnicastMasterTable,
+            0x2019 => Self::UnicastMasterMaxTableSize,
+            0x201a => Self::AcceptableMasterTable,
+            0x201b => Self::AcceptableMasterTableEnabled,
+            0x201c => Self::AcceptableMasterMaxTableSize,
+            0x201d => Self::AlternateMaster,
+            0x201e => Self::AlternateTimeOffsetEnable,
+            0x201f => Self::AlternateTimeOffsetName,
+            0x2020 => Self::AlternateTimeOffsetMaxKey,
+            0x2021 => Self::AlternateTimeOffsetProperties,
+            0x3000 => Self::ExternalPortConfigurationEnabled,
+            0x3001 => Self::MasterOnly,
+            0x3002 => Self::HoldoverUpgradeEnable,
+            0x3003 => Self::ExtPortConfigPortDataSet,
+            0x4000 => Self::TransparentClockDefaultDataSet,
+            0x4001 => Self::TransparentClockPortDataSet,
+            0x4002 => Self::PrimaryDomain,
+            0x6000 => Self::DelayMechanism,
+            0x6001 => Self::LogMinPdelayReqInterval,
+            0xc000..=0xdfff => Self::Experimental(value),
+            _ => Self::Reserved(value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fixed::traits::ToFixed;
+
+    use super::*;
+    use crate::datastructures::common::NetworkProtocol;
+
+    #[test]
+    fn management_id_primitives() {
+        for value in 0..=u16::MAX {
+            assert_eq!(ManagementId::from_primitive(value).to_primitive(), value);
+            assert_eq!(
+                ManagementErrorId::from_primitive(value).to_primitive(),
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn priority_wireformat() {
+        let bytes = [0x00, 0x01, 0x00, 0x04, 0x20, 0x05, 0x80, 0x00];
+        let tlv = ManagementTlv::Priority1(128);
+
+        let mut buffer = [0; 8];
+        tlv.serialize(&mut buffer).unwrap();
+        assert_eq!(buffer, bytes);
+        assert_eq!(ManagementTlv::deserialize(&bytes).unwrap(), tlv);
+    }
+
+    #[test]
+    fn get_request_has_no_data() {
+        let bytes = [0x00, 0x01, 0x00, 0x02, 0x20, 0x00];
+        let tlv = ManagementTlv::deserialize(&bytes).unwrap();
+        assert_eq!(tlv, ManagementTlv::Empty(ManagementId::DefaultDataSet));
+        assert_eq!(tlv.wire_size(), 6);
+    }
+
+    #[test]
+    fn default_data_set_wireformat() {
+        let tlv = ManagementTlv::DefaultDataSet(DefaultDataSetTlv {
+            two_step_flag: true,
+            slave_only: false,
+            number_ports: 1,
+            priority_1: 128,
+            clock_quality: ClockQuality::default(),
+            priority_2: 127,
+            clock_identity: ClockIdentity([1, 2, 3, 4, 5, 6, 7, 8]),
+            domain_number: 4,
+        });
+
+        let mut buffer = [0; 26];
+        tlv.serialize(&mut buffer).unwrap();
+        assert_eq!(&buffer[0..6], &[0x00, 0x01, 0x00, 0x16, 0x20, 0x00]);
+        assert_eq!(buffer[6], 0x01);
+        assert_eq!(ManagementTlv::deserialize(&buffer).unwrap(), tlv);
+    }
+
+    #[test]
+    fn error_status_wireformat() {
+        let bytes = [
+            0x00, 0x02, 0x00, 0x0a, 0x00, 0x05, 0x20, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, b'x',
+        ];
+        let tlv = ManagementTlv::ErrorStatus(ManagementErrorStatusTlv {
+            management_error_id: ManagementErrorId::NotSetable,
+            management_id: ManagementId::DefaultDataSet,
+            display_data: PtpText::new("x").unwrap(),
+        });
+
+        assert_eq!(tlv.wire_size(), bytes.len());
+        let mut buffer = [0; 14];
+        tlv.serialize(&mut buffer).unwrap();
+        assert_eq!(buffer, bytes);
+        assert_eq!(ManagementTlv::deserialize(&bytes).unwrap(), tlv);
+        assert_eq!(tlv.management_id(), ManagementId::DefaultDataSet);
+
+        let without_display_data = ManagementTlv::ErrorStatus(ManagementErrorStatusTlv {
+            management_error_id: ManagementErrorId::NoSuchId,
+            management_id: ManagementId::Reserved(0x1234),
+            display_data: PtpText::default(),
+        });
+        assert_eq!(without_display_data.wire_size(), 12);
+        let mut buffer = [0; 12];
+        without_display_data.serialize(&mut buffer).unwrap();
+        assert_eq!(
+            ManagementTlv::deserialize(&buffer).unwrap(),
+            without_display_data
+        );
+    }
+
+    #[test]
+    fn round_trips() {
+        let port_identity = PortIdentity {
+            clock_identity: ClockIdentity([1, 2, 3, 4, 5, 6, 7, 8]),
+            port_number: 3,
+        };
+        let clock_quality = ClockQuality {
+            clock_class: 6,
+            clock_accuracy: ClockAccuracy::NS100,
+            offset_scaled_log_variance: 0x4e5d,
+        };
+        let port_address = PortAddress {
+            network_protocol: NetworkProtocol::UdpIPv4,
+            address: ArrayVec::from_iter([192, 168, 0, 25]),
+        };
+        let text = |text| PtpText::new(text).unwrap();
+
+        let tlvs = [
+            ManagementTlv::Empty(ManagementId::NullPtpManagement),
+            ManagementTlv::Empty(ManagementId::EnablePort),
+            ManagementTlv::ClockDescription(ClockDescriptionTlv {
+                clock_type: 0x8000,
+                physical_layer_protocol: text("IEEE 802.3"),
+                physical_address: ArrayVec::from_iter([1, 2, 3, 4, 5, 6]),
+                protocol_address: port_address.clone(),
+                manufacturer_identity: [0xaa, 0xbb, 0xcc],
+                product_description: text("statime;test;1"),
+                revision_data: text("1;2;3"),
+                user_description: text("rack 4"),
+                profile_identity: [0, 0x1b, 0x19, 0, 1, 0],
+            }),
+            ManagementTlv::UserDescription(text("odd")),
+            ManagementTlv::Initialize(0),
+            ManagementTlv::FaultLog(FaultLogTlv {
+                fault_records: ArrayVec::from_iter([FaultRecord {
+                    fault_time: Timestamp {
+                        seconds: 1000,
+                        nanos: 5,
+                    },
+                    severity_code: 3,
+                    fault_name: text("name"),
+                    fault_value: text("value"),
+                    fault_description: text("description"),
+                }]),
+            }),
+            ManagementTlv::DefaultDataSet(DefaultDataSetTlv {
+                two_step_flag: false,
+                slave_only: true,
+                number_ports: 2,
+                priority_1: 1,
+                clock_quality,
+                priority_2: 2,
+                clock_identity: port_identity.clock_identity,
+                domain_number: 24,
+            }),
+            ManagementTlv::CurrentDataSet(CurrentDataSetTlv {
+                steps_removed: 2,
+                offset_from_master: TimeInterval(1234.5.to_fixed()),
+                mean_path_delay: TimeInterval((-4.25).to_fixed()),
+            }),
+            ManagementTlv::ParentDataSet(ParentDataSetTlv {
+                parent_port_identity: port_identity,
+                parent_stats: true,
+                observed_parent_offset_scaled_log_variance: 0xffff,
+                observed_parent_clock_phase_change_rate: 0x7fff_ffff,
+                grandmaster_priority_1: 3,
+                grandmaster_clock_quality: clock_quality,
+                grandmaster_priority_2: 4,
+                grandmaster_identity: ClockIdentity([8; 8]),
+            }),
+            ManagementTlv::TimePropertiesDataSet(TimePropertiesDataSetTlv {
+                current_utc_offset: 37,
+                leap61: false,
+                leap59: true,
+                current_utc_offset_valid: true,
+                ptp_timescale: true,
+                time_traceable: false,
+                frequency_traceable: true,
+                time_source: TimeSource::Gnss,
+            }),
+            ManagementTlv::PortDataSet(PortDataSetTlv {
+                port_identity,
+                port_state: 9,
+                log_min_delay_req_interval: -3,
+                peer_mean_path_delay: TimeInterval(100.into()),
+                log_announce_interval: 1,
+                announce_receipt_timeout: 3,
+                log_sync_interval: -4,
+                delay_mechanism: 2,
+                log_min_pdelay_req_interval: 0,
+                version_number: 2,
+                delay_asymmetry: None,
+            }),
+            ManagementTlv::PortDataSet(PortDataSetTlv {
+                port_identity,
+                port_state: 9,
+                log_min_delay_req_interval: -3,
+                peer_mean_path_delay: TimeInterval(100.into()),
+                log_announce_interval: 1,
+                announce_receipt_timeout: 3,
+                log_sync_interval: -4,
+                delay_mechanism: 2,
+                log_min_pdelay_req_interval: 0,
+                version_number: 2,
+                delay_asymmetry: Some(TimeInterval((-250.5).to_fixed())),
+            }),
+            ManagementTlv::Priority1(255),
+            ManagementTlv::Priority2(0),
+            ManagementTlv::Domain(127),
+            ManagementTlv::SlaveOnly(true),
+            ManagementTlv::LogAnnounceInterval(-2),
+            ManagementTlv::AnnounceReceiptTimeout(5),
+            ManagementTlv::LogSyncInterval(-7),
+            ManagementTlv::VersionNumber(2),
+            ManagementTlv::Time(Timestamp {
+                seconds: 0x1234_5678_9abc,
+                nanos: 999_999_999,
+            }),
+            ManagementTlv::ClockAccuracy(ClockAccuracy::US25),
+            ManagementTlv::UtcProperties(UtcPropertiesTlv {
+                current_utc_offset: -1,
+                leap61: true,
+                leap59: false,
+                current_utc_offset_valid: true,
+            }),
+            ManagementTlv::TraceabilityProperties(TraceabilityPropertiesTlv {
+                time_traceable: true,
+                frequency_traceable: false,
+            }),
+            ManagementTlv::TimescaleProperties(TimescalePropertiesTlv {
+                ptp_timescale: true,
+                time_source: TimeSource::AtomicClock,
+            }),
+            ManagementTlv::UnicastNegotiationEnable(true),
+            ManagementTlv::PathTraceList(ArrayVec::from_iter([
+                ClockIdentity([1; 8]),
+                ClockIdentity([2; 8]),
+            ])),
+            ManagementTlv::PathTraceEnable(false),
+            ManagementTlv::GrandmasterClusterTable(PortAddressTableTlv {
+                log_query_interval: 2,
+                port_addresses: ArrayVec::from_iter([port_address.clone()]),
+            }),
+            ManagementTlv::UnicastMasterTable(PortAddressTableTlv {
+                log_query_interval: -1,
+                port_addresses: ArrayVec::from_iter([
+                    port_address.clone(),
+                    PortAddress {
+                        network_protocol: NetworkProtocol::IEEE802_3,
+                        address: ArrayVec::from_iter([1, 2, 3, 4, 5, 6]),
+                    },
+                ]),
+            }),
+            ManagementTlv::UnicastMasterMaxTableSize(16),
+            ManagementTlv::AcceptableMasterTable(ArrayVec::from_iter([AcceptableMaster {
+                acceptable_port_identity: port_identity,
+                alternate_priority_1: 7,
+            }])),
+            ManagementTlv::AcceptableMasterTableEnabled(true),
+            ManagementTlv::AcceptableMasterMaxTableSize(8),
+            ManagementTlv::AlternateMaster(AlternateMasterTlv {
+                transmit_alternate_multicast_sync: true,
+                log_alternate_multicast_sync_interval: -3,
+                number_of_alternate_masters: 2,
+            }),
+            ManagementTlv::AlternateTimeOffsetEnable(AlternateTimeOffsetEnableTlv {
+                key_field: 1,
+                enable: true,
+            }),
+            ManagementTlv::AlternateTimeOffsetName(AlternateTimeOffsetNameTlv {
+                key_field: 1,
+                display_name: text("CEST"),
+            }),
+            ManagementTlv::AlternateTimeOffsetMaxKey(4),
+            ManagementTlv::AlternateTimeOffsetProperties(AlternateTimeOffsetPropertiesTlv {
+                key_field: 1,
+                current_offset: 7200,
+                jump_seconds: -3600,
+                time_of_next_jump: 0xffff_ffff_ffff,
+            }),
+            ManagementTlv::ExternalPortConfigurationEnabled(true),
+            ManagementTlv::MasterOnly(true),
+            ManagementTlv::HoldoverUpgradeEnable(false),
+            ManagementTlv::ExtPortConfigPortDataSet(6),
+            ManagementTlv::TransparentClockDefaultDataSet(TransparentClockDefaultDataSetTlv {
+                clock_identity: port_identity.clock_identity,
+                number_ports: 4,
+                delay_mechanism: 2,
+                primary_domain: 0,
+            }),
+            ManagementTlv::TransparentClockPortDataSet(TransparentClockPortDataSetTlv {
+                port_identity,
+                faulty: true,
+                log_min_pdelay_req_interval: 1,
+                peer_mean_path_delay: TimeInterval(12.into()),
+            }),
+            ManagementTlv::PrimaryDomain(1),
+            ManagementTlv::DelayMechanism(1),
+            ManagementTlv::LogMinPdelayReqInterval(-1),
+            ManagementTlv::Other(
+                ManagementId::Experimental(0xc001),
+                ArrayVec::from_iter([1, 2]),
+            ),
+            ManagementTlv::ErrorStatus(ManagementErrorStatusTlv {
+                management_error_id: ManagementErrorId::WrongValue,
+                management_id: ManagementId::Priority1,
+                display_data: text("no"),
+            }),
+        ];
+
+        for tlv in tlvs {
+            let mut buffer = [0xaa; 256];
+            tlv.serialize(&mut buffer).unwrap();
+
+            let size = tlv.wire_size();
+            assert_eq!(size % 2, 0, "{:?}", tlv);
+            assert_eq!(
+                u16::from_be_bytes([buffer[2], buffer[3]]) as usize,
+                size - 4,
+                "{:?}",
+                tlv
+            );
+
+            assert_eq!(ManagementTlv::deserialize(&buffer[..size]).unwrap(), tlv);
+        }
+    }
+
+    #[test]
+    fn short_data_is_rejected() {
+        // A PORT_DATA_SET TLV with only part of its data
+        let bytes = [0x00, 0x01, 0x00, 0x06, 0x20, 0x04, 0x01, 0x02, 0x03, 0x04];
+        assert!(matches!(
+            ManagementTlv::deserialize(&bytes),
+            Err(WireFormatError::BufferTooShort)
+        ));
+
+        // A length field pointing past the end of the buffer
+        let bytes = [0x00, 0x01, 0x00, 0x08, 0x20, 0x05, 0x01, 0x00];
+        assert!(matches!(
+            ManagementTlv::deserialize(&bytes),
+            Err(WireFormatError::BufferTooShort)
+        ));
+    }
+}
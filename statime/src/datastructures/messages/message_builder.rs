@@ -1,6 +1,11 @@
+use arrayvec::ArrayVec;
+
 use super::{
-    AnnounceMessage, DelayReqMessage, DelayRespMessage, FollowUpMessage, Header, Message,
-    PtpVersion, SdoId, SyncMessage,
+    AlternateTimeOffsetIndicatorTlv, AnnounceMessage, DelayReqMessage, DelayRespMessage,
+    EnhancedAccuracyMetricsTlv, FollowUpInformationTlv, FollowUpMessage, Header, ManagementAction,
+    ManagementMessage, ManagementTlv, Message, PDelayReqMessage, PDelayRespFollowUpMessage,
+    PDelayRespMessage, PowerProfileTlv, PtpVersion, SdoId, SignalingMessage, SignalingTlv,
+    SmpteSyncMetadataTlv, SyncMessage, MAX_ALTERNATE_TIME_OFFSETS, MAX_PATH_TRACE_LEN,
 };
 use crate::datastructures::common::{
     ClockIdentity, ClockQuality, PortIdentity, TimeInterval, TimeSource, Timestamp,
@@ -66,7 +71,6 @@ impl MessageBuilder {
         self
     }
 
-    #[allow(unused)]
     pub fn unicast_flag(mut self, unicast_flag: bool) -> Self {
         self.header.unicast_flag = unicast_flag;
         self
@@ -159,10 +163,15 @@ impl MessageBuilder {
         })
     }
 
-    pub fn follow_up_message(self, precise_origin_timestamp: Timestamp) -> Message {
+    pub fn follow_up_message(
+        self,
+        precise_origin_timestamp: Timestamp,
+        follow_up_information: Option<FollowUpInformationTlv>,
+    ) -> Message {
         Message::FollowUp(FollowUpMessage {
             header: self.header,
             precise_origin_timestamp,
+            follow_up_information,
         })
     }
 
@@ -178,6 +187,37 @@ impl MessageBuilder {
         })
     }
 
+    pub fn pdelay_req_message(self, origin_timestamp: Timestamp) -> Message {
+        Message::PDelayReq(PDelayReqMessage {
+            header: self.header,
+            origin_timestamp,
+        })
+    }
+
+    pub fn pdelay_resp_message(
+        self,
+        request_receive_timestamp: Timestamp,
+        requesting_port_identity: PortIdentity,
+    ) -> Message {
+        Message::PDelayResp(PDelayRespMessage {
+            header: self.header,
+            request_receive_timestamp,
+            requesting_port_identity,
+        })
+    }
+
+    pub fn pdelay_resp_follow_up_message(
+        self,
+        response_origin_timestamp: Timestamp,
+        requesting_port_identity: PortIdentity,
+    ) -> Message {
+        Message::PDelayRespFollowUp(PDelayRespFollowUpMessage {
+            header: self.header,
+            response_origin_timestamp,
+            requesting_port_identity,
+        })
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub fn announce_message(
         self,
@@ -189,6 +229,14 @@ impl MessageBuilder {
         grandmaster_identity: ClockIdentity,
         steps_removed: u16,
         time_source: TimeSource,
+        path_trace: ArrayVec<ClockIdentity, MAX_PATH_TRACE_LEN>,
+        alternate_time_offsets: ArrayVec<
+            AlternateTimeOffsetIndicatorTlv,
+            MAX_ALTERNATE_TIME_OFFSETS,
+        >,
+        smpte_sync_metadata: Option<SmpteSyncMetadataTlv>,
+        power_profile: Option<PowerProfileTlv>,
+        enhanced_accuracy_metrics: Option<EnhancedAccuracyMetricsTlv>,
     ) -> Message {
         Message::Announce(AnnounceMessage {
             header: self.header,
@@ -200,6 +248,45 @@ impl MessageBuilder {
             grandmaster_identity,
             steps_removed,
             time_source,
+            path_trace,
+            alternate_time_offsets,
+            smpte_sync_metadata,
+            power_profile,
+            enhanced_accuracy_metrics,
+        })
+    }
+
+    pub fn management_message(
+        self,
+        target_port_identity: PortIdentity,
+        starting_boundary_hops: u8,
+        boundary_hops: u8,
+        action: ManagementAction,
+        management_tlv: ManagementTlv,
+    ) -> Message {
+        Message::Management(ManagementMessage {
+            header: self.header,
+            target_port_identity,
+            starting_boundary_hops,
+            boundary_hops,
+            action,
+            management_tlv,
+        })
+    }
+
+    /// Build a signaling message carrying a single TLV
+    pub fn signaling_message(
+        self,
+        target_port_identity: PortIdentity,
+        tlv: SignalingTlv,
+    ) -> Message {
+        let mut value = ArrayVec::new();
+        value.push(tlv);
+
+        Message::Signaling(SignalingMessage {
+            header: self.header,
+            target_port_identity,
+            value,
         })
     }
 }
@@ -1,32 +1,50 @@
 //! Ptp network messages
 
+pub use alternate_time_offset::*;
 pub use announce::*;
 use arrayvec::ArrayVec;
+pub use authentication::*;
 pub use delay_req::*;
 pub use delay_resp::*;
+pub use enhanced_accuracy_metrics::*;
 pub use follow_up::*;
+pub use follow_up_information::*;
 pub use header::*;
+pub use management::*;
+pub use management_tlv::*;
 pub use message_builder::*;
 use num_enum::{IntoPrimitive, TryFromPrimitive};
+pub use p_delay_req::*;
+pub use p_delay_resp::*;
+pub use p_delay_resp_follow_up::*;
+pub use power_profile::*;
+pub use signalling::*;
+pub use signalling_tlv::*;
+pub use slave_event_monitoring::*;
+pub use smpte_sync_metadata::*;
 pub use sync::*;
 
-use self::{
-    management::ManagementMessage, p_delay_req::PDelayReqMessage, p_delay_resp::PDelayRespMessage,
-    p_delay_resp_follow_up::PDelayRespFollowUpMessage, signalling::SignalingMessage,
-};
-
+mod alternate_time_offset;
 mod announce;
+mod authentication;
 mod control_field;
 mod delay_req;
 mod delay_resp;
+mod enhanced_accuracy_metrics;
 mod follow_up;
+mod follow_up_information;
 mod header;
 mod management;
+mod management_tlv;
 mod message_builder;
 mod p_delay_req;
 mod p_delay_resp;
 mod p_delay_resp_follow_up;
+mod power_profile;
 mod signalling;
+mod signalling_tlv;
+mod slave_event_monitoring;
+mod smpte_sync_metadata;
 mod sync;
 
 pub const MAX_DATA_LEN: usize = 255;
@@ -47,6 +65,7 @@ pub enum MessageType {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[allow(clippy::large_enum_variant)]
 pub enum Message {
     Sync(SyncMessage),
     DelayReq(DelayReqMessage),
@@ -81,6 +100,18 @@ impl Message {
         }
     }
 
+    /// Whether this is an event message, which needs to be timestamped on
+    /// transmission and reception
+    pub(crate) fn is_event(&self) -> bool {
+        matches!(
+            self,
+            Message::Sync(_)
+                | Message::DelayReq(_)
+                | Message::PDelayReq(_)
+                | Message::PDelayResp(_)
+        )
+    }
+
     /// The byte size on the wire of this message
     pub fn wire_size(&self) -> usize {
         self.header().wire_size() + self.content_size()
@@ -155,8 +186,11 @@ impl Message {
     pub fn deserialize(buffer: &[u8]) -> Result<Self, super::WireFormatError> {
         let header_data = Header::deserialize_header(buffer)?;
 
-        // Skip the header bytes and only keep the content
-        let content_buffer = &buffer[34..];
+        // Skip the header bytes and only keep the content, without any padding the
+        // transport might have added after the message
+        let content_buffer = buffer
+            .get(34..header_data.message_length as usize)
+            .ok_or(super::WireFormatError::BufferTooShort)?;
 
         Ok(match header_data.message_type {
             MessageType::Sync => Message::Sync(SyncMessage::deserialize_content(
@@ -9,9 +9,9 @@ use crate::datastructures::{
 #[derive(Debug, Clone, Copy, PartialEq, Eq, CopyGetters)]
 #[getset(get_copy = "pub")]
 pub struct PDelayRespMessage {
-    pub(super) header: Header,
-    pub(super) request_receive_timestamp: Timestamp,
-    pub(super) requesting_port_identity: PortIdentity,
+    pub(crate) header: Header,
+    pub(crate) request_receive_timestamp: Timestamp,
+    pub(crate) requesting_port_identity: PortIdentity,
 }
 
 impl PDelayRespMessage {
@@ -9,9 +9,9 @@ use crate::datastructures::{
 #[derive(Debug, Clone, Copy, PartialEq, Eq, CopyGetters)]
 #[getset(get_copy = "pub")]
 pub struct PDelayRespFollowUpMessage {
-    pub(super) header: Header,
-    pub(super) response_origin_timestamp: Timestamp,
-    pub(super) requesting_port_identity: PortIdentity,
+    pub(crate) header: Header,
+    pub(crate) response_origin_timestamp: Timestamp,
+    pub(crate) requesting_port_identity: PortIdentity,
 }
 
 impl PDelayRespFollowUpMessage {
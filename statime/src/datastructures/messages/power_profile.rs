@@ -0,0 +1,129 @@
+use crate::datastructures::{common::TlvType, WireFormat, WireFormatError};
+
+/// The organization id (IEEE C37) and subtype of the IEEE_C37_238 TLV
+const ORGANIZATION_ID: [u8; 3] = [0x1c, 0x12, 0x9d];
+const ORGANIZATION_SUB_TYPE: [u8; 3] = [0x00, 0x00, 0x02];
+
+/// The IEEE_C37_238 TLV of the power profile of IEEE C37.238-2017
+///
+/// Carried by the announce messages of a power profile domain. It identifies
+/// the grandmaster, and tells slaves how far off their time may be: every
+/// boundary and transparent clock on the way adds its own time inaccuracy to
+/// the total. Inaccuracies are in nanoseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PowerProfileTlv {
+    /// The configured id of the grandmaster, in the range 3 to 254
+    pub grandmaster_id: u16,
+    /// The time inaccuracy of the grandmaster itself
+    pub grandmaster_time_inaccuracy: u32,
+    /// The time inaccuracy of the grandmaster plus that of every clock the
+    /// announce passed through
+    pub total_time_inaccuracy: u32,
+}
+
+impl PowerProfileTlv {
+    /// The TLV as forwarded by a clock with the given time inaccuracy
+    pub(crate) fn forwarded(self, time_inaccuracy: u32) -> Self {
+        Self {
+            total_time_inaccuracy: self.total_time_inaccuracy.saturating_add(time_inaccuracy),
+            ..self
+        }
+    }
+
+    /// Add the time inaccuracy of a forwarding clock to the total of the
+    /// IEEE_C37_238 TLV of the serialized announce `message` in place, leaving
+    /// the rest of the message, its other TLVs included, as it is
+    pub(crate) fn forward_in_place(message: &mut [u8], time_inaccuracy: u32) {
+        // The TLVs follow the header and the body of the announce
+        let mut tlvs = message.get_mut(64..).unwrap_or_default();
+        while tlvs.len() >= 4 {
+            let tlv_type = TlvType::from_primitive(u16::from_be_bytes([tlvs[0], tlvs[1]]));
+            let length = u16::from_be_bytes([tlvs[2], tlvs[3]]) as usize;
+            let Some(tlv) = tlvs.get_mut(..4 + length) else {
+                return;
+            };
+
+            if tlv_type == TlvType::OrganizationExtension && Self::matches(&tlv[4..]) {
+                // Only a TLV exactly as long as its serialization is overwritten
+                if let Ok(power_profile) = Self::deserialize(tlv) {
+                    if tlv.len() == power_profile.wire_size() {
+                        power_profile
+                            .forwarded(time_inaccuracy)
+                            .serialize(tlv)
+                            .expect("the TLV fits in its own place");
+                    }
+                }
+                return;
+            }
+
+            tlvs = &mut core::mem::take(&mut tlvs)[4 + length..];
+        }
+    }
+
+    /// Whether an organization extension TLV with this `value` is an
+    /// IEEE_C37_238 TLV, only those should be deserialized as one
+    pub(crate) fn matches(value: &[u8]) -> bool {
+        value.get(0..3) == Some(&ORGANIZATION_ID) && value.get(3..6) == Some(&ORGANIZATION_SUB_TYPE)
+    }
+}
+
+impl WireFormat for PowerProfileTlv {
+    fn wire_size(&self) -> usize {
+        20
+    }
+
+    fn serialize(&self, buffer: &mut [u8]) -> Result<(), WireFormatError> {
+        if buffer.len() < self.wire_size() {
+            return Err(WireFormatError::BufferTooShort);
+        }
+
+        buffer[0..2].copy_from_slice(&TlvType::OrganizationExtension.to_primitive().to_be_bytes());
+        buffer[2..4].copy_from_slice(&16u16.to_be_bytes());
+        buffer[4..7].copy_from_slice(&ORGANIZATION_ID);
+        buffer[7..10].copy_from_slice(&ORGANIZATION_SUB_TYPE);
+        buffer[10..12].copy_from_slice(&self.grandmaster_id.to_be_bytes());
+        buffer[12..16].copy_from_slice(&self.grandmaster_time_inaccuracy.to_be_bytes());
+        buffer[16..20].copy_from_slice(&self.total_time_inaccuracy.to_be_bytes());
+
+        Ok(())
+    }
+
+    fn deserialize(buffer: &[u8]) -> Result<Self, WireFormatError> {
+        let buffer = buffer.get(0..20).ok_or(WireFormatError::BufferTooShort)?;
+
+        Ok(Self {
+            grandmaster_id: u16::from_be_bytes([buffer[10], buffer[11]]),
+            grandmaster_time_inaccuracy: u32::from_be_bytes(buffer[12..16].try_into().unwrap()),
+            total_time_inaccuracy: u32::from_be_bytes(buffer[16..20].try_into().unwrap()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn power_profile_wireformat() {
+        let byte_representation = [
+            0x00, 0x03, 0x00, 0x10, 0x1c, 0x12, 0x9d, 0x00, 0x00, 0x02, 0x00, 0x05, 0x00, 0x00,
+            0x00, 0x32, 0x00, 0x00, 0x01, 0x2c,
+        ];
+        let object_representation = PowerProfileTlv {
+            grandmaster_id: 5,
+            grandmaster_time_inaccuracy: 50,
+            total_time_inaccuracy: 300,
+        };
+
+        // Test the serialization output
+        let mut serialization_buffer = [0; 20];
+        object_representation
+            .serialize(&mut serialization_buffer)
+            .unwrap();
+        assert_eq!(serialization_buffer, byte_representation);
+
+        // Test the deserialization output
+        let deserialized_data = PowerProfileTlv::deserialize(&byte_representation).unwrap();
+        assert_eq!(deserialized_data, object_representation);
+    }
+}
@@ -1,29 +1,26 @@
 use arrayvec::ArrayVec;
 
-use super::Header;
-use crate::datastructures::{
-    common::{PortIdentity, Tlv},
-    WireFormat, WireFormatError,
-};
+use super::{Header, SignalingTlv};
+use crate::datastructures::{common::PortIdentity, WireFormat, WireFormatError};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SignalingMessage {
-    pub(super) header: Header,
-    pub(super) target_port_identity: PortIdentity,
+    pub(crate) header: Header,
+    pub(crate) target_port_identity: PortIdentity,
 
-    pub(super) value: ArrayVec<Tlv, { Self::CAPACITY }>,
+    pub(crate) value: ArrayVec<SignalingTlv, { Self::CAPACITY }>,
 }
 
 impl SignalingMessage {
     // TODO: determine the best max length value
-    const CAPACITY: usize = 4;
+    pub(crate) const CAPACITY: usize = 4;
 
     pub fn content_size(&self) -> usize {
-        10
+        10 + self.value.iter().map(|tlv| tlv.wire_size()).sum::<usize>()
     }
 
     pub fn serialize_content(&self, buffer: &mut [u8]) -> Result<(), WireFormatError> {
-        if buffer.len() < 11 {
+        if buffer.len() < self.content_size() {
             return Err(WireFormatError::BufferTooShort);
         }
 
@@ -48,9 +45,9 @@ impl SignalingMessage {
 
         let mut buffer = &buffer[10..];
 
-        let mut tlvs = ArrayVec::<Tlv, { Self::CAPACITY }>::new();
-        while buffer.len() > 4 {
-            let tlv = Tlv::deserialize(buffer)?;
+        let mut tlvs = ArrayVec::<SignalingTlv, { Self::CAPACITY }>::new();
+        while buffer.len() >= 4 {
+            let tlv = SignalingTlv::deserialize(buffer)?;
 
             buffer = &buffer[tlv.wire_size()..];
 
@@ -65,3 +62,39 @@ impl SignalingMessage {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datastructures::{
+        common::ClockIdentity,
+        messages::{Message, MessageBuilder, MessageType, RequestUnicastTransmissionTlv},
+    };
+
+    #[test]
+    fn signaling_wireformat() {
+        let target_port_identity = PortIdentity {
+            clock_identity: ClockIdentity([1, 2, 3, 4, 5, 6, 7, 8]),
+            port_number: 1,
+        };
+        let tlv = SignalingTlv::RequestUnicastTransmission(RequestUnicastTransmissionTlv {
+            message_type: MessageType::Sync,
+            log_inter_message_period: 0,
+            duration_field: 60,
+        });
+
+        let message = MessageBuilder::new().signaling_message(target_port_identity, tlv.clone());
+        let data = message.serialize_vec().unwrap();
+        assert_eq!(data.len(), 34 + 10 + 10);
+        assert_eq!(&data[34..44], &[1, 2, 3, 4, 5, 6, 7, 8, 0, 1]);
+
+        // Padding after the message is ignored
+        let mut padded = data.clone();
+        padded.extend([0; 6]);
+        let Message::Signaling(signaling) = Message::deserialize(&padded).unwrap() else {
+            panic!("Expected a signaling message");
+        };
+        assert_eq!(signaling.target_port_identity, target_port_identity);
+        assert_eq!(signaling.value.as_slice(), &[tlv]);
+    }
+}
@@ -0,0 +1,250 @@
+use super::{
+    MessageType, SlaveRxSyncComputedDataTlv, SlaveRxSyncTimingDataTlv, SlaveTxEventTimestampsTlv,
+};
+use crate::datastructures::{
+    common::{Tlv, TlvType},
+    WireFormat, WireFormatError,
+};
+
+/// The TLVs carried by signaling messages (IEEE1588-2019 section 13.12)
+///
+/// The unicast negotiation TLVs (section 16.1.4) and slave event monitoring
+/// TLVs (section 16.11.4) are parsed, all other TLVs are kept as is in
+/// [`SignalingTlv::Other`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignalingTlv {
+    RequestUnicastTransmission(RequestUnicastTransmissionTlv),
+    GrantUnicastTransmission(GrantUnicastTransmissionTlv),
+    CancelUnicastTransmission(CancelUnicastTransmissionTlv),
+    AcknowledgeCancelUnicastTransmission(CancelUnicastTransmissionTlv),
+    SlaveRxSyncTimingData(SlaveRxSyncTimingDataTlv),
+    SlaveRxSyncComputedData(SlaveRxSyncComputedDataTlv),
+    SlaveTxEventTimestamps(SlaveTxEventTimestampsTlv),
+    Other(Tlv),
+}
+
+impl SignalingTlv {
+    fn tlv_type(&self) -> TlvType {
+        match self {
+            Self::RequestUnicastTransmission(_) => TlvType::RequestUnicastTransmission,
+            Self::GrantUnicastTransmission(_) => TlvType::GrantUnicastTransmission,
+            Self::CancelUnicastTransmission(_) => TlvType::CancelUnicastTransmission,
+            Self::AcknowledgeCancelUnicastTransmission(_) => {
+                TlvType::AcknowledgeCancelUnicastTransmission
+            }
+            Self::SlaveRxSyncTimingData(_) => TlvType::SlaveRxSyncTimingData,
+            Self::SlaveRxSyncComputedData(_) => TlvType::SlaveRxSyncComputedData,
+            Self::SlaveTxEventTimestamps(_) => TlvType::SlaveTxEventTimestamps,
+            Self::Other(tlv) => tlv.tlv_type,
+        }
+    }
+}
+
+impl WireFormat for SignalingTlv {
+    fn wire_size(&self) -> usize {
+        match self {
+            Self::RequestUnicastTransmission(_) => 10,
+            Self::GrantUnicastTransmission(_) => 12,
+            Self::CancelUnicastTransmission(_) | Self::AcknowledgeCancelUnicastTransmission(_) => 6,
+            Self::SlaveRxSyncTimingData(tlv) => 4 + tlv.value_size(),
+            Self::SlaveRxSyncComputedData(tlv) => 4 + tlv.value_size(),
+            Self::SlaveTxEventTimestamps(tlv) => 4 + tlv.value_size(),
+            Self::Other(tlv) => tlv.wire_size(),
+        }
+    }
+
+    fn serialize(&self, buffer: &mut [u8]) -> Result<(), WireFormatError> {
+        if buffer.len() < self.wire_size() {
+            return Err(WireFormatError::BufferTooShort);
+        }
+
+        let value = &mut buffer[4..self.wire_size()];
+        match self {
+            Self::RequestUnicastTransmission(request) => {
+                value[0] = u8::from(request.message_type) << 4;
+                value[1] = request.log_inter_message_period as u8;
+                value[2..6].copy_from_slice(&request.duration_field.to_be_bytes());
+            }
+            Self::GrantUnicastTransmission(grant) => {
+                value[0] = u8::from(grant.message_type) << 4;
+                value[1] = grant.log_inter_message_period as u8;
+                value[2..6].copy_from_slice(&grant.duration_field.to_be_bytes());
+                value[6] = 0;
+                value[7] = grant.renewal_invited as u8;
+            }
+            Self::CancelUnicastTransmission(cancel)
+            | Self::AcknowledgeCancelUnicastTransmission(cancel) => {
+                value[0] = u8::from(cancel.message_type) << 4;
+                value[1] = 0;
+            }
+            Self::SlaveRxSyncTimingData(tlv) => tlv.serialize_value(value)?,
+            Self::SlaveRxSyncComputedData(tlv) => tlv.serialize_value(value)?,
+            Self::SlaveTxEventTimestamps(tlv) => tlv.serialize_value(value)?,
+            Self::Other(tlv) => return tlv.serialize(buffer),
+        }
+
+        buffer[0..2].copy_from_slice(&self.tlv_type().to_primitive().to_be_bytes());
+        buffer[2..4].copy_from_slice(&((self.wire_size() - 4) as u16).to_be_bytes());
+
+        Ok(())
+    }
+
+    fn deserialize(buffer: &[u8]) -> Result<Self, WireFormatError> {
+        if buffer.len() < 4 {
+            return Err(WireFormatError::BufferTooShort);
+        }
+
+        let tlv_type = TlvType::from_primitive(u16::from_be_bytes([buffer[0], buffer[1]]));
+        let length = u16::from_be_bytes([buffer[2], buffer[3]]) as usize;
+        let value = buffer
+            .get(4..4 + length)
+            .ok_or(WireFormatError::BufferTooShort)?;
+
+        let min_length = match tlv_type {
+            TlvType::RequestUnicastTransmission => 6,
+            TlvType::GrantUnicastTransmission => 8,
+            TlvType::CancelUnicastTransmission | TlvType::AcknowledgeCancelUnicastTransmission => 2,
+            _ => 0,
+        };
+        if value.len() < min_length {
+            return Err(WireFormatError::BufferTooShort);
+        }
+
+        let message_type = || MessageType::try_from(value[0] >> 4);
+        let duration_field = || u32::from_be_bytes(value[2..6].try_into().unwrap());
+
+        Ok(match tlv_type {
+            TlvType::RequestUnicastTransmission => {
+                Self::RequestUnicastTransmission(RequestUnicastTransmissionTlv {
+                    message_type: message_type()?,
+                    log_inter_message_period: value[1] as i8,
+                    duration_field: duration_field(),
+                })
+            }
+            TlvType::GrantUnicastTransmission => {
+                Self::GrantUnicastTransmission(GrantUnicastTransmissionTlv {
+                    message_type: message_type()?,
+                    log_inter_message_period: value[1] as i8,
+                    duration_field: duration_field(),
+                    renewal_invited: value[7] & 1 != 0,
+                })
+            }
+            TlvType::CancelUnicastTransmission => {
+                Self::CancelUnicastTransmission(CancelUnicastTransmissionTlv {
+                    message_type: message_type()?,
+                })
+            }
+            TlvType::AcknowledgeCancelUnicastTransmission => {
+                Self::AcknowledgeCancelUnicastTransmission(CancelUnicastTransmissionTlv {
+                    message_type: message_type()?,
+                })
+            }
+            TlvType::SlaveRxSyncTimingData => {
+                Self::SlaveRxSyncTimingData(SlaveRxSyncTimingDataTlv::deserialize_value(value)?)
+            }
+            TlvType::SlaveRxSyncComputedData => {
+                Self::SlaveRxSyncComputedData(SlaveRxSyncComputedDataTlv::deserialize_value(value)?)
+            }
+            TlvType::SlaveTxEventTimestamps => {
+                Self::SlaveTxEventTimestamps(SlaveTxEventTimestampsTlv::deserialize_value(value)?)
+            }
+            _ => Self::Other(Tlv::deserialize(buffer)?),
+        })
+    }
+}
+
+/// A REQUEST_UNICAST_TRANSMISSION TLV (IEEE1588-2019 section 16.1.4.1)
+///
+/// Requests a stream of unicast messages of `message_type`, one every
+/// 2^`log_inter_message_period` seconds, for `duration_field` seconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RequestUnicastTransmissionTlv {
+    pub message_type: MessageType,
+    pub log_inter_message_period: i8,
+    pub duration_field: u32,
+}
+
+/// A GRANT_UNICAST_TRANSMISSION TLV (IEEE1588-2019 section 16.1.4.2)
+///
+/// A `duration_field` of zero denies the request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GrantUnicastTransmissionTlv {
+    pub message_type: MessageType,
+    pub log_inter_message_period: i8,
+    pub duration_field: u32,
+    pub renewal_invited: bool,
+}
+
+/// A CANCEL_UNICAST_TRANSMISSION or ACKNOWLEDGE_CANCEL_UNICAST_TRANSMISSION
+/// TLV (IEEE1588-2019 sections 16.1.4.3 and 16.1.4.4)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CancelUnicastTransmissionTlv {
+    pub message_type: MessageType,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unicast_negotiation_wireformat() {
+        let representations: [(&[u8], SignalingTlv); 4] = [
+            (
+                &[0x00, 0x04, 0x00, 0x06, 0xb0, 0x01, 0x00, 0x00, 0x01, 0x2c],
+                SignalingTlv::RequestUnicastTransmission(RequestUnicastTransmissionTlv {
+                    message_type: MessageType::Announce,
+                    log_inter_message_period: 1,
+                    duration_field: 300,
+                }),
+            ),
+            (
+                &[
+                    0x00, 0x05, 0x00, 0x08, 0x00, 0xfd, 0x00, 0x00, 0x00, 0x3c, 0x00, 0x01,
+                ],
+                SignalingTlv::GrantUnicastTransmission(GrantUnicastTransmissionTlv {
+                    message_type: MessageType::Sync,
+                    log_inter_message_period: -3,
+                    duration_field: 60,
+                    renewal_invited: true,
+                }),
+            ),
+            (
+                &[0x00, 0x06, 0x00, 0x02, 0x90, 0x00],
+                SignalingTlv::CancelUnicastTransmission(CancelUnicastTransmissionTlv {
+                    message_type: MessageType::DelayResp,
+                }),
+            ),
+            (
+                &[0x00, 0x07, 0x00, 0x02, 0x90, 0x00],
+                SignalingTlv::AcknowledgeCancelUnicastTransmission(CancelUnicastTransmissionTlv {
+                    message_type: MessageType::DelayResp,
+                }),
+            ),
+        ];
+
+        for (byte_representation, object_representation) in representations {
+            // Test the serialization output
+            let mut serialization_buffer = [0; 12];
+            object_representation
+                .serialize(&mut serialization_buffer)
+                .unwrap();
+            assert_eq!(
+                &serialization_buffer[..object_representation.wire_size()],
+                byte_representation
+            );
+
+            // Test the deserialization output
+            let deserialized_data = SignalingTlv::deserialize(byte_representation).unwrap();
+            assert_eq!(deserialized_data, object_representation);
+        }
+    }
+
+    #[test]
+    fn unknown_message_type_is_rejected() {
+        let data = [0x00, 0x06, 0x00, 0x02, 0x40, 0x00];
+        assert!(matches!(
+            SignalingTlv::deserialize(&data),
+            Err(WireFormatError::EnumConversionError)
+        ));
+    }
+}
@@ -0,0 +1,313 @@
+//! The slave event monitoring TLVs (IEEE1588-2019 section 16.11)
+//!
+//! Slaves batch the timing of the messages they exchange with their master in
+//! these TLVs, and send them in signaling messages to a monitoring node.
+
+use arrayvec::ArrayVec;
+
+use super::MessageType;
+use crate::datastructures::{
+    common::{PortIdentity, TimeInterval, Timestamp},
+    WireFormat, WireFormatError,
+};
+
+/// The maximum number of records of a SLAVE_RX_SYNC_TIMING_DATA TLV
+///
+/// The number of records of each TLV is limited so a signaling message with
+/// the TLV still fits in [`MAX_DATA_LEN`](super::MAX_DATA_LEN) bytes, with
+/// room to spare for an AUTHENTICATION TLV.
+pub const MAX_SYNC_TIMING_RECORDS: usize = 4;
+/// The maximum number of records of a SLAVE_RX_SYNC_COMPUTED_DATA TLV
+pub const MAX_SYNC_COMPUTED_RECORDS: usize = 7;
+/// The maximum number of records of a SLAVE_TX_EVENT_TIMESTAMPS TLV
+pub const MAX_TX_EVENT_RECORDS: usize = 13;
+
+/// The bits of the computedFlags, each marking a field of the computed data
+/// records as valid (IEEE1588-2019 section 16.11.4.2). Bit 0 marks the
+/// scaledNeighborRateRatio, which is never filled in.
+const MEAN_PATH_DELAY_VALID: u8 = 1 << 1;
+const OFFSET_FROM_MASTER_VALID: u8 = 1 << 2;
+
+/// The computedFlags of the computed data records sent by this slave
+const COMPUTED_FLAGS: u8 = OFFSET_FROM_MASTER_VALID | MEAN_PATH_DELAY_VALID;
+
+/// The timing of a Sync message received by a slave
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SyncTimingRecord {
+    pub sequence_id: u16,
+    /// The precise origin timestamp of the Sync, from its Follow_Up for a two
+    /// step master
+    pub sync_origin_timestamp: Timestamp,
+    /// The sum of the corrections of the Sync and its Follow_Up
+    pub total_correction_field: TimeInterval,
+    /// The cumulative rate offset of the Follow_Up, if it has one
+    pub scaled_cumulative_rate_offset: i32,
+    /// The time the Sync was received
+    pub sync_event_ingress_timestamp: Timestamp,
+}
+
+/// The SLAVE_RX_SYNC_TIMING_DATA TLV (IEEE1588-2019 section 16.11.4.1)
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SlaveRxSyncTimingDataTlv {
+    /// The master the Sync messages were received from
+    pub sync_source_port_identity: PortIdentity,
+    pub records: ArrayVec<SyncTimingRecord, MAX_SYNC_TIMING_RECORDS>,
+}
+
+/// The offset and delay a slave computed from a Sync message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SyncComputedRecord {
+    pub sequence_id: u16,
+    pub offset_from_master: TimeInterval,
+    pub mean_path_delay: TimeInterval,
+    pub scaled_neighbor_rate_ratio: i32,
+}
+
+/// The SLAVE_RX_SYNC_COMPUTED_DATA TLV (IEEE1588-2019 section 16.11.4.2)
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SlaveRxSyncComputedDataTlv {
+    /// The master the Sync messages were received from
+    pub source_port_identity: PortIdentity,
+    pub records: ArrayVec<SyncComputedRecord, MAX_SYNC_COMPUTED_RECORDS>,
+}
+
+/// The time an event message was sent by a slave
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TxEventRecord {
+    pub sequence_id: u16,
+    pub event_egress_timestamp: Timestamp,
+}
+
+/// The SLAVE_TX_EVENT_TIMESTAMPS TLV (IEEE1588-2019 section 16.11.4.3)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlaveTxEventTimestampsTlv {
+    /// The port of the slave that sent the messages
+    pub source_port_identity: PortIdentity,
+    /// The type of the messages sent, Delay_Req for a slave using the end to
+    /// end delay mechanism
+    pub event_message_type: MessageType,
+    pub records: ArrayVec<TxEventRecord, MAX_TX_EVENT_RECORDS>,
+}
+
+impl SlaveRxSyncTimingDataTlv {
+    pub(crate) fn value_size(&self) -> usize {
+        10 + 34 * self.records.len()
+    }
+
+    pub(crate) fn serialize_value(&self, buffer: &mut [u8]) -> Result<(), WireFormatError> {
+        self.sync_source_port_identity
+            .serialize(&mut buffer[0..10])?;
+        for (record, buffer) in self.records.iter().zip(buffer[10..].chunks_exact_mut(34)) {
+            buffer[0..2].copy_from_slice(&record.sequence_id.to_be_bytes());
+            record.sync_origin_timestamp.serialize(&mut buffer[2..12])?;
+            record
+                .total_correction_field
+                .serialize(&mut buffer[12..20])?;
+            buffer[20..24].copy_from_slice(&record.scaled_cumulative_rate_offset.to_be_bytes());
+            record
+                .sync_event_ingress_timestamp
+                .serialize(&mut buffer[24..34])?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn deserialize_value(value: &[u8]) -> Result<Self, WireFormatError> {
+        let identity = value.get(0..10).ok_or(WireFormatError::BufferTooShort)?;
+        let chunks = value[10..].chunks_exact(34);
+        if !chunks.remainder().is_empty() {
+            return Err(WireFormatError::BufferTooShort);
+        }
+        let mut records = ArrayVec::new();
+        for record in chunks {
+            records.try_push(SyncTimingRecord {
+                sequence_id: u16::from_be_bytes([record[0], record[1]]),
+                sync_origin_timestamp: Timestamp::deserialize(&record[2..12])?,
+                total_correction_field: TimeInterval::deserialize(&record[12..20])?,
+                scaled_cumulative_rate_offset: i32::from_be_bytes(
+                    record[20..24].try_into().unwrap(),
+                ),
+                sync_event_ingress_timestamp: Timestamp::deserialize(&record[24..34])?,
+            })?;
+        }
+
+        Ok(Self {
+            sync_source_port_identity: PortIdentity::deserialize(identity)?,
+            records,
+        })
+    }
+}
+
+impl SlaveRxSyncComputedDataTlv {
+    pub(crate) fn value_size(&self) -> usize {
+        12 + 22 * self.records.len()
+    }
+
+    pub(crate) fn serialize_value(&self, buffer: &mut [u8]) -> Result<(), WireFormatError> {
+        self.source_port_identity.serialize(&mut buffer[0..10])?;
+        buffer[10] = COMPUTED_FLAGS;
+        buffer[11] = 0;
+        for (record, buffer) in self.records.iter().zip(buffer[12..].chunks_exact_mut(22)) {
+            buffer[0..2].copy_from_slice(&record.sequence_id.to_be_bytes());
+            record.offset_from_master.serialize(&mut buffer[2..10])?;
+            record.mean_path_delay.serialize(&mut buffer[10..18])?;
+            buffer[18..22].copy_from_slice(&record.scaled_neighbor_rate_ratio.to_be_bytes());
+        }
+        Ok(())
+    }
+
+    pub(crate) fn deserialize_value(value: &[u8]) -> Result<Self, WireFormatError> {
+        let identity = value.get(0..12).ok_or(WireFormatError::BufferTooShort)?;
+        let chunks = value[12..].chunks_exact(22);
+        if !chunks.remainder().is_empty() {
+            return Err(WireFormatError::BufferTooShort);
+        }
+        let mut records = ArrayVec::new();
+        for record in chunks {
+            records.try_push(SyncComputedRecord {
+                sequence_id: u16::from_be_bytes([record[0], record[1]]),
+                offset_from_master: TimeInterval::deserialize(&record[2..10])?,
+                mean_path_delay: TimeInterval::deserialize(&record[10..18])?,
+                scaled_neighbor_rate_ratio: i32::from_be_bytes(record[18..22].try_into().unwrap()),
+            })?;
+        }
+
+        Ok(Self {
+            source_port_identity: PortIdentity::deserialize(&identity[0..10])?,
+            records,
+        })
+    }
+}
+
+impl SlaveTxEventTimestampsTlv {
+    pub(crate) fn value_size(&self) -> usize {
+        12 + 12 * self.records.len()
+    }
+
+    pub(crate) fn serialize_value(&self, buffer: &mut [u8]) -> Result<(), WireFormatError> {
+        self.source_port_identity.serialize(&mut buffer[0..10])?;
+        buffer[10] = u8::from(self.event_message_type);
+        buffer[11] = 0;
+        for (record, buffer) in self.records.iter().zip(buffer[12..].chunks_exact_mut(12)) {
+            buffer[0..2].copy_from_slice(&record.sequence_id.to_be_bytes());
+            record
+                .event_egress_timestamp
+                .serialize(&mut buffer[2..12])?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn deserialize_value(value: &[u8]) -> Result<Self, WireFormatError> {
+        let identity = value.get(0..12).ok_or(WireFormatError::BufferTooShort)?;
+        let chunks = value[12..].chunks_exact(12);
+        if !chunks.remainder().is_empty() {
+            return Err(WireFormatError::BufferTooShort);
+        }
+        let mut records = ArrayVec::new();
+        for record in chunks {
+            records.try_push(TxEventRecord {
+                sequence_id: u16::from_be_bytes([record[0], record[1]]),
+                event_egress_timestamp: Timestamp::deserialize(&record[2..12])?,
+            })?;
+        }
+
+        Ok(Self {
+            source_port_identity: PortIdentity::deserialize(&identity[0..10])?,
+            event_message_type: MessageType::try_from(identity[10] & 0x0f)?,
+            records,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fixed::types::I48F16;
+
+    use super::*;
+    use crate::datastructures::{common::ClockIdentity, messages::SignalingTlv};
+
+    fn identity() -> PortIdentity {
+        PortIdentity {
+            clock_identity: ClockIdentity([1, 2, 3, 4, 5, 6, 7, 8]),
+            port_number: 1,
+        }
+    }
+
+    fn roundtrip(tlv: SignalingTlv, header: [u8; 4]) {
+        let mut buffer = [0; 512];
+        tlv.serialize(&mut buffer).unwrap();
+        assert_eq!(buffer[0..4], header);
+        assert_eq!(buffer[4..14], [1, 2, 3, 4, 5, 6, 7, 8, 0, 1]);
+
+        let buffer = &buffer[..tlv.wire_size()];
+        assert_eq!(SignalingTlv::deserialize(buffer).unwrap(), tlv);
+
+        // A record cut short is rejected rather than skipped
+        let mut truncated = [0; 512];
+        truncated[..buffer.len() - 1].copy_from_slice(&buffer[..buffer.len() - 1]);
+        truncated[2..4].copy_from_slice(&(buffer.len() as u16 - 5).to_be_bytes());
+        assert!(SignalingTlv::deserialize(&truncated[..buffer.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn slave_event_monitoring_wireformat() {
+        let mut timing = SlaveRxSyncTimingDataTlv {
+            sync_source_port_identity: identity(),
+            records: ArrayVec::new(),
+        };
+        for sequence_id in 0..2 {
+            timing.records.push(SyncTimingRecord {
+                sequence_id,
+                sync_origin_timestamp: Timestamp {
+                    seconds: 10,
+                    nanos: 500,
+                },
+                total_correction_field: TimeInterval(I48F16::from_num(20)),
+                scaled_cumulative_rate_offset: -3,
+                sync_event_ingress_timestamp: Timestamp {
+                    seconds: 10,
+                    nanos: 900,
+                },
+            });
+        }
+        roundtrip(
+            SignalingTlv::SlaveRxSyncTimingData(timing),
+            [0x80, 0x04, 0x00, 78],
+        );
+
+        let mut computed = SlaveRxSyncComputedDataTlv {
+            source_port_identity: identity(),
+            records: ArrayVec::new(),
+        };
+        computed.records.push(SyncComputedRecord {
+            sequence_id: 7,
+            offset_from_master: TimeInterval(I48F16::from_num(-100)),
+            mean_path_delay: TimeInterval(I48F16::from_num(300)),
+            scaled_neighbor_rate_ratio: 0,
+        });
+        let mut value = [0; 64];
+        computed.serialize_value(&mut value).unwrap();
+        // Only the offset from master and mean path delay are valid
+        assert_eq!(value[10], 0b110);
+        roundtrip(
+            SignalingTlv::SlaveRxSyncComputedData(computed),
+            [0x80, 0x05, 0x00, 34],
+        );
+
+        let mut tx = SlaveTxEventTimestampsTlv {
+            source_port_identity: identity(),
+            event_message_type: MessageType::DelayReq,
+            records: ArrayVec::new(),
+        };
+        tx.records.push(TxEventRecord {
+            sequence_id: 3,
+            event_egress_timestamp: Timestamp {
+                seconds: 11,
+                nanos: 0,
+            },
+        });
+        roundtrip(
+            SignalingTlv::SlaveTxEventTimestamps(tx),
+            [0x80, 0x06, 0x00, 24],
+        );
+    }
+}
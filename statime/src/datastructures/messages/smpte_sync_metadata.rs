@@ -0,0 +1,212 @@
+use crate::datastructures::{common::TlvType, WireFormat, WireFormatError};
+
+/// The organization id (SMPTE) and subtype of the synchronization metadata TLV
+const ORGANIZATION_ID: [u8; 3] = [0x68, 0x97, 0xe8];
+const ORGANIZATION_SUB_TYPE: [u8; 3] = [0x00, 0x00, 0x01];
+
+/// How the grandmaster is locked to its reference, as advertised in the
+/// synchronization metadata TLV
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmpteLockingStatus {
+    #[default]
+    NotInUse,
+    FreeRun,
+    ColdLocking,
+    WarmLocking,
+    Locked,
+    Unknown(u8),
+}
+
+impl SmpteLockingStatus {
+    pub fn to_primitive(&self) -> u8 {
+        match self {
+            Self::NotInUse => 0,
+            Self::FreeRun => 1,
+            Self::ColdLocking => 2,
+            Self::WarmLocking => 3,
+            Self::Locked => 4,
+            Self::Unknown(v) => *v,
+        }
+    }
+
+    pub fn from_primitive(value: u8) -> Self {
+        match value {
+            0 => Self::NotInUse,
+            1 => Self::FreeRun,
+            2 => Self::ColdLocking,
+            3 => Self::WarmLocking,
+            4 => Self::Locked,
+            v => Self::Unknown(v),
+        }
+    }
+}
+
+/// The synchronization metadata TLV of SMPTE ST 2059-2 (section 7.3)
+///
+/// Tells the slaves of a broadcast facility how to derive time codes and
+/// local time from the PTP time. All jam and jump times are in seconds of the
+/// PTP timescale, of which only the lower 48 bits are used. A jam is the
+/// moment at which devices realign their time code generators.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SmpteSyncMetadataTlv {
+    /// The numerator of the default video frame rate of the facility, e.g.
+    /// 30000 for 29.97 frames per second
+    pub default_system_frame_rate_numerator: u32,
+    /// The denominator of the default video frame rate, e.g. 1001 for 29.97
+    /// frames per second
+    pub default_system_frame_rate_denominator: u32,
+    pub master_locking_status: SmpteLockingStatus,
+    /// Whether time codes use drop frame counting
+    pub drop_frame: bool,
+    /// Whether time codes use color frame identification
+    pub color_frame_identification: bool,
+    /// The offset in seconds of local time from the PTP timescale
+    pub current_local_offset: i32,
+    /// The change of the local offset that happens at `time_of_next_jump`
+    pub jump_seconds: i32,
+    pub time_of_next_jump: u64,
+    pub time_of_next_jam: u64,
+    pub time_of_previous_jam: u64,
+    /// The local offset at the time of the previous jam
+    pub previous_jam_local_offset: i32,
+    /// Whether daylight saving time is in effect now
+    pub daylight_saving: bool,
+    /// Whether daylight saving time is in effect after the next jump
+    pub daylight_saving_at_next_jump: bool,
+    /// Whether daylight saving time was in effect at the previous jam
+    pub daylight_saving_at_previous_jam: bool,
+    /// Whether the next jump is caused by a leap second
+    pub leap_second_jump: bool,
+}
+
+impl SmpteSyncMetadataTlv {
+    /// The default video frame rate of the facility in frames per second
+    pub fn default_system_frame_rate(&self) -> f64 {
+        self.default_system_frame_rate_numerator as f64
+            / self.default_system_frame_rate_denominator as f64
+    }
+
+    /// Whether an organization extension TLV with this `value` is a
+    /// synchronization metadata TLV, only those should be deserialized as one
+    pub(crate) fn matches(value: &[u8]) -> bool {
+        value.get(0..3) == Some(&ORGANIZATION_ID) && value.get(3..6) == Some(&ORGANIZATION_SUB_TYPE)
+    }
+}
+
+impl WireFormat for SmpteSyncMetadataTlv {
+    fn wire_size(&self) -> usize {
+        52
+    }
+
+    fn serialize(&self, buffer: &mut [u8]) -> Result<(), WireFormatError> {
+        if buffer.len() < self.wire_size() {
+            return Err(WireFormatError::BufferTooShort);
+        }
+
+        buffer[0..2].copy_from_slice(&TlvType::OrganizationExtension.to_primitive().to_be_bytes());
+        buffer[2..4].copy_from_slice(&48u16.to_be_bytes());
+        buffer[4..7].copy_from_slice(&ORGANIZATION_ID);
+        buffer[7..10].copy_from_slice(&ORGANIZATION_SUB_TYPE);
+        buffer[10..14].copy_from_slice(&self.default_system_frame_rate_numerator.to_be_bytes());
+        buffer[14..18].copy_from_slice(&self.default_system_frame_rate_denominator.to_be_bytes());
+        buffer[18] = self.master_locking_status.to_primitive();
+        buffer[19] = self.drop_frame as u8 | (self.color_frame_identification as u8) << 1;
+        buffer[20..24].copy_from_slice(&self.current_local_offset.to_be_bytes());
+        buffer[24..28].copy_from_slice(&self.jump_seconds.to_be_bytes());
+        buffer[28..34].copy_from_slice(&self.time_of_next_jump.to_be_bytes()[2..8]);
+        buffer[34..40].copy_from_slice(&self.time_of_next_jam.to_be_bytes()[2..8]);
+        buffer[40..46].copy_from_slice(&self.time_of_previous_jam.to_be_bytes()[2..8]);
+        buffer[46..50].copy_from_slice(&self.previous_jam_local_offset.to_be_bytes());
+        buffer[50] = self.daylight_saving as u8
+            | (self.daylight_saving_at_next_jump as u8) << 1
+            | (self.daylight_saving_at_previous_jam as u8) << 2;
+        buffer[51] = self.leap_second_jump as u8;
+
+        Ok(())
+    }
+
+    fn deserialize(buffer: &[u8]) -> Result<Self, WireFormatError> {
+        let buffer = buffer.get(0..52).ok_or(WireFormatError::BufferTooShort)?;
+
+        let u48 = |bytes: &[u8]| {
+            let mut value = [0; 8];
+            value[2..8].copy_from_slice(bytes);
+            u64::from_be_bytes(value)
+        };
+
+        Ok(Self {
+            default_system_frame_rate_numerator: u32::from_be_bytes(
+                buffer[10..14].try_into().unwrap(),
+            ),
+            default_system_frame_rate_denominator: u32::from_be_bytes(
+                buffer[14..18].try_into().unwrap(),
+            ),
+            master_locking_status: SmpteLockingStatus::from_primitive(buffer[18]),
+            drop_frame: buffer[19] & 0x01 != 0,
+            color_frame_identification: buffer[19] & 0x02 != 0,
+            current_local_offset: i32::from_be_bytes(buffer[20..24].try_into().unwrap()),
+            jump_seconds: i32::from_be_bytes(buffer[24..28].try_into().unwrap()),
+            time_of_next_jump: u48(&buffer[28..34]),
+            time_of_next_jam: u48(&buffer[34..40]),
+            time_of_previous_jam: u48(&buffer[40..46]),
+            previous_jam_local_offset: i32::from_be_bytes(buffer[46..50].try_into().unwrap()),
+            daylight_saving: buffer[50] & 0x01 != 0,
+            daylight_saving_at_next_jump: buffer[50] & 0x02 != 0,
+            daylight_saving_at_previous_jam: buffer[50] & 0x04 != 0,
+            leap_second_jump: buffer[51] & 0x01 != 0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn smpte_sync_metadata_wireformat() {
+        let byte_representation = [
+            0x00, 0x03, 0x00, 0x30, 0x68, 0x97, 0xe8, 0x00, 0x00, 0x01, 0x00, 0x00, 0x75, 0x30,
+            0x00, 0x00, 0x03, 0xe9, 0x04, 0x01, 0x00, 0x00, 0x1c, 0x20, 0xff, 0xff, 0xf1, 0xf0,
+            0x00, 0x00, 0x65, 0x3b, 0x1d, 0x90, 0x00, 0x00, 0x65, 0x3b, 0x1d, 0x80, 0x00, 0x00,
+            0x65, 0x3a, 0xcc, 0x00, 0x00, 0x00, 0x1c, 0x20, 0x03, 0x00,
+        ];
+        let object_representation = SmpteSyncMetadataTlv {
+            default_system_frame_rate_numerator: 30000,
+            default_system_frame_rate_denominator: 1001,
+            master_locking_status: SmpteLockingStatus::Locked,
+            drop_frame: true,
+            color_frame_identification: false,
+            current_local_offset: 7200,
+            jump_seconds: -3600,
+            time_of_next_jump: 1698373008,
+            time_of_next_jam: 1698372992,
+            time_of_previous_jam: 1698352128,
+            previous_jam_local_offset: 7200,
+            daylight_saving: true,
+            daylight_saving_at_next_jump: true,
+            daylight_saving_at_previous_jam: false,
+            leap_second_jump: false,
+        };
+
+        // Test the serialization output
+        let mut serialization_buffer = [0; 52];
+        object_representation
+            .serialize(&mut serialization_buffer)
+            .unwrap();
+        assert_eq!(serialization_buffer, byte_representation);
+
+        // Test the deserialization output
+        let deserialized_data = SmpteSyncMetadataTlv::deserialize(&byte_representation).unwrap();
+        assert_eq!(deserialized_data, object_representation);
+    }
+
+    #[test]
+    fn default_system_frame_rate() {
+        let tlv = SmpteSyncMetadataTlv {
+            default_system_frame_rate_numerator: 30000,
+            default_system_frame_rate_denominator: 1001,
+            ..Default::default()
+        };
+        assert!((tlv.default_system_frame_rate() - 29.97).abs() < 0.001);
+    }
+}
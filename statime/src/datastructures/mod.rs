@@ -17,8 +17,8 @@ pub enum WireFormatError {
     CapacityError,
 }
 
-impl From<arrayvec::CapacityError> for WireFormatError {
-    fn from(_: arrayvec::CapacityError) -> Self {
+impl<T> From<arrayvec::CapacityError<T>> for WireFormatError {
+    fn from(_: arrayvec::CapacityError<T>) -> Self {
         WireFormatError::CapacityError
     }
 }
@@ -31,7 +31,7 @@ impl<Enum: num_enum::TryFromPrimitive> From<num_enum::TryFromPrimitiveError<Enum
     }
 }
 
-trait WireFormat: Debug + Clone + Eq {
+pub(crate) trait WireFormat: Debug + Clone + Eq {
     /// The byte size on the wire of this object
     fn wire_size(&self) -> usize;
 
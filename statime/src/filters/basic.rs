@@ -102,4 +102,10 @@ impl Filter for BasicFilter {
 
         (correction, freq_corr)
     }
+
+    fn converged(&self) -> bool {
+        // Within a tenth of a millisecond, which is when the offsets stop being
+        // clamped for any reasonable gain
+        self.last_step.is_some() && self.offset_confidence <= Duration::from_nanos(100_000)
+    }
 }
@@ -22,4 +22,10 @@ pub trait Filter {
     /// the master time. To prevent overshooting, oscillating, etc, the
     /// filter is allowed to apply some algorithms to prevent that.
     fn absorb(&mut self, m: Measurement) -> (Duration, f64);
+
+    /// Whether the filter has settled on the time of the master. Ports stay
+    /// uncalibrated, and are not reported as synchronized, until it has.
+    fn converged(&self) -> bool {
+        true
+    }
 }
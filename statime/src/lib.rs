@@ -123,6 +123,78 @@
 //!
 //! instance.run(&TimerImpl).await;
 //! ```
+//!
+//! # Transparent clock
+//! A transparent clock is not run by a [`PtpInstance`], but by a
+//! [`TransparentClock`]. It is set up from a default dataset created for a
+//! transparent clock and multiple ports, and does not need the time
+//! properties dataset nor a filter. Both end to end and peer to peer
+//! transparent clocks are supported:
+//!
+//! ```ignore
+//! let default_ds =
+//!     DefaultDS::new_e2e_transparent_clock(clock_identity, 2, 0, SdoId::new(0).unwrap());
+//! let port_1 = Port::new(port_1_ds, &mut network_runtime, interface_name_1).await;
+//! let port_2 = Port::new(port_2_ds, &mut network_runtime, interface_name_2).await;
+//! let mut transparent_clock = TransparentClock::new(default_ds, [port_1, port_2], local_clock);
+//!
+//! transparent_clock.run(&TimerImpl).await;
+//! ```
+//!
+//! # gPTP
+//! An instance joins a gPTP (IEEE 802.1AS) domain when its default dataset
+//! uses the [`Profile::Gptp`] profile, and its ports are set up with
+//! [`PortDS::new_gptp`]. gPTP needs a network runtime that sends and receives
+//! ethernet frames:
+//!
+//! ```ignore
+//! let default_ds = DefaultDS::new_ordinary_clock(
+//!     clock_identity,
+//!     246,
+//!     248,
+//!     0,
+//!     false,
+//!     SdoId::default(),
+//! )
+//! .with_profile(Profile::Gptp);
+//! let port_ds = PortDS::new_gptp(PortIdentity {
+//!     clock_identity,
+//!     port_number: 1,
+//! });
+//! ```
+//!
+//! # G.8275.1
+//! The telecom profile of ITU-T G.8275.1 is selected the same way, with
+//! [`Profile::G8275_1`] and ports set up with [`PortDS::new_g8275_1`]. The
+//! domain number needs to be in the range of the profile, and a grandmaster
+//! can configure the clock classes it advertises after losing its reference:
+//!
+//! ```ignore
+//! let default_ds = DefaultDS::new_ordinary_clock(
+//!     clock_identity,
+//!     128,
+//!     128,
+//!     24,
+//!     false,
+//!     SdoId::default(),
+//! )
+//! .with_profile(Profile::G8275_1)
+//! .with_holdover(Holdover {
+//!     in_spec_class: 7,
+//!     out_of_spec_class: 140,
+//!     in_spec_duration: Duration::from_secs(3600),
+//! });
+//! let port_ds = PortDS::new_g8275_1(PortIdentity {
+//!     clock_identity,
+//!     port_number: 1,
+//! })
+//! .with_local_priority(128);
+//! ```
+//!
+//! The partial timing support profile of ITU-T G.8275.2 works the same, with
+//! [`Profile::G8275_2`], a domain number from 44 to 63 and ports set up with
+//! [`PortDS::new_g8275_2`], which negotiate unicast messages with the masters
+//! given to them.
 
 #![no_std]
 #![feature(async_fn_in_trait)]
@@ -137,20 +209,34 @@ mod datastructures;
 mod filters;
 mod network;
 mod port;
+mod profile;
 mod ptp_instance;
 mod time;
+mod transparent_clock;
 mod utils;
 
 pub use clock::{Clock, Timer};
 #[cfg(feature = "fuzz")]
 pub use datastructures::messages::Message;
 pub use datastructures::{
-    common::{ClockAccuracy, ClockIdentity, ClockQuality, PortIdentity, TimeSource},
-    datasets::{DefaultDS, DelayMechanism, PortDS, TimePropertiesDS},
-    messages::{SdoId, MAX_DATA_LEN},
+    common::{
+        ClockAccuracy, ClockIdentity, ClockQuality, NetworkProtocol, PortAddress, PortIdentity,
+        PtpText, TimeSource,
+    },
+    datasets::{
+        CurrentDS, DefaultDS, DelayMechanism, DesiredState, Holdover, PortDS, TimePropertiesDS,
+        MAX_UNICAST_MASTERS,
+    },
+    messages::{
+        AlternateTimeOffsetIndicatorTlv, EnhancedAccuracyMetricsTlv, Inaccuracy, PowerProfileTlv,
+        SdoId, SmpteLockingStatus, SmpteSyncMetadataTlv, MAX_ALTERNATE_TIME_OFFSETS, MAX_DATA_LEN,
+        MAX_DISPLAY_NAME_LEN,
+    },
 };
 pub use filters::{basic::BasicFilter, Filter};
 pub use network::{NetworkPacket, NetworkPort, NetworkRuntime};
-pub use port::{Measurement, Port};
+pub use port::{Measurement, Port, SecurityAssociation, MAX_SECURITY_KEYS};
+pub use profile::Profile;
 pub use ptp_instance::PtpInstance;
 pub use time::{Duration, Instant};
+pub use transparent_clock::TransparentClock;
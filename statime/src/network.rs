@@ -2,7 +2,10 @@
 
 use arrayvec::ArrayVec;
 
-use crate::{datastructures::messages::MAX_DATA_LEN, time::Instant};
+use crate::{
+    datastructures::{common::PortAddress, messages::MAX_DATA_LEN},
+    time::Instant,
+};
 
 /// Abstract interface for interacting with the network.
 ///
@@ -55,6 +58,11 @@ pub struct NetworkPacket {
     /// instant doesn't have to be very precise. Just requesting the
     /// timestamp in software is good enough.
     pub timestamp: Instant,
+    /// The address the packet was sent from, if known.
+    ///
+    /// This is needed to answer unicast messages, for example when using
+    /// unicast negotiation.
+    pub source_address: Option<PortAddress>,
 }
 
 /// Abstract representation of a single port's network connection
@@ -80,6 +88,39 @@ pub trait NetworkPort {
     /// stability of the system clock.
     async fn send_time_critical(&mut self, data: &[u8]) -> Result<Option<Instant>, Self::Error>;
 
+    /// Send the given time-critical one-step event message.
+    ///
+    /// The data contains a complete message carrying an origin timestamp taken
+    /// from the local clock just before sending. Network ports that can insert
+    /// the actual transmit time into the message on the fly (for example
+    /// hardware with one-step timestamping support) should do so here.
+    ///
+    /// By default, the message is sent as is using
+    /// [send_time_critical](NetworkPort::send_time_critical).
+    async fn send_one_step(&mut self, data: &[u8]) -> Result<(), Self::Error> {
+        self.send_time_critical(data).await.map(|_| ())
+    }
+
+    /// Send the given non-time-critical data to a single address, instead of
+    /// to all PTP nodes on the network.
+    ///
+    /// Network ports that can't reach the address should return an error,
+    /// rather than send the data to all PTP nodes.
+    async fn send_to(&mut self, data: &[u8], address: &PortAddress) -> Result<(), Self::Error>;
+
+    /// Send the given time-critical data to a single address, instead of to
+    /// all PTP nodes on the network.
+    ///
+    /// The same timestamping requirements as for
+    /// [send_time_critical](NetworkPort::send_time_critical) apply, and the
+    /// same as for [send_to](NetworkPort::send_to) when the address can't be
+    /// reached.
+    async fn send_time_critical_to(
+        &mut self,
+        data: &[u8],
+        address: &PortAddress,
+    ) -> Result<Option<Instant>, Self::Error>;
+
     /// Wait until a message is received
     ///
     /// This future should wait until a network packet is received from either
@@ -0,0 +1,629 @@
+//! Authentication of PTP messages with the AUTHENTICATION TLV (IEEE1588-2019
+//! section 16.14 and annex P)
+
+use arrayvec::ArrayVec;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::{
+    datastructures::{
+        common::{PortAddress, PortIdentity},
+        messages::{AuthenticationTlv, Header, CORRECTION_FIELD, ICV_LEN, MAX_DATA_LEN},
+        WireFormat,
+    },
+    network::{NetworkPacket, NetworkPort},
+    time::Instant,
+};
+
+/// The maximum number of keys of a security association
+pub const MAX_SECURITY_KEYS: usize = 4;
+
+/// The maximum length of a key, longer keys are no stronger than the block
+/// size of SHA-256
+const MAX_KEY_LEN: usize = 64;
+
+/// The maximum number of senders a port keeps the sequence numbers of
+const MAX_AUTHENTICATED_SOURCES: usize = 32;
+
+/// The number of sequence numbers below the highest one received that are
+/// still accepted, event and general messages may arrive out of order
+const REPLAY_WINDOW: u64 = 64;
+
+/// The size of the AUTHENTICATION TLV appended to every message
+const TLV_LEN: usize = AuthenticationTlv::ICV_OFFSET + ICV_LEN;
+
+/// A security association with pre-shared keys (IEEE1588-2019 section 16.14.2)
+///
+/// All messages sent by a port with a security association carry an
+/// AUTHENTICATION TLV with an HMAC-SHA256-128 integrity check value, computed
+/// with the transmit key. Received messages are only processed when their ICV
+/// is valid for one of the keys of the association and their sequence number
+/// was not seen before.
+///
+/// Keys can be rolled over without losing synchronization: first add the new
+/// key to the association of every PTP instance with [`with_key`], then
+/// switch the transmit key to it with [`with_transmit_key`], and finally
+/// leave out the old key.
+///
+/// [`with_key`]: SecurityAssociation::with_key
+/// [`with_transmit_key`]: SecurityAssociation::with_transmit_key
+#[derive(Clone)]
+pub struct SecurityAssociation {
+    spp: u8,
+    keys: ArrayVec<SecurityKey, MAX_SECURITY_KEYS>,
+    transmit_key_id: u32,
+}
+
+#[derive(Clone)]
+struct SecurityKey {
+    key_id: u32,
+    key: ArrayVec<u8, MAX_KEY_LEN>,
+}
+
+impl core::fmt::Debug for SecurityAssociation {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        // Keep the keys themselves out of the logs
+        f.debug_struct("SecurityAssociation")
+            .field("spp", &self.spp)
+            .field(
+                "key_ids",
+                &self
+                    .keys
+                    .iter()
+                    .map(|key| key.key_id)
+                    .collect::<ArrayVec<_, MAX_SECURITY_KEYS>>(),
+            )
+            .field("transmit_key_id", &self.transmit_key_id)
+            .finish()
+    }
+}
+
+impl SecurityAssociation {
+    /// Create a security association identified by the security parameter
+    /// pointer `spp`, that authenticates messages with the key `key_id`
+    ///
+    /// # Panics
+    ///
+    /// When the key is longer than 64 bytes
+    pub fn new(spp: u8, key_id: u32, key: &[u8]) -> Self {
+        Self {
+            spp,
+            keys: ArrayVec::new(),
+            transmit_key_id: key_id,
+        }
+        .with_key(key_id, key)
+    }
+
+    /// Also accept received messages authenticated with the key `key_id`
+    ///
+    /// # Panics
+    ///
+    /// When the key is longer than 64 bytes, or the association already has
+    /// [`MAX_SECURITY_KEYS`] keys
+    pub fn with_key(mut self, key_id: u32, key: &[u8]) -> Self {
+        let key = SecurityKey {
+            key_id,
+            key: key.try_into().expect("security key is too long"),
+        };
+        self.keys.retain(|existing| existing.key_id != key_id);
+        self.keys.push(key);
+        self
+    }
+
+    /// Authenticate sent messages with the key `key_id`, which was added with
+    /// [`with_key`](SecurityAssociation::with_key)
+    ///
+    /// # Panics
+    ///
+    /// When the association has no key `key_id`
+    pub fn with_transmit_key(mut self, key_id: u32) -> Self {
+        assert!(self.key(key_id).is_some(), "unknown security key {key_id}");
+        self.transmit_key_id = key_id;
+        self
+    }
+
+    fn key(&self, key_id: u32) -> Option<&SecurityKey> {
+        self.keys.iter().find(|key| key.key_id == key_id)
+    }
+}
+
+impl SecurityKey {
+    /// The HMAC-SHA256 of the message, up to where the ICV is truncated to
+    ///
+    /// Transparent clocks change the correction field, so it is taken as zero.
+    fn mac(&self, message: &[u8]) -> Hmac<Sha256> {
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(&self.key).expect("HMAC accepts keys of any length");
+        mac.update(&message[..CORRECTION_FIELD.start]);
+        mac.update(&[0; 8]);
+        mac.update(&message[CORRECTION_FIELD.end..]);
+        mac
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum AuthenticationError {
+    /// The message could not be parsed far enough to authenticate it
+    Malformed,
+    /// The message does not end with an AUTHENTICATION TLV
+    Missing,
+    /// The message is authenticated by another security association
+    UnknownSecurityAssociation(u8),
+    /// The message is authenticated with a key the association doesn't have
+    UnknownKey(u32),
+    /// The ICV of the message is not valid
+    InvalidIcv,
+    /// The sequence number of the message was already seen
+    Replay(u64),
+    /// The sequence numbers of too many senders are being tracked already
+    TooManySources,
+    /// The message with the TLV does not fit in a buffer
+    TooLong,
+    /// One-step messages are changed by the network port after they are
+    /// authenticated, which invalidates their ICV
+    OneStep,
+}
+
+/// The sequence numbers received from a single sender
+#[derive(Debug, Clone)]
+struct ReplayWindow {
+    source: PortIdentity,
+    highest: u64,
+    /// Bit `n` is set when `highest - n` was received
+    seen: u64,
+}
+
+impl ReplayWindow {
+    fn new(source: PortIdentity, sequence_no: u64) -> Self {
+        Self {
+            source,
+            highest: sequence_no,
+            seen: 1,
+        }
+    }
+
+    /// Record the sequence number, returns whether it was not seen before
+    fn accept(&mut self, sequence_no: u64) -> bool {
+        if sequence_no > self.highest {
+            let shift = sequence_no - self.highest;
+            self.seen = if shift >= REPLAY_WINDOW {
+                0
+            } else {
+                self.seen << shift
+            };
+            self.seen |= 1;
+            self.highest = sequence_no;
+            return true;
+        }
+
+        let offset = self.highest - sequence_no;
+        if offset >= REPLAY_WINDOW || self.seen & (1 << offset) != 0 {
+            return false;
+        }
+
+        self.seen |= 1 << offset;
+        true
+    }
+}
+
+/// The security association of a port, and the sequence numbers it sent and
+/// received
+#[derive(Debug, Clone)]
+pub(crate) struct Authentication {
+    security_association: SecurityAssociation,
+    sequence_no: u64,
+    windows: ArrayVec<ReplayWindow, MAX_AUTHENTICATED_SOURCES>,
+}
+
+impl Authentication {
+    pub(crate) fn new(security_association: SecurityAssociation) -> Self {
+        Self {
+            security_association,
+            sequence_no: 0,
+            windows: ArrayVec::new(),
+        }
+    }
+
+    /// Continue the sequence numbers from at least the current time in
+    /// nanoseconds, so a restarted instance doesn't send sequence numbers its
+    /// peers have already seen
+    pub(crate) fn seed(&mut self, now: Instant) {
+        self.sequence_no = self.sequence_no.max(now.nanos().saturating_to_num());
+    }
+
+    /// Append an AUTHENTICATION TLV to the serialized message
+    pub(crate) fn authenticate(
+        &mut self,
+        data: &[u8],
+    ) -> Result<ArrayVec<u8, MAX_DATA_LEN>, AuthenticationError> {
+        let length = message_length(data)?;
+        if length + TLV_LEN > MAX_DATA_LEN {
+            return Err(AuthenticationError::TooLong);
+        }
+        let mut message: ArrayVec<u8, MAX_DATA_LEN> = data[..length].iter().copied().collect();
+
+        let key = self
+            .security_association
+            .key(self.security_association.transmit_key_id)
+            .expect("transmit key is part of the security association");
+        self.sequence_no += 1;
+
+        let tlv = AuthenticationTlv {
+            spp: self.security_association.spp,
+            key_id: key.key_id,
+            sequence_no: self.sequence_no,
+            icv: [0; ICV_LEN],
+        };
+        message.extend([0; TLV_LEN]);
+        message[2..4].copy_from_slice(&((length + TLV_LEN) as u16).to_be_bytes());
+        tlv.serialize(&mut message[length..])
+            .map_err(|_| AuthenticationError::TooLong)?;
+
+        let icv_start = length + AuthenticationTlv::ICV_OFFSET;
+        let mac = key.mac(&message[..icv_start]).finalize().into_bytes();
+        message[icv_start..].copy_from_slice(&mac[..ICV_LEN]);
+
+        Ok(message)
+    }
+
+    /// Verify the AUTHENTICATION TLV of the received message, and strip it
+    /// from the packet when it is valid
+    pub(crate) fn verify(&mut self, packet: &mut NetworkPacket) -> Result<(), AuthenticationError> {
+        let length = message_length(&packet.data)?;
+        let message = &packet.data[..length];
+        let tlv_start = length
+            .checked_sub(TLV_LEN)
+            .filter(|start| *start >= 34)
+            .ok_or(AuthenticationError::Missing)?;
+        if !AuthenticationTlv::matches(&message[tlv_start..]) {
+            return Err(AuthenticationError::Missing);
+        }
+
+        let tlv = AuthenticationTlv::deserialize(&message[tlv_start..])
+            .map_err(|_| AuthenticationError::Malformed)?;
+        if tlv.spp != self.security_association.spp {
+            return Err(AuthenticationError::UnknownSecurityAssociation(tlv.spp));
+        }
+        let key = self
+            .security_association
+            .key(tlv.key_id)
+            .ok_or(AuthenticationError::UnknownKey(tlv.key_id))?;
+
+        let icv_start = tlv_start + AuthenticationTlv::ICV_OFFSET;
+        key.mac(&message[..icv_start])
+            .verify_truncated_left(&tlv.icv)
+            .map_err(|_| AuthenticationError::InvalidIcv)?;
+
+        // Only authentic messages move the replay window forward
+        let source = Header::deserialize_header(message)
+            .map_err(|_| AuthenticationError::Malformed)?
+            .header
+            .source_port_identity();
+        match self
+            .windows
+            .iter_mut()
+            .find(|window| window.source == source)
+        {
+            Some(window) => {
+                if !window.accept(tlv.sequence_no) {
+                    return Err(AuthenticationError::Replay(tlv.sequence_no));
+                }
+            }
+            None => self
+                .windows
+                .try_push(ReplayWindow::new(source, tlv.sequence_no))
+                .map_err(|_| AuthenticationError::TooManySources)?,
+        }
+
+        packet.data.truncate(tlv_start);
+        packet.data[2..4].copy_from_slice(&(tlv_start as u16).to_be_bytes());
+
+        Ok(())
+    }
+}
+
+/// The length of the message in the header, without any padding the transport
+/// might have added after it
+fn message_length(data: &[u8]) -> Result<usize, AuthenticationError> {
+    let length = data
+        .get(2..4)
+        .map(|length| u16::from_be_bytes([length[0], length[1]]) as usize)
+        .ok_or(AuthenticationError::Malformed)?;
+    if length < 34 || length > data.len() {
+        return Err(AuthenticationError::Malformed);
+    }
+    Ok(length)
+}
+
+/// A network port that authenticates every message sent through it, when the
+/// port has a security association
+pub(crate) struct AuthenticatedPort<'a, P> {
+    network_port: &'a mut P,
+    authentication: Option<&'a mut Authentication>,
+}
+
+/// An error sending or receiving through an [`AuthenticatedPort`]
+#[derive(Debug)]
+pub(crate) enum AuthenticatedPortError<E> {
+    /// The message could not be authenticated, so it was not sent
+    Unauthenticated,
+    /// The network port failed to send or receive
+    Network(E),
+}
+
+impl<'a, P> AuthenticatedPort<'a, P> {
+    pub(crate) fn new(
+        network_port: &'a mut P,
+        authentication: &'a mut Option<Authentication>,
+    ) -> Self {
+        Self {
+            network_port,
+            authentication: authentication.as_mut(),
+        }
+    }
+
+    /// The data to send instead of `data`
+    fn authenticated<E>(
+        &mut self,
+        data: &[u8],
+    ) -> Result<ArrayVec<u8, MAX_DATA_LEN>, AuthenticatedPortError<E>> {
+        let Some(authentication) = &mut self.authentication else {
+            return Ok(data.iter().copied().collect());
+        };
+
+        authentication.authenticate(data).map_err(|error| {
+            log::error!("failed to authenticate message: {:?}", error);
+            AuthenticatedPortError::Unauthenticated
+        })
+    }
+}
+
+impl<'a, P: NetworkPort> NetworkPort for AuthenticatedPort<'a, P> {
+    type Error = AuthenticatedPortError<P::Error>;
+
+    async fn send(&mut self, data: &[u8]) -> Result<(), Self::Error> {
+        let data = self.authenticated(data)?;
+        self.network_port
+            .send(&data)
+            .await
+            .map_err(AuthenticatedPortError::Network)
+    }
+
+    async fn send_time_critical(&mut self, data: &[u8]) -> Result<Option<Instant>, Self::Error> {
+        let data = self.authenticated(data)?;
+        self.network_port
+            .send_time_critical(&data)
+            .await
+            .map_err(AuthenticatedPortError::Network)
+    }
+
+    async fn send_one_step(&mut self, data: &[u8]) -> Result<(), Self::Error> {
+        // The network port may insert the transmit time after the ICV is computed
+        if self.authentication.is_some() {
+            log::error!(
+                "failed to authenticate message: {:?}",
+                AuthenticationError::OneStep
+            );
+            return Err(AuthenticatedPortError::Unauthenticated);
+        }
+        self.network_port
+            .send_one_step(data)
+            .await
+            .map_err(AuthenticatedPortError::Network)
+    }
+
+    async fn send_to(&mut self, data: &[u8], address: &PortAddress) -> Result<(), Self::Error> {
+        let data = self.authenticated(data)?;
+        self.network_port
+            .send_to(&data, address)
+            .await
+            .map_err(AuthenticatedPortError::Network)
+    }
+
+    async fn send_time_critical_to(
+        &mut self,
+        data: &[u8],
+        address: &PortAddress,
+    ) -> Result<Option<Instant>, Self::Error> {
+        let data = self.authenticated(data)?;
+        self.network_port
+            .send_time_critical_to(&data, address)
+            .await
+            .map_err(AuthenticatedPortError::Network)
+    }
+
+    async fn recv(&mut self) -> Result<NetworkPacket, Self::Error> {
+        loop {
+            let mut packet = self
+                .network_port
+                .recv()
+                .await
+                .map_err(AuthenticatedPortError::Network)?;
+            let Some(authentication) = &mut self.authentication else {
+                return Ok(packet);
+            };
+
+            match authentication.verify(&mut packet) {
+                Ok(()) => return Ok(packet),
+                Err(error) => log::debug!("Ignoring unauthenticated message: {:?}", error),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        datastructures::{
+            common::{ClockIdentity, TimeInterval},
+            messages::{Message, MessageBuilder},
+        },
+        port::tests::RecordingNetworkPort,
+    };
+
+    fn sync(clock_identity: u8) -> ArrayVec<u8, MAX_DATA_LEN> {
+        MessageBuilder::new()
+            .source_port_identity(PortIdentity {
+                clock_identity: ClockIdentity([clock_identity; 8]),
+                port_number: 1,
+            })
+            .sequence_id(5)
+            .sync_message(Instant::from_secs(10).into())
+            .serialize_vec()
+            .unwrap()
+    }
+
+    fn packet(data: ArrayVec<u8, MAX_DATA_LEN>) -> NetworkPacket {
+        NetworkPacket {
+            data,
+            timestamp: Instant::from_secs(10),
+            source_address: None,
+        }
+    }
+
+    #[test]
+    fn test_authenticate_verify() {
+        let security_association = SecurityAssociation::new(1, 7, b"secret");
+        let mut sender = Authentication::new(security_association.clone());
+        let mut receiver = Authentication::new(security_association);
+
+        let data = sender.authenticate(&sync(1)).unwrap();
+        assert_eq!(data.len(), sync(1).len() + TLV_LEN);
+
+        let mut received = packet(data);
+        receiver.verify(&mut received).unwrap();
+        assert_eq!(received.data, sync(1));
+    }
+
+    #[test]
+    fn test_verify_rejects() {
+        let security_association = SecurityAssociation::new(1, 7, b"secret");
+        let mut sender = Authentication::new(security_association.clone());
+        let mut receiver = Authentication::new(security_association);
+
+        // Unauthenticated
+        assert_eq!(
+            receiver.verify(&mut packet(sync(1))),
+            Err(AuthenticationError::Missing)
+        );
+
+        // Tampered with
+        let mut data = sender.authenticate(&sync(1)).unwrap();
+        data[35] ^= 1;
+        assert_eq!(
+            receiver.verify(&mut packet(data)),
+            Err(AuthenticationError::InvalidIcv)
+        );
+
+        // Other key
+        let mut other = Authentication::new(SecurityAssociation::new(1, 8, b"secret"));
+        assert_eq!(
+            receiver.verify(&mut packet(other.authenticate(&sync(1)).unwrap())),
+            Err(AuthenticationError::UnknownKey(8))
+        );
+        let mut other = Authentication::new(SecurityAssociation::new(1, 7, b"other"));
+        assert_eq!(
+            receiver.verify(&mut packet(other.authenticate(&sync(1)).unwrap())),
+            Err(AuthenticationError::InvalidIcv)
+        );
+    }
+
+    #[test]
+    fn test_correction_not_covered() {
+        let security_association = SecurityAssociation::new(1, 7, b"secret");
+        let mut sender = Authentication::new(security_association.clone());
+        let mut receiver = Authentication::new(security_association);
+
+        let mut data = sender.authenticate(&sync(1)).unwrap();
+        data[CORRECTION_FIELD].copy_from_slice(&(100i64 << 16).to_be_bytes());
+
+        let mut received = packet(data);
+        receiver.verify(&mut received).unwrap();
+        let message = Message::deserialize(&received.data).unwrap();
+        assert_eq!(
+            message.header().correction_field(),
+            TimeInterval(100.into())
+        );
+    }
+
+    #[test]
+    fn test_replay() {
+        let security_association = SecurityAssociation::new(1, 7, b"secret");
+        let mut sender = Authentication::new(security_association.clone());
+        let mut receiver = Authentication::new(security_association);
+
+        let first = sender.authenticate(&sync(1)).unwrap();
+        let second = sender.authenticate(&sync(1)).unwrap();
+
+        // Out of order is fine, a second time is not
+        receiver.verify(&mut packet(second.clone())).unwrap();
+        receiver.verify(&mut packet(first.clone())).unwrap();
+        assert_eq!(
+            receiver.verify(&mut packet(first)),
+            Err(AuthenticationError::Replay(1))
+        );
+        assert_eq!(
+            receiver.verify(&mut packet(second)),
+            Err(AuthenticationError::Replay(2))
+        );
+
+        // Sequence numbers are tracked per sender
+        let mut other = Authentication::new(SecurityAssociation::new(1, 7, b"secret"));
+        receiver
+            .verify(&mut packet(other.authenticate(&sync(2)).unwrap()))
+            .unwrap();
+
+        // A restarted sender continues from the current time
+        sender.seed(Instant::from_secs(1));
+        let data = sender.authenticate(&sync(1)).unwrap();
+        receiver.verify(&mut packet(data)).unwrap();
+    }
+
+    #[test]
+    fn test_key_rollover() {
+        let old = SecurityAssociation::new(1, 7, b"secret");
+        let new = SecurityAssociation::new(1, 7, b"secret")
+            .with_key(8, b"new secret")
+            .with_transmit_key(8);
+        let mut old_sender = Authentication::new(old);
+        let mut new_sender = Authentication::new(new.clone());
+        let mut receiver = Authentication::new(new);
+
+        receiver
+            .verify(&mut packet(old_sender.authenticate(&sync(1)).unwrap()))
+            .unwrap();
+        receiver
+            .verify(&mut packet(new_sender.authenticate(&sync(2)).unwrap()))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_send_unauthenticated() {
+        let mut network_port = RecordingNetworkPort::default();
+        let mut authentication = Some(Authentication::new(SecurityAssociation::new(
+            1, 7, b"secret",
+        )));
+        let mut port = AuthenticatedPort::new(&mut network_port, &mut authentication);
+
+        // Messages that can't be authenticated are not sent
+        let mut too_long = [0; MAX_DATA_LEN];
+        too_long[2..4].copy_from_slice(&(MAX_DATA_LEN as u16).to_be_bytes());
+        assert!(matches!(
+            embassy_futures::block_on(port.send(&too_long)),
+            Err(AuthenticatedPortError::Unauthenticated)
+        ));
+        assert!(matches!(
+            embassy_futures::block_on(port.send_one_step(&sync(1))),
+            Err(AuthenticatedPortError::Unauthenticated)
+        ));
+        assert!(network_port.normal.is_empty());
+        assert!(network_port.time.is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_unknown_transmit_key() {
+        SecurityAssociation::new(1, 7, b"secret").with_transmit_key(8);
+    }
+}
@@ -0,0 +1,150 @@
+//! Correction of event timestamps for the latency of the port (IEEE1588-2019
+//! section 7.3.4)
+
+use arrayvec::ArrayVec;
+
+use crate::{
+    datastructures::{
+        common::{PortAddress, TimeInterval},
+        datasets::PortDS,
+        messages::{add_to_correction_field, MAX_DATA_LEN},
+    },
+    network::{NetworkPacket, NetworkPort},
+    time::{Duration, Instant},
+};
+
+/// A network port that moves the timestamps of event messages from the point
+/// where they are taken to the reference plane of the port
+pub(crate) struct LatencyCorrectedPort<'a, P> {
+    network_port: &'a mut P,
+    ingress_latency: Duration,
+    egress_latency: Duration,
+}
+
+impl<'a, P> LatencyCorrectedPort<'a, P> {
+    pub(crate) fn new(network_port: &'a mut P, port_ds: &PortDS) -> Self {
+        Self {
+            network_port,
+            ingress_latency: port_ds.ingress_latency(),
+            egress_latency: port_ds.egress_latency(),
+        }
+    }
+}
+
+/// Add the egress latency to the correction field of a one-step message, as
+/// its origin timestamp can no longer be changed after it is sent
+fn with_egress_correction(data: &[u8], egress_latency: Duration) -> ArrayVec<u8, MAX_DATA_LEN> {
+    // All messages are serialized into buffers of MAX_DATA_LEN bytes
+    let mut data: ArrayVec<u8, MAX_DATA_LEN> = data.iter().copied().collect();
+    add_to_correction_field(&mut data, TimeInterval::from(egress_latency));
+    data
+}
+
+impl<'a, P: NetworkPort> NetworkPort for LatencyCorrectedPort<'a, P> {
+    type Error = P::Error;
+
+    async fn send(&mut self, data: &[u8]) -> Result<(), Self::Error> {
+        self.network_port.send(data).await
+    }
+
+    async fn send_time_critical(&mut self, data: &[u8]) -> Result<Option<Instant>, Self::Error> {
+        let timestamp = self.network_port.send_time_critical(data).await?;
+        Ok(timestamp.map(|timestamp| timestamp + self.egress_latency))
+    }
+
+    async fn send_one_step(&mut self, data: &[u8]) -> Result<(), Self::Error> {
+        self.network_port
+            .send_one_step(&with_egress_correction(data, self.egress_latency))
+            .await
+    }
+
+    async fn send_to(&mut self, data: &[u8], address: &PortAddress) -> Result<(), Self::Error> {
+        self.network_port.send_to(data, address).await
+    }
+
+    async fn send_time_critical_to(
+        &mut self,
+        data: &[u8],
+        address: &PortAddress,
+    ) -> Result<Option<Instant>, Self::Error> {
+        let timestamp = self
+            .network_port
+            .send_time_critical_to(data, address)
+            .await?;
+        Ok(timestamp.map(|timestamp| timestamp + self.egress_latency))
+    }
+
+    async fn recv(&mut self) -> Result<NetworkPacket, Self::Error> {
+        let mut packet = self.network_port.recv().await?;
+        packet.timestamp -= self.ingress_latency;
+        Ok(packet)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        datastructures::{
+            common::{ClockIdentity, PortIdentity},
+            datasets::DelayMechanism,
+            messages::{Message, MessageBuilder},
+        },
+        port::tests::RecordingNetworkPort,
+    };
+
+    fn port_ds() -> PortDS {
+        let port_identity = PortIdentity {
+            clock_identity: ClockIdentity([1; 8]),
+            port_number: 1,
+        };
+        PortDS::new(port_identity, 0, 1, 3, 0, DelayMechanism::E2E, 0)
+            .with_ingress_latency(Duration::from_nanos(300))
+            .with_egress_latency(Duration::from_nanos(500))
+    }
+
+    #[test]
+    fn test_timestamps_corrected() {
+        let mut network_port = RecordingNetworkPort {
+            current_time: Instant::from_secs(10),
+            received: std::vec![NetworkPacket {
+                data: ArrayVec::new(),
+                timestamp: Instant::from_secs(10),
+                source_address: None,
+            }],
+            ..Default::default()
+        };
+        let mut port = LatencyCorrectedPort::new(&mut network_port, &port_ds());
+
+        let sent = embassy_futures::block_on(port.send_time_critical(&[])).unwrap();
+        assert_eq!(
+            sent,
+            Some(Instant::from_secs(10) + Duration::from_nanos(500))
+        );
+
+        let received = embassy_futures::block_on(port.recv()).unwrap();
+        assert_eq!(
+            received.timestamp,
+            Instant::from_secs(10) - Duration::from_nanos(300)
+        );
+    }
+
+    #[test]
+    fn test_one_step_corrected() {
+        let mut network_port = RecordingNetworkPort::default();
+        let mut port = LatencyCorrectedPort::new(&mut network_port, &port_ds());
+
+        let sync = MessageBuilder::new()
+            .correction_field(Duration::from_nanos(20).into())
+            .sync_message(Instant::from_secs(10).into())
+            .serialize_vec()
+            .unwrap();
+        embassy_futures::block_on(port.send_one_step(&sync)).unwrap();
+
+        let sent = Message::deserialize(&network_port.time[0]).unwrap();
+        assert_eq!(
+            sent.header().correction_field(),
+            Duration::from_nanos(520).into()
+        );
+    }
+}
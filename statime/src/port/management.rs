@@ -0,0 +1,1041 @@
+use arrayvec::ArrayVec;
+
+use super::{
+    error::Result,
+    unicast::{UnicastPort, LOG_QUERY_INTERVAL},
+    DomainPort, Port, PortError,
+};
+use crate::{
+    datastructures::{
+        common::{ClockIdentity, PortAddress, PtpText},
+        datasets::{CurrentDS, DefaultDS, ParentDS, TimePropertiesDS, MAX_UNICAST_MASTERS},
+        messages::{
+            CurrentDataSetTlv, DefaultDataSetTlv, ManagementAction, ManagementErrorId,
+            ManagementErrorStatusTlv, ManagementId, ManagementMessage, ManagementTlv,
+            MessageBuilder, ParentDataSetTlv, PortAddressTableTlv, PortDataSetTlv,
+            TimePropertiesDataSetTlv, TimescalePropertiesTlv, TraceabilityPropertiesTlv,
+            UtcPropertiesTlv, MAX_DATA_LEN,
+        },
+    },
+    network::NetworkPort,
+};
+
+/// Port number addressing all ports of a PTP instance
+pub(super) const ALL_PORTS: u16 = 0xffff;
+
+/// The maximum number of management requests a port keeps around until the
+/// instance gets to them
+const MAX_PENDING: usize = 4;
+
+/// A change to the instance wide datasets, requested through a management
+/// message on one of the ports.
+///
+/// Ports only have shared access to the instance datasets while running, so
+/// these changes are applied by the instance before it runs the BMCA.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum InstanceChange {
+    Priority1(u8),
+    Priority2(u8),
+    Domain(u8),
+}
+
+impl InstanceChange {
+    pub(crate) fn apply(self, default_ds: &mut DefaultDS) {
+        match self {
+            InstanceChange::Priority1(priority_1) => default_ds.priority_1 = priority_1,
+            InstanceChange::Priority2(priority_2) => default_ds.priority_2 = priority_2,
+            InstanceChange::Domain(domain_number) => default_ds.domain_number = domain_number,
+        }
+    }
+}
+
+/// Management work a port can't complete on its own
+#[derive(Debug, Default)]
+pub(crate) struct PendingManagement {
+    pub(crate) instance_changes: ArrayVec<InstanceChange, MAX_PENDING>,
+    /// Messages that still have boundary hops left, to be sent out on the
+    /// other ports of the instance
+    pub(crate) forwards: ArrayVec<ManagementMessage, MAX_PENDING>,
+    /// Messages for another port of the instance, with the address of the
+    /// requester
+    pub(crate) dispatches: ArrayVec<(ManagementMessage, Option<PortAddress>), MAX_PENDING>,
+}
+
+impl DomainPort {
+    /// Handle a management message (IEEE1588-2019 section 15), independently
+    /// of the port state
+    ///
+    /// Messages for another port of the instance are left to the instance,
+    /// see [`respond_management`](Self::respond_management). The domain can't
+    /// be moved to the `other_domain_numbers` of the other domains of the
+    /// instance.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) async fn handle_management_message(
+        &mut self,
+        network_port: &mut impl NetworkPort,
+        message: ManagementMessage,
+        source_address: Option<&PortAddress>,
+        default_ds: &DefaultDS,
+        time_properties_ds: &TimePropertiesDS,
+        parent_ds: &ParentDS,
+        current_ds: &CurrentDS,
+        other_domain_numbers: &[u8],
+    ) -> Result<()> {
+        let target = message.target_port_identity;
+
+        // Messages not meant for this instance alone travel on to the rest of the
+        // network through our other ports
+        if message.boundary_hops > 0 && target.clock_identity != default_ds.clock_identity {
+            let mut forward = message.clone();
+            forward.boundary_hops -= 1;
+            if self.pending_management.forwards.try_push(forward).is_err() {
+                log::warn!("Dropping management message to forward, too many pending");
+            }
+        }
+
+        if target.clock_identity != default_ds.clock_identity
+            && target.clock_identity != ClockIdentity::ALL
+        {
+            return Ok(());
+        }
+
+        let response = if target.port_number == self.port_ds.port_identity.port_number
+            || target.port_number == ALL_PORTS
+        {
+            self.respond_management(
+                &message,
+                default_ds,
+                time_properties_ds,
+                parent_ds,
+                current_ds,
+                other_domain_numbers,
+            )?
+        } else if (1..=default_ds.number_ports).contains(&target.port_number) {
+            // The addressed port answers through this one, once the instance gets to it
+            if self
+                .pending_management
+                .dispatches
+                .try_push((message, source_address.cloned()))
+                .is_err()
+            {
+                log::warn!("Dropping management message for another port, too many pending");
+            }
+            return Ok(());
+        } else {
+            self.management_response(&message, Err(ManagementErrorId::WrongValue), default_ds)?
+        };
+
+        match response {
+            Some(response) => {
+                send_management_response(
+                    network_port,
+                    self.port_ds.unicast(),
+                    &response,
+                    source_address,
+                )
+                .await
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// The response of this port to a management message addressed to it, if
+    /// the message asks for one
+    pub(crate) fn respond_management(
+        &mut self,
+        message: &ManagementMessage,
+        default_ds: &DefaultDS,
+        time_properties_ds: &TimePropertiesDS,
+        parent_ds: &ParentDS,
+        current_ds: &CurrentDS,
+        other_domain_numbers: &[u8],
+    ) -> Result<Option<ArrayVec<u8, MAX_DATA_LEN>>> {
+        let management_id = message.management_tlv.management_id();
+        let get = |port: &Self| {
+            port.management_get(
+                management_id,
+                default_ds,
+                time_properties_ds,
+                parent_ds,
+                current_ds,
+            )
+        };
+        let result = match message.action {
+            ManagementAction::GET => get(self),
+            ManagementAction::SET => {
+                match self.management_set(&message.management_tlv, default_ds, other_domain_numbers)
+                {
+                    // Report values we do know, but can't change, as such
+                    Err(ManagementErrorId::NotSupported) if get(self).is_ok() => {
+                        Err(ManagementErrorId::NotSetable)
+                    }
+                    result => result,
+                }
+            }
+            ManagementAction::COMMAND => self.management_command(management_id),
+            ManagementAction::RESPONSE
+            | ManagementAction::ACKNOWLEDGE
+            | ManagementAction::Reserved => return Ok(None),
+        };
+
+        self.management_response(message, result, default_ds)
+    }
+
+    /// The response to a management message with the `result` of its action
+    fn management_response(
+        &self,
+        message: &ManagementMessage,
+        result: core::result::Result<ManagementTlv, ManagementErrorId>,
+        default_ds: &DefaultDS,
+    ) -> Result<Option<ArrayVec<u8, MAX_DATA_LEN>>> {
+        let action = match message.action {
+            ManagementAction::GET | ManagementAction::SET => ManagementAction::RESPONSE,
+            ManagementAction::COMMAND => ManagementAction::ACKNOWLEDGE,
+            // Responses and acknowledgements are meant for management nodes
+            ManagementAction::RESPONSE
+            | ManagementAction::ACKNOWLEDGE
+            | ManagementAction::Reserved => return Ok(None),
+        };
+
+        let management_id = message.management_tlv.management_id();
+        let management_tlv = result.unwrap_or_else(|management_error_id| {
+            log::debug!(
+                "Management {:?} of {:?} failed: {:?}",
+                message.action,
+                management_id,
+                management_error_id
+            );
+            ManagementTlv::ErrorStatus(ManagementErrorStatusTlv {
+                management_error_id,
+                management_id,
+                display_data: PtpText::default(),
+            })
+        });
+
+        let hops_travelled = message
+            .starting_boundary_hops
+            .saturating_sub(message.boundary_hops);
+        let response = MessageBuilder::new()
+            .sdo_id(default_ds.sdo_id)
+            .domain_number(default_ds.domain_number)
+            .source_port_identity(self.port_ds.port_identity)
+            .sequence_id(message.header.sequence_id)
+            .log_message_interval(0x7f)
+            .management_message(
+                message.header.source_port_identity,
+                hops_travelled,
+                hops_travelled,
+                action,
+                management_tlv,
+            )
+            .serialize_vec()?;
+
+        Ok(Some(response))
+    }
+
+    fn management_get(
+        &self,
+        management_id: ManagementId,
+        default_ds: &DefaultDS,
+        time_properties_ds: &TimePropertiesDS,
+        parent_ds: &ParentDS,
+        current_ds: &CurrentDS,
+    ) -> core::result::Result<ManagementTlv, ManagementErrorId> {
+        Ok(match management_id {
+            ManagementId::NullPtpManagement => ManagementTlv::Empty(management_id),
+            ManagementId::DefaultDataSet => ManagementTlv::DefaultDataSet(DefaultDataSetTlv {
+                two_step_flag: !self.port_ds.one_step_sync(),
+                slave_only: default_ds.slave_only,
+                number_ports: default_ds.number_ports,
+                priority_1: default_ds.priority_1,
+                clock_quality: default_ds.clock_quality,
+                priority_2: default_ds.priority_2,
+                clock_identity: default_ds.clock_identity,
+                domain_number: default_ds.domain_number,
+            }),
+            ManagementId::CurrentDataSet => ManagementTlv::CurrentDataSet(CurrentDataSetTlv {
+                steps_removed: current_ds.steps_removed,
+                offset_from_master: current_ds.offset_from_master.into(),
+                mean_path_delay: current_ds.mean_delay.into(),
+            }),
+            ManagementId::ParentDataSet => ManagementTlv::ParentDataSet(ParentDataSetTlv {
+                parent_port_identity: parent_ds.parent_port_identity,
+                parent_stats: parent_ds.parent_stats,
+                observed_parent_offset_scaled_log_variance: parent_ds
+                    .observed_parent_offset_scaled_log_variance,
+                observed_parent_clock_phase_change_rate: parent_ds
+                    .observed_parent_clock_phase_change_rate,
+                grandmaster_priority_1: parent_ds.grandmaster_priority_1,
+                grandmaster_clock_quality: parent_ds.grandmaster_clock_quality,
+                grandmaster_priority_2: parent_ds.grandmaster_priority_2,
+                grandmaster_identity: parent_ds.grandmaster_identity,
+            }),
+            ManagementId::TimePropertiesDataSet => {
+                ManagementTlv::TimePropertiesDataSet(TimePropertiesDataSetTlv {
+                    current_utc_offset: time_properties_ds.current_utc_offset,
+                    leap61: time_properties_ds.leap61,
+                    leap59: time_properties_ds.leap59,
+                    current_utc_offset_valid: time_properties_ds.current_utc_offset_valid,
+                    ptp_timescale: time_properties_ds.ptp_timescale,
+                    time_traceable: time_properties_ds.time_traceable,
+                    frequency_traceable: time_properties_ds.frequency_traceable,
+                    time_source: time_properties_ds.time_source,
+                })
+            }
+            ManagementId::PortDataSet => {
+                ManagementTlv::PortDataSet(PortDataSetTlv::from(&self.port_ds))
+            }
+            ManagementId::Priority1 => ManagementTlv::Priority1(default_ds.priority_1),
+            ManagementId::Priority2 => ManagementTlv::Priority2(default_ds.priority_2),
+            ManagementId::Domain => ManagementTlv::Domain(default_ds.domain_number),
+            ManagementId::SlaveOnly => ManagementTlv::SlaveOnly(default_ds.slave_only),
+            ManagementId::ExternalPortConfigurationEnabled => {
+                ManagementTlv::ExternalPortConfigurationEnabled(
+                    default_ds.external_port_configuration_enabled,
+                )
+            }
+            ManagementId::VersionNumber => {
+                ManagementTlv::VersionNumber(PortDataSetTlv::from(&self.port_ds).version_number)
+            }
+            ManagementId::ClockAccuracy => {
+                ManagementTlv::ClockAccuracy(default_ds.clock_quality.clock_accuracy)
+            }
+            ManagementId::UtcProperties => ManagementTlv::UtcProperties(UtcPropertiesTlv {
+                current_utc_offset: time_properties_ds.current_utc_offset,
+                leap61: time_properties_ds.leap61,
+                leap59: time_properties_ds.leap59,
+                current_utc_offset_valid: time_properties_ds.current_utc_offset_valid,
+            }),
+            ManagementId::TraceabilityProperties => {
+                ManagementTlv::TraceabilityProperties(TraceabilityPropertiesTlv {
+                    time_traceable: time_properties_ds.time_traceable,
+                    frequency_traceable: time_properties_ds.frequency_traceable,
+                })
+            }
+            ManagementId::TimescaleProperties => {
+                ManagementTlv::TimescaleProperties(TimescalePropertiesTlv {
+                    ptp_timescale: time_properties_ds.ptp_timescale,
+                    time_source: time_properties_ds.time_source,
+                })
+            }
+            ManagementId::DelayMechanism => {
+                ManagementTlv::DelayMechanism(self.port_ds.delay_mechanism() as u8)
+            }
+            ManagementId::PathTraceList => {
+                ManagementTlv::PathTraceList(parent_ds.path_trace().iter().copied().collect())
+            }
+            // Path trace is always used, to keep boundary clocks out of timing loops
+            ManagementId::PathTraceEnable => ManagementTlv::PathTraceEnable(true),
+            ManagementId::LogAnnounceInterval => ManagementTlv::LogAnnounceInterval(
+                PortDataSetTlv::from(&self.port_ds).log_announce_interval,
+            ),
+            ManagementId::AnnounceReceiptTimeout => ManagementTlv::AnnounceReceiptTimeout(
+                PortDataSetTlv::from(&self.port_ds).announce_receipt_timeout,
+            ),
+            ManagementId::LogSyncInterval => ManagementTlv::LogSyncInterval(
+                PortDataSetTlv::from(&self.port_ds).log_sync_interval,
+            ),
+            ManagementId::LogMinPdelayReqInterval => ManagementTlv::LogMinPdelayReqInterval(
+                PortDataSetTlv::from(&self.port_ds).log_min_pdelay_req_interval,
+            ),
+            ManagementId::UnicastNegotiationEnable => {
+                ManagementTlv::UnicastNegotiationEnable(self.port_ds.unicast_negotiation())
+            }
+            ManagementId::UnicastMasterTable => {
+                ManagementTlv::UnicastMasterTable(PortAddressTableTlv {
+                    log_query_interval: LOG_QUERY_INTERVAL,
+                    port_addresses: self
+                        .port_ds
+                        .unicast_master_table()
+                        .iter()
+                        .cloned()
+                        .collect(),
+                })
+            }
+            ManagementId::UnicastMasterMaxTableSize => {
+                ManagementTlv::UnicastMasterMaxTableSize(MAX_UNICAST_MASTERS as u16)
+            }
+            ManagementId::Reserved(_) | ManagementId::Experimental(_) => {
+                return Err(ManagementErrorId::NoSuchId)
+            }
+            _ => return Err(ManagementErrorId::NotSupported),
+        })
+    }
+
+    fn management_set(
+        &mut self,
+        management_tlv: &ManagementTlv,
+        default_ds: &DefaultDS,
+        other_domain_numbers: &[u8],
+    ) -> core::result::Result<ManagementTlv, ManagementErrorId> {
+        let instance_change = match *management_tlv {
+            ManagementTlv::Priority1(priority_1) => Some(InstanceChange::Priority1(priority_1)),
+            ManagementTlv::Priority2(priority_2) => Some(InstanceChange::Priority2(priority_2)),
+            ManagementTlv::Domain(domain_number) => {
+                if !default_ds.profile.domain_numbers().contains(&domain_number)
+                    || other_domain_numbers.contains(&domain_number)
+                {
+                    return Err(ManagementErrorId::WrongValue);
+                }
+                Some(InstanceChange::Domain(domain_number))
+            }
+            ManagementTlv::LogAnnounceInterval(log_announce_interval) => {
+                self.port_ds
+                    .set_log_announce_interval(log_announce_interval);
+                None
+            }
+            ManagementTlv::AnnounceReceiptTimeout(announce_receipt_timeout) => {
+                self.port_ds
+                    .set_announce_receipt_timeout(announce_receipt_timeout);
+                None
+            }
+            ManagementTlv::LogSyncInterval(log_sync_interval) => {
+                self.port_ds.set_log_sync_interval(log_sync_interval);
+                None
+            }
+            ManagementTlv::LogMinPdelayReqInterval(log_min_p_delay_req_interval) => {
+                self.port_ds
+                    .set_log_min_p_delay_req_interval(log_min_p_delay_req_interval);
+                None
+            }
+            ManagementTlv::UnicastNegotiationEnable(unicast_negotiation) => {
+                self.port_ds.set_unicast_negotiation(unicast_negotiation);
+                None
+            }
+            ManagementTlv::UnicastMasterTable(ref table) => {
+                self.port_ds
+                    .set_unicast_master_table(table.port_addresses.clone());
+                None
+            }
+            // Of the port dataset, only the delay asymmetry has no management id of its own
+            ManagementTlv::PortDataSet(PortDataSetTlv {
+                delay_asymmetry: Some(delay_asymmetry),
+                ..
+            }) => {
+                self.port_ds.set_delay_asymmetry(delay_asymmetry.into());
+                // Respond with the members that did not change as they are
+                return Ok(ManagementTlv::PortDataSet(PortDataSetTlv::from(
+                    &self.port_ds,
+                )));
+            }
+            ManagementTlv::Empty(ManagementId::Reserved(_) | ManagementId::Experimental(_))
+            | ManagementTlv::Other(ManagementId::Reserved(_) | ManagementId::Experimental(_), _) => {
+                return Err(ManagementErrorId::NoSuchId)
+            }
+            ManagementTlv::Empty(_) => return Err(ManagementErrorId::WrongLength),
+            _ => return Err(ManagementErrorId::NotSupported),
+        };
+
+        if let Some(instance_change) = instance_change {
+            if self
+                .pending_management
+                .instance_changes
+                .try_push(instance_change)
+                .is_err()
+            {
+                log::warn!("Dropping management SET, too many pending");
+                return Err(ManagementErrorId::GeneralError);
+            }
+        }
+
+        Ok(management_tlv.clone())
+    }
+
+    fn management_command(
+        &mut self,
+        management_id: ManagementId,
+    ) -> core::result::Result<ManagementTlv, ManagementErrorId> {
+        match management_id {
+            ManagementId::NullPtpManagement => (),
+            ManagementId::EnablePort => self.port_ds.enable(),
+            ManagementId::DisablePort => self.port_ds.disable(),
+            ManagementId::Reserved(_) | ManagementId::Experimental(_) => {
+                return Err(ManagementErrorId::NoSuchId)
+            }
+            _ => return Err(ManagementErrorId::NotSupported),
+        }
+
+        Ok(ManagementTlv::Empty(management_id))
+    }
+
+    /// Take the management work this port couldn't complete on its own
+    pub(crate) fn take_pending_management(&mut self) -> PendingManagement {
+        core::mem::take(&mut self.pending_management)
+    }
+
+    /// Take the management messages this port received for other ports of
+    /// the instance
+    pub(crate) fn take_management_dispatches(
+        &mut self,
+    ) -> ArrayVec<(ManagementMessage, Option<PortAddress>), MAX_PENDING> {
+        core::mem::take(&mut self.pending_management.dispatches)
+    }
+}
+
+impl<P: NetworkPort, const D: usize> Port<P, D> {
+    /// Send the `response` of another port of the instance to a management
+    /// message this port received in the domain at `index`
+    pub(crate) async fn send_management_response(
+        &mut self,
+        index: usize,
+        response: &[u8],
+        source_address: Option<&PortAddress>,
+    ) -> Result<()> {
+        let unicast = self.domains[index].port_ds.unicast();
+        send_management_response(
+            &mut self.authenticated_port(),
+            unicast,
+            response,
+            source_address,
+        )
+        .await
+    }
+}
+
+async fn send_management_response(
+    network_port: &mut impl NetworkPort,
+    unicast: bool,
+    response: &[u8],
+    source_address: Option<&PortAddress>,
+) -> Result<()> {
+    // Ports without multicast answer the requester directly
+    let result = match source_address {
+        Some(address) if unicast => UnicastPort::new(network_port, address).send(response).await,
+        _ => network_port.send(response).await,
+    };
+    if let Err(error) = result {
+        log::error!("failed to send management response: {:?}", error);
+        return Err(PortError::Network);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        datastructures::{
+            common::{NetworkProtocol, PortIdentity, TimeSource},
+            datasets::{DelayMechanism, PortDS},
+            messages::{Message, SdoId},
+        },
+        port::{
+            state::PortState,
+            tests::{RecordingNetworkPort, RecordingRuntime},
+            Port,
+        },
+        profile::Profile,
+        time::Instant,
+    };
+
+    fn clock_identity() -> ClockIdentity {
+        ClockIdentity([1, 2, 3, 4, 5, 6, 7, 8])
+    }
+
+    fn manager() -> PortIdentity {
+        PortIdentity {
+            clock_identity: ClockIdentity([9; 8]),
+            port_number: 1,
+        }
+    }
+
+    fn setup() -> (Port<RecordingNetworkPort>, DefaultDS) {
+        let default_ds =
+            DefaultDS::new_ordinary_clock(clock_identity(), 128, 128, 0, false, SdoId::default());
+        let port_ds = PortDS::new(
+            PortIdentity {
+                clock_identity: clock_identity(),
+                port_number: 1,
+            },
+            0,
+            1,
+            3,
+            0,
+            DelayMechanism::E2E,
+            0,
+        );
+        let port = embassy_futures::block_on(Port::new(
+            port_ds,
+            &mut RecordingRuntime,
+            Instant::default(),
+        ));
+
+        (port, default_ds)
+    }
+
+    fn request(
+        target_port_identity: PortIdentity,
+        boundary_hops: u8,
+        action: ManagementAction,
+        management_tlv: ManagementTlv,
+    ) -> ManagementMessage {
+        let Message::Management(message) = MessageBuilder::new()
+            .source_port_identity(manager())
+            .sequence_id(42)
+            .management_message(
+                target_port_identity,
+                2,
+                boundary_hops,
+                action,
+                management_tlv,
+            )
+        else {
+            unreachable!()
+        };
+        message
+    }
+
+    fn handle(
+        port: &mut Port<RecordingNetworkPort>,
+        default_ds: &DefaultDS,
+        message: ManagementMessage,
+    ) -> Option<ManagementMessage> {
+        embassy_futures::block_on(port.domains[0].handle_management_message(
+            &mut port.network_port,
+            message,
+            None,
+            default_ds,
+            &TimePropertiesDS::new_arbitrary_time(false, false, TimeSource::InternalOscillator),
+            &ParentDS::default(),
+            &CurrentDS::default(),
+            // Another domain of the instance
+            &[3],
+        ))
+        .unwrap();
+
+        let data = port.network_port.normal.pop()?;
+        assert!(port.network_port.normal.is_empty());
+        match Message::deserialize(&data).unwrap() {
+            Message::Management(response) => Some(response),
+            _ => panic!("Unexpected message type"),
+        }
+    }
+
+    fn all_ports() -> PortIdentity {
+        PortIdentity {
+            clock_identity: ClockIdentity::ALL,
+            port_number: ALL_PORTS,
+        }
+    }
+
+    #[test]
+    fn test_get_default_ds() {
+        let (mut port, default_ds) = setup();
+
+        let response = handle(
+            &mut port,
+            &default_ds,
+            request(
+                all_ports(),
+                1,
+                ManagementAction::GET,
+                ManagementTlv::Empty(ManagementId::DefaultDataSet),
+            ),
+        )
+        .unwrap();
+
+        assert_eq!(response.action, ManagementAction::RESPONSE);
+        assert_eq!(response.target_port_identity, manager());
+        assert_eq!(response.header.sequence_id, 42);
+        assert_eq!(response.starting_boundary_hops, 1);
+        assert_eq!(response.boundary_hops, 1);
+        let ManagementTlv::DefaultDataSet(data) = response.management_tlv else {
+            panic!("Unexpected management tlv");
+        };
+        assert_eq!(data.clock_identity, clock_identity());
+        assert_eq!(data.priority_1, 128);
+        assert_eq!(data.number_ports, 1);
+        assert!(data.two_step_flag);
+    }
+
+    #[test]
+    fn test_get_port_ds() {
+        let (mut port, default_ds) = setup();
+        let port_identity = port.domains[0].port_ds.port_identity;
+
+        let response = handle(
+            &mut port,
+            &default_ds,
+            request(
+                port_identity,
+                0,
+                ManagementAction::GET,
+                ManagementTlv::Empty(ManagementId::PortDataSet),
+            ),
+        )
+        .unwrap();
+
+        let ManagementTlv::PortDataSet(data) = response.management_tlv else {
+            panic!("Unexpected management tlv");
+        };
+        assert_eq!(data.port_identity, port_identity);
+        assert_eq!(data.port_state, 0x01);
+        assert_eq!(data.log_announce_interval, 1);
+        assert_eq!(data.announce_receipt_timeout, 3);
+        assert_eq!(data.delay_mechanism, 0x01);
+        assert_eq!(data.version_number, 2);
+    }
+
+    #[test]
+    fn test_other_targets() {
+        let (mut port, mut default_ds) = setup();
+        let other_port = PortIdentity {
+            clock_identity: clock_identity(),
+            port_number: 2,
+        };
+        let get = |target| {
+            request(
+                target,
+                0,
+                ManagementAction::GET,
+                ManagementTlv::Empty(ManagementId::DefaultDataSet),
+            )
+        };
+
+        // Other clocks answer for themselves
+        let other_clock = PortIdentity {
+            clock_identity: ClockIdentity([2; 8]),
+            port_number: ALL_PORTS,
+        };
+        assert_eq!(handle(&mut port, &default_ds, get(other_clock)), None);
+
+        // Ports the instance doesn't have can't answer
+        let response = handle(&mut port, &default_ds, get(other_port)).unwrap();
+        assert_eq!(
+            response.management_tlv,
+            ManagementTlv::ErrorStatus(ManagementErrorStatusTlv {
+                management_error_id: ManagementErrorId::WrongValue,
+                management_id: ManagementId::DefaultDataSet,
+                display_data: PtpText::default(),
+            })
+        );
+
+        // Other ports of the instance answer once the instance gets to it
+        default_ds.number_ports = 2;
+        assert_eq!(handle(&mut port, &default_ds, get(other_port)), None);
+        let dispatches = port.domains[0].take_management_dispatches();
+        assert_eq!(dispatches.len(), 1);
+        assert_eq!(dispatches[0].0.target_port_identity, other_port);
+    }
+
+    #[test]
+    fn test_set() {
+        let (mut port, default_ds) = setup();
+
+        let response = handle(
+            &mut port,
+            &default_ds,
+            request(
+                all_ports(),
+                0,
+                ManagementAction::SET,
+                ManagementTlv::Priority1(12),
+            ),
+        )
+        .unwrap();
+        assert_eq!(response.action, ManagementAction::RESPONSE);
+        assert_eq!(response.management_tlv, ManagementTlv::Priority1(12));
+
+        handle(
+            &mut port,
+            &default_ds,
+            request(
+                all_ports(),
+                0,
+                ManagementAction::SET,
+                ManagementTlv::LogSyncInterval(-3),
+            ),
+        )
+        .unwrap();
+        assert_eq!(
+            port.domains[0].sync_interval(),
+            crate::time::Duration::from_log_interval(-3)
+        );
+
+        // The delay asymmetry is set through the port dataset
+        let delay_asymmetry = crate::time::Duration::from_nanos(-300);
+        let port_data_set = PortDataSetTlv {
+            delay_asymmetry: Some(delay_asymmetry.into()),
+            log_sync_interval: 5,
+            ..PortDataSetTlv::from(&port.domains[0].port_ds)
+        };
+        let response = handle(
+            &mut port,
+            &default_ds,
+            request(
+                all_ports(),
+                0,
+                ManagementAction::SET,
+                ManagementTlv::PortDataSet(port_data_set),
+            ),
+        )
+        .unwrap();
+        assert_eq!(port.domains[0].port_ds.delay_asymmetry(), delay_asymmetry);
+        assert_eq!(
+            response.management_tlv,
+            ManagementTlv::PortDataSet(PortDataSetTlv {
+                log_sync_interval: -3,
+                ..port_data_set
+            })
+        );
+
+        let pending = port.domains[0].take_pending_management();
+        assert_eq!(
+            pending.instance_changes.as_slice(),
+            &[InstanceChange::Priority1(12)]
+        );
+        assert!(pending.forwards.is_empty());
+
+        let mut default_ds = default_ds;
+        pending.instance_changes[0].apply(&mut default_ds);
+        assert_eq!(default_ds.priority_1, 12);
+
+        // Data sets can't be set
+        let default_data_set = DefaultDataSetTlv {
+            two_step_flag: true,
+            slave_only: false,
+            number_ports: 1,
+            priority_1: 1,
+            clock_quality: Default::default(),
+            priority_2: 1,
+            clock_identity: clock_identity(),
+            domain_number: 0,
+        };
+        assert_error(
+            handle(
+                &mut port,
+                &default_ds,
+                request(
+                    all_ports(),
+                    0,
+                    ManagementAction::SET,
+                    ManagementTlv::DefaultDataSet(default_data_set),
+                ),
+            ),
+            ManagementErrorId::NotSetable,
+        );
+    }
+
+    #[test]
+    fn test_set_domain() {
+        let (mut port, default_ds) = setup();
+
+        let mut set_domain = |default_ds: &DefaultDS, domain_number| {
+            handle(
+                &mut port,
+                default_ds,
+                request(
+                    all_ports(),
+                    0,
+                    ManagementAction::SET,
+                    ManagementTlv::Domain(domain_number),
+                ),
+            )
+        };
+
+        assert_eq!(
+            set_domain(&default_ds, 5).unwrap().management_tlv,
+            ManagementTlv::Domain(5)
+        );
+        // Domain 3 is taken by another domain of the instance
+        assert_error(set_domain(&default_ds, 3), ManagementErrorId::WrongValue);
+
+        // G.8275.1 only allows domains 24 to 43
+        let default_ds =
+            DefaultDS::new_ordinary_clock(clock_identity(), 128, 128, 24, false, SdoId::default())
+                .with_profile(Profile::G8275_1);
+        assert_eq!(
+            set_domain(&default_ds, 43).unwrap().management_tlv,
+            ManagementTlv::Domain(43)
+        );
+        assert_error(set_domain(&default_ds, 44), ManagementErrorId::WrongValue);
+
+        assert_eq!(
+            port.domains[0]
+                .take_pending_management()
+                .instance_changes
+                .as_slice(),
+            &[InstanceChange::Domain(5), InstanceChange::Domain(43)]
+        );
+    }
+
+    #[test]
+    fn test_unicast_master_table() {
+        let (mut port, default_ds) = setup();
+        let table = PortAddressTableTlv {
+            log_query_interval: LOG_QUERY_INTERVAL,
+            port_addresses: [PortAddress {
+                network_protocol: NetworkProtocol::UdpIPv4,
+                address: [10, 0, 0, 2].into_iter().collect(),
+            }]
+            .into_iter()
+            .collect(),
+        };
+
+        assert!(!port.domains[0].port_ds.unicast());
+        let response = handle(
+            &mut port,
+            &default_ds,
+            request(
+                all_ports(),
+                0,
+                ManagementAction::SET,
+                ManagementTlv::UnicastMasterTable(table.clone()),
+            ),
+        )
+        .unwrap();
+        assert_eq!(
+            response.management_tlv,
+            ManagementTlv::UnicastMasterTable(table)
+        );
+        assert!(port.domains[0].port_ds.unicast());
+
+        let response = handle(
+            &mut port,
+            &default_ds,
+            request(
+                all_ports(),
+                0,
+                ManagementAction::GET,
+                ManagementTlv::Empty(ManagementId::UnicastMasterMaxTableSize),
+            ),
+        )
+        .unwrap();
+        assert_eq!(
+            response.management_tlv,
+            ManagementTlv::UnicastMasterMaxTableSize(MAX_UNICAST_MASTERS as u16)
+        );
+    }
+
+    fn assert_error(response: Option<ManagementMessage>, error: ManagementErrorId) {
+        let response = response.unwrap();
+        assert_eq!(response.action, ManagementAction::RESPONSE);
+        let ManagementTlv::ErrorStatus(error_status) = response.management_tlv else {
+            panic!(
+                "Expected an error status, got {:?}",
+                response.management_tlv
+            );
+        };
+        assert_eq!(error_status.management_error_id, error);
+    }
+
+    #[test]
+    fn test_errors() {
+        let (mut port, default_ds) = setup();
+
+        let mut send = |action, management_tlv| {
+            handle(
+                &mut port,
+                &default_ds,
+                request(all_ports(), 0, action, management_tlv),
+            )
+        };
+
+        assert_error(
+            send(
+                ManagementAction::GET,
+                ManagementTlv::Empty(ManagementId::Reserved(0x1234)),
+            ),
+            ManagementErrorId::NoSuchId,
+        );
+        assert_error(
+            send(
+                ManagementAction::GET,
+                ManagementTlv::Empty(ManagementId::FaultLog),
+            ),
+            ManagementErrorId::NotSupported,
+        );
+        assert_error(
+            send(
+                ManagementAction::SET,
+                ManagementTlv::Empty(ManagementId::Priority1),
+            ),
+            ManagementErrorId::WrongLength,
+        );
+        assert_error(
+            send(ManagementAction::SET, ManagementTlv::SlaveOnly(true)),
+            ManagementErrorId::NotSetable,
+        );
+    }
+
+    #[test]
+    fn test_disable_enable_port() {
+        let (mut port, default_ds) = setup();
+
+        let response = handle(
+            &mut port,
+            &default_ds,
+            request(
+                all_ports(),
+                0,
+                ManagementAction::COMMAND,
+                ManagementTlv::Empty(ManagementId::DisablePort),
+            ),
+        )
+        .unwrap();
+        assert_eq!(response.action, ManagementAction::ACKNOWLEDGE);
+        assert!(matches!(
+            port.domains[0].port_ds.port_state,
+            PortState::Disabled
+        ));
+
+        handle(
+            &mut port,
+            &default_ds,
+            request(
+                all_ports(),
+                0,
+                ManagementAction::COMMAND,
+                ManagementTlv::Empty(ManagementId::EnablePort),
+            ),
+        )
+        .unwrap();
+        assert!(matches!(
+            port.domains[0].port_ds.port_state,
+            PortState::Listening
+        ));
+    }
+
+    #[test]
+    fn test_boundary_hops() {
+        let (mut port, default_ds) = setup();
+
+        handle(
+            &mut port,
+            &default_ds,
+            request(
+                all_ports(),
+                2,
+                ManagementAction::GET,
+                ManagementTlv::Empty(ManagementId::Priority1),
+            ),
+        )
+        .unwrap();
+
+        // Messages without hops left, or addressed to this instance only, stay here
+        let port_identity = port.domains[0].port_ds.port_identity;
+        for (target, boundary_hops) in [(all_ports(), 0), (port_identity, 2)] {
+            handle(
+                &mut port,
+                &default_ds,
+                request(
+                    target,
+                    boundary_hops,
+                    ManagementAction::GET,
+                    ManagementTlv::Empty(ManagementId::Priority1),
+                ),
+            )
+            .unwrap();
+        }
+
+        let pending = port.domains[0].take_pending_management();
+        assert_eq!(pending.forwards.len(), 1);
+        assert_eq!(pending.forwards[0].boundary_hops, 1);
+        assert_eq!(pending.forwards[0].starting_boundary_hops, 2);
+        assert!(port.domains[0]
+            .take_pending_management()
+            .forwards
+            .is_empty());
+    }
+}
@@ -1,46 +1,94 @@
-use core::{cell::RefCell, future::Future, pin::Pin};
+use core::{
+    cell::{Cell, RefCell},
+    future::Future,
+    pin::Pin,
+};
 
-use embassy_futures::{select, select::Either3};
+use arrayvec::ArrayVec;
+use authentication::AuthenticatedPort;
+pub(crate) use authentication::{Authentication, AuthenticationError};
+pub use authentication::{SecurityAssociation, MAX_SECURITY_KEYS};
+use embassy_futures::{
+    select,
+    select::{select_slice, Either, Either3, Either4},
+};
 pub use error::{PortError, Result};
 use futures::StreamExt;
+use latency::LatencyCorrectedPort;
+pub(crate) use management::InstanceChange;
+use management::PendingManagement;
 pub use measurement::Measurement;
+use monitoring::SlaveEventMonitor;
+use peer_delay::PeerDelayState;
 use state::{MasterState, PortState};
 pub use ticker::Ticker;
+pub(crate) use unicast::MAX_UNICAST_GRANTS;
+use unicast::{UnicastNegotiation, UnicastPort};
 
 use crate::{
     bmc::bmca::{BestAnnounceMessage, Bmca, RecommendedState},
     clock::Clock,
     datastructures::{
         common::{PortIdentity, TimeSource, Timestamp},
-        datasets::{CurrentDS, DefaultDS, ParentDS, PortDS, TimePropertiesDS},
-        messages::Message,
+        datasets::{
+            CurrentDS, DefaultDS, DelayMechanism, DesiredState, ParentDS, PortDS, TimePropertiesDS,
+        },
+        messages::{Header, Message, MessageType},
     },
     filters::Filter,
     network::{NetworkPacket, NetworkPort, NetworkRuntime},
-    time::Duration,
+    profile::Profile,
+    ptp_instance::Domain,
+    time::{Duration, Instant},
     utils::Signal,
 };
 
+mod authentication;
 mod error;
+mod latency;
+mod management;
 mod measurement;
+mod monitoring;
+mod peer_delay;
 mod sequence_id;
 pub mod state;
 #[cfg(test)]
-mod tests;
+pub(crate) mod tests;
 mod ticker;
+mod unicast;
 
 /// A single port of the PTP instance
 ///
-/// One of these needs to be created per port of the PTP instance.
-pub struct Port<P> {
-    port_ds: PortDS,
+/// One of these needs to be created per port of the PTP instance. The port
+/// carries the messages of every domain of the instance, and keeps a separate
+/// state for each of them. It has room for the state of `D` domains, see
+/// [`Port::with_max_domains`].
+pub struct Port<P, const D: usize = 1> {
     network_port: P,
+    authentication: Option<Authentication>,
+    domains: ArrayVec<DomainPort, D>,
+}
+
+/// The state of a [`Port`] in a single domain of the PTP instance
+pub(crate) struct DomainPort {
+    port_ds: PortDS,
     bmca: Bmca,
+    peer_delay: PeerDelayState,
+    pending_management: PendingManagement,
+    unicast_negotiation: UnicastNegotiation,
+    slave_event_monitor: SlaveEventMonitor,
+    /// When the last sync from the master was received, and the sync interval
+    /// it announced
+    last_sync: Option<(Instant, i8)>,
 }
 
 impl<P> Port<P> {
     /// Create a new port from a port dataset on a given interface.
     ///
+    /// The port dataset configures the port for the first domain of the
+    /// instance, see [`PtpInstance::with_domain`](crate::PtpInstance::with_domain)
+    /// for the other domains.
+    ///
     /// For example, when using the `statime-linux` network runtime, a port on
     /// `eth0` for an ordinary clock can be created with
     ///
@@ -72,126 +120,529 @@ impl<P> Port<P> {
             .await
             .expect("Could not create network port");
 
-        let bmca = Bmca::new(port_ds.announce_interval().into(), port_ds.port_identity);
+        let mut domains = ArrayVec::new();
+        domains.push(DomainPort::new(port_ds));
 
         Port {
-            port_ds,
             network_port,
-            bmca,
+            authentication: None,
+            domains,
+        }
+    }
+}
+
+impl<P, const D: usize> Port<P, D> {
+    /// Make room for the state of `E` domains in the port, so the
+    /// [instance](crate::PtpInstance::with_domain) can take part in `E`
+    /// domains
+    ///
+    /// Every domain takes memory in all ports of the instance, so a port only
+    /// has room for a single domain by default.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the port already has more than `E` domains.
+    pub fn with_max_domains<const E: usize>(self) -> Port<P, E> {
+        Port {
+            network_port: self.network_port,
+            authentication: self.authentication,
+            domains: self.domains.into_iter().collect(),
         }
     }
 
+    /// Authenticate all messages of the port with the security association
+    /// (IEEE1588-2019 section 16.14)
+    ///
+    /// Every sent message gets an AUTHENTICATION TLV, and received messages
+    /// without a valid one are dropped before they reach any domain, or before
+    /// a transparent clock forwards them. The correction field is not covered,
+    /// so authenticated messages can pass through transparent clocks. Power
+    /// profile transparent clocks also update the IEEE_C37_238 TLV of
+    /// announces, which does invalidate them.
+    ///
+    /// One-step Sync messages can't be authenticated, as the network port may
+    /// insert the transmit time after the ICV is computed. A port with a
+    /// security association fails to send them, so its domains need to use
+    /// two-step Sync messages.
+    pub fn with_security_association(mut self, security_association: SecurityAssociation) -> Self {
+        self.authentication = Some(Authentication::new(security_association));
+        self
+    }
+
+    /// Take part in another domain, with the port configuration of that domain
+    pub(crate) fn add_domain(&mut self, port_ds: PortDS) {
+        self.domains
+            .try_push(DomainPort::new(port_ds))
+            .expect("too many domains");
+    }
+
+    /// The identity of the port in the first domain
     pub(crate) fn identity(&self) -> PortIdentity {
-        self.port_ds.port_identity
+        self.domains[0].port_ds.port_identity
+    }
+
+    pub(crate) fn network_port(&mut self) -> &mut P {
+        &mut self.network_port
+    }
+
+    /// The network port, correcting timestamps for the latency of the port in
+    /// the first domain
+    pub(crate) fn latency_corrected_port(&mut self) -> LatencyCorrectedPort<'_, P> {
+        LatencyCorrectedPort::new(&mut self.network_port, &self.domains[0].port_ds)
+    }
+
+    pub(crate) fn ingress_latency(&self) -> Duration {
+        self.domains[0].port_ds.ingress_latency()
+    }
+
+    /// The network port, authenticating the messages sent through it
+    pub(crate) fn authenticated_port(&mut self) -> AuthenticatedPort<'_, P> {
+        AuthenticatedPort::new(&mut self.network_port, &mut self.authentication)
+    }
+
+    /// Verify the AUTHENTICATION TLV of a received packet and strip it, when
+    /// the port has a security association
+    pub(crate) fn verify(
+        &mut self,
+        packet: &mut NetworkPacket,
+    ) -> core::result::Result<(), AuthenticationError> {
+        match &mut self.authentication {
+            Some(authentication) => authentication.verify(packet),
+            None => Ok(()),
+        }
+    }
+
+    pub(crate) fn domains(&self) -> &[DomainPort] {
+        &self.domains
+    }
+
+    pub(crate) fn domain_mut(&mut self, index: usize) -> &mut DomainPort {
+        &mut self.domains[index]
+    }
+
+    /// Finish the initialization of the port in all domains
+    pub(crate) fn initialize(&mut self) {
+        for domain_port in &mut self.domains {
+            domain_port.initialize();
+        }
+    }
+
+    pub(crate) fn min_p_delay_req_interval(&self) -> Duration {
+        self.domains[0].min_p_delay_req_interval()
+    }
+
+    pub(crate) fn mean_link_delay(&self) -> Duration {
+        self.domains[0].mean_link_delay()
     }
 }
 
-impl<P: NetworkPort> Port<P> {
+impl<P: NetworkPort, const D: usize> Port<P, D> {
+    /// Run the port until the BMCA is due
+    ///
+    /// Every domain of the port has its own timeouts, given in the order of
+    /// the domains of the instance. Received messages are handed to the domain
+    /// they belong to, messages of other domains are dropped.
     #[allow(clippy::too_many_arguments)]
     pub(crate) async fn run_port<F: Future>(
         &mut self,
         local_clock: &RefCell<impl Clock>,
-        filter: &RefCell<impl Filter>,
-        announce_receipt_timeout: &mut Pin<&mut Ticker<F, impl FnMut(Duration) -> F>>,
-        sync_timeout: &mut Pin<&mut Ticker<F, impl FnMut(Duration) -> F>>,
-        announce_timeout: &mut Pin<&mut Ticker<F, impl FnMut(Duration) -> F>>,
-        default_ds: &DefaultDS,
-        time_properties_ds: &TimePropertiesDS,
-        parent_ds: &ParentDS,
-        current_ds: &CurrentDS,
+        announce_receipt_timeouts: &mut [Pin<&mut Ticker<F, impl FnMut(Duration) -> F>>],
+        sync_timeouts: &mut [Pin<&mut Ticker<F, impl FnMut(Duration) -> F>>],
+        announce_timeouts: &mut [Pin<&mut Ticker<F, impl FnMut(Duration) -> F>>],
+        pdelay_timeouts: &mut [Pin<&mut Ticker<F, impl FnMut(Duration) -> F>>],
+        unicast_timeouts: &mut [Pin<&mut Ticker<F, impl FnMut(Duration) -> F>>],
+        domains: &[Domain<impl Filter>],
+        clock_domain: usize,
         mut stop: Signal<'_>,
     ) {
+        self.initialize();
+
+        if let (Some(authentication), Ok(clock)) =
+            (&mut self.authentication, local_clock.try_borrow())
+        {
+            authentication.seed(clock.now());
+        }
+
         loop {
-            log::trace!("Loop iter port {}", self.port_ds.port_identity.port_number);
-            let timeouts = select::select3(
-                announce_receipt_timeout.next(),
-                sync_timeout.next(),
-                announce_timeout.next(),
-            );
-            let packet = self.network_port.recv();
-            match select::select3(timeouts, packet, stop.wait_for()).await {
-                Either3::First(timeout) => match timeout {
-                    Either3::First(_) => {
-                        log::trace!(
-                            "Port {} force master timeout",
-                            self.port_ds.port_identity.port_number
-                        );
-                        // No announces received for a long time, become master
-                        match self.port_ds.port_state {
-                            PortState::Master(_) => (),
-                            _ => self
-                                .port_ds
-                                .set_forced_port_state(PortState::Master(MasterState::new())),
-                        }
+            log::trace!("Loop iter port {}", self.identity().port_number);
+            let event = {
+                let mut announce_receipt_ticks = next_ticks::<_, _, D>(announce_receipt_timeouts);
+                let mut sync_ticks = next_ticks::<_, _, D>(sync_timeouts);
+                let mut announce_ticks = next_ticks::<_, _, D>(announce_timeouts);
+                let mut pdelay_ticks = next_ticks::<_, _, D>(pdelay_timeouts);
+                let mut unicast_ticks = next_ticks::<_, _, D>(unicast_timeouts);
+                let timeouts = select::select(
+                    select::select4(
+                        select_slice(Pin::new(announce_receipt_ticks.as_mut_slice())),
+                        select_slice(Pin::new(sync_ticks.as_mut_slice())),
+                        select_slice(Pin::new(announce_ticks.as_mut_slice())),
+                        select_slice(Pin::new(pdelay_ticks.as_mut_slice())),
+                    ),
+                    select_slice(Pin::new(unicast_ticks.as_mut_slice())),
+                );
+                let packet = self.network_port.recv();
+                select::select3(timeouts, packet, stop.wait_for()).await
+            };
+
+            match event {
+                Either3::First(Either::Second((_, index))) => {
+                    let domain_port = &mut self.domains[index];
+                    if let Err(error) = domain_port
+                        .handle_unicast_timeout(
+                            &mut AuthenticatedPort::new(
+                                &mut self.network_port,
+                                &mut self.authentication,
+                            ),
+                            &domains[index].default_ds,
+                        )
+                        .await
+                    {
+                        domain_port.handle_error(error);
+                    }
+                }
+                Either3::First(Either::First(timeout)) => match timeout {
+                    Either4::First((_, index)) => {
+                        self.domains[index]
+                            .handle_announce_receipt_timeout(&domains[index].default_ds);
                     }
-                    Either3::Second(_) => {
+                    Either4::Second((_, index)) => {
+                        let domain_port = &mut self.domains[index];
                         log::trace!(
                             "Port {} sync timeout",
-                            self.port_ds.port_identity.port_number
+                            domain_port.port_ds.port_identity.port_number
                         );
+                        if let Ok(clock) = local_clock.try_borrow() {
+                            domain_port.check_sync_receipt_timeout(
+                                &domains[index].default_ds,
+                                clock.now(),
+                            );
+                        }
                         // Send sync message
-                        if let Err(error) = self.send_sync(local_clock, default_ds).await {
-                            log::error!("{:?}", error);
+                        if let Err(error) = domain_port
+                            .send_sync(
+                                &mut LatencyCorrectedPort::new(
+                                    &mut AuthenticatedPort::new(
+                                        &mut self.network_port,
+                                        &mut self.authentication,
+                                    ),
+                                    &domain_port.port_ds,
+                                ),
+                                local_clock,
+                                &domains[index].default_ds,
+                            )
+                            .await
+                        {
+                            domain_port.handle_error(error);
                         }
                     }
-                    Either3::Third(_) => {
+                    Either4::Third((_, index)) => {
+                        let domain = &domains[index];
+                        let domain_port = &mut self.domains[index];
                         log::trace!(
                             "Port {} announce timeout",
-                            self.port_ds.port_identity.port_number
+                            domain_port.port_ds.port_identity.port_number
                         );
+                        domain_port.port_ds.qualification_timeout();
                         // Send announce message
-                        if let Err(error) = self
+                        if let Err(error) = domain_port
                             .send_announce(
+                                &mut AuthenticatedPort::new(
+                                    &mut self.network_port,
+                                    &mut self.authentication,
+                                ),
+                                local_clock,
+                                &domain.default_ds,
+                                &domain.time_properties_ds,
+                                &domain.parent_ds,
+                                &domain.current_ds.get(),
+                            )
+                            .await
+                        {
+                            domain_port.handle_error(error);
+                        }
+                    }
+                    Either4::Fourth((_, index)) => {
+                        let domain_port = &mut self.domains[index];
+                        log::trace!(
+                            "Port {} pdelay timeout",
+                            domain_port.port_ds.port_identity.port_number
+                        );
+                        // Send peer delay request
+                        if let Err(error) = domain_port
+                            .send_pdelay_req(
+                                &mut LatencyCorrectedPort::new(
+                                    &mut AuthenticatedPort::new(
+                                        &mut self.network_port,
+                                        &mut self.authentication,
+                                    ),
+                                    &domain_port.port_ds,
+                                ),
                                 local_clock,
-                                default_ds,
-                                time_properties_ds,
-                                parent_ds,
-                                current_ds,
+                                &domains[index].default_ds,
                             )
                             .await
                         {
-                            log::error!("{:?}", error);
+                            domain_port.handle_error(error);
                         }
                     }
                 },
-                Either3::Second(Ok(packet)) => {
+                Either3::Second(Ok(mut packet)) => {
                     log::trace!(
                         "Port {} message received: {:?}",
-                        self.port_ds.port_identity.port_number,
+                        self.identity().port_number,
                         packet
                     );
-                    // Process packet
-                    if let Err(error) = self
+
+                    // Only authentic messages are processed (IEEE1588-2019 section 16.14.3)
+                    if let Err(error) = self.verify(&mut packet) {
+                        log::debug!("Ignoring unauthenticated message: {:?}", error);
+                        continue;
+                    }
+
+                    // Every domain only processes its own messages (IEEE1588-2019 section
+                    // 7.1)
+                    let header = match Header::deserialize_header(&packet.data) {
+                        Ok(deserialized) => deserialized.header,
+                        Err(error) => {
+                            log::error!("{:?}", PortError::from(error));
+                            continue;
+                        }
+                    };
+                    let Some(index) = domains.iter().position(|domain| {
+                        header.sdo_id() == domain.default_ds.sdo_id
+                            && header.domain_number() == domain.default_ds.domain_number
+                    }) else {
+                        continue;
+                    };
+
+                    let domain = &domains[index];
+                    let other_domain_numbers = Domain::other_domain_numbers::<D>(domains, index);
+                    let domain_port = &mut self.domains[index];
+                    packet.timestamp -= domain_port.port_ds.ingress_latency();
+                    if let Err(error) = domain_port
                         .handle_packet(
+                            &mut LatencyCorrectedPort::new(
+                                &mut AuthenticatedPort::new(
+                                    &mut self.network_port,
+                                    &mut self.authentication,
+                                ),
+                                &domain_port.port_ds,
+                            ),
                             packet,
                             local_clock,
-                            filter,
-                            announce_receipt_timeout,
-                            default_ds,
-                            time_properties_ds,
+                            &domain.filter,
+                            &mut announce_receipt_timeouts[index],
+                            &domain.default_ds,
+                            &domain.time_properties_ds,
+                            &domain.parent_ds,
+                            &domain.current_ds,
+                            &other_domain_numbers,
+                            index == clock_domain,
                         )
                         .await
                     {
-                        log::error!("{:?}", error);
+                        domain_port.handle_error(error);
+                    }
+                }
+                Either3::Second(Err(error)) => {
+                    log::error!("failed to receive packet {:?}", error);
+                    for domain_port in &mut self.domains {
+                        domain_port.handle_error(PortError::Network);
                     }
                 }
-                Either3::Second(Err(error)) => log::error!("failed to parse packet {:?}", error),
                 Either3::Third(_) => {
-                    log::trace!(
-                        "Port {} bmca trigger",
-                        self.port_ds.port_identity.port_number
-                    );
+                    log::trace!("Port {} bmca trigger", self.identity().port_number);
                     break;
                 }
             }
         }
     }
 
+    pub(crate) async fn send_pdelay_req(
+        &mut self,
+        local_clock: &RefCell<impl Clock>,
+        default_ds: &DefaultDS,
+    ) -> Result<()> {
+        let domain_port = &mut self.domains[0];
+        domain_port
+            .send_pdelay_req(
+                &mut LatencyCorrectedPort::new(
+                    &mut AuthenticatedPort::new(&mut self.network_port, &mut self.authentication),
+                    &domain_port.port_ds,
+                ),
+                local_clock,
+                default_ds,
+            )
+            .await
+    }
+
+    /// Handle a peer delay message in the first domain, independently of the
+    /// port state
+    ///
+    /// The `timestamp` needs to be corrected for the ingress latency already.
+    pub(crate) async fn handle_peer_delay_message(
+        &mut self,
+        message: Message,
+        timestamp: Instant,
+    ) -> Result<()> {
+        let domain_port = &mut self.domains[0];
+        domain_port
+            .handle_peer_delay_message(
+                &mut LatencyCorrectedPort::new(
+                    &mut AuthenticatedPort::new(&mut self.network_port, &mut self.authentication),
+                    &domain_port.port_ds,
+                ),
+                message,
+                timestamp,
+            )
+            .await
+    }
+}
+
+/// The next tick of each of the tickers
+fn next_ticks<'a, 'b, F: Future, R: FnMut(Duration) -> F, const D: usize>(
+    tickers: &'a mut [Pin<&'b mut Ticker<F, R>>],
+) -> ArrayVec<futures::stream::Next<'a, Pin<&'b mut Ticker<F, R>>>, D> {
+    tickers.iter_mut().map(|ticker| ticker.next()).collect()
+}
+
+impl DomainPort {
+    fn new(port_ds: PortDS) -> Self {
+        let bmca = Bmca::new(port_ds.announce_interval().into(), port_ds.port_identity);
+
+        DomainPort {
+            port_ds,
+            bmca,
+            peer_delay: PeerDelayState::new(),
+            pending_management: PendingManagement::default(),
+            unicast_negotiation: UnicastNegotiation::default(),
+            slave_event_monitor: SlaveEventMonitor::default(),
+            last_sync: None,
+        }
+    }
+
+    pub(crate) fn port_ds(&self) -> &PortDS {
+        &self.port_ds
+    }
+
+    /// Finish the initialization of the port, and give faulty ports another
+    /// try (IEEE1588-2019 section 9.2.5)
+    fn initialize(&mut self) {
+        if let PortState::Faulty = self.port_ds.port_state {
+            self.port_ds.set_forced_port_state(PortState::Initializing);
+        }
+        if let PortState::Initializing = self.port_ds.port_state {
+            self.port_ds.set_forced_port_state(PortState::Listening);
+        }
+    }
+
+    fn handle_announce_receipt_timeout(&mut self, default_ds: &DefaultDS) {
+        log::trace!(
+            "Port {} force master timeout",
+            self.port_ds.port_identity.port_number
+        );
+        // No announces received for a long time, become master
+        match self.port_ds.port_state {
+            PortState::Listening if default_ds.slave_only => (),
+            PortState::Listening | PortState::Passive
+                if default_ds.external_port_configuration_enabled => {}
+            PortState::Listening
+            | PortState::Passive
+            | PortState::Uncalibrated(_)
+            | PortState::Slave(_) => self
+                .port_ds
+                .set_forced_port_state(Self::timeout_state(default_ds)),
+            _ => (),
+        }
+    }
+
+    /// Log an error, network errors make the port faulty until the next run of
+    /// the BMCA
+    fn handle_error(&mut self, error: PortError) {
+        log::error!("{:?}", error);
+
+        if matches!(error, PortError::Network)
+            && !matches!(
+                self.port_ds.port_state,
+                PortState::Faulty | PortState::Disabled
+            )
+        {
+            self.port_ds.set_forced_port_state(PortState::Faulty);
+        }
+    }
+
     pub(crate) fn best_local_announce_message(
         &mut self,
         current_time: Timestamp,
+        default_ds: &DefaultDS,
     ) -> Option<BestAnnounceMessage> {
-        self.bmca.take_best_port_announce_message(current_time)
+        let erbest = self.bmca.take_best_port_announce_message(
+            current_time,
+            default_ds.profile,
+            self.port_ds.local_priority(),
+        );
+
+        // A master only port never synchronizes to the masters it hears about
+        if self.port_ds.master_only() {
+            return None;
+        }
+
+        erbest
+    }
+
+    /// Whether the port takes part in synchronization. In a gPTP domain, that
+    /// depends on the measurements to its neighbour (asCapable, IEEE
+    /// 802.1AS-2020 section 11.2.2)
+    fn as_capable(&self, default_ds: &DefaultDS) -> bool {
+        default_ds.profile != Profile::Gptp
+            || self
+                .peer_delay
+                .as_capable(self.port_ds.neighbor_prop_delay_thresh())
+    }
+
+    fn check_sync_receipt_timeout(&mut self, default_ds: &DefaultDS, current_time: Instant) {
+        let (Some(_), Some(sync_receipt_timeout)) = (
+            self.port_ds.port_state.remote_master(),
+            self.port_ds.sync_receipt_timeout(),
+        ) else {
+            self.last_sync = None;
+            return;
+        };
+
+        // Until the first sync of the master, wait as long as for our own syncs
+        let (last_sync, log_interval) = *self
+            .last_sync
+            .get_or_insert((current_time, self.port_ds.log_sync_interval()));
+        // The master may not specify its interval (0x7f)
+        let log_interval = if log_interval == 0x7f {
+            self.port_ds.log_sync_interval()
+        } else {
+            log_interval
+        };
+
+        if current_time - last_sync
+            > Duration::from_log_interval(log_interval) * sync_receipt_timeout
+        {
+            // No syncs received for a long time, become master
+            log::info!(
+                "Port {} sync receipt timeout",
+                self.port_ds.port_identity.port_number
+            );
+            self.last_sync = None;
+            self.port_ds
+                .set_forced_port_state(Self::timeout_state(default_ds));
+        }
+    }
+
+    /// The state to go to when the master disappeared. A slave only clock
+    /// never becomes master, it waits for a new master instead. So does a port
+    /// with an externally configured state.
+    fn timeout_state(default_ds: &DefaultDS) -> PortState {
+        if default_ds.slave_only || default_ds.external_port_configuration_enabled {
+            PortState::Listening
+        } else {
+            PortState::Master(MasterState::new())
+        }
     }
 
     pub(crate) fn set_recommended_state<F: Future>(
@@ -202,8 +653,16 @@ impl<P: NetworkPort> Port<P> {
         current_ds: &mut CurrentDS,
         parent_ds: &mut ParentDS,
     ) -> Result<()> {
-        self.port_ds
-            .set_recommended_port_state(&recommended_state, announce_receipt_timeout);
+        self.port_ds.set_recommended_port_state(
+            &recommended_state,
+            current_ds.steps_removed,
+            announce_receipt_timeout,
+        );
+
+        // A new slave state does not know about earlier peer delay measurements
+        if let Some(mean_link_delay) = self.peer_delay.mean_link_delay() {
+            self.port_ds.port_state.set_mean_link_delay(mean_link_delay);
+        }
 
         match recommended_state {
             RecommendedState::M1(defaultds) | RecommendedState::M2(defaultds) => {
@@ -217,6 +676,7 @@ impl<P: NetworkPort> Port<P> {
                 parent_ds.grandmaster_clock_quality = defaultds.clock_quality;
                 parent_ds.grandmaster_priority_1 = defaultds.priority_1;
                 parent_ds.grandmaster_priority_2 = defaultds.priority_2;
+                parent_ds.path_trace.clear();
 
                 time_properties_ds.leap59 = false;
                 time_properties_ds.leap61 = false;
@@ -226,9 +686,14 @@ impl<P: NetworkPort> Port<P> {
                 time_properties_ds.time_traceable = false;
                 time_properties_ds.frequency_traceable = false;
                 time_properties_ds.time_source = TimeSource::InternalOscillator;
+                time_properties_ds.power_profile = None;
+                time_properties_ds.enhanced_accuracy_metrics = None;
             }
-            RecommendedState::M3(_) | RecommendedState::P1(_) | RecommendedState::P2(_) => {}
-            RecommendedState::S1(announce_message) => {
+            RecommendedState::M3(_)
+            | RecommendedState::P1(_)
+            | RecommendedState::P2(_)
+            | RecommendedState::Listening => {}
+            RecommendedState::S1(ref announce_message) => {
                 current_ds.steps_removed = announce_message.steps_removed() + 1;
 
                 parent_ds.parent_port_identity = announce_message.header().source_port_identity();
@@ -236,6 +701,7 @@ impl<P: NetworkPort> Port<P> {
                 parent_ds.grandmaster_clock_quality = announce_message.grandmaster_clock_quality();
                 parent_ds.grandmaster_priority_1 = announce_message.grandmaster_priority_1();
                 parent_ds.grandmaster_priority_2 = announce_message.grandmaster_priority_2();
+                parent_ds.path_trace = announce_message.path_trace.clone();
 
                 *time_properties_ds = announce_message.time_properties();
             }
@@ -251,30 +717,103 @@ impl<P: NetworkPort> Port<P> {
         Ok(())
     }
 
+    /// Move the port to its desired state, for instances with external port
+    /// configuration (IEEE1588-2019 section 17.6.5)
+    ///
+    /// A port configured as slave synchronizes to the master it receives
+    /// announce messages from, and the instance datasets follow those
+    /// announces. Without slave ports, the instance is the grandmaster.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn set_configured_state<F: Future>(
+        &mut self,
+        best_port_announce_message: Option<BestAnnounceMessage>,
+        has_slave_port: bool,
+        default_ds: &DefaultDS,
+        announce_receipt_timeout: &mut Pin<&mut Ticker<F, impl FnMut(Duration) -> F>>,
+        time_properties_ds: &mut TimePropertiesDS,
+        current_ds: &mut CurrentDS,
+        parent_ds: &mut ParentDS,
+    ) -> Result<()> {
+        let recommended_state = match self.port_ds.desired_state() {
+            Some(DesiredState::Slave) => {
+                best_port_announce_message.map(|best| RecommendedState::S1(best.message))
+            }
+            Some(DesiredState::Master) if !has_slave_port => {
+                Some(RecommendedState::M1(*default_ds))
+            }
+            Some(desired_state) => {
+                self.port_ds.set_configured_port_state(desired_state);
+                None
+            }
+            None => None,
+        };
+
+        match recommended_state {
+            Some(recommended_state) => self.set_recommended_state(
+                recommended_state,
+                announce_receipt_timeout,
+                time_properties_ds,
+                current_ds,
+                parent_ds,
+            ),
+            None => Ok(()),
+        }
+    }
+
     async fn send_sync(
         &mut self,
+        network_port: &mut impl NetworkPort,
         local_clock: &RefCell<impl Clock>,
         default_ds: &DefaultDS,
     ) -> Result<()> {
+        if !self.as_capable(default_ds) {
+            return Ok(());
+        }
+
+        if self.port_ds.unicast() {
+            return self
+                .send_unicast_sync(network_port, local_clock, default_ds)
+                .await;
+        }
+
         self.port_ds
             .port_state
             .send_sync(
                 local_clock,
-                &mut self.network_port,
+                network_port,
                 self.port_ds.port_identity,
                 default_ds,
+                self.port_ds.one_step_sync(),
             )
             .await
     }
 
     async fn send_announce(
         &mut self,
+        network_port: &mut impl NetworkPort,
         local_clock: &RefCell<impl Clock>,
         default_ds: &DefaultDS,
         time_properties: &TimePropertiesDS,
         parent_ds: &ParentDS,
         current_ds: &CurrentDS,
     ) -> Result<()> {
+        if !self.as_capable(default_ds) {
+            return Ok(());
+        }
+
+        if self.port_ds.unicast() {
+            return self
+                .send_unicast_announce(
+                    network_port,
+                    local_clock,
+                    default_ds,
+                    time_properties,
+                    parent_ds,
+                    current_ds,
+                )
+                .await;
+        }
+
         self.port_ds
             .port_state
             .send_announce(
@@ -283,59 +822,208 @@ impl<P: NetworkPort> Port<P> {
                 time_properties,
                 parent_ds,
                 current_ds,
-                &mut self.network_port,
+                network_port,
                 self.port_ds.port_identity,
             )
             .await
     }
 
+    async fn send_pdelay_req(
+        &mut self,
+        network_port: &mut impl NetworkPort,
+        local_clock: &RefCell<impl Clock>,
+        default_ds: &DefaultDS,
+    ) -> Result<()> {
+        match (self.port_ds.delay_mechanism(), &self.port_ds.port_state) {
+            (_, PortState::Initializing | PortState::Faulty | PortState::Disabled) => Ok(()),
+            (DelayMechanism::P2P, _) => {
+                self.peer_delay
+                    .send_request(
+                        local_clock,
+                        network_port,
+                        self.port_ds.port_identity,
+                        default_ds,
+                    )
+                    .await
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Handle a peer delay message, independently of the port state
+    async fn handle_peer_delay_message(
+        &mut self,
+        network_port: &mut impl NetworkPort,
+        message: Message,
+        timestamp: Instant,
+    ) -> Result<()> {
+        if self.port_ds.delay_mechanism() != DelayMechanism::P2P
+            || matches!(
+                self.port_ds.port_state,
+                PortState::Initializing | PortState::Faulty | PortState::Disabled
+            )
+        {
+            return Ok(());
+        }
+
+        if let Some(mean_link_delay) = self
+            .peer_delay
+            .handle_message(message, timestamp, network_port, self.port_ds.port_identity)
+            .await?
+        {
+            self.port_ds.set_mean_link_delay(mean_link_delay);
+        }
+
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
     async fn handle_packet<F: Future>(
         &mut self,
+        network_port: &mut impl NetworkPort,
         packet: NetworkPacket,
         local_clock: &RefCell<impl Clock>,
         filter: &RefCell<impl Filter>,
         announce_receipt_timeout: &mut Pin<&mut Ticker<F, impl FnMut(Duration) -> F>>,
         default_ds: &DefaultDS,
         time_properties_ds: &TimePropertiesDS,
+        parent_ds: &ParentDS,
+        current_ds: &Cell<CurrentDS>,
+        other_domain_numbers: &[u8],
+        steer_clock: bool,
     ) -> Result<()> {
         let message = Message::deserialize(&packet.data)?;
 
-        // Only process messages from the same domain
-        if message.header().sdo_id() != default_ds.sdo_id
-            || message.header().domain_number() != default_ds.domain_number
-        {
-            return Ok(());
+        if let Message::Sync(sync) = &message {
+            if Some(sync.header().source_port_identity()) == self.port_ds.port_state.remote_master()
+            {
+                self.last_sync = Some((packet.timestamp, sync.header().log_message_interval()));
+            }
         }
 
-        if let Message::Announce(announce) = &message {
-            log::debug!(
-                "Received announce message on port {}, {:?}.",
-                self.port_ds.port_identity.port_number,
-                message
-            );
-            self.bmca
-                .register_announce_message(announce, packet.timestamp.into());
-            announce_receipt_timeout.reset();
-        } else {
-            self.port_ds
-                .port_state
-                .handle_message(
-                    message,
-                    packet.timestamp,
-                    &mut self.network_port,
-                    self.port_ds.min_delay_req_interval(),
-                    self.port_ds.port_identity,
+        match message {
+            Message::Announce(_) | Message::Sync(_) | Message::FollowUp(_)
+                if !self.as_capable(default_ds) =>
+            {
+                log::debug!(
+                    "Ignoring message on port {}, it is not capable of synchronization",
+                    self.port_ds.port_identity.port_number
+                );
+            }
+            Message::Announce(_) if !self.is_unicast_master(packet.source_address.as_ref()) => {
+                log::debug!(
+                    "Ignoring announce from {:?}, not in the unicast master table",
+                    packet.source_address
+                );
+            }
+            Message::Announce(announce) => {
+                log::debug!(
+                    "Received announce message on port {}, {:?}.",
+                    self.port_ds.port_identity.port_number,
+                    announce
+                );
+                self.bmca
+                    .register_announce_message(&announce, packet.timestamp.into());
+                announce_receipt_timeout.reset();
+            }
+            Message::PDelayReq(_) | Message::PDelayResp(_) | Message::PDelayRespFollowUp(_) => {
+                self.handle_peer_delay_message(network_port, message, packet.timestamp)
+                    .await?;
+            }
+            Message::Management(management) => {
+                self.handle_management_message(
+                    network_port,
+                    management,
+                    packet.source_address.as_ref(),
                     default_ds,
+                    time_properties_ds,
+                    parent_ds,
+                    &current_ds.get(),
+                    other_domain_numbers,
                 )
                 .await?;
+            }
+            Message::Signaling(signaling) => {
+                self.handle_signaling_message(
+                    network_port,
+                    signaling,
+                    packet.source_address,
+                    default_ds,
+                )
+                .await?;
+            }
+            Message::DelayReq(_) | Message::DelayResp(_)
+                if self.port_ds.delay_mechanism() == DelayMechanism::P2P =>
+            {
+                // End to end delay messages are not used on peer to peer ports
+            }
+            _ if self.port_ds.unicast() => {
+                let Some(address) = &packet.source_address else {
+                    log::debug!("Ignoring message without a source address");
+                    return Ok(());
+                };
 
-            // If the received message allowed the (slave) state to calculate its offset
-            // from the master, update the local clock
-            if let Some(measurement) = self.port_ds.port_state.extract_measurement() {
-                let (offset, freq_corr) = filter
+                // Only slaves that negotiated it get their delay requests answered
+                if matches!(message, Message::DelayReq(_))
+                    && self.port_ds.unicast_negotiation()
+                    && !self
+                        .unicast_negotiation
+                        .has_grant(address, MessageType::DelayResp)
+                {
+                    log::debug!("Ignoring delay request from {:?} without a grant", address);
+                    return Ok(());
+                }
+
+                self.port_ds
+                    .port_state
+                    .handle_message(
+                        message,
+                        packet.timestamp,
+                        &mut UnicastPort::new(network_port, address),
+                        self.port_ds.min_delay_req_interval(),
+                        self.port_ds.port_identity,
+                        self.port_ds.delay_asymmetry(),
+                        default_ds,
+                    )
+                    .await?;
+            }
+            _ => {
+                self.port_ds
+                    .port_state
+                    .handle_message(
+                        message,
+                        packet.timestamp,
+                        network_port,
+                        self.port_ds.min_delay_req_interval(),
+                        self.port_ds.port_identity,
+                        self.port_ds.delay_asymmetry(),
+                        default_ds,
+                    )
+                    .await?;
+            }
+        }
+
+        // If the received message allowed the (slave) state to calculate its offset
+        // from the master, update the local clock
+        if let Some(measurement) = self.port_ds.port_state.extract_measurement() {
+            // Keep the measurement of every domain for applications
+            // (IEEE1588-2019 section 8.2.2)
+            let mut measured_ds = current_ds.get();
+            measured_ds.offset_from_master = measurement.master_offset;
+            if let Some(mean_delay) = self.port_ds.port_state.mean_delay() {
+                measured_ds.mean_delay = mean_delay;
+            }
+            current_ds.set(measured_ds);
+
+            if steer_clock {
+                let (offset, freq_corr, converged) = filter
                     .try_borrow_mut()
-                    .map(|mut borrow| borrow.absorb(measurement))
+                    .map(|mut borrow| {
+                        let (offset, freq_corr) = borrow.absorb(measurement);
+                        (offset, freq_corr, borrow.converged())
+                    })
                     .map_err(|_| PortError::FilterBusy)?;
+                self.port_ds.set_calibrated(converged);
 
                 let mut local_clock = local_clock
                     .try_borrow_mut()
@@ -344,9 +1032,26 @@ impl<P: NetworkPort> Port<P> {
                 if let Err(error) = local_clock.adjust(offset, freq_corr, time_properties_ds) {
                     log::error!("failed to adjust clock: {:?}", error);
                 }
+            } else {
+                // Other domains are only measured, they don't steer the clock,
+                // so their measurements stay out of the filter
+                log::debug!(
+                    "Domain {} offset from master: {}",
+                    default_ds.domain_number,
+                    measurement.master_offset
+                );
+                self.port_ds.set_calibrated(true);
             }
         }
 
+        // Pass what the slave saw of its master on to the monitoring node
+        let events = self.port_ds.port_state.take_events();
+        if let Some(master) = self.port_ds.port_state.remote_master() {
+            self.slave_event_monitor
+                .report(network_port, &self.port_ds, default_ds, master, events)
+                .await;
+        }
+
         Ok(())
     }
 
@@ -362,6 +1067,14 @@ impl<P: NetworkPort> Port<P> {
         self.port_ds.announce_receipt_interval()
     }
 
+    pub(crate) fn min_p_delay_req_interval(&self) -> Duration {
+        self.port_ds.min_p_delay_req_interval()
+    }
+
+    pub(crate) fn mean_link_delay(&self) -> Duration {
+        self.port_ds.mean_link_delay()
+    }
+
     pub(crate) fn state(&self) -> &PortState {
         &self.port_ds.port_state
     }
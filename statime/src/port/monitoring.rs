@@ -0,0 +1,349 @@
+//! Slave event monitoring (IEEE1588-2019 section 16.11)
+
+use arrayvec::ArrayVec;
+
+use super::{management::ALL_PORTS, sequence_id::SequenceIdGenerator, state::SlaveEvents};
+use crate::{
+    datastructures::{
+        common::{ClockIdentity, PortIdentity},
+        datasets::{DefaultDS, PortDS},
+        messages::{
+            MessageBuilder, MessageType, SignalingTlv, SlaveRxSyncComputedDataTlv,
+            SlaveRxSyncTimingDataTlv, SlaveTxEventTimestampsTlv, TxEventRecord,
+            MAX_TX_EVENT_RECORDS,
+        },
+    },
+    network::NetworkPort,
+};
+
+/// The batches of slave events of a port that are not yet sent to the
+/// monitoring node
+#[derive(Debug, Default)]
+pub(crate) struct SlaveEventMonitor {
+    seq_ids: SequenceIdGenerator,
+    sync_timing: SlaveRxSyncTimingDataTlv,
+    sync_computed: SlaveRxSyncComputedDataTlv,
+    delay_req: ArrayVec<TxEventRecord, MAX_TX_EVENT_RECORDS>,
+}
+
+impl SlaveEventMonitor {
+    /// Add the `events` of the slave of `master` to the batches, and send the
+    /// batches that are full to the monitoring node of the port
+    ///
+    /// The monitoring node is not needed to synchronize, so a batch that can't
+    /// be sent to it is logged and dropped.
+    pub(crate) async fn report(
+        &mut self,
+        network_port: &mut impl NetworkPort,
+        port_ds: &PortDS,
+        default_ds: &DefaultDS,
+        master: PortIdentity,
+        events: SlaveEvents,
+    ) {
+        if port_ds.slave_event_monitoring().is_none() {
+            return;
+        }
+
+        // The records of a batch all belong to the same master
+        if self.sync_timing.sync_source_port_identity != master {
+            self.flush_sync_timing(network_port, port_ds, default_ds)
+                .await;
+            self.sync_timing.sync_source_port_identity = master;
+        }
+        if self.sync_computed.source_port_identity != master {
+            self.flush_sync_computed(network_port, port_ds, default_ds)
+                .await;
+            self.sync_computed.source_port_identity = master;
+        }
+
+        if let Some(record) = events.sync_timing {
+            self.sync_timing.records.push(record);
+            if self.sync_timing.records.is_full() {
+                self.flush_sync_timing(network_port, port_ds, default_ds)
+                    .await;
+            }
+        }
+
+        if let Some(record) = events.sync_computed {
+            self.sync_computed.records.push(record);
+            if self.sync_computed.records.is_full() {
+                self.flush_sync_computed(network_port, port_ds, default_ds)
+                    .await;
+            }
+        }
+
+        if let Some(record) = events.delay_req {
+            self.delay_req.push(record);
+            if self.delay_req.is_full() {
+                let tlv = SignalingTlv::SlaveTxEventTimestamps(SlaveTxEventTimestampsTlv {
+                    source_port_identity: port_ds.port_identity,
+                    event_message_type: MessageType::DelayReq,
+                    records: core::mem::take(&mut self.delay_req),
+                });
+                self.send(network_port, port_ds, default_ds, tlv).await;
+            }
+        }
+    }
+
+    async fn flush_sync_timing(
+        &mut self,
+        network_port: &mut impl NetworkPort,
+        port_ds: &PortDS,
+        default_ds: &DefaultDS,
+    ) {
+        if self.sync_timing.records.is_empty() {
+            return;
+        }
+
+        let tlv = SignalingTlv::SlaveRxSyncTimingData(SlaveRxSyncTimingDataTlv {
+            sync_source_port_identity: self.sync_timing.sync_source_port_identity,
+            records: core::mem::take(&mut self.sync_timing.records),
+        });
+        self.send(network_port, port_ds, default_ds, tlv).await
+    }
+
+    async fn flush_sync_computed(
+        &mut self,
+        network_port: &mut impl NetworkPort,
+        port_ds: &PortDS,
+        default_ds: &DefaultDS,
+    ) {
+        if self.sync_computed.records.is_empty() {
+            return;
+        }
+
+        let tlv = SignalingTlv::SlaveRxSyncComputedData(SlaveRxSyncComputedDataTlv {
+            source_port_identity: self.sync_computed.source_port_identity,
+            records: core::mem::take(&mut self.sync_computed.records),
+        });
+        self.send(network_port, port_ds, default_ds, tlv).await
+    }
+
+    async fn send(
+        &mut self,
+        network_port: &mut impl NetworkPort,
+        port_ds: &PortDS,
+        default_ds: &DefaultDS,
+        tlv: SignalingTlv,
+    ) {
+        let Some(address) = port_ds.slave_event_monitoring() else {
+            return;
+        };
+
+        let target_port_identity = PortIdentity {
+            clock_identity: ClockIdentity::ALL,
+            port_number: ALL_PORTS,
+        };
+        let message = MessageBuilder::new()
+            .sdo_id(default_ds.sdo_id)
+            .domain_number(default_ds.domain_number)
+            .unicast_flag(true)
+            .sequence_id(self.seq_ids.generate())
+            .source_port_identity(port_ds.port_identity)
+            .log_message_interval(0x7f)
+            .signaling_message(target_port_identity, tlv)
+            .serialize_vec();
+        let message = match message {
+            Ok(message) => message,
+            Err(error) => {
+                log::error!(
+                    "failed to serialize slave event monitoring message: {:?}",
+                    error
+                );
+                return;
+            }
+        };
+
+        if let Err(error) = network_port.send_to(&message, address).await {
+            log::error!("failed to send slave event monitoring message: {:?}", error);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use super::*;
+    use crate::{
+        datastructures::{
+            common::{NetworkProtocol, PortAddress, Timestamp},
+            datasets::DelayMechanism,
+            messages::{
+                Message, SdoId, SyncComputedRecord, SyncTimingRecord, MAX_SYNC_TIMING_RECORDS,
+            },
+        },
+        port::tests::RecordingNetworkPort,
+    };
+
+    fn identity(id: u8) -> PortIdentity {
+        PortIdentity {
+            clock_identity: ClockIdentity([id; 8]),
+            port_number: 1,
+        }
+    }
+
+    fn monitoring_address() -> PortAddress {
+        PortAddress {
+            network_protocol: NetworkProtocol::UdpIPv4,
+            address: [10, 0, 0, 1].into_iter().collect(),
+        }
+    }
+
+    fn events(sequence_id: u16) -> SlaveEvents {
+        SlaveEvents {
+            sync_timing: Some(SyncTimingRecord {
+                sequence_id,
+                ..Default::default()
+            }),
+            sync_computed: Some(SyncComputedRecord {
+                sequence_id,
+                ..Default::default()
+            }),
+            delay_req: Some(TxEventRecord {
+                sequence_id,
+                event_egress_timestamp: Timestamp::default(),
+            }),
+        }
+    }
+
+    fn sent_tlvs(network_port: &RecordingNetworkPort) -> Vec<SignalingTlv> {
+        network_port
+            .sent_to
+            .iter()
+            .map(|(data, address)| {
+                assert_eq!(address, &monitoring_address());
+                let Message::Signaling(signaling) = Message::deserialize(data).unwrap() else {
+                    panic!("Expected a signaling message");
+                };
+                assert_eq!(signaling.header.source_port_identity(), identity(1));
+                signaling.value[0].clone()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_events_batched() {
+        let mut network_port = RecordingNetworkPort::default();
+        let port_ds = PortDS::new(identity(1), 0, 1, 3, 0, DelayMechanism::E2E, 0)
+            .with_slave_event_monitoring(monitoring_address());
+        let default_ds = DefaultDS::new_ordinary_clock(
+            ClockIdentity([1; 8]),
+            128,
+            128,
+            0,
+            false,
+            SdoId::default(),
+        );
+        let mut monitor = SlaveEventMonitor::default();
+
+        for sequence_id in 0..MAX_SYNC_TIMING_RECORDS as u16 {
+            embassy_futures::block_on(monitor.report(
+                &mut network_port,
+                &port_ds,
+                &default_ds,
+                identity(2),
+                events(sequence_id),
+            ));
+        }
+
+        // Only the sync timing batch is full
+        let tlvs = sent_tlvs(&network_port);
+        let [SignalingTlv::SlaveRxSyncTimingData(timing)] = tlvs.as_slice() else {
+            panic!("Expected a single sync timing TLV, got {:?}", tlvs);
+        };
+        assert_eq!(timing.sync_source_port_identity, identity(2));
+        assert_eq!(timing.records.len(), MAX_SYNC_TIMING_RECORDS);
+
+        // A new master sends the batches of the previous one
+        embassy_futures::block_on(monitor.report(
+            &mut network_port,
+            &port_ds,
+            &default_ds,
+            identity(3),
+            SlaveEvents::default(),
+        ));
+
+        let tlvs = sent_tlvs(&network_port);
+        let [_, SignalingTlv::SlaveRxSyncComputedData(computed)] = tlvs.as_slice() else {
+            panic!("Expected a sync computed TLV, got {:?}", tlvs);
+        };
+        assert_eq!(computed.source_port_identity, identity(2));
+        assert_eq!(computed.records.len(), MAX_SYNC_TIMING_RECORDS);
+    }
+
+    #[test]
+    fn test_unreachable_batch_dropped() {
+        let mut network_port = RecordingNetworkPort {
+            unreachable: true,
+            ..Default::default()
+        };
+        let port_ds = PortDS::new(identity(1), 0, 1, 3, 0, DelayMechanism::E2E, 0)
+            .with_slave_event_monitoring(monitoring_address());
+        let default_ds = DefaultDS::new_ordinary_clock(
+            ClockIdentity([1; 8]),
+            128,
+            128,
+            0,
+            false,
+            SdoId::default(),
+        );
+        let mut monitor = SlaveEventMonitor::default();
+
+        for sequence_id in 0..MAX_SYNC_TIMING_RECORDS as u16 {
+            embassy_futures::block_on(monitor.report(
+                &mut network_port,
+                &port_ds,
+                &default_ds,
+                identity(2),
+                events(sequence_id),
+            ));
+        }
+        assert!(network_port.sent_to.is_empty());
+
+        // The next batch only has the records after the failed one
+        network_port.unreachable = false;
+        for sequence_id in 0..MAX_SYNC_TIMING_RECORDS as u16 {
+            embassy_futures::block_on(monitor.report(
+                &mut network_port,
+                &port_ds,
+                &default_ds,
+                identity(2),
+                events(100 + sequence_id),
+            ));
+        }
+
+        let tlvs = sent_tlvs(&network_port);
+        let Some(SignalingTlv::SlaveRxSyncTimingData(timing)) = tlvs.last() else {
+            panic!("Expected a sync timing TLV, got {:?}", tlvs);
+        };
+        assert_eq!(timing.records[0].sequence_id, 100);
+    }
+
+    #[test]
+    fn test_nothing_sent_without_address() {
+        let mut network_port = RecordingNetworkPort::default();
+        let port_ds = PortDS::new(identity(1), 0, 1, 3, 0, DelayMechanism::E2E, 0);
+        let default_ds = DefaultDS::new_ordinary_clock(
+            ClockIdentity([1; 8]),
+            128,
+            128,
+            0,
+            false,
+            SdoId::default(),
+        );
+        let mut monitor = SlaveEventMonitor::default();
+
+        for sequence_id in 0..2 * MAX_TX_EVENT_RECORDS as u16 {
+            embassy_futures::block_on(monitor.report(
+                &mut network_port,
+                &port_ds,
+                &default_ds,
+                identity(2),
+                events(sequence_id),
+            ));
+        }
+
+        assert!(network_port.sent_to.is_empty());
+    }
+}
@@ -0,0 +1,741 @@
+use core::cell::RefCell;
+
+use crate::{
+    clock::Clock,
+    datastructures::{
+        common::{PortIdentity, TimeInterval, Timestamp},
+        datasets::DefaultDS,
+        messages::{
+            Message, MessageBuilder, PDelayReqMessage, PDelayRespFollowUpMessage, PDelayRespMessage,
+        },
+    },
+    network::NetworkPort,
+    port::{
+        error::{PortError, Result},
+        sequence_id::SequenceIdGenerator,
+    },
+    time::{Duration, Instant},
+};
+
+/// The number of consecutive requests that may go without a response before
+/// the port is no longer considered capable of synchronization (IEEE
+/// 802.1AS-2020 section 11.5.3.1)
+const ALLOWED_LOST_RESPONSES: u8 = 3;
+
+/// State of the peer delay mechanism (IEEE1588-2019 section 11.4) of a single
+/// port.
+///
+/// Unlike the end to end delay mechanism, peer delay measurements are made
+/// independently of the port state: every port using the peer to peer delay
+/// mechanism both initiates measurements to its neighbour and responds to the
+/// requests of its neighbour.
+#[derive(Debug, Default)]
+pub(crate) struct PeerDelayState {
+    pdelay_req_ids: SequenceIdGenerator,
+    request_state: RequestState,
+    mean_link_delay: Option<Duration>,
+    neighbor_rate_ratio: Option<f64>,
+    /// Responder, response origin and response receive time of the last
+    /// completed measurement
+    last_response: Option<(PortIdentity, Instant, Instant)>,
+    lost_responses: u8,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+enum RequestState {
+    #[default]
+    Idle,
+    AfterRequest {
+        sequence_id: u16,
+        request_send_time: Instant,
+    },
+    AfterResponse {
+        sequence_id: u16,
+        request_send_time: Instant,
+        response_recv_time: Instant,
+        request_recv_time: Instant,
+        response_correction: Duration,
+        responder: PortIdentity,
+    },
+}
+
+impl PeerDelayState {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// The most recently measured mean link delay, if any measurement has
+    /// completed yet
+    pub(crate) fn mean_link_delay(&self) -> Option<Duration> {
+        self.mean_link_delay
+    }
+
+    /// The ratio of the frequency of the neighbour to the frequency of the
+    /// local clock (IEEE1588-2019 section 16.10.2)
+    pub(crate) fn neighbor_rate_ratio(&self) -> f64 {
+        self.neighbor_rate_ratio.unwrap_or(1.0)
+    }
+
+    /// Whether the measurements to the neighbour succeed, and the neighbour is
+    /// close enough, for the port to take part in synchronization in a gPTP
+    /// domain (asCapable, IEEE 802.1AS-2020 section 11.2.2)
+    pub(crate) fn as_capable(&self, neighbor_prop_delay_thresh: Duration) -> bool {
+        self.lost_responses <= ALLOWED_LOST_RESPONSES
+            && self
+                .mean_link_delay
+                .is_some_and(|mean_link_delay| mean_link_delay <= neighbor_prop_delay_thresh)
+    }
+
+    pub(crate) async fn send_request<P: NetworkPort>(
+        &mut self,
+        local_clock: &RefCell<impl Clock>,
+        network_port: &mut P,
+        port_identity: PortIdentity,
+        default_ds: &DefaultDS,
+    ) -> Result<()> {
+        log::trace!("sending pdelay request");
+
+        let current_time = local_clock
+            .try_borrow()
+            .map(|borrow| borrow.now())
+            .map_err(|_| PortError::ClockBusy)?;
+
+        if self.request_state != RequestState::Idle {
+            self.lost_responses = self.lost_responses.saturating_add(1);
+            log::debug!("No pdelay response for {} requests", self.lost_responses);
+        }
+
+        let sequence_id = self.pdelay_req_ids.generate();
+        let pdelay_req = MessageBuilder::new()
+            .sdo_id(default_ds.sdo_id)
+            .domain_number(default_ds.domain_number)
+            .source_port_identity(port_identity)
+            .sequence_id(sequence_id)
+            .log_message_interval(0x7f)
+            .pdelay_req_message(Timestamp::default())
+            .serialize_vec()?;
+
+        let request_send_time = match network_port.send_time_critical(&pdelay_req).await {
+            Ok(opt_time) => opt_time.unwrap_or(current_time),
+            Err(error) => {
+                log::error!("failed to send pdelay request: {:?}", error);
+                return Err(PortError::Network);
+            }
+        };
+
+        self.request_state = RequestState::AfterRequest {
+            sequence_id,
+            request_send_time,
+        };
+
+        Ok(())
+    }
+
+    /// Handle a peer delay message, returning the new mean link delay when the
+    /// message completed a measurement
+    pub(crate) async fn handle_message<P: NetworkPort>(
+        &mut self,
+        message: Message,
+        current_time: Instant,
+        network_port: &mut P,
+        port_identity: PortIdentity,
+    ) -> Result<Option<Duration>> {
+        // Always ignore messages from own port
+        if message.header().source_port_identity() == port_identity {
+            return Ok(None);
+        }
+
+        match message {
+            Message::PDelayReq(message) => {
+                self.handle_request(message, current_time, network_port, port_identity)
+                    .await?;
+                Ok(None)
+            }
+            Message::PDelayResp(message) => {
+                Ok(self.handle_response(message, current_time, port_identity))
+            }
+            Message::PDelayRespFollowUp(message) => {
+                Ok(self.handle_follow_up(message, port_identity))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    async fn handle_request<P: NetworkPort>(
+        &mut self,
+        message: PDelayReqMessage,
+        current_time: Instant,
+        network_port: &mut P,
+        port_identity: PortIdentity,
+    ) -> Result<()> {
+        log::debug!("Received PDelayReq");
+        let requesting_port_identity = message.header().source_port_identity();
+
+        let pdelay_resp = MessageBuilder::new()
+            .copy_header(Message::PDelayReq(message))
+            .two_step_flag(true)
+            .source_port_identity(port_identity)
+            .correction_field(TimeInterval::default())
+            .pdelay_resp_message(current_time.into(), requesting_port_identity)
+            .serialize_vec()?;
+
+        let response_send_time = match network_port.send_time_critical(&pdelay_resp).await {
+            Ok(opt_time) => opt_time.unwrap_or(current_time),
+            Err(error) => {
+                log::error!("failed to send pdelay response: {:?}", error);
+                return Err(PortError::Network);
+            }
+        };
+
+        // The follow up carries the correction of the request, plus the
+        // fractional nanoseconds of the turnaround time that did not fit in the
+        // timestamps
+        let pdelay_resp_follow_up = MessageBuilder::new()
+            .copy_header(Message::PDelayReq(message))
+            .two_step_flag(false)
+            .source_port_identity(port_identity)
+            .add_to_correction(TimeInterval(
+                response_send_time.subnano().0 - current_time.subnano().0,
+            ))
+            .pdelay_resp_follow_up_message(response_send_time.into(), requesting_port_identity)
+            .serialize_vec()?;
+
+        if let Err(error) = network_port.send(&pdelay_resp_follow_up).await {
+            log::error!("failed to send pdelay response follow-up: {:?}", error);
+            return Err(PortError::Network);
+        }
+
+        Ok(())
+    }
+
+    fn handle_response(
+        &mut self,
+        message: PDelayRespMessage,
+        current_time: Instant,
+        port_identity: PortIdentity,
+    ) -> Option<Duration> {
+        log::debug!("Received PDelayResp");
+        let (sequence_id, request_send_time) = match self.request_state {
+            RequestState::AfterRequest {
+                sequence_id,
+                request_send_time,
+            } => (sequence_id, request_send_time),
+            RequestState::AfterResponse {
+                sequence_id,
+                responder,
+                ..
+            } if message.requesting_port_identity() == port_identity
+                && message.header().sequence_id() == sequence_id
+                && message.header().source_port_identity() != responder =>
+            {
+                // With multiple responders on the link there is no single link delay
+                log::warn!("Received multiple pdelay responses to a single request");
+                self.request_state = RequestState::Idle;
+                return None;
+            }
+            _ => {
+                log::debug!("Unexpected PDelayResp");
+                return None;
+            }
+        };
+
+        // Ignore responses not aimed at our current request
+        if message.requesting_port_identity() != port_identity
+            || message.header().sequence_id() != sequence_id
+        {
+            return None;
+        }
+
+        let response_correction = Duration::from(message.header().correction_field());
+
+        if message.header().two_step_flag() {
+            self.request_state = RequestState::AfterResponse {
+                sequence_id,
+                request_send_time,
+                response_recv_time: current_time,
+                request_recv_time: Instant::from(message.request_receive_timestamp()),
+                response_correction,
+                responder: message.header().source_port_identity(),
+            };
+            None
+        } else {
+            // The one-step responder has already put its turnaround time in the
+            // correction field
+            self.request_state = RequestState::Idle;
+            self.update_mean_link_delay(
+                (current_time - request_send_time - response_correction) / 2,
+            )
+        }
+    }
+
+    fn handle_follow_up(
+        &mut self,
+        message: PDelayRespFollowUpMessage,
+        port_identity: PortIdentity,
+    ) -> Option<Duration> {
+        log::debug!("Received PDelayRespFollowUp");
+        let RequestState::AfterResponse {
+            sequence_id,
+            request_send_time,
+            response_recv_time,
+            request_recv_time,
+            response_correction,
+            responder,
+        } = self.request_state
+        else {
+            log::debug!("Unexpected PDelayRespFollowUp");
+            return None;
+        };
+
+        // Ignore follow ups not belonging to the response we got
+        if message.requesting_port_identity() != port_identity
+            || message.header().sequence_id() != sequence_id
+            || message.header().source_port_identity() != responder
+        {
+            return None;
+        }
+
+        let response_origin_time = Instant::from(message.response_origin_timestamp());
+        self.update_neighbor_rate_ratio(responder, response_origin_time, response_recv_time);
+
+        // The turnaround time is measured by the clock of the neighbour, so it is
+        // converted to the local time base
+        let turnaround = (response_origin_time - request_recv_time
+            + response_correction
+            + Duration::from(message.header().correction_field()))
+            / self.neighbor_rate_ratio();
+
+        self.request_state = RequestState::Idle;
+        self.update_mean_link_delay((response_recv_time - request_send_time - turnaround) / 2)
+    }
+
+    /// Measure the neighbor rate ratio from the response times of successive
+    /// measurements to the same responder
+    fn update_neighbor_rate_ratio(
+        &mut self,
+        responder: PortIdentity,
+        response_origin_time: Instant,
+        response_recv_time: Instant,
+    ) {
+        if let Some((last_responder, last_origin_time, last_recv_time)) = self.last_response {
+            let origin_interval = response_origin_time - last_origin_time;
+            let recv_interval = response_recv_time - last_recv_time;
+
+            if last_responder == responder && recv_interval > Duration::ZERO {
+                let neighbor_rate_ratio =
+                    origin_interval.nanos().to_num::<f64>() / recv_interval.nanos().to_num::<f64>();
+                log::debug!("New neighbor rate ratio {}", neighbor_rate_ratio);
+                self.neighbor_rate_ratio = Some(neighbor_rate_ratio);
+            } else {
+                self.neighbor_rate_ratio = None;
+            }
+        }
+
+        self.last_response = Some((responder, response_origin_time, response_recv_time));
+    }
+
+    fn update_mean_link_delay(&mut self, mean_link_delay: Duration) -> Option<Duration> {
+        log::debug!("New mean link delay {}", mean_link_delay);
+        self.lost_responses = 0;
+        self.mean_link_delay = Some(mean_link_delay);
+        self.mean_link_delay
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fixed::types::{I48F16, U96F32};
+
+    use super::*;
+    use crate::{
+        datastructures::{
+            common::ClockIdentity,
+            messages::{Header, SdoId},
+        },
+        port::tests::{RecordingNetworkPort, TestClock},
+    };
+
+    fn default_ds() -> DefaultDS {
+        DefaultDS::new_ordinary_clock(
+            ClockIdentity::default(),
+            15,
+            128,
+            0,
+            false,
+            SdoId::default(),
+        )
+    }
+
+    fn own_identity() -> PortIdentity {
+        PortIdentity {
+            port_number: 1,
+            ..Default::default()
+        }
+    }
+
+    fn peer_identity() -> PortIdentity {
+        PortIdentity {
+            port_number: 2,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_pdelay_response() {
+        let mut port = RecordingNetworkPort {
+            current_time: Instant::from_fixed_nanos(U96F32::from_bits(
+                (250000 << 32) + (700 << 16),
+            )),
+            ..Default::default()
+        };
+
+        let mut state = PeerDelayState::new();
+
+        let result = embassy_futures::block_on(state.handle_message(
+            Message::PDelayReq(PDelayReqMessage {
+                header: Header {
+                    sequence_id: 42,
+                    source_port_identity: peer_identity(),
+                    correction_field: TimeInterval(I48F16::from_bits(400)),
+                    ..Default::default()
+                },
+                origin_timestamp: Timestamp::default(),
+            }),
+            Instant::from_fixed_nanos(U96F32::from_bits((200000 << 32) + (500 << 16))),
+            &mut port,
+            own_identity(),
+        ))
+        .unwrap();
+
+        assert_eq!(result, None);
+        assert_eq!(port.normal.len(), 1);
+        assert_eq!(port.time.len(), 1);
+
+        let resp = match Message::deserialize(&port.time.pop().unwrap()).unwrap() {
+            Message::PDelayResp(msg) => msg,
+            _ => panic!("Unexpected message type"),
+        };
+        assert_eq!(resp.header.sequence_id, 42);
+        assert!(resp.header.two_step_flag);
+        assert_eq!(resp.header.source_port_identity, own_identity());
+        assert_eq!(resp.header.correction_field, TimeInterval::default());
+        assert_eq!(resp.requesting_port_identity, peer_identity());
+        assert_eq!(
+            resp.request_receive_timestamp,
+            Instant::from_micros(200).into()
+        );
+
+        let follow_up = match Message::deserialize(&port.normal.pop().unwrap()).unwrap() {
+            Message::PDelayRespFollowUp(msg) => msg,
+            _ => panic!("Unexpected message type"),
+        };
+        assert_eq!(follow_up.header.sequence_id, 42);
+        assert_eq!(follow_up.header.source_port_identity, own_identity());
+        assert_eq!(
+            follow_up.header.correction_field,
+            TimeInterval(I48F16::from_bits(600))
+        );
+        assert_eq!(follow_up.requesting_port_identity, peer_identity());
+        assert_eq!(
+            follow_up.response_origin_timestamp,
+            Instant::from_micros(250).into()
+        );
+    }
+
+    #[test]
+    fn test_pdelay_ignore_own_request() {
+        let mut port = RecordingNetworkPort::default();
+
+        let mut state = PeerDelayState::new();
+
+        embassy_futures::block_on(state.handle_message(
+            Message::PDelayReq(PDelayReqMessage {
+                header: Header {
+                    source_port_identity: own_identity(),
+                    ..Default::default()
+                },
+                origin_timestamp: Timestamp::default(),
+            }),
+            Instant::from_micros(100),
+            &mut port,
+            own_identity(),
+        ))
+        .unwrap();
+
+        assert_eq!(port.normal.len(), 0);
+        assert_eq!(port.time.len(), 0);
+    }
+
+    #[test]
+    fn test_pdelay_two_step_measurement() {
+        let mut port = RecordingNetworkPort {
+            current_time: Instant::from_micros(100),
+            ..Default::default()
+        };
+        let clock = RefCell::new(TestClock {
+            current_time: Instant::from_micros(90),
+        });
+
+        let mut state = PeerDelayState::new();
+
+        embassy_futures::block_on(state.send_request(
+            &clock,
+            &mut port,
+            own_identity(),
+            &default_ds(),
+        ))
+        .unwrap();
+
+        assert_eq!(port.normal.len(), 0);
+        assert_eq!(port.time.len(), 1);
+
+        let req = match Message::deserialize(&port.time.pop().unwrap()).unwrap() {
+            Message::PDelayReq(msg) => msg,
+            _ => panic!("Unexpected message type"),
+        };
+        assert_eq!(req.header.source_port_identity, own_identity());
+
+        let result = embassy_futures::block_on(state.handle_message(
+            Message::PDelayResp(PDelayRespMessage {
+                header: Header {
+                    two_step_flag: true,
+                    sequence_id: req.header.sequence_id,
+                    source_port_identity: peer_identity(),
+                    ..Default::default()
+                },
+                request_receive_timestamp: Instant::from_micros(1000).into(),
+                requesting_port_identity: own_identity(),
+            }),
+            Instant::from_micros(300),
+            &mut port,
+            own_identity(),
+        ))
+        .unwrap();
+
+        assert_eq!(result, None);
+        assert_eq!(state.mean_link_delay(), None);
+
+        let result = embassy_futures::block_on(state.handle_message(
+            Message::PDelayRespFollowUp(PDelayRespFollowUpMessage {
+                header: Header {
+                    sequence_id: req.header.sequence_id,
+                    source_port_identity: peer_identity(),
+                    correction_field: TimeInterval(2000.into()),
+                    ..Default::default()
+                },
+                response_origin_timestamp: Instant::from_micros(1098).into(),
+                requesting_port_identity: own_identity(),
+            }),
+            Instant::from_micros(310),
+            &mut port,
+            own_identity(),
+        ))
+        .unwrap();
+
+        // ((300 - 100) - (1098 - 1000 + 2)) / 2 = 50
+        assert_eq!(result, Some(Duration::from_micros(50)));
+        assert_eq!(state.mean_link_delay(), Some(Duration::from_micros(50)));
+        assert_eq!(port.normal.len(), 0);
+        assert_eq!(port.time.len(), 0);
+    }
+
+    #[test]
+    fn test_pdelay_one_step_measurement() {
+        let mut port = RecordingNetworkPort {
+            current_time: Instant::from_micros(100),
+            ..Default::default()
+        };
+        let clock = RefCell::new(TestClock {
+            current_time: Instant::from_micros(90),
+        });
+
+        let mut state = PeerDelayState::new();
+
+        embassy_futures::block_on(state.send_request(
+            &clock,
+            &mut port,
+            own_identity(),
+            &default_ds(),
+        ))
+        .unwrap();
+
+        let req = match Message::deserialize(&port.time.pop().unwrap()).unwrap() {
+            Message::PDelayReq(msg) => msg,
+            _ => panic!("Unexpected message type"),
+        };
+
+        // Response to some other port must be ignored
+        let result = embassy_futures::block_on(state.handle_message(
+            Message::PDelayResp(PDelayRespMessage {
+                header: Header {
+                    two_step_flag: false,
+                    sequence_id: req.header.sequence_id,
+                    source_port_identity: peer_identity(),
+                    correction_field: TimeInterval(80000.into()),
+                    ..Default::default()
+                },
+                request_receive_timestamp: Timestamp::default(),
+                requesting_port_identity: peer_identity(),
+            }),
+            Instant::from_micros(200),
+            &mut port,
+            own_identity(),
+        ))
+        .unwrap();
+        assert_eq!(result, None);
+
+        let result = embassy_futures::block_on(state.handle_message(
+            Message::PDelayResp(PDelayRespMessage {
+                header: Header {
+                    two_step_flag: false,
+                    sequence_id: req.header.sequence_id,
+                    source_port_identity: peer_identity(),
+                    correction_field: TimeInterval(80000.into()),
+                    ..Default::default()
+                },
+                request_receive_timestamp: Timestamp::default(),
+                requesting_port_identity: own_identity(),
+            }),
+            Instant::from_micros(200),
+            &mut port,
+            own_identity(),
+        ))
+        .unwrap();
+
+        // ((200 - 100) - 80) / 2 = 10
+        assert_eq!(result, Some(Duration::from_micros(10)));
+
+        // A late duplicate must not produce a new measurement
+        let result = embassy_futures::block_on(state.handle_message(
+            Message::PDelayResp(PDelayRespMessage {
+                header: Header {
+                    two_step_flag: false,
+                    sequence_id: req.header.sequence_id,
+                    source_port_identity: peer_identity(),
+                    correction_field: TimeInterval(0.into()),
+                    ..Default::default()
+                },
+                request_receive_timestamp: Timestamp::default(),
+                requesting_port_identity: own_identity(),
+            }),
+            Instant::from_micros(250),
+            &mut port,
+            own_identity(),
+        ))
+        .unwrap();
+        assert_eq!(result, None);
+        assert_eq!(state.mean_link_delay(), Some(Duration::from_micros(10)));
+    }
+
+    /// Perform a two step measurement, requesting at `t1`, with the neighbour
+    /// receiving at `t2` and responding at `t3`, and the response arriving at
+    /// `t4`
+    fn measure(state: &mut PeerDelayState, t1: u64, t2: u64, t3: u64, t4: u64) -> Option<Duration> {
+        let mut port = RecordingNetworkPort {
+            current_time: Instant::from_micros(t1),
+            ..Default::default()
+        };
+        let clock = RefCell::new(TestClock {
+            current_time: Instant::from_micros(t1),
+        });
+
+        embassy_futures::block_on(state.send_request(
+            &clock,
+            &mut port,
+            own_identity(),
+            &default_ds(),
+        ))
+        .unwrap();
+
+        let req = match Message::deserialize(&port.time.pop().unwrap()).unwrap() {
+            Message::PDelayReq(msg) => msg,
+            _ => panic!("Unexpected message type"),
+        };
+
+        embassy_futures::block_on(state.handle_message(
+            Message::PDelayResp(PDelayRespMessage {
+                header: Header {
+                    two_step_flag: true,
+                    sequence_id: req.header.sequence_id,
+                    source_port_identity: peer_identity(),
+                    ..Default::default()
+                },
+                request_receive_timestamp: Instant::from_micros(t2).into(),
+                requesting_port_identity: own_identity(),
+            }),
+            Instant::from_micros(t4),
+            &mut port,
+            own_identity(),
+        ))
+        .unwrap();
+
+        embassy_futures::block_on(state.handle_message(
+            Message::PDelayRespFollowUp(PDelayRespFollowUpMessage {
+                header: Header {
+                    sequence_id: req.header.sequence_id,
+                    source_port_identity: peer_identity(),
+                    ..Default::default()
+                },
+                response_origin_timestamp: Instant::from_micros(t3).into(),
+                requesting_port_identity: own_identity(),
+            }),
+            Instant::from_micros(t4 + 10),
+            &mut port,
+            own_identity(),
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_pdelay_neighbor_rate_ratio() {
+        let mut state = PeerDelayState::new();
+
+        assert_eq!(
+            measure(&mut state, 100, 1000, 1100, 300),
+            Some(Duration::from_micros(50))
+        );
+        assert_eq!(state.neighbor_rate_ratio(), 1.0);
+
+        // The clock of the neighbour advanced 1500us in 1000us of the local clock, so
+        // its turnaround time of 150us is 100us locally
+        assert_eq!(
+            measure(&mut state, 1100, 2450, 2600, 1300),
+            Some(Duration::from_micros(50))
+        );
+        assert_eq!(state.neighbor_rate_ratio(), 1.5);
+    }
+
+    #[test]
+    fn test_pdelay_as_capable() {
+        let mut state = PeerDelayState::new();
+        let threshold = Duration::from_micros(100);
+        assert!(!state.as_capable(threshold));
+
+        measure(&mut state, 100, 1000, 1100, 300);
+        assert!(state.as_capable(threshold));
+        assert!(!state.as_capable(Duration::from_nanos(800)));
+
+        // Requests without a response
+        let mut port = RecordingNetworkPort::default();
+        let clock = RefCell::new(TestClock {
+            current_time: Instant::from_micros(400),
+        });
+        for lost_responses in 0..=ALLOWED_LOST_RESPONSES + 1 {
+            embassy_futures::block_on(state.send_request(
+                &clock,
+                &mut port,
+                own_identity(),
+                &default_ds(),
+            ))
+            .unwrap();
+            assert_eq!(
+                state.as_capable(threshold),
+                lost_responses <= ALLOWED_LOST_RESPONSES
+            );
+        }
+
+        // A completed measurement makes the port capable again
+        measure(&mut state, 1100, 2000, 2100, 1300);
+        assert!(state.as_capable(threshold));
+    }
+}
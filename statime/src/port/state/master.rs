@@ -5,13 +5,17 @@ use crate::{
     datastructures::{
         common::{PortIdentity, Timestamp},
         datasets::{CurrentDS, DefaultDS, ParentDS, TimePropertiesDS},
-        messages::{DelayReqMessage, Message, MessageBuilder},
+        messages::{
+            DelayReqMessage, EnhancedAccuracyMetricsTlv, FollowUpInformationTlv, Message,
+            MessageBuilder, PowerProfileTlv, MAX_DATA_LEN,
+        },
     },
     network::NetworkPort,
     port::{
         error::{PortError, Result},
         sequence_id::SequenceIdGenerator,
     },
+    profile::Profile,
     time::Instant,
 };
 
@@ -35,6 +39,7 @@ impl MasterState {
         network_port: &mut P,
         port_identity: PortIdentity,
         default_ds: &DefaultDS,
+        one_step: bool,
     ) -> Result<()> {
         log::trace!("sending sync message");
 
@@ -44,6 +49,28 @@ impl MasterState {
             .map_err(|_| PortError::ClockBusy)?;
 
         let seq_id = self.sync_seq_ids.generate();
+
+        if one_step {
+            // The origin timestamp is carried in the sync itself, the network port may
+            // still replace it with the actual transmit time
+            let sync_message = MessageBuilder::new()
+                .sdo_id(default_ds.sdo_id)
+                .domain_number(default_ds.domain_number)
+                .two_step_flag(false)
+                .sequence_id(seq_id)
+                .source_port_identity(port_identity)
+                .correction_field(current_time.subnano())
+                .sync_message(current_time.into())
+                .serialize_vec()?;
+
+            if let Err(error) = network_port.send_one_step(&sync_message).await {
+                log::error!("failed to send sync message: {:?}", error);
+                return Err(PortError::Network);
+            }
+
+            return Ok(());
+        }
+
         let sync_message = MessageBuilder::new()
             .sdo_id(default_ds.sdo_id)
             .domain_number(default_ds.domain_number)
@@ -61,14 +88,18 @@ impl MasterState {
             }
         };
 
-        // TODO: Discuss whether follow up is a config?
+        // The local clock is syntonized to the grandmaster, so there is no rate
+        // offset to accumulate
+        let follow_up_information =
+            (default_ds.profile == Profile::Gptp).then(FollowUpInformationTlv::default);
+
         let follow_up_message = MessageBuilder::new()
             .sdo_id(default_ds.sdo_id)
             .domain_number(default_ds.domain_number)
             .sequence_id(seq_id)
             .source_port_identity(port_identity)
             .correction_field(current_time.subnano())
-            .follow_up_message(current_time.into())
+            .follow_up_message(current_time.into(), follow_up_information)
             .serialize_vec()?;
 
         if let Err(error) = network_port.send(&follow_up_message).await {
@@ -97,7 +128,45 @@ impl MasterState {
             .map(|borrow| borrow.now())
             .map_err(|_| PortError::ClockBusy)?;
 
-        let announce_message = MessageBuilder::new()
+        // Our own identity is appended to the path of our parent, unless that makes
+        // it too long, in which case no path trace is sent (IEEE1588-2019 16.2.5)
+        let mut path_trace = parent_ds.path_trace.clone();
+        if path_trace.try_push(default_ds.clock_identity).is_err() {
+            path_trace.clear();
+        }
+
+        // In a power profile domain, the grandmaster identifies itself and every
+        // clock on the way adds its time inaccuracy
+        let power_profile = match default_ds.profile {
+            Profile::C37_238 if parent_ds.grandmaster_identity == default_ds.clock_identity => {
+                Some(PowerProfileTlv {
+                    grandmaster_id: default_ds.grandmaster_id,
+                    grandmaster_time_inaccuracy: default_ds.time_inaccuracy,
+                    total_time_inaccuracy: default_ds.time_inaccuracy,
+                })
+            }
+            Profile::C37_238 => time_properties
+                .power_profile
+                .map(|tlv| tlv.forwarded(default_ds.time_inaccuracy)),
+            _ => None,
+        };
+
+        // The grandmaster sends its own metrics, boundary clocks add their
+        // inaccuracies to those of their master (IEEE1588-2019 section 16.12)
+        let enhanced_accuracy_metrics = match &default_ds.enhanced_accuracy_metrics {
+            Some(own) if parent_ds.grandmaster_identity == default_ds.clock_identity => {
+                Some(EnhancedAccuracyMetricsTlv {
+                    bc_hop_count: 0,
+                    ..*own
+                })
+            }
+            Some(own) => time_properties
+                .enhanced_accuracy_metrics
+                .map(|tlv| tlv.forwarded(own)),
+            None => None,
+        };
+
+        let mut announce_message = MessageBuilder::new()
             .sdo_id(default_ds.sdo_id)
             .domain_number(default_ds.domain_number)
             .leap59(time_properties.leap59())
@@ -117,8 +186,24 @@ impl MasterState {
                 parent_ds.grandmaster_identity,
                 current_ds.steps_removed,
                 time_properties.time_source,
-            )
-            .serialize_vec()?;
+                path_trace,
+                time_properties.alternate_time_offsets.clone(),
+                time_properties.smpte_sync_metadata,
+                power_profile,
+                enhanced_accuracy_metrics,
+            );
+
+        // Alternate timescales are only advertised while they fit in the message
+        if let Message::Announce(announce) = &mut announce_message {
+            if announce.fit_alternate_time_offsets(MAX_DATA_LEN - 34) {
+                log::warn!("Not all alternate time offsets fit in the announce message");
+            }
+            if announce.content_size() > MAX_DATA_LEN - 34 {
+                log::warn!("The enhanced accuracy metrics don't fit in the announce message");
+                announce.enhanced_accuracy_metrics = None;
+            }
+        }
+        let announce_message = announce_message.serialize_vec()?;
 
         if let Err(error) = network_port.send(&announce_message).await {
             log::error!("failed to send announce message: {:?}", error);
@@ -198,75 +283,20 @@ pub enum MasterError {
 
 #[cfg(test)]
 mod tests {
-    use std::vec::Vec;
-
     use fixed::types::{I48F16, U96F32};
 
     use super::*;
-    use crate::datastructures::{
-        common::{ClockIdentity, TimeInterval},
-        messages::{Header, SdoId},
+    use crate::{
+        datastructures::{
+            common::{ClockIdentity, TimeInterval},
+            messages::{Header, Inaccuracy, SdoId},
+        },
+        port::tests::{RecordingNetworkPort, TestClock},
     };
 
-    #[derive(Debug, Default)]
-    struct TestNetworkPort {
-        normal: Vec<Vec<u8>>,
-        time: Vec<Vec<u8>>,
-
-        current_time: Instant,
-    }
-
-    impl NetworkPort for TestNetworkPort {
-        type Error = std::convert::Infallible;
-
-        async fn send(&mut self, data: &[u8]) -> core::result::Result<(), Self::Error> {
-            self.normal.push(Vec::from(data));
-            Ok(())
-        }
-
-        async fn send_time_critical(
-            &mut self,
-            data: &[u8],
-        ) -> core::result::Result<Option<Instant>, Self::Error> {
-            self.time.push(Vec::from(data));
-            Ok(Some(self.current_time))
-        }
-
-        async fn recv(
-            &mut self,
-        ) -> core::result::Result<crate::network::NetworkPacket, Self::Error> {
-            panic!("Recv shouldn't be called by state");
-        }
-    }
-
-    struct TestClock {
-        current_time: Instant,
-    }
-
-    impl Clock for TestClock {
-        type Error = std::convert::Infallible;
-
-        fn now(&self) -> Instant {
-            self.current_time
-        }
-
-        fn quality(&self) -> crate::datastructures::common::ClockQuality {
-            panic!("Shouldn't be called");
-        }
-
-        fn adjust(
-            &mut self,
-            _time_offset: crate::time::Duration,
-            _frequency_multiplier: f64,
-            _time_properties_ds: &crate::datastructures::datasets::TimePropertiesDS,
-        ) -> core::result::Result<(), Self::Error> {
-            panic!("Shouldn't be called");
-        }
-    }
-
     #[test]
     fn test_delay_response() {
-        let mut port = TestNetworkPort::default();
+        let mut port = RecordingNetworkPort::default();
 
         let mut state = MasterState::new();
 
@@ -359,7 +389,7 @@ mod tests {
 
     #[test]
     fn test_announce() {
-        let mut port = TestNetworkPort::default();
+        let mut port = RecordingNetworkPort::default();
         let clock = RefCell::new(TestClock {
             current_time: Instant::from_micros(600),
         });
@@ -418,9 +448,201 @@ mod tests {
         assert_ne!(msg2.header.sequence_id, msg.header.sequence_id);
     }
 
+    #[test]
+    fn test_announce_path_trace() {
+        let mut port = RecordingNetworkPort::default();
+        let clock = RefCell::new(TestClock {
+            current_time: Instant::from_micros(600),
+        });
+
+        let defaultds = DefaultDS::new_ordinary_clock(
+            ClockIdentity([2; 8]),
+            15,
+            128,
+            0,
+            false,
+            SdoId::default(),
+        );
+        let mut parent_ds = ParentDS::default();
+        parent_ds.path_trace.push(ClockIdentity([1; 8]));
+
+        let mut state = MasterState::new();
+        let mut send_announce = |parent_ds: &ParentDS| {
+            embassy_futures::block_on(state.send_announce(
+                &clock,
+                &defaultds,
+                &TimePropertiesDS::default(),
+                parent_ds,
+                &CurrentDS::default(),
+                &mut port,
+                PortIdentity::default(),
+            ))
+            .unwrap();
+
+            match Message::deserialize(&port.normal.pop().unwrap()).unwrap() {
+                Message::Announce(msg) => msg,
+                _ => panic!("Unexpected message type"),
+            }
+        };
+
+        // Our own identity is appended to the path of the parent
+        let msg = send_announce(&parent_ds);
+        assert_eq!(
+            msg.path_trace(),
+            [ClockIdentity([1; 8]), ClockIdentity([2; 8])]
+        );
+
+        // Paths that would grow too long are left out
+        while !parent_ds.path_trace.is_full() {
+            parent_ds.path_trace.push(ClockIdentity([1; 8]));
+        }
+        let msg = send_announce(&parent_ds);
+        assert!(msg.path_trace().is_empty());
+    }
+
+    #[test]
+    fn test_announce_power_profile() {
+        let mut port = RecordingNetworkPort::default();
+        let clock = RefCell::new(TestClock {
+            current_time: Instant::from_micros(600),
+        });
+
+        let defaultds = DefaultDS::new_ordinary_clock(
+            ClockIdentity([2; 8]),
+            128,
+            128,
+            254,
+            false,
+            SdoId::default(),
+        )
+        .with_profile(Profile::C37_238)
+        .with_grandmaster_id(5)
+        .with_time_inaccuracy(50);
+        let mut parent_ds = ParentDS::default();
+        let mut time_properties = TimePropertiesDS::default();
+
+        let mut state = MasterState::new();
+        let mut send_announce = |parent_ds: &ParentDS, time_properties: &TimePropertiesDS| {
+            embassy_futures::block_on(state.send_announce(
+                &clock,
+                &defaultds,
+                time_properties,
+                parent_ds,
+                &CurrentDS::default(),
+                &mut port,
+                PortIdentity::default(),
+            ))
+            .unwrap();
+
+            match Message::deserialize(&port.normal.pop().unwrap()).unwrap() {
+                Message::Announce(msg) => msg,
+                _ => panic!("Unexpected message type"),
+            }
+        };
+
+        // As grandmaster, we advertise ourselves
+        parent_ds.grandmaster_identity = ClockIdentity([2; 8]);
+        let msg = send_announce(&parent_ds, &time_properties);
+        assert_eq!(
+            msg.power_profile,
+            Some(PowerProfileTlv {
+                grandmaster_id: 5,
+                grandmaster_time_inaccuracy: 50,
+                total_time_inaccuracy: 50,
+            })
+        );
+
+        // As boundary clock, we add our inaccuracy to that of the grandmaster
+        parent_ds.grandmaster_identity = ClockIdentity([1; 8]);
+        time_properties.power_profile = Some(PowerProfileTlv {
+            grandmaster_id: 3,
+            grandmaster_time_inaccuracy: 100,
+            total_time_inaccuracy: 200,
+        });
+        let msg = send_announce(&parent_ds, &time_properties);
+        assert_eq!(
+            msg.power_profile,
+            Some(PowerProfileTlv {
+                grandmaster_id: 3,
+                grandmaster_time_inaccuracy: 100,
+                total_time_inaccuracy: 250,
+            })
+        );
+    }
+
+    #[test]
+    fn test_announce_enhanced_accuracy_metrics() {
+        let mut port = RecordingNetworkPort::default();
+        let clock = RefCell::new(TestClock {
+            current_time: Instant::from_micros(600),
+        });
+
+        let own = EnhancedAccuracyMetricsTlv {
+            gm_inaccuracy: Inaccuracy::new(100.0, 25.0),
+            dynamic_inaccuracy: Inaccuracy::new(10.0, 4.0),
+            ..Default::default()
+        };
+        let defaultds = DefaultDS::new_ordinary_clock(
+            ClockIdentity([2; 8]),
+            128,
+            128,
+            0,
+            false,
+            SdoId::default(),
+        )
+        .with_enhanced_accuracy_metrics(own);
+        let mut parent_ds = ParentDS::default();
+        let mut time_properties = TimePropertiesDS::default();
+
+        let mut state = MasterState::new();
+        let mut send_announce = |parent_ds: &ParentDS, time_properties: &TimePropertiesDS| {
+            embassy_futures::block_on(state.send_announce(
+                &clock,
+                &defaultds,
+                time_properties,
+                parent_ds,
+                &CurrentDS::default(),
+                &mut port,
+                PortIdentity::default(),
+            ))
+            .unwrap();
+
+            match Message::deserialize(&port.normal.pop().unwrap()).unwrap() {
+                Message::Announce(msg) => msg,
+                _ => panic!("Unexpected message type"),
+            }
+        };
+
+        // As grandmaster, we send our own metrics
+        parent_ds.grandmaster_identity = ClockIdentity([2; 8]);
+        let msg = send_announce(&parent_ds, &time_properties);
+        assert_eq!(msg.enhanced_accuracy_metrics, Some(own));
+
+        // As boundary clock, only when the master sends them
+        parent_ds.grandmaster_identity = ClockIdentity([1; 8]);
+        let msg = send_announce(&parent_ds, &time_properties);
+        assert_eq!(msg.enhanced_accuracy_metrics, None);
+
+        time_properties.enhanced_accuracy_metrics = Some(EnhancedAccuracyMetricsTlv {
+            gm_inaccuracy: Inaccuracy::new(50.0, 1.0),
+            dynamic_inaccuracy: Inaccuracy::new(20.0, 9.0),
+            ..Default::default()
+        });
+        let msg = send_announce(&parent_ds, &time_properties);
+        assert_eq!(
+            msg.enhanced_accuracy_metrics,
+            Some(EnhancedAccuracyMetricsTlv {
+                bc_hop_count: 1,
+                gm_inaccuracy: Inaccuracy::new(50.0, 1.0),
+                dynamic_inaccuracy: Inaccuracy::new(30.0, 13.0),
+                ..Default::default()
+            })
+        );
+    }
+
     #[test]
     fn test_sync() {
-        let mut port = TestNetworkPort::default();
+        let mut port = RecordingNetworkPort::default();
         let clock = RefCell::new(TestClock {
             current_time: Instant::from_fixed_nanos(U96F32::from_bits(
                 (600000 << 32) + (248 << 16),
@@ -444,6 +666,7 @@ mod tests {
             &mut port,
             PortIdentity::default(),
             &defaultds,
+            false,
         ))
         .unwrap();
 
@@ -474,6 +697,7 @@ mod tests {
             follow.header.correction_field,
             TimeInterval(I48F16::from_bits(230))
         );
+        assert_eq!(follow.follow_up_information, None);
 
         clock.borrow_mut().current_time =
             Instant::from_fixed_nanos(U96F32::from_bits((1000600000 << 32) + (192 << 16)));
@@ -484,6 +708,7 @@ mod tests {
             &mut port,
             PortIdentity::default(),
             &defaultds,
+            false,
         ))
         .unwrap();
 
@@ -516,4 +741,93 @@ mod tests {
             TimeInterval(I48F16::from_bits(543))
         );
     }
+
+    #[test]
+    fn test_one_step_sync() {
+        let mut port = RecordingNetworkPort::default();
+        let clock = RefCell::new(TestClock {
+            current_time: Instant::from_fixed_nanos(U96F32::from_bits(
+                (600000 << 32) + (248 << 16),
+            )),
+        });
+
+        let mut state = MasterState::new();
+        let defaultds = DefaultDS::new_ordinary_clock(
+            ClockIdentity::default(),
+            15,
+            128,
+            0,
+            false,
+            SdoId::default(),
+        );
+
+        embassy_futures::block_on(state.send_sync(
+            &clock,
+            &mut port,
+            PortIdentity::default(),
+            &defaultds,
+            true,
+        ))
+        .unwrap();
+
+        assert_eq!(port.normal.len(), 0);
+        assert_eq!(port.time.len(), 1);
+
+        let sync = match Message::deserialize(&port.time.pop().unwrap()).unwrap() {
+            Message::Sync(msg) => msg,
+            _ => panic!("Unexpected message type"),
+        };
+
+        assert!(!sync.header.two_step_flag);
+        assert_eq!(sync.origin_timestamp, Instant::from_micros(600).into());
+        assert_eq!(
+            sync.header.correction_field,
+            TimeInterval(I48F16::from_bits(248))
+        );
+    }
+
+    #[test]
+    fn test_gptp_sync() {
+        let mut port = RecordingNetworkPort::default();
+        let clock = RefCell::new(TestClock {
+            current_time: Instant::from_micros(600),
+        });
+
+        let mut state = MasterState::new();
+        let defaultds = DefaultDS::new_ordinary_clock(
+            ClockIdentity::default(),
+            15,
+            128,
+            0,
+            false,
+            SdoId::default(),
+        )
+        .with_profile(Profile::Gptp);
+
+        embassy_futures::block_on(state.send_sync(
+            &clock,
+            &mut port,
+            PortIdentity::default(),
+            &defaultds,
+            false,
+        ))
+        .unwrap();
+
+        let sync = match Message::deserialize(&port.time.pop().unwrap()).unwrap() {
+            Message::Sync(msg) => msg,
+            _ => panic!("Unexpected message type"),
+        };
+
+        let follow = match Message::deserialize(&port.normal.pop().unwrap()).unwrap() {
+            Message::FollowUp(msg) => msg,
+            _ => panic!("Unexpected message type"),
+        };
+
+        assert_eq!(sync.header.sdo_id, SdoId::new(0x100).unwrap());
+        assert_eq!(follow.header.sdo_id, SdoId::new(0x100).unwrap());
+        assert_eq!(
+            follow.follow_up_information,
+            Some(FollowUpInformationTlv::default())
+        );
+    }
 }
@@ -4,6 +4,7 @@ use core::{
 };
 
 pub use master::{MasterError, MasterState};
+pub(crate) use slave::SlaveEvents;
 pub use slave::{SlaveError, SlaveState};
 
 use super::Measurement;
@@ -16,19 +17,28 @@ use crate::{
     },
     network::NetworkPort,
     port::error::Result,
-    time::Instant,
+    time::{Duration, Instant},
 };
 
 mod master;
 mod slave;
 
+/// The state of a port (IEEE1588-2019 section 9.2.5)
 #[derive(Debug, Default)]
+#[allow(clippy::large_enum_variant)]
 pub enum PortState {
-    Disabled,
     #[default]
+    Initializing,
+    Faulty,
+    Disabled,
     Listening,
+    /// Waiting the given number of announce intervals before becoming master,
+    /// to make sure the port does not create a timing loop
+    PreMaster(u16),
     Master(MasterState),
     Passive,
+    /// Synchronizing to a master, but the servo has not converged yet
+    Uncalibrated(SlaveState),
     Slave(SlaveState),
 }
 
@@ -39,17 +49,28 @@ impl PortState {
         network_port: &mut P,
         port_identity: PortIdentity,
         default_ds: &DefaultDS,
+        one_step: bool,
     ) -> Result<()> {
         match self {
             PortState::Master(master) => {
                 master
-                    .send_sync(local_clock, network_port, port_identity, default_ds)
+                    .send_sync(
+                        local_clock,
+                        network_port,
+                        port_identity,
+                        default_ds,
+                        one_step,
+                    )
                     .await
             }
-            PortState::Slave(_)
-            | PortState::Listening
+            PortState::Initializing
+            | PortState::Faulty
             | PortState::Disabled
-            | PortState::Passive => Ok(()),
+            | PortState::Listening
+            | PortState::PreMaster(_)
+            | PortState::Passive
+            | PortState::Uncalibrated(_)
+            | PortState::Slave(_) => Ok(()),
         }
     }
 
@@ -78,13 +99,18 @@ impl PortState {
                     )
                     .await
             }
-            PortState::Slave(_)
-            | PortState::Listening
+            PortState::Initializing
+            | PortState::Faulty
             | PortState::Disabled
-            | PortState::Passive => Ok(()),
+            | PortState::Listening
+            | PortState::PreMaster(_)
+            | PortState::Passive
+            | PortState::Uncalibrated(_)
+            | PortState::Slave(_) => Ok(()),
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub async fn handle_message(
         &mut self,
         message: Message,
@@ -92,6 +118,7 @@ impl PortState {
         network_port: &mut impl NetworkPort,
         log_message_interval: i8,
         port_identity: PortIdentity,
+        delay_asymmetry: Duration,
         default_ds: &DefaultDS,
     ) -> Result<()> {
         match self {
@@ -107,28 +134,112 @@ impl PortState {
                     .await?;
                 Ok(())
             }
-            PortState::Slave(slave) => {
+            PortState::Uncalibrated(slave) | PortState::Slave(slave) => {
                 slave
                     .handle_message(
                         message,
                         current_time,
                         network_port,
                         port_identity,
+                        delay_asymmetry,
                         default_ds,
                     )
                     .await?;
                 Ok(())
             }
-            PortState::Listening | PortState::Disabled | PortState::Passive => Ok(()),
+            PortState::Initializing
+            | PortState::Faulty
+            | PortState::Disabled
+            | PortState::Listening
+            | PortState::PreMaster(_)
+            | PortState::Passive => Ok(()),
+        }
+    }
+
+    pub(crate) fn set_mean_link_delay(&mut self, mean_link_delay: Duration) {
+        match self {
+            PortState::Uncalibrated(slave) | PortState::Slave(slave) => {
+                slave.set_mean_link_delay(mean_link_delay)
+            }
+            PortState::Initializing
+            | PortState::Faulty
+            | PortState::Disabled
+            | PortState::Listening
+            | PortState::PreMaster(_)
+            | PortState::Master(_)
+            | PortState::Passive => (),
+        }
+    }
+
+    /// The value of the state in the portState enumeration (IEEE1588-2019 table
+    /// 20)
+    pub(crate) fn to_primitive(&self) -> u8 {
+        match self {
+            PortState::Initializing => 0x01,
+            PortState::Faulty => 0x02,
+            PortState::Disabled => 0x03,
+            PortState::Listening => 0x04,
+            PortState::PreMaster(_) => 0x05,
+            PortState::Master(_) => 0x06,
+            PortState::Passive => 0x07,
+            PortState::Uncalibrated(_) => 0x08,
+            PortState::Slave(_) => 0x09,
         }
     }
 
     pub fn extract_measurement(&mut self) -> Option<Measurement> {
         match self {
-            PortState::Slave(slave) => slave.extract_measurement(),
-            PortState::Master(_)
+            PortState::Uncalibrated(slave) | PortState::Slave(slave) => slave.extract_measurement(),
+            PortState::Initializing
+            | PortState::Faulty
+            | PortState::Disabled
+            | PortState::Listening
+            | PortState::PreMaster(_)
+            | PortState::Master(_)
+            | PortState::Passive => None,
+        }
+    }
+
+    /// The mean delay to the master, while uncalibrated or slave and once it is
+    /// known
+    pub(crate) fn mean_delay(&self) -> Option<Duration> {
+        match self {
+            PortState::Uncalibrated(slave) | PortState::Slave(slave) => slave.mean_delay(),
+            PortState::Initializing
+            | PortState::Faulty
+            | PortState::Disabled
+            | PortState::Listening
+            | PortState::PreMaster(_)
+            | PortState::Master(_)
+            | PortState::Passive => None,
+        }
+    }
+
+    /// The events of the slave since they were last taken, nothing when not a
+    /// slave
+    pub(crate) fn take_events(&mut self) -> SlaveEvents {
+        match self {
+            PortState::Uncalibrated(slave) | PortState::Slave(slave) => slave.take_events(),
+            PortState::Initializing
+            | PortState::Faulty
+            | PortState::Disabled
             | PortState::Listening
+            | PortState::PreMaster(_)
+            | PortState::Master(_)
+            | PortState::Passive => SlaveEvents::default(),
+        }
+    }
+
+    /// The master the port synchronizes to, while uncalibrated or slave
+    pub(crate) fn remote_master(&self) -> Option<PortIdentity> {
+        match self {
+            PortState::Uncalibrated(slave) | PortState::Slave(slave) => Some(slave.remote_master()),
+            PortState::Initializing
+            | PortState::Faulty
             | PortState::Disabled
+            | PortState::Listening
+            | PortState::PreMaster(_)
+            | PortState::Master(_)
             | PortState::Passive => None,
         }
     }
@@ -137,10 +248,14 @@ impl PortState {
 impl Display for PortState {
     fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         match self {
+            PortState::Initializing => write!(f, "Initializing"),
+            PortState::Faulty => write!(f, "Faulty"),
             PortState::Disabled => write!(f, "Disabled"),
             PortState::Listening => write!(f, "Listening"),
+            PortState::PreMaster(_) => write!(f, "PreMaster"),
             PortState::Master(_) => write!(f, "Master"),
             PortState::Passive => write!(f, "Passive"),
+            PortState::Uncalibrated(_) => write!(f, "Uncalibrated"),
             PortState::Slave(_) => write!(f, "Slave"),
         }
     }
@@ -1,19 +1,25 @@
 use crate::{
     datastructures::{
-        common::{PortIdentity, Timestamp},
-        datasets::DefaultDS,
-        messages::{DelayRespMessage, FollowUpMessage, Message, MessageBuilder, SyncMessage},
+        common::{PortIdentity, TimeInterval, Timestamp},
+        datasets::{DefaultDS, DelayMechanism},
+        messages::{
+            DelayRespMessage, FollowUpMessage, Message, MessageBuilder, SyncComputedRecord,
+            SyncMessage, SyncTimingRecord, TxEventRecord,
+        },
     },
     network::NetworkPort,
-    port::{sequence_id::SequenceIdGenerator, Measurement},
+    port::{
+        error::{PortError, Result},
+        sequence_id::SequenceIdGenerator,
+        Measurement,
+    },
     time::{Duration, Instant},
 };
 
-type Result<T, E = SlaveError> = core::result::Result<T, E>;
-
 #[derive(Debug)]
 pub struct SlaveState {
     remote_master: PortIdentity,
+    delay_mechanism: DelayMechanism,
 
     sync_state: SyncState,
     delay_state: DelayState,
@@ -22,25 +28,52 @@ pub struct SlaveState {
 
     next_delay_measurement: Option<Instant>,
     pending_followup: Option<FollowUpMessage>,
+
+    events: SlaveEvents,
 }
 
 impl SlaveState {
     pub fn remote_master(&self) -> PortIdentity {
         self.remote_master
     }
+
+    /// The mean delay to the master, once it is known
+    pub(crate) fn mean_delay(&self) -> Option<Duration> {
+        match self.delay_state {
+            DelayState::AfterDelayResp { mean_delay } => Some(mean_delay),
+            DelayState::Initial | DelayState::AfterSync { .. } => None,
+        }
+    }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+/// The events of a slave since they were last taken, for slave event
+/// monitoring (IEEE1588-2019 section 16.11)
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct SlaveEvents {
+    /// The timing of the last Sync completed by its Follow_Up, if needed
+    pub(crate) sync_timing: Option<SyncTimingRecord>,
+    /// The offset and delay computed from the last Sync
+    pub(crate) sync_computed: Option<SyncComputedRecord>,
+    /// The transmission of the last Delay_Req
+    pub(crate) delay_req: Option<TxEventRecord>,
+}
+
+#[derive(Debug, PartialEq)]
 enum SyncState {
     Initial,
     AfterSync {
         sync_id: u16,
         sync_recv_time: Instant,
         sync_correction: Duration,
+        /// The timing of the Sync, completed by its Follow_Up
+        sync_timing: SyncTimingRecord,
     },
     AfterFollowUp {
+        sync_id: u16,
         sync_recv_time: Instant,
         sync_send_time: Instant,
+        /// Ratio of the frequency of the grandmaster to that of the master
+        rate_ratio: f64,
     },
 }
 
@@ -66,23 +99,31 @@ impl DelayState {
 }
 
 impl SlaveState {
-    pub fn new(remote_master: PortIdentity) -> Self {
+    pub fn new(remote_master: PortIdentity, delay_mechanism: DelayMechanism) -> Self {
         SlaveState {
             remote_master,
+            delay_mechanism,
             sync_state: SyncState::Initial,
             delay_state: DelayState::Initial,
             delay_req_ids: SequenceIdGenerator::new(),
             next_delay_measurement: None,
             pending_followup: None,
+            events: SlaveEvents::default(),
         }
     }
 
+    /// Take the events of the slave since the last call
+    pub(crate) fn take_events(&mut self) -> SlaveEvents {
+        core::mem::take(&mut self.events)
+    }
+
     pub(crate) async fn handle_message<P: NetworkPort>(
         &mut self,
         message: Message,
         current_time: Instant,
         network_port: &mut P,
         port_identity: PortIdentity,
+        delay_asymmetry: Duration,
         default_ds: &DefaultDS,
     ) -> Result<()> {
         // Only listen to master
@@ -94,13 +135,14 @@ impl SlaveState {
                         current_time,
                         network_port,
                         port_identity,
+                        delay_asymmetry,
                         default_ds,
                     )
                     .await
                 }
                 Message::FollowUp(message) => self.handle_follow_up(message),
                 Message::DelayResp(message) => self.handle_delay_resp(message, port_identity),
-                _ => Err(SlaveError::UnexpectedMessage),
+                _ => Err(SlaveError::UnexpectedMessage.into()),
             }
         } else {
             Ok(())
@@ -113,45 +155,71 @@ impl SlaveState {
         current_time: Instant,
         network_port: &mut P,
         port_identity: PortIdentity,
+        delay_asymmetry: Duration,
         default_ds: &DefaultDS,
     ) -> Result<()> {
         log::debug!("Received sync {:?}", message.header().sequence_id());
+        // The delay asymmetry is added to the correction of received Sync messages
+        // (IEEE1588-2019 section 11.6.2)
+        let sync_correction = Duration::from(message.header().correction_field()) + delay_asymmetry;
+        let sync_timing = SyncTimingRecord {
+            sequence_id: message.header().sequence_id(),
+            sync_origin_timestamp: message.origin_timestamp(),
+            total_correction_field: message.header().correction_field(),
+            scaled_cumulative_rate_offset: 0,
+            sync_event_ingress_timestamp: current_time.into(),
+        };
         self.sync_state = if message.header().two_step_flag() {
             SyncState::AfterSync {
                 sync_id: message.header().sequence_id(),
                 sync_recv_time: current_time,
-                sync_correction: Duration::from(message.header().correction_field()),
+                sync_correction,
+                sync_timing,
             }
         } else {
+            self.events.sync_timing = Some(sync_timing);
             SyncState::AfterFollowUp {
+                sync_id: message.header().sequence_id(),
                 sync_recv_time: current_time,
-                sync_send_time: Instant::from(message.origin_timestamp())
-                    + Duration::from(message.header().correction_field()),
+                sync_send_time: Instant::from(message.origin_timestamp()) + sync_correction,
+                rate_ratio: 1.0,
             }
         };
 
-        if !self.delay_state.finished()
-            || self.next_delay_measurement.unwrap_or_default() < current_time
+        // With the peer to peer delay mechanism, the mean delay is the link delay
+        // measured by the port, so no delay requests are sent to the master
+        if self.delay_mechanism != DelayMechanism::P2P
+            && (!self.delay_state.finished()
+                || self.next_delay_measurement.unwrap_or_default() < current_time)
         {
             log::debug!("Starting new delay measurement");
             let delay_id = self.delay_req_ids.generate();
+            // and subtracted from the correction of sent Delay_Req messages, which the
+            // master returns in its Delay_Resp (IEEE1588-2019 section 11.6.3)
             let delay_req = MessageBuilder::new()
                 .sdo_id(default_ds.sdo_id)
                 .domain_number(default_ds.domain_number)
                 .source_port_identity(port_identity)
                 .sequence_id(delay_id)
                 .log_message_interval(0x7f)
+                .correction_field((-delay_asymmetry).into())
                 .delay_req_message(Timestamp::default());
-            let delay_req_encode = delay_req.serialize_vec().unwrap();
-            let delay_send_time = network_port
-                .send_time_critical(&delay_req_encode)
-                .await
-                .expect("Program error: missing timestamp id")
-                .unwrap_or(current_time);
+            let delay_req_encode = delay_req.serialize_vec()?;
+            let delay_send_time = match network_port.send_time_critical(&delay_req_encode).await {
+                Ok(opt_time) => opt_time.unwrap_or(current_time),
+                Err(error) => {
+                    log::error!("failed to send delay request: {:?}", error);
+                    return Err(PortError::Network);
+                }
+            };
             self.delay_state = DelayState::AfterSync {
                 delay_id,
                 delay_send_time,
             };
+            self.events.delay_req = Some(TxEventRecord {
+                sequence_id: delay_id,
+                event_egress_timestamp: delay_send_time.into(),
+            });
         }
 
         if let Some(follow_up) = self.pending_followup {
@@ -169,6 +237,7 @@ impl SlaveState {
                 sync_id,
                 sync_recv_time,
                 sync_correction,
+                sync_timing,
             } => {
                 // Ignore messages not belonging to currently processing sync
                 if sync_id == message.header().sequence_id() {
@@ -180,9 +249,24 @@ impl SlaveState {
                         + Duration::from(message.header().correction_field())
                         + sync_correction;
                     self.sync_state = SyncState::AfterFollowUp {
+                        sync_id,
                         sync_recv_time,
                         sync_send_time,
+                        rate_ratio: message
+                            .follow_up_information
+                            .map_or(1.0, |tlv| tlv.cumulative_rate_ratio()),
                     };
+                    self.events.sync_timing = Some(SyncTimingRecord {
+                        sync_origin_timestamp: message.precise_origin_timestamp(),
+                        total_correction_field: TimeInterval(
+                            *sync_timing.total_correction_field
+                                + *message.header().correction_field(),
+                        ),
+                        scaled_cumulative_rate_offset: message
+                            .follow_up_information
+                            .map_or(0, |tlv| tlv.cumulative_scaled_rate_offset),
+                        ..sync_timing
+                    });
 
                     Ok(())
                 } else {
@@ -211,6 +295,7 @@ impl SlaveState {
             SyncState::AfterFollowUp {
                 sync_recv_time,
                 sync_send_time,
+                ..
             } => {
                 match self.delay_state {
                     DelayState::AfterSync {
@@ -254,30 +339,52 @@ impl SlaveState {
                     // Wrong state
                     DelayState::Initial | DelayState::AfterDelayResp { .. } => {
                         log::debug!("Unexpected DelayResponse");
-                        Err(SlaveError::OutOfSequence)
+                        Err(SlaveError::OutOfSequence.into())
                     }
                 }
             }
             // Wrong state
-            SyncState::Initial | SyncState::AfterSync { .. } => Err(SlaveError::OutOfSequence),
+            SyncState::Initial | SyncState::AfterSync { .. } => {
+                Err(SlaveError::OutOfSequence.into())
+            }
+        }
+    }
+
+    /// Use the mean link delay measured by the peer delay mechanism as the
+    /// delay to the master. Ignored when using the end to end delay mechanism.
+    pub(crate) fn set_mean_link_delay(&mut self, mean_delay: Duration) {
+        if self.delay_mechanism == DelayMechanism::P2P {
+            self.delay_state = DelayState::AfterDelayResp { mean_delay };
         }
     }
 
     pub(crate) fn extract_measurement(&mut self) -> Option<Measurement> {
         match self.sync_state {
             SyncState::AfterFollowUp {
+                sync_id,
                 sync_recv_time,
                 sync_send_time,
-                ..
+                rate_ratio,
             } => {
                 match self.delay_state {
                     DelayState::AfterDelayResp { mean_delay } => {
+                        // The sync send time is in the time base of the grandmaster, so the
+                        // delay, measured by the local clock that is syntonized to the master,
+                        // is converted to it as well (IEEE 802.1AS-2020 section 11.2.14.2.1)
                         let result = Measurement {
-                            master_offset: sync_recv_time - sync_send_time - mean_delay,
+                            master_offset: sync_recv_time
+                                - sync_send_time
+                                - mean_delay * rate_ratio,
                             event_time: sync_recv_time,
                         };
 
                         self.sync_state = SyncState::Initial;
+                        self.events.sync_computed = Some(SyncComputedRecord {
+                            sequence_id: sync_id,
+                            offset_from_master: result.master_offset.into(),
+                            mean_path_delay: mean_delay.into(),
+                            scaled_neighbor_rate_ratio: 0,
+                        });
 
                         log::debug!("Extracted measurement {:?}", result);
 
@@ -310,50 +417,20 @@ pub enum SlaveError {
 
 #[cfg(test)]
 mod tests {
-    use std::vec::Vec;
-
     use super::*;
-    use crate::datastructures::{
-        common::{ClockIdentity, TimeInterval},
-        messages::{Header, SdoId},
+    use crate::{
+        datastructures::{
+            common::{ClockIdentity, TimeInterval},
+            messages::{FollowUpInformationTlv, Header, SdoId},
+        },
+        port::tests::RecordingNetworkPort,
     };
 
-    #[derive(Debug, Default)]
-    struct TestNetworkPort {
-        normal: Vec<Vec<u8>>,
-        time: Vec<Vec<u8>>,
-
-        current_time: Instant,
-    }
-
-    impl NetworkPort for TestNetworkPort {
-        type Error = std::convert::Infallible;
-
-        async fn send(&mut self, data: &[u8]) -> core::result::Result<(), Self::Error> {
-            self.normal.push(Vec::from(data));
-            Ok(())
-        }
-
-        async fn send_time_critical(
-            &mut self,
-            data: &[u8],
-        ) -> core::result::Result<Option<Instant>, Self::Error> {
-            self.time.push(Vec::from(data));
-            Ok(Some(self.current_time))
-        }
-
-        async fn recv(
-            &mut self,
-        ) -> core::result::Result<crate::network::NetworkPacket, Self::Error> {
-            panic!("Recv shouldn't be called by state");
-        }
-    }
-
     #[test]
     fn test_sync_without_delay_msg() {
-        let mut port = TestNetworkPort::default();
+        let mut port = RecordingNetworkPort::default();
 
-        let mut state = SlaveState::new(Default::default());
+        let mut state = SlaveState::new(Default::default(), DelayMechanism::E2E);
         state.delay_state = DelayState::AfterDelayResp {
             mean_delay: Duration::from_micros(100),
         };
@@ -380,6 +457,7 @@ mod tests {
             Instant::from_micros(50),
             &mut port,
             PortIdentity::default(),
+            Duration::ZERO,
             &defaultds,
         ))
         .unwrap();
@@ -407,6 +485,7 @@ mod tests {
             Instant::from_micros(1050),
             &mut port,
             PortIdentity::default(),
+            Duration::ZERO,
             &defaultds,
         ))
         .unwrap();
@@ -423,10 +502,12 @@ mod tests {
                     ..Default::default()
                 },
                 precise_origin_timestamp: Instant::from_micros(1000).into(),
+                follow_up_information: None,
             }),
             Instant::from_micros(1100),
             &mut port,
             PortIdentity::default(),
+            Duration::ZERO,
             &defaultds,
         ))
         .unwrap();
@@ -444,9 +525,9 @@ mod tests {
 
     #[test]
     fn test_sync_with_delay() {
-        let mut port = TestNetworkPort::default();
+        let mut port = RecordingNetworkPort::default();
 
-        let mut state = SlaveState::new(Default::default());
+        let mut state = SlaveState::new(Default::default(), DelayMechanism::E2E);
 
         let defaultds = DefaultDS::new_ordinary_clock(
             ClockIdentity::default(),
@@ -470,6 +551,7 @@ mod tests {
             Instant::from_micros(50),
             &mut port,
             PortIdentity::default(),
+            Duration::ZERO,
             &defaultds,
         ))
         .unwrap();
@@ -495,6 +577,7 @@ mod tests {
             Instant::from_micros(50),
             &mut port,
             PortIdentity::default(),
+            Duration::ZERO,
             &defaultds,
         ))
         .unwrap();
@@ -530,6 +613,7 @@ mod tests {
             Instant::from_micros(1050),
             &mut port,
             PortIdentity::default(),
+            Duration::ZERO,
             &defaultds,
         ))
         .unwrap();
@@ -545,10 +629,12 @@ mod tests {
                     ..Default::default()
                 },
                 precise_origin_timestamp: Instant::from_micros(1000).into(),
+                follow_up_information: None,
             }),
             Instant::from_micros(1150),
             &mut port,
             PortIdentity::default(),
+            Duration::ZERO,
             &defaultds,
         ))
         .unwrap();
@@ -570,6 +656,7 @@ mod tests {
             Instant::from_micros(50),
             &mut port,
             PortIdentity::default(),
+            Duration::ZERO,
             &defaultds,
         ))
         .unwrap();
@@ -591,11 +678,88 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_sync_with_delay_asymmetry() {
+        let mut port = RecordingNetworkPort::default();
+
+        let mut state = SlaveState::new(Default::default(), DelayMechanism::E2E);
+
+        let defaultds = DefaultDS::new_ordinary_clock(
+            ClockIdentity::default(),
+            15,
+            128,
+            0,
+            false,
+            SdoId::default(),
+        );
+
+        // The path from the master takes 120us, the path back 80us
+        let delay_asymmetry = Duration::from_micros(20);
+
+        port.current_time = Instant::from_micros(200);
+        embassy_futures::block_on(state.handle_message(
+            Message::Sync(SyncMessage {
+                header: Header {
+                    two_step_flag: false,
+                    ..Default::default()
+                },
+                origin_timestamp: Instant::from_micros(0).into(),
+            }),
+            Instant::from_micros(120),
+            &mut port,
+            PortIdentity::default(),
+            delay_asymmetry,
+            &defaultds,
+        ))
+        .unwrap();
+
+        let req = match Message::deserialize(&port.time.pop().unwrap()).unwrap() {
+            Message::DelayReq(msg) => msg,
+            _ => panic!("Incorrect message type"),
+        };
+        assert_eq!(
+            Duration::from(req.header.correction_field),
+            Duration::from_micros(-20)
+        );
+
+        embassy_futures::block_on(state.handle_message(
+            Message::DelayResp(DelayRespMessage {
+                header: Header {
+                    correction_field: req.header.correction_field,
+                    sequence_id: req.header.sequence_id,
+                    ..Default::default()
+                },
+                receive_timestamp: Instant::from_micros(280).into(),
+                requesting_port_identity: req.header.source_port_identity(),
+            }),
+            Instant::from_micros(300),
+            &mut port,
+            PortIdentity::default(),
+            delay_asymmetry,
+            &defaultds,
+        ))
+        .unwrap();
+
+        assert_eq!(
+            state.delay_state,
+            DelayState::AfterDelayResp {
+                mean_delay: Duration::from_micros(100)
+            }
+        );
+        assert_eq!(
+            state.extract_measurement(),
+            Some(Measurement {
+                event_time: Instant::from_micros(120),
+                master_offset: Duration::ZERO,
+            })
+        );
+    }
+
     #[test]
     fn test_follow_up_before_sync() {
-        let mut port = TestNetworkPort::default();
+        let mut port = RecordingNetworkPort::default();
 
-        let mut state = SlaveState::new(Default::default());
+        let mut state = SlaveState::new(Default::default(), DelayMechanism::E2E);
         state.delay_state = DelayState::AfterDelayResp {
             mean_delay: Duration::from_micros(100),
         };
@@ -618,10 +782,12 @@ mod tests {
                     ..Default::default()
                 },
                 precise_origin_timestamp: Instant::from_micros(10).into(),
+                follow_up_information: None,
             }),
             Instant::from_micros(100),
             &mut port,
             PortIdentity::default(),
+            Duration::ZERO,
             &defaultds,
         ))
         .unwrap();
@@ -643,6 +809,7 @@ mod tests {
             Instant::from_micros(50),
             &mut port,
             PortIdentity::default(),
+            Duration::ZERO,
             &defaultds,
         ))
         .unwrap();
@@ -660,9 +827,9 @@ mod tests {
 
     #[test]
     fn test_old_followup_during() {
-        let mut port = TestNetworkPort::default();
+        let mut port = RecordingNetworkPort::default();
 
-        let mut state = SlaveState::new(Default::default());
+        let mut state = SlaveState::new(Default::default(), DelayMechanism::E2E);
         state.delay_state = DelayState::AfterDelayResp {
             mean_delay: Duration::from_micros(100),
         };
@@ -690,6 +857,7 @@ mod tests {
             Instant::from_micros(50),
             &mut port,
             PortIdentity::default(),
+            Duration::ZERO,
             &defaultds,
         ))
         .unwrap();
@@ -706,10 +874,12 @@ mod tests {
                     ..Default::default()
                 },
                 precise_origin_timestamp: Instant::from_micros(10).into(),
+                follow_up_information: None,
             }),
             Instant::from_micros(100),
             &mut port,
             PortIdentity::default(),
+            Duration::ZERO,
             &defaultds,
         ))
         .unwrap();
@@ -726,10 +896,12 @@ mod tests {
                     ..Default::default()
                 },
                 precise_origin_timestamp: Instant::from_micros(10).into(),
+                follow_up_information: None,
             }),
             Instant::from_micros(100),
             &mut port,
             PortIdentity::default(),
+            Duration::ZERO,
             &defaultds,
         ))
         .unwrap();
@@ -747,9 +919,9 @@ mod tests {
 
     #[test]
     fn test_reset_after_missing_followup() {
-        let mut port = TestNetworkPort::default();
+        let mut port = RecordingNetworkPort::default();
 
-        let mut state = SlaveState::new(Default::default());
+        let mut state = SlaveState::new(Default::default(), DelayMechanism::E2E);
         state.delay_state = DelayState::AfterDelayResp {
             mean_delay: Duration::from_micros(100),
         };
@@ -777,6 +949,7 @@ mod tests {
             Instant::from_micros(50),
             &mut port,
             PortIdentity::default(),
+            Duration::ZERO,
             &defaultds,
         ))
         .unwrap();
@@ -798,6 +971,7 @@ mod tests {
             Instant::from_micros(1050),
             &mut port,
             PortIdentity::default(),
+            Duration::ZERO,
             &defaultds,
         ))
         .unwrap();
@@ -814,10 +988,12 @@ mod tests {
                     ..Default::default()
                 },
                 precise_origin_timestamp: Instant::from_micros(1000).into(),
+                follow_up_information: None,
             }),
             Instant::from_micros(1100),
             &mut port,
             PortIdentity::default(),
+            Duration::ZERO,
             &defaultds,
         ))
         .unwrap();
@@ -833,11 +1009,242 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_sync_with_peer_delay() {
+        let mut port = RecordingNetworkPort::default();
+
+        let mut state = SlaveState::new(Default::default(), DelayMechanism::P2P);
+
+        let defaultds = DefaultDS::new_ordinary_clock(
+            ClockIdentity::default(),
+            15,
+            128,
+            0,
+            false,
+            SdoId::default(),
+        );
+
+        let sync = Message::Sync(SyncMessage {
+            header: Header {
+                two_step_flag: false,
+                correction_field: TimeInterval(1000.into()),
+                ..Default::default()
+            },
+            origin_timestamp: Instant::from_micros(0).into(),
+        });
+
+        port.current_time = Instant::from_micros(100);
+        embassy_futures::block_on(state.handle_message(
+            sync.clone(),
+            Instant::from_micros(50),
+            &mut port,
+            PortIdentity::default(),
+            Duration::ZERO,
+            &defaultds,
+        ))
+        .unwrap();
+
+        // No delay request is sent, and without a link delay there is no measurement
+        assert_eq!(port.normal.len(), 0);
+        assert_eq!(port.time.len(), 0);
+        assert_eq!(state.extract_measurement(), None);
+
+        state.set_mean_link_delay(Duration::from_micros(100));
+
+        embassy_futures::block_on(state.handle_message(
+            sync,
+            Instant::from_micros(50),
+            &mut port,
+            PortIdentity::default(),
+            Duration::ZERO,
+            &defaultds,
+        ))
+        .unwrap();
+
+        assert_eq!(port.normal.len(), 0);
+        assert_eq!(port.time.len(), 0);
+        assert_eq!(
+            state.extract_measurement(),
+            Some(Measurement {
+                event_time: Instant::from_micros(50),
+                master_offset: Duration::from_micros(-51)
+            })
+        );
+    }
+
+    #[test]
+    fn test_follow_up_rate_ratio() {
+        let mut port = RecordingNetworkPort::default();
+
+        let mut state = SlaveState::new(Default::default(), DelayMechanism::P2P);
+        state.set_mean_link_delay(Duration::from_nanos(2048));
+
+        let defaultds = DefaultDS::new_ordinary_clock(
+            ClockIdentity::default(),
+            15,
+            128,
+            0,
+            false,
+            SdoId::default(),
+        );
+
+        embassy_futures::block_on(state.handle_message(
+            Message::Sync(SyncMessage {
+                header: Header {
+                    two_step_flag: true,
+                    sequence_id: 3,
+                    ..Default::default()
+                },
+                origin_timestamp: Instant::from_micros(0).into(),
+            }),
+            Instant::from_micros(50),
+            &mut port,
+            PortIdentity::default(),
+            Duration::ZERO,
+            &defaultds,
+        ))
+        .unwrap();
+
+        embassy_futures::block_on(state.handle_message(
+            Message::FollowUp(FollowUpMessage {
+                header: Header {
+                    sequence_id: 3,
+                    ..Default::default()
+                },
+                precise_origin_timestamp: Instant::from_micros(0).into(),
+                follow_up_information: Some(FollowUpInformationTlv {
+                    cumulative_scaled_rate_offset: 1 << 30,
+                    ..Default::default()
+                }),
+            }),
+            Instant::from_micros(51),
+            &mut port,
+            PortIdentity::default(),
+            Duration::ZERO,
+            &defaultds,
+        ))
+        .unwrap();
+
+        // The grandmaster runs 2^-11 faster than the master, so the link delay is
+        // 2049ns in the time base of the grandmaster
+        assert_eq!(
+            state.extract_measurement(),
+            Some(Measurement {
+                event_time: Instant::from_micros(50),
+                master_offset: Duration::from_nanos(50000 - 2049)
+            })
+        );
+    }
+
+    #[test]
+    fn test_slave_events() {
+        let mut port = RecordingNetworkPort {
+            current_time: Instant::from_micros(60),
+            ..Default::default()
+        };
+
+        let mut state = SlaveState::new(Default::default(), DelayMechanism::E2E);
+        state.delay_state = DelayState::AfterDelayResp {
+            mean_delay: Duration::from_micros(100),
+        };
+
+        let defaultds = DefaultDS::new_ordinary_clock(
+            ClockIdentity::default(),
+            15,
+            128,
+            0,
+            false,
+            SdoId::default(),
+        );
+
+        embassy_futures::block_on(state.handle_message(
+            Message::Sync(SyncMessage {
+                header: Header {
+                    two_step_flag: true,
+                    sequence_id: 15,
+                    correction_field: TimeInterval(1000.into()),
+                    ..Default::default()
+                },
+                origin_timestamp: Instant::from_micros(0).into(),
+            }),
+            Instant::from_micros(50),
+            &mut port,
+            PortIdentity::default(),
+            Duration::ZERO,
+            &defaultds,
+        ))
+        .unwrap();
+
+        // The sync timing is only complete with the follow up
+        let events = state.take_events();
+        assert_eq!(events.sync_timing, None);
+        assert_eq!(
+            events.delay_req,
+            Some(TxEventRecord {
+                sequence_id: 0,
+                event_egress_timestamp: Instant::from_micros(60).into(),
+            })
+        );
+
+        embassy_futures::block_on(state.handle_message(
+            Message::FollowUp(FollowUpMessage {
+                header: Header {
+                    sequence_id: 15,
+                    correction_field: TimeInterval(2000.into()),
+                    ..Default::default()
+                },
+                precise_origin_timestamp: Instant::from_micros(10).into(),
+                follow_up_information: Some(FollowUpInformationTlv {
+                    cumulative_scaled_rate_offset: 1 << 21,
+                    gm_time_base_indicator: 0,
+                    last_gm_phase_change: 0,
+                    scaled_last_gm_freq_change: 0,
+                }),
+            }),
+            Instant::from_micros(100),
+            &mut port,
+            PortIdentity::default(),
+            Duration::ZERO,
+            &defaultds,
+        ))
+        .unwrap();
+
+        // Skip the delay response to the sent delay request
+        state.delay_state = DelayState::AfterDelayResp {
+            mean_delay: Duration::from_micros(100),
+        };
+        let measurement = state.extract_measurement().unwrap();
+        let events = state.take_events();
+        assert_eq!(
+            events.sync_timing,
+            Some(SyncTimingRecord {
+                sequence_id: 15,
+                sync_origin_timestamp: Instant::from_micros(10).into(),
+                total_correction_field: TimeInterval(3000.into()),
+                scaled_cumulative_rate_offset: 1 << 21,
+                sync_event_ingress_timestamp: Instant::from_micros(50).into(),
+            })
+        );
+        assert_eq!(
+            events.sync_computed,
+            Some(SyncComputedRecord {
+                sequence_id: 15,
+                offset_from_master: measurement.master_offset.into(),
+                mean_path_delay: Duration::from_micros(100).into(),
+                scaled_neighbor_rate_ratio: 0,
+            })
+        );
+        assert_eq!(events.delay_req, None);
+
+        // Events are only reported once
+        assert_eq!(state.take_events(), SlaveEvents::default());
+    }
+
     #[test]
     fn test_ignore_unrelated_delayresp() {
-        let mut port = TestNetworkPort::default();
+        let mut port = RecordingNetworkPort::default();
 
-        let mut state = SlaveState::new(Default::default());
+        let mut state = SlaveState::new(Default::default(), DelayMechanism::E2E);
 
         let defaultds = DefaultDS::new_ordinary_clock(
             ClockIdentity::default(),
@@ -861,6 +1268,7 @@ mod tests {
             Instant::from_micros(50),
             &mut port,
             PortIdentity::default(),
+            Duration::ZERO,
             &defaultds,
         ))
         .unwrap();
@@ -890,6 +1298,7 @@ mod tests {
             Instant::from_micros(40),
             &mut port,
             PortIdentity::default(),
+            Duration::ZERO,
             &defaultds,
         ))
         .unwrap();
@@ -911,6 +1320,7 @@ mod tests {
             Instant::from_micros(40),
             &mut port,
             PortIdentity::default(),
+            Duration::ZERO,
             &defaultds,
         ))
         .unwrap();
@@ -932,6 +1342,7 @@ mod tests {
             Instant::from_micros(50),
             &mut port,
             PortIdentity::default(),
+            Duration::ZERO,
             &defaultds,
         ))
         .unwrap();
@@ -952,4 +1363,40 @@ mod tests {
             })
         );
     }
+
+    #[test]
+    fn test_delay_req_unreachable() {
+        let mut port = RecordingNetworkPort {
+            unreachable: true,
+            ..Default::default()
+        };
+
+        let mut state = SlaveState::new(Default::default(), DelayMechanism::E2E);
+
+        let defaultds = DefaultDS::new_ordinary_clock(
+            ClockIdentity::default(),
+            15,
+            128,
+            0,
+            false,
+            SdoId::default(),
+        );
+
+        // The port becomes faulty instead of waiting for a response that never comes
+        let result = embassy_futures::block_on(state.handle_message(
+            Message::Sync(SyncMessage {
+                header: Header {
+                    two_step_flag: false,
+                    ..Default::default()
+                },
+                origin_timestamp: Instant::from_micros(0).into(),
+            }),
+            Instant::from_micros(50),
+            &mut port,
+            PortIdentity::default(),
+            Duration::ZERO,
+            &defaultds,
+        ));
+        assert!(matches!(result, Err(PortError::Network)));
+    }
 }
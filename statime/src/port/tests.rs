@@ -1,16 +1,29 @@
-use std::convert::Infallible;
+use std::{
+    cell::{Cell, RefCell},
+    convert::Infallible,
+    pin::pin,
+    vec::Vec,
+};
 
 use arrayvec::{ArrayVec, CapacityError};
 use fixed::traits::ToFixed;
 
 use crate::{
+    bmc::bmca::{Bmca, RecommendedState},
+    clock::Clock,
     datastructures::{
-        common::{ClockIdentity, PortIdentity, TimeInterval, Timestamp},
-        datasets::DefaultDS,
-        messages::{MessageBuilder, SdoId, MAX_DATA_LEN},
+        common::{
+            ClockIdentity, ClockQuality, PortAddress, PortIdentity, TimeInterval, TimeSource,
+            Timestamp,
+        },
+        datasets::{
+            CurrentDS, DefaultDS, DelayMechanism, DesiredState, ParentDS, PortDS, TimePropertiesDS,
+        },
+        messages::{AnnounceMessage, Message, MessageBuilder, SdoId, MAX_DATA_LEN},
     },
+    filters::basic::BasicFilter,
     network::{NetworkPacket, NetworkPort, NetworkRuntime},
-    port::{state::SlaveState, Measurement},
+    port::{state::SlaveState, ticker::Ticker, Measurement, Port, PortState},
     time::{Duration, Instant},
 };
 
@@ -72,10 +85,24 @@ impl NetworkPort for TestNetworkPort {
         Ok(Some(Instant::from_nanos(7)))
     }
 
+    // All ports of the test network receive everything
+    async fn send_to(&mut self, data: &[u8], _address: &PortAddress) -> Result<(), Self::Error> {
+        self.send(data).await
+    }
+
+    async fn send_time_critical_to(
+        &mut self,
+        data: &[u8],
+        _address: &PortAddress,
+    ) -> Result<Option<Instant>, Self::Error> {
+        self.send_time_critical(data).await
+    }
+
     async fn recv(&mut self) -> Result<NetworkPacket, Self::Error> {
         Ok(NetworkPacket {
             data: self.data_receiver.recv().await.unwrap().data,
             timestamp: Instant::from_secs(0),
+            source_address: None,
         })
     }
 }
@@ -85,6 +112,115 @@ pub struct TestNetworkPacket {
     data: ArrayVec<u8, MAX_DATA_LEN>,
 }
 
+/// Opens recording ports that timestamp time critical messages at the
+/// interface descriptor
+pub(crate) struct RecordingRuntime;
+
+impl NetworkRuntime for RecordingRuntime {
+    type InterfaceDescriptor = Instant;
+    type NetworkPort = RecordingNetworkPort;
+    type Error = Infallible;
+
+    async fn open(
+        &mut self,
+        interface: Self::InterfaceDescriptor,
+    ) -> Result<Self::NetworkPort, Self::Error> {
+        Ok(RecordingNetworkPort {
+            current_time: interface,
+            ..Default::default()
+        })
+    }
+}
+
+/// Records every message sent over it, and receives the packets queued in it
+#[derive(Debug, Default)]
+pub(crate) struct RecordingNetworkPort {
+    pub(crate) normal: Vec<Vec<u8>>,
+    pub(crate) time: Vec<Vec<u8>>,
+    /// Messages sent to an address, time critical or not
+    pub(crate) sent_to: Vec<(Vec<u8>, PortAddress)>,
+    /// Received in order, receiving panics when none are left
+    pub(crate) received: Vec<NetworkPacket>,
+
+    /// The transmit timestamp of time critical messages
+    pub(crate) current_time: Instant,
+    /// Sending fails, as if the destination can't be reached
+    pub(crate) unreachable: bool,
+}
+
+/// The destination of a message sent over a [`RecordingNetworkPort`] can't be
+/// reached
+#[derive(Debug)]
+pub(crate) struct Unreachable;
+
+impl NetworkPort for RecordingNetworkPort {
+    type Error = Unreachable;
+
+    async fn send(&mut self, data: &[u8]) -> Result<(), Self::Error> {
+        if self.unreachable {
+            return Err(Unreachable);
+        }
+        self.normal.push(Vec::from(data));
+        Ok(())
+    }
+
+    async fn send_time_critical(&mut self, data: &[u8]) -> Result<Option<Instant>, Self::Error> {
+        if self.unreachable {
+            return Err(Unreachable);
+        }
+        self.time.push(Vec::from(data));
+        Ok(Some(self.current_time))
+    }
+
+    async fn send_to(&mut self, data: &[u8], address: &PortAddress) -> Result<(), Self::Error> {
+        if self.unreachable {
+            return Err(Unreachable);
+        }
+        self.sent_to.push((Vec::from(data), address.clone()));
+        Ok(())
+    }
+
+    async fn send_time_critical_to(
+        &mut self,
+        data: &[u8],
+        address: &PortAddress,
+    ) -> Result<Option<Instant>, Self::Error> {
+        self.send_to(data, address).await?;
+        Ok(Some(self.current_time))
+    }
+
+    async fn recv(&mut self) -> Result<NetworkPacket, Self::Error> {
+        assert!(!self.received.is_empty(), "No packet left to receive");
+        Ok(self.received.remove(0))
+    }
+}
+
+/// A clock standing still at the current time, that can't be adjusted
+pub(crate) struct TestClock {
+    pub(crate) current_time: Instant,
+}
+
+impl Clock for TestClock {
+    type Error = Infallible;
+
+    fn now(&self) -> Instant {
+        self.current_time
+    }
+
+    fn quality(&self) -> ClockQuality {
+        panic!("Shouldn't be called");
+    }
+
+    fn adjust(
+        &mut self,
+        _time_offset: Duration,
+        _frequency_multiplier: f64,
+        _time_properties_ds: &TimePropertiesDS,
+    ) -> Result<(), Self::Error> {
+        panic!("Shouldn't be called");
+    }
+}
+
 #[tokio::test]
 async fn test_measurement_flow() {
     let mut network_runtime = TestRuntime::default();
@@ -107,7 +243,7 @@ async fn test_measurement_flow() {
 
     let remote_master = PortIdentity::default();
 
-    let mut test_state = SlaveState::new(remote_master);
+    let mut test_state = SlaveState::new(remote_master, DelayMechanism::E2E);
 
     assert_eq!(test_state.extract_measurement(), None);
 
@@ -126,6 +262,7 @@ async fn test_measurement_flow() {
             Instant::from_nanos(5),
             &mut network_port,
             port_identity,
+            Duration::ZERO,
             &defaultds,
         )
         .await
@@ -151,6 +288,7 @@ async fn test_measurement_flow() {
             Instant::from_nanos(13),
             &mut network_port,
             port_identity,
+            Duration::ZERO,
             &defaultds,
         )
         .await
@@ -164,3 +302,286 @@ async fn test_measurement_flow() {
         })
     );
 }
+
+fn announce(source_port_identity: PortIdentity) -> AnnounceMessage {
+    let message = MessageBuilder::new()
+        .source_port_identity(source_port_identity)
+        .announce_message(
+            Timestamp::default(),
+            37,
+            128,
+            ClockQuality::default(),
+            128,
+            source_port_identity.clock_identity,
+            0,
+            TimeSource::InternalOscillator,
+            Default::default(),
+            Default::default(),
+            None,
+            None,
+            None,
+        );
+    match message {
+        Message::Announce(announce) => announce,
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn test_port_state_transitions() {
+    let mut announce_receipt_timeout = pin!(Ticker::new(
+        |_| core::future::pending::<()>(),
+        Duration::from_secs(3)
+    ));
+
+    let mut port_ds = PortDS::new(PortIdentity::default(), 1, 1, 3, 0, DelayMechanism::E2E, 1);
+    assert!(matches!(port_ds.port_state, PortState::Initializing));
+
+    // Ports take no part in the BMCA before they are initialized
+    let master = PortIdentity {
+        clock_identity: ClockIdentity([2; 8]),
+        port_number: 1,
+    };
+    port_ds.set_recommended_port_state(
+        &RecommendedState::S1(announce(master)),
+        0,
+        &mut announce_receipt_timeout,
+    );
+    assert!(matches!(port_ds.port_state, PortState::Initializing));
+    port_ds.set_forced_port_state(PortState::Listening);
+
+    // A new master makes the port uncalibrated, until the servo converged
+    port_ds.set_recommended_port_state(
+        &RecommendedState::S1(announce(master)),
+        0,
+        &mut announce_receipt_timeout,
+    );
+    assert!(matches!(port_ds.port_state, PortState::Uncalibrated(_)));
+    port_ds.set_calibrated(false);
+    assert!(matches!(port_ds.port_state, PortState::Uncalibrated(_)));
+    port_ds.set_calibrated(true);
+    assert!(matches!(port_ds.port_state, PortState::Slave(_)));
+    assert_eq!(port_ds.port_state.remote_master(), Some(master));
+
+    // The same master keeps the port calibrated
+    port_ds.set_recommended_port_state(
+        &RecommendedState::S1(announce(master)),
+        0,
+        &mut announce_receipt_timeout,
+    );
+    assert!(matches!(port_ds.port_state, PortState::Slave(_)));
+
+    // A port that should become master waits steps removed + 1 announce
+    // intervals
+    port_ds.set_recommended_port_state(
+        &RecommendedState::M3(announce(master)),
+        2,
+        &mut announce_receipt_timeout,
+    );
+    assert!(matches!(port_ds.port_state, PortState::PreMaster(3)));
+    port_ds.qualification_timeout();
+    port_ds.qualification_timeout();
+    assert!(matches!(port_ds.port_state, PortState::PreMaster(1)));
+    port_ds.qualification_timeout();
+    assert!(matches!(port_ds.port_state, PortState::Master(_)));
+}
+
+#[test]
+fn test_slave_only() {
+    let default_ds = DefaultDS::new_ordinary_clock(
+        ClockIdentity::default(),
+        128,
+        128,
+        0,
+        true,
+        SdoId::default(),
+    );
+    assert_eq!(default_ds.clock_quality.clock_class, 255);
+
+    // Without any masters, a slave only clock keeps listening
+    let master = PortIdentity {
+        clock_identity: ClockIdentity([2; 8]),
+        port_number: 1,
+    };
+    let recommended_state = Bmca::calculate_recommended_state(
+        &default_ds,
+        None,
+        None,
+        &PortState::Slave(SlaveState::new(master, DelayMechanism::E2E)),
+    );
+    assert!(matches!(
+        recommended_state,
+        Some(RecommendedState::Listening)
+    ));
+
+    let recommended_state =
+        Bmca::calculate_recommended_state(&default_ds, None, None, &PortState::Listening);
+    assert!(recommended_state.is_none());
+}
+
+#[test]
+fn test_configured_port_state() {
+    let mut port_ds = PortDS::new(PortIdentity::default(), 1, 1, 3, 0, DelayMechanism::E2E, 1)
+        .with_desired_state(DesiredState::Master);
+
+    // Ports that are not running stay out of their configured state
+    port_ds.set_configured_port_state(DesiredState::Master);
+    assert!(matches!(port_ds.port_state, PortState::Initializing));
+    port_ds.set_forced_port_state(PortState::Listening);
+
+    // Configured ports become master without qualification
+    port_ds.set_configured_port_state(DesiredState::Master);
+    assert!(matches!(port_ds.port_state, PortState::Master(_)));
+
+    port_ds.set_configured_port_state(DesiredState::Passive);
+    assert!(matches!(port_ds.port_state, PortState::Passive));
+
+    // Slave ports wait for the announce messages of their master
+    port_ds.set_configured_port_state(DesiredState::Slave);
+    assert!(matches!(port_ds.port_state, PortState::Passive));
+}
+
+#[test]
+fn test_domain_measured() {
+    let mut announce_receipt_timeout = pin!(Ticker::new(
+        |_| core::future::pending::<()>(),
+        Duration::from_secs(3)
+    ));
+
+    let default_ds = DefaultDS::new_ordinary_clock(
+        ClockIdentity::default(),
+        128,
+        128,
+        1,
+        false,
+        SdoId::default(),
+    );
+    let mut port = embassy_futures::block_on(Port::new(
+        PortDS::new(PortIdentity::default(), 1, 1, 3, 0, DelayMechanism::P2P, 1),
+        &mut RecordingRuntime,
+        Instant::default(),
+    ));
+    let master = PortIdentity {
+        clock_identity: ClockIdentity([2; 8]),
+        port_number: 1,
+    };
+    let domain_port = &mut port.domains[0];
+    domain_port.port_ds.port_state = PortState::Slave(SlaveState::new(master, DelayMechanism::P2P));
+    domain_port
+        .port_ds
+        .port_state
+        .set_mean_link_delay(Duration::from_micros(100));
+
+    let sync = MessageBuilder::new()
+        .domain_number(1)
+        .source_port_identity(master)
+        .sync_message(Timestamp::default());
+    let current_ds = Cell::new(CurrentDS::default());
+    // Its measurements don't go through the filter, so it may as well be busy
+    let filter = RefCell::new(BasicFilter::new(0.25));
+    let _filter_busy = filter.borrow_mut();
+
+    // A domain that doesn't steer the clock still keeps its measurement
+    embassy_futures::block_on(domain_port.handle_packet(
+        &mut port.network_port,
+        NetworkPacket {
+            data: sync.serialize_vec().unwrap(),
+            timestamp: Instant::from_micros(1000),
+            source_address: None,
+        },
+        &RefCell::new(TestClock {
+            current_time: Instant::from_micros(1000),
+        }),
+        &filter,
+        &mut announce_receipt_timeout,
+        &default_ds,
+        &TimePropertiesDS::default(),
+        &ParentDS::default(),
+        &current_ds,
+        &[],
+        false,
+    ))
+    .unwrap();
+
+    assert_eq!(
+        current_ds.get().offset_from_master(),
+        Duration::from_micros(900)
+    );
+    assert_eq!(current_ds.get().mean_delay(), Duration::from_micros(100));
+}
+
+#[test]
+fn test_sync_receipt_timeout() {
+    let mut announce_receipt_timeout = pin!(Ticker::new(
+        |_| core::future::pending::<()>(),
+        Duration::from_secs(3)
+    ));
+
+    let default_ds = DefaultDS::new_ordinary_clock(
+        ClockIdentity::default(),
+        128,
+        128,
+        0,
+        false,
+        SdoId::default(),
+    );
+    let mut port = embassy_futures::block_on(Port::new(
+        PortDS::new(PortIdentity::default(), 1, 1, 3, 0, DelayMechanism::P2P, 1)
+            .with_sync_receipt_timeout(3),
+        &mut RecordingRuntime,
+        Instant::default(),
+    ));
+    let master = PortIdentity {
+        clock_identity: ClockIdentity([2; 8]),
+        port_number: 1,
+    };
+    let domain_port = &mut port.domains[0];
+    domain_port.port_ds.port_state = PortState::Slave(SlaveState::new(master, DelayMechanism::P2P));
+
+    // Without syncs, the port waits for its own sync intervals
+    domain_port.check_sync_receipt_timeout(&default_ds, Instant::from_secs(0));
+    domain_port.check_sync_receipt_timeout(&default_ds, Instant::from_secs(2));
+    assert!(matches!(
+        domain_port.port_ds.port_state,
+        PortState::Slave(_)
+    ));
+
+    // The master sends a sync every 4 seconds
+    let sync = MessageBuilder::new()
+        .source_port_identity(master)
+        .log_message_interval(2)
+        .sync_message(Timestamp::default());
+    embassy_futures::block_on(domain_port.handle_packet(
+        &mut port.network_port,
+        NetworkPacket {
+            data: sync.serialize_vec().unwrap(),
+            timestamp: Instant::from_secs(2),
+            source_address: None,
+        },
+        &RefCell::new(TestClock {
+            current_time: Instant::from_secs(2),
+        }),
+        &RefCell::new(BasicFilter::new(0.25)),
+        &mut announce_receipt_timeout,
+        &default_ds,
+        &TimePropertiesDS::default(),
+        &ParentDS::default(),
+        &Cell::new(CurrentDS::default()),
+        &[],
+        false,
+    ))
+    .unwrap();
+
+    // So it times out after 3 of its intervals, rather than of ours
+    domain_port.check_sync_receipt_timeout(&default_ds, Instant::from_secs(10));
+    domain_port.check_sync_receipt_timeout(&default_ds, Instant::from_secs(13));
+    assert!(matches!(
+        domain_port.port_ds.port_state,
+        PortState::Slave(_)
+    ));
+    domain_port.check_sync_receipt_timeout(&default_ds, Instant::from_secs(15));
+    assert!(matches!(
+        domain_port.port_ds.port_state,
+        PortState::Master(_)
+    ));
+}
@@ -35,6 +35,15 @@ where
         this.timer.set((this.reset)(interval));
         log::trace!("Timer reset");
     }
+
+    /// Change the interval of the ticker, restarting the current tick if the
+    /// interval changed
+    pub fn set_interval(self: &mut Pin<&mut Self>, interval: Duration) {
+        if self.interval != interval {
+            *self.as_mut().project().interval = interval;
+            self.reset();
+        }
+    }
 }
 
 impl<F, R> Stream for Ticker<F, R>
@@ -0,0 +1,953 @@
+//! Unicast message negotiation (IEEE1588-2019 section 16.1)
+
+use core::{cell::RefCell, ops::RangeInclusive};
+
+use arrayvec::ArrayVec;
+
+use super::{
+    error::{PortError, Result},
+    management::ALL_PORTS,
+    sequence_id::SequenceIdGenerator,
+    state::PortState,
+    DomainPort,
+};
+use crate::{
+    clock::Clock,
+    datastructures::{
+        common::{ClockIdentity, PortAddress, PortIdentity},
+        datasets::{
+            CurrentDS, DefaultDS, DelayMechanism, ParentDS, TimePropertiesDS, MAX_UNICAST_MASTERS,
+        },
+        messages::{
+            CancelUnicastTransmissionTlv, GrantUnicastTransmissionTlv, MessageBuilder, MessageType,
+            RequestUnicastTransmissionTlv, SignalingMessage, SignalingTlv, MAX_DATA_LEN,
+        },
+    },
+    network::{NetworkPacket, NetworkPort},
+    time::Instant,
+};
+
+/// The maximum number of grants a port hands out to slaves
+pub(crate) const MAX_UNICAST_GRANTS: usize = 32;
+
+/// The grant durations in seconds a master hands out, longer or shorter
+/// requests get a grant of the nearest duration in this range
+const GRANT_DURATIONS: RangeInclusive<u32> = 10..=1000;
+
+/// The 2-log of the number of seconds to wait before repeating a request that
+/// was denied or not answered
+pub(crate) const LOG_QUERY_INTERVAL: i8 = 3;
+const REQUEST_RETRY_INTERVAL: u32 = 1 << LOG_QUERY_INTERVAL;
+
+/// A request for unicast messages from a master, and its grant
+#[derive(Debug, Clone)]
+struct Request {
+    address: PortAddress,
+    message_type: MessageType,
+    /// The identity of the master, known once it answered any request
+    master: Option<PortIdentity>,
+    /// Seconds left on the grant, zero when nothing is granted
+    granted: u32,
+    /// Seconds until the request is sent again
+    request_in: u32,
+}
+
+/// A grant for unicast messages handed out to a slave
+#[derive(Debug, Clone)]
+struct Grant {
+    address: PortAddress,
+    message_type: MessageType,
+    log_inter_message_period: i8,
+    /// Seconds left on the grant
+    remaining: u32,
+    /// Number of intervals of the port to skip before sending the next message
+    skip: u32,
+}
+
+impl Grant {
+    /// Whether a message is due to this slave, given that the port reached the
+    /// end of an interval of 2^`log_port_interval` seconds
+    fn due(&mut self, log_port_interval: i8) -> bool {
+        if self.skip > 0 {
+            self.skip -= 1;
+            return false;
+        }
+
+        let log_ratio =
+            (self.log_inter_message_period as i32 - log_port_interval as i32).clamp(0, 31);
+        self.skip = (1u32 << log_ratio) - 1;
+        true
+    }
+}
+
+/// The unicast negotiation state of a port, for both its slave and master side
+#[derive(Debug, Default)]
+pub(crate) struct UnicastNegotiation {
+    requests: ArrayVec<Request, { 3 * MAX_UNICAST_MASTERS }>,
+    grants: ArrayVec<Grant, MAX_UNICAST_GRANTS>,
+    seq_ids: SequenceIdGenerator,
+}
+
+impl UnicastNegotiation {
+    pub(crate) fn has_grant(&self, address: &PortAddress, message_type: MessageType) -> bool {
+        self.grants
+            .iter()
+            .any(|grant| &grant.address == address && grant.message_type == message_type)
+    }
+}
+
+impl DomainPort {
+    /// Whether Announce messages from `address` should be considered: ports
+    /// with a unicast master table only listen to the masters in it
+    pub(crate) fn is_unicast_master(&self, address: Option<&PortAddress>) -> bool {
+        let table = self.port_ds.unicast_master_table();
+        table.is_empty() || address.is_some_and(|address| table.contains(address))
+    }
+
+    /// Expire old grants and send the requests, renewals and cancellations the
+    /// port needs. Should be called once every second.
+    pub(crate) async fn handle_unicast_timeout(
+        &mut self,
+        network_port: &mut impl NetworkPort,
+        default_ds: &DefaultDS,
+    ) -> Result<()> {
+        let negotiation = &mut self.unicast_negotiation;
+
+        if !self.port_ds.unicast_negotiation()
+            || matches!(self.port_ds.port_state, PortState::Disabled)
+        {
+            negotiation.requests.clear();
+            negotiation.grants.clear();
+            return Ok(());
+        }
+
+        negotiation.grants.retain(|grant| {
+            grant.remaining = grant.remaining.saturating_sub(1);
+            if grant.remaining == 0 {
+                log::info!(
+                    "Unicast {:?} grant for {:?} expired",
+                    grant.message_type,
+                    grant.address
+                );
+            }
+            grant.remaining > 0
+        });
+
+        // Announce messages are needed from all masters to select the best one, the
+        // other messages only from the master we synchronize to
+        let parent = self.port_ds.port_state.remote_master();
+        let message_types = |master: Option<PortIdentity>| -> &[MessageType] {
+            match self.port_ds.delay_mechanism() {
+                _ if parent.is_none() || master != parent => &[MessageType::Announce],
+                DelayMechanism::E2E => &[
+                    MessageType::Announce,
+                    MessageType::Sync,
+                    MessageType::DelayResp,
+                ],
+                _ => &[MessageType::Announce, MessageType::Sync],
+            }
+        };
+        let master_table = self.port_ds.unicast_master_table();
+
+        // At most a request or cancellation for every request we keep or drop
+        let mut messages = ArrayVec::<_, { 6 * MAX_UNICAST_MASTERS }>::new();
+        negotiation.requests.retain(|request| {
+            let wanted = master_table.contains(&request.address)
+                && message_types(request.master).contains(&request.message_type);
+            if !wanted && request.granted > 0 {
+                let cancel = CancelUnicastTransmissionTlv {
+                    message_type: request.message_type,
+                };
+                messages.push((
+                    request.address.clone(),
+                    request.master,
+                    SignalingTlv::CancelUnicastTransmission(cancel),
+                ));
+            }
+            wanted
+        });
+
+        for address in master_table {
+            let master = negotiation
+                .requests
+                .iter()
+                .find(|request| &request.address == address)
+                .and_then(|request| request.master);
+
+            for &message_type in message_types(master) {
+                let exists = negotiation.requests.iter().any(|request| {
+                    &request.address == address && request.message_type == message_type
+                });
+                if !exists {
+                    negotiation.requests.push(Request {
+                        address: address.clone(),
+                        message_type,
+                        master,
+                        granted: 0,
+                        request_in: 0,
+                    });
+                }
+            }
+        }
+
+        for request in &mut negotiation.requests {
+            request.granted = request.granted.saturating_sub(1);
+            request.request_in = request.request_in.saturating_sub(1);
+
+            if request.request_in == 0 {
+                request.request_in = REQUEST_RETRY_INTERVAL;
+
+                let log_inter_message_period = match request.message_type {
+                    MessageType::Announce => self.port_ds.log_announce_interval(),
+                    MessageType::Sync => self.port_ds.log_sync_interval(),
+                    _ => self.port_ds.min_delay_req_interval(),
+                };
+                let request_tlv = RequestUnicastTransmissionTlv {
+                    message_type: request.message_type,
+                    log_inter_message_period,
+                    duration_field: self.port_ds.unicast_grant_duration(),
+                };
+                messages.push((
+                    request.address.clone(),
+                    request.master,
+                    SignalingTlv::RequestUnicastTransmission(request_tlv),
+                ));
+            }
+        }
+
+        for (address, master, tlv) in messages {
+            let target_port_identity = master.unwrap_or(PortIdentity {
+                clock_identity: ClockIdentity::ALL,
+                port_number: ALL_PORTS,
+            });
+            self.send_signaling(
+                network_port,
+                default_ds,
+                &address,
+                target_port_identity,
+                tlv,
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    pub(crate) async fn handle_signaling_message(
+        &mut self,
+        network_port: &mut impl NetworkPort,
+        message: SignalingMessage,
+        source_address: Option<PortAddress>,
+        default_ds: &DefaultDS,
+    ) -> Result<()> {
+        if !self.port_ds.unicast_negotiation() {
+            return Ok(());
+        }
+
+        let Some(address) = source_address else {
+            log::debug!("Ignoring signaling message without a source address");
+            return Ok(());
+        };
+
+        let target = message.target_port_identity;
+        if (target.clock_identity != default_ds.clock_identity
+            && target.clock_identity != ClockIdentity::ALL)
+            || (target.port_number != self.port_ds.port_identity.port_number
+                && target.port_number != ALL_PORTS)
+        {
+            return Ok(());
+        }
+
+        let source = message.header.source_port_identity();
+        for tlv in message.value {
+            let response = match tlv {
+                SignalingTlv::RequestUnicastTransmission(request) => {
+                    let grant = self.grant_request(&address, request, default_ds);
+                    Some(SignalingTlv::GrantUnicastTransmission(grant))
+                }
+                SignalingTlv::GrantUnicastTransmission(grant) => {
+                    self.handle_grant(&address, source, grant);
+                    None
+                }
+                SignalingTlv::CancelUnicastTransmission(cancel) => {
+                    self.handle_cancel(&address, cancel.message_type);
+                    Some(SignalingTlv::AcknowledgeCancelUnicastTransmission(cancel))
+                }
+                // Acknowledgements need no further action, and the other TLVs
+                // are not part of unicast negotiation
+                _ => None,
+            };
+
+            if let Some(tlv) = response {
+                self.send_signaling(network_port, default_ds, &address, source, tlv)
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn grant_request(
+        &mut self,
+        address: &PortAddress,
+        request: RequestUnicastTransmissionTlv,
+        default_ds: &DefaultDS,
+    ) -> GrantUnicastTransmissionTlv {
+        let mut response = GrantUnicastTransmissionTlv {
+            message_type: request.message_type,
+            log_inter_message_period: request.log_inter_message_period,
+            duration_field: 0,
+            renewal_invited: false,
+        };
+
+        // Delay responses answer the requests of the slave, so they have no rate of
+        // their own
+        let log_port_interval = match request.message_type {
+            MessageType::Announce => self.port_ds.log_announce_interval(),
+            MessageType::Sync => self.port_ds.log_sync_interval(),
+            MessageType::DelayResp if self.port_ds.delay_mechanism() == DelayMechanism::E2E => {
+                i8::MIN
+            }
+            _ => {
+                log::info!("Denying unsupported unicast {:?}", request.message_type);
+                return response;
+            }
+        };
+
+        if default_ds.slave_only || request.log_inter_message_period < log_port_interval {
+            log::info!(
+                "Denying unicast {:?} every 2^{}s to {:?}",
+                request.message_type,
+                request.log_inter_message_period,
+                address
+            );
+            return response;
+        }
+
+        let max_grants = self.port_ds.max_unicast_grants().min(MAX_UNICAST_GRANTS);
+        let grants = &mut self.unicast_negotiation.grants;
+        let full = grants.len() >= max_grants;
+        let duration = request
+            .duration_field
+            .clamp(*GRANT_DURATIONS.start(), *GRANT_DURATIONS.end());
+        match grants
+            .iter_mut()
+            .find(|grant| &grant.address == address && grant.message_type == request.message_type)
+        {
+            Some(grant) => {
+                grant.log_inter_message_period = request.log_inter_message_period;
+                grant.remaining = duration;
+            }
+            None if !full => {
+                log::info!(
+                    "Granting unicast {:?} to {:?}",
+                    request.message_type,
+                    address
+                );
+                grants.push(Grant {
+                    address: address.clone(),
+                    message_type: request.message_type,
+                    log_inter_message_period: request.log_inter_message_period,
+                    remaining: duration,
+                    skip: 0,
+                });
+            }
+            None => {
+                log::warn!(
+                    "Denying unicast {:?} to {:?}, too many grants",
+                    request.message_type,
+                    address
+                );
+                return response;
+            }
+        }
+
+        response.duration_field = duration;
+        response.renewal_invited = true;
+        response
+    }
+
+    fn handle_grant(
+        &mut self,
+        address: &PortAddress,
+        master: PortIdentity,
+        grant: GrantUnicastTransmissionTlv,
+    ) {
+        for request in &mut self.unicast_negotiation.requests {
+            if &request.address != address {
+                continue;
+            }
+
+            request.master = Some(master);
+
+            if request.message_type != grant.message_type {
+                continue;
+            }
+
+            if grant.duration_field == 0 {
+                log::warn!(
+                    "Unicast {:?} request denied by {:?}",
+                    grant.message_type,
+                    address
+                );
+                request.granted = 0;
+                request.request_in = REQUEST_RETRY_INTERVAL;
+            } else {
+                request.granted = grant.duration_field;
+                // Renew halfway through the grant, or ask again once it ran out when the
+                // master doesn't want renewals
+                request.request_in = match grant.renewal_invited {
+                    true => (grant.duration_field / 2).max(1),
+                    false => grant.duration_field,
+                };
+            }
+        }
+    }
+
+    fn handle_cancel(&mut self, address: &PortAddress, message_type: MessageType) {
+        log::info!("Unicast {:?} cancelled by {:?}", message_type, address);
+
+        // Either a master cancelled our grant...
+        for request in &mut self.unicast_negotiation.requests {
+            if &request.address == address && request.message_type == message_type {
+                request.granted = 0;
+                request.request_in = REQUEST_RETRY_INTERVAL;
+            }
+        }
+
+        // ...or a slave cancelled the grant we gave it
+        self.unicast_negotiation
+            .grants
+            .retain(|grant| !(&grant.address == address && grant.message_type == message_type));
+    }
+
+    async fn send_signaling(
+        &mut self,
+        network_port: &mut impl NetworkPort,
+        default_ds: &DefaultDS,
+        address: &PortAddress,
+        target_port_identity: PortIdentity,
+        tlv: SignalingTlv,
+    ) -> Result<()> {
+        let message = MessageBuilder::new()
+            .sdo_id(default_ds.sdo_id)
+            .domain_number(default_ds.domain_number)
+            .unicast_flag(true)
+            .sequence_id(self.unicast_negotiation.seq_ids.generate())
+            .source_port_identity(self.port_ds.port_identity)
+            .log_message_interval(0x7f)
+            .signaling_message(target_port_identity, tlv)
+            .serialize_vec()?;
+
+        if let Err(error) = network_port.send_to(&message, address).await {
+            log::error!("failed to send signaling message: {:?}", error);
+            return Err(PortError::Network);
+        }
+
+        Ok(())
+    }
+
+    /// The addresses due a message of the given type at this interval of the
+    /// port: the slaves with a grant when using unicast negotiation, all
+    /// addresses in the unicast master table otherwise
+    fn unicast_destinations(
+        &mut self,
+        message_type: MessageType,
+        log_port_interval: i8,
+    ) -> ArrayVec<PortAddress, MAX_UNICAST_GRANTS> {
+        if !self.port_ds.unicast_negotiation() {
+            return self
+                .port_ds
+                .unicast_master_table()
+                .iter()
+                .cloned()
+                .collect();
+        }
+
+        self.unicast_negotiation
+            .grants
+            .iter_mut()
+            .filter(|grant| grant.message_type == message_type)
+            .filter_map(|grant| grant.due(log_port_interval).then(|| grant.address.clone()))
+            .collect()
+    }
+
+    /// Send a Sync message to every unicast destination that is due one
+    pub(crate) async fn send_unicast_sync(
+        &mut self,
+        network_port: &mut impl NetworkPort,
+        local_clock: &RefCell<impl Clock>,
+        default_ds: &DefaultDS,
+    ) -> Result<()> {
+        let one_step = self.port_ds.one_step_sync();
+        let log_port_interval = self.port_ds.log_sync_interval();
+
+        for address in self.unicast_destinations(MessageType::Sync, log_port_interval) {
+            // Failing to reach one slave shouldn't stop the others from getting their
+            // messages
+            if let Err(error) = self
+                .port_ds
+                .port_state
+                .send_sync(
+                    local_clock,
+                    &mut UnicastPort::new(network_port, &address),
+                    self.port_ds.port_identity,
+                    default_ds,
+                    one_step,
+                )
+                .await
+            {
+                log::error!("{:?}", error);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Send an Announce message to every unicast destination that is due one
+    pub(crate) async fn send_unicast_announce(
+        &mut self,
+        network_port: &mut impl NetworkPort,
+        local_clock: &RefCell<impl Clock>,
+        default_ds: &DefaultDS,
+        time_properties: &TimePropertiesDS,
+        parent_ds: &ParentDS,
+        current_ds: &CurrentDS,
+    ) -> Result<()> {
+        let log_port_interval = self.port_ds.log_announce_interval();
+
+        for address in self.unicast_destinations(MessageType::Announce, log_port_interval) {
+            self.port_ds
+                .port_state
+                .send_announce(
+                    local_clock,
+                    default_ds,
+                    time_properties,
+                    parent_ds,
+                    current_ds,
+                    &mut UnicastPort::new(network_port, &address),
+                    self.port_ds.port_identity,
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A network port that sends all messages to a single address, with the
+/// unicast flag set
+pub(crate) struct UnicastPort<'a, P> {
+    network_port: &'a mut P,
+    address: &'a PortAddress,
+}
+
+impl<'a, P> UnicastPort<'a, P> {
+    pub(crate) fn new(network_port: &'a mut P, address: &'a PortAddress) -> Self {
+        Self {
+            network_port,
+            address,
+        }
+    }
+}
+
+/// A copy of the message with the unicast flag set in its header
+fn with_unicast_flag(data: &[u8]) -> ArrayVec<u8, MAX_DATA_LEN> {
+    // All messages are serialized into buffers of MAX_DATA_LEN bytes
+    let mut data: ArrayVec<u8, MAX_DATA_LEN> = data.iter().copied().collect();
+    if let Some(flags) = data.get_mut(6) {
+        *flags |= 1 << 2;
+    }
+    data
+}
+
+impl<'a, P: NetworkPort> NetworkPort for UnicastPort<'a, P> {
+    type Error = P::Error;
+
+    async fn send(&mut self, data: &[u8]) -> core::result::Result<(), Self::Error> {
+        self.network_port
+            .send_to(&with_unicast_flag(data), self.address)
+            .await
+    }
+
+    async fn send_time_critical(
+        &mut self,
+        data: &[u8],
+    ) -> core::result::Result<Option<Instant>, Self::Error> {
+        self.network_port
+            .send_time_critical_to(&with_unicast_flag(data), self.address)
+            .await
+    }
+
+    async fn send_to(
+        &mut self,
+        data: &[u8],
+        address: &PortAddress,
+    ) -> core::result::Result<(), Self::Error> {
+        self.network_port
+            .send_to(&with_unicast_flag(data), address)
+            .await
+    }
+
+    async fn send_time_critical_to(
+        &mut self,
+        data: &[u8],
+        address: &PortAddress,
+    ) -> core::result::Result<Option<Instant>, Self::Error> {
+        self.network_port
+            .send_time_critical_to(&with_unicast_flag(data), address)
+            .await
+    }
+
+    async fn recv(&mut self) -> core::result::Result<NetworkPacket, Self::Error> {
+        self.network_port.recv().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use super::*;
+    use crate::{
+        datastructures::{
+            common::NetworkProtocol,
+            datasets::PortDS,
+            messages::{Message, SdoId},
+        },
+        port::{
+            state::{MasterState, SlaveState},
+            tests::{RecordingNetworkPort, RecordingRuntime, TestClock},
+            Port,
+        },
+    };
+
+    fn identity(id: u8) -> PortIdentity {
+        PortIdentity {
+            clock_identity: ClockIdentity([id; 8]),
+            port_number: 1,
+        }
+    }
+
+    fn address(id: u8) -> PortAddress {
+        PortAddress {
+            network_protocol: NetworkProtocol::UdpIPv4,
+            address: [10, 0, 0, id].into_iter().collect(),
+        }
+    }
+
+    fn setup(port_ds: PortDS) -> (Port<RecordingNetworkPort>, DefaultDS) {
+        let default_ds = DefaultDS::new_ordinary_clock(
+            identity(1).clock_identity,
+            128,
+            128,
+            0,
+            false,
+            SdoId::default(),
+        );
+        let port = embassy_futures::block_on(Port::new(
+            port_ds,
+            &mut RecordingRuntime,
+            Instant::default(),
+        ));
+        (port, default_ds)
+    }
+
+    fn port_ds() -> PortDS {
+        PortDS::new(identity(1), 0, 1, 3, 0, DelayMechanism::E2E, 0)
+    }
+
+    fn signaling(source: u8, tlv: SignalingTlv) -> SignalingMessage {
+        let message = MessageBuilder::new()
+            .source_port_identity(identity(source))
+            .signaling_message(identity(1), tlv);
+        match message {
+            Message::Signaling(signaling) => signaling,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Deliver a signaling message from `source`, and return the responses
+    fn handle(
+        port: &mut Port<RecordingNetworkPort>,
+        default_ds: &DefaultDS,
+        source: u8,
+        tlv: SignalingTlv,
+    ) -> Vec<(PortAddress, SignalingTlv)> {
+        embassy_futures::block_on(port.domains[0].handle_signaling_message(
+            &mut port.network_port,
+            signaling(source, tlv),
+            Some(address(source)),
+            default_ds,
+        ))
+        .unwrap();
+        take_signaling(port)
+    }
+
+    fn take_signaling(port: &mut Port<RecordingNetworkPort>) -> Vec<(PortAddress, SignalingTlv)> {
+        port.network_port
+            .sent_to
+            .drain(..)
+            .map(
+                |(data, address)| match Message::deserialize(&data).unwrap() {
+                    Message::Signaling(mut signaling) => {
+                        assert!(signaling.header.unicast_flag);
+                        (address, signaling.value.remove(0))
+                    }
+                    _ => panic!("Unexpected message type"),
+                },
+            )
+            .collect()
+    }
+
+    fn request(message_type: MessageType, log_inter_message_period: i8) -> SignalingTlv {
+        SignalingTlv::RequestUnicastTransmission(RequestUnicastTransmissionTlv {
+            message_type,
+            log_inter_message_period,
+            duration_field: 5,
+        })
+    }
+
+    fn grant(message_type: MessageType, duration_field: u32) -> SignalingTlv {
+        SignalingTlv::GrantUnicastTransmission(GrantUnicastTransmissionTlv {
+            message_type,
+            log_inter_message_period: 0,
+            duration_field,
+            renewal_invited: true,
+        })
+    }
+
+    #[test]
+    fn test_grants() {
+        let (mut port, default_ds) = setup(port_ds().with_unicast_negotiation(60, 1));
+
+        // Durations are kept within the supported range
+        let responses = handle(&mut port, &default_ds, 2, request(MessageType::Sync, 1));
+        assert_eq!(
+            responses,
+            [(
+                address(2),
+                SignalingTlv::GrantUnicastTransmission(GrantUnicastTransmissionTlv {
+                    message_type: MessageType::Sync,
+                    log_inter_message_period: 1,
+                    duration_field: 10,
+                    renewal_invited: true,
+                })
+            )]
+        );
+
+        // The grant table is full
+        let responses = handle(&mut port, &default_ds, 3, request(MessageType::Sync, 1));
+        let SignalingTlv::GrantUnicastTransmission(denial) = responses[0].1 else {
+            panic!("Expected a grant");
+        };
+        assert_eq!(denial.duration_field, 0);
+
+        // Renewals don't need an extra entry
+        let responses = handle(&mut port, &default_ds, 2, request(MessageType::Sync, 1));
+        let SignalingTlv::GrantUnicastTransmission(renewal) = responses[0].1 else {
+            panic!("Expected a grant");
+        };
+        assert_eq!(renewal.duration_field, 10);
+
+        // Faster than the port sends
+        let (mut port, default_ds) = setup(port_ds().with_unicast_negotiation(60, 8));
+        let responses = handle(&mut port, &default_ds, 2, request(MessageType::Sync, -1));
+        let SignalingTlv::GrantUnicastTransmission(denial) = responses[0].1 else {
+            panic!("Expected a grant");
+        };
+        assert_eq!(denial.duration_field, 0);
+    }
+
+    #[test]
+    fn test_grants_expire_and_cancel() {
+        let (mut port, default_ds) = setup(port_ds().with_unicast_negotiation(60, 8));
+
+        handle(&mut port, &default_ds, 2, request(MessageType::Sync, 0));
+        handle(
+            &mut port,
+            &default_ds,
+            3,
+            request(MessageType::DelayResp, 0),
+        );
+        assert!(port.domains[0]
+            .unicast_negotiation
+            .has_grant(&address(2), MessageType::Sync));
+
+        let cancel = CancelUnicastTransmissionTlv {
+            message_type: MessageType::DelayResp,
+        };
+        let responses = handle(
+            &mut port,
+            &default_ds,
+            3,
+            SignalingTlv::CancelUnicastTransmission(cancel),
+        );
+        assert_eq!(
+            responses,
+            [(
+                address(3),
+                SignalingTlv::AcknowledgeCancelUnicastTransmission(cancel)
+            )]
+        );
+        assert!(!port.domains[0]
+            .unicast_negotiation
+            .has_grant(&address(3), MessageType::DelayResp));
+
+        for _ in 0..10 {
+            embassy_futures::block_on(
+                port.domains[0].handle_unicast_timeout(&mut port.network_port, &default_ds),
+            )
+            .unwrap();
+        }
+        assert!(!port.domains[0]
+            .unicast_negotiation
+            .has_grant(&address(2), MessageType::Sync));
+    }
+
+    #[test]
+    fn test_master_sends_to_grantees() {
+        let (mut port, default_ds) = setup(port_ds().with_unicast_negotiation(60, 8));
+        port.domains[0].port_ds.port_state = PortState::Master(MasterState::new());
+        let local_clock = RefCell::new(TestClock {
+            current_time: Instant::from_secs(1000),
+        });
+
+        // Every two sync intervals of the port
+        handle(&mut port, &default_ds, 2, request(MessageType::Sync, 1));
+
+        for _ in 0..4 {
+            embassy_futures::block_on(port.domains[0].send_sync(
+                &mut port.network_port,
+                &local_clock,
+                &default_ds,
+            ))
+            .unwrap();
+        }
+
+        // Each sync comes with a follow up
+        let sent = core::mem::take(&mut port.network_port.sent_to);
+        assert_eq!(sent.len(), 4);
+        for (data, destination) in sent {
+            assert_eq!(destination, address(2));
+            assert!(Message::deserialize(&data).unwrap().header().unicast_flag);
+        }
+        assert!(port.network_port.normal.is_empty());
+        assert!(port.network_port.time.is_empty());
+    }
+
+    #[test]
+    fn test_slave_requests() {
+        let (mut port, default_ds) = setup(
+            port_ds()
+                .with_unicast_master_table(&[address(2)])
+                .with_unicast_negotiation(60, 0),
+        );
+        let tick = |port: &mut Port<RecordingNetworkPort>| {
+            embassy_futures::block_on(
+                port.domains[0].handle_unicast_timeout(&mut port.network_port, &default_ds),
+            )
+            .unwrap();
+            take_signaling(port)
+        };
+
+        // Without knowing the master, only announces are requested
+        let requests = tick(&mut port);
+        assert_eq!(
+            requests,
+            [(
+                address(2),
+                SignalingTlv::RequestUnicastTransmission(RequestUnicastTransmissionTlv {
+                    message_type: MessageType::Announce,
+                    log_inter_message_period: 1,
+                    duration_field: 60,
+                })
+            )]
+        );
+
+        // Renewed halfway through the grant
+        handle(&mut port, &default_ds, 2, grant(MessageType::Announce, 60));
+        for _ in 0..29 {
+            assert!(tick(&mut port).is_empty());
+        }
+        assert_eq!(tick(&mut port).len(), 1);
+        handle(&mut port, &default_ds, 2, grant(MessageType::Announce, 60));
+
+        // Once it is our parent, it also has to send syncs and delay responses
+        port.domains[0].port_ds.port_state =
+            PortState::Slave(SlaveState::new(identity(2), DelayMechanism::E2E));
+        let requests = tick(&mut port);
+        let message_types: Vec<_> = requests
+            .iter()
+            .map(|(_, tlv)| match tlv {
+                SignalingTlv::RequestUnicastTransmission(request) => request.message_type,
+                _ => panic!("Expected a request"),
+            })
+            .collect();
+        assert_eq!(message_types, [MessageType::Sync, MessageType::DelayResp]);
+
+        // A denied request is repeated later
+        handle(&mut port, &default_ds, 2, grant(MessageType::Sync, 0));
+        handle(&mut port, &default_ds, 2, grant(MessageType::DelayResp, 60));
+        for _ in 0..REQUEST_RETRY_INTERVAL - 1 {
+            assert!(tick(&mut port).is_empty());
+        }
+        assert_eq!(tick(&mut port).len(), 1);
+
+        // Grants are cancelled when the master is no longer our parent
+        port.domains[0].port_ds.port_state = PortState::Listening;
+        let cancels = tick(&mut port);
+        assert_eq!(
+            cancels,
+            [(
+                address(2),
+                SignalingTlv::CancelUnicastTransmission(CancelUnicastTransmissionTlv {
+                    message_type: MessageType::DelayResp,
+                })
+            )]
+        );
+    }
+
+    #[test]
+    fn test_static_unicast() {
+        let (mut port, default_ds) =
+            setup(port_ds().with_unicast_master_table(&[address(2), address(3)]));
+        port.domains[0].port_ds.port_state = PortState::Master(MasterState::new());
+        let local_clock = RefCell::new(TestClock {
+            current_time: Instant::from_secs(1000),
+        });
+
+        // Nothing is negotiated
+        embassy_futures::block_on(
+            port.domains[0].handle_unicast_timeout(&mut port.network_port, &default_ds),
+        )
+        .unwrap();
+        assert!(port.network_port.sent_to.is_empty());
+
+        // Only the masters in the table are listened to
+        assert!(port.domains[0].is_unicast_master(Some(&address(2))));
+        assert!(!port.domains[0].is_unicast_master(Some(&address(4))));
+        assert!(!port.domains[0].is_unicast_master(None));
+
+        // Every address in the table gets a sync and a follow up
+        embassy_futures::block_on(port.domains[0].send_sync(
+            &mut port.network_port,
+            &local_clock,
+            &default_ds,
+        ))
+        .unwrap();
+        let destinations: Vec<_> = port
+            .network_port
+            .sent_to
+            .drain(..)
+            .map(|(data, destination)| {
+                assert!(Message::deserialize(&data).unwrap().header().unicast_flag);
+                destination
+            })
+            .collect();
+        assert_eq!(
+            destinations,
+            [address(2), address(2), address(3), address(3)]
+        );
+    }
+}
@@ -0,0 +1,355 @@
+//! Implementation of the [Profile] type
+
+use core::ops::RangeInclusive;
+
+use crate::datastructures::{
+    datasets::{DefaultDS, DelayMechanism, PortDS},
+    messages::SdoId,
+};
+
+/// The PTP profile a PTP instance operates under
+///
+/// A profile restricts and extends the behaviour of the PTP default profile of
+/// IEEE1588-2019 for a specific application. Set it on the default dataset
+/// with [`DefaultDS::with_profile`](crate::DefaultDS::with_profile).
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum Profile {
+    /// The default delay request-response and peer-to-peer profiles of
+    /// IEEE1588-2019 (annex I)
+    #[default]
+    Default,
+    /// The generalized PTP (gPTP) profile of IEEE 802.1AS-2020
+    ///
+    /// gPTP runs over Layer-2 (ethernet) only, so the network runtime needs to
+    /// send and receive raw ethernet frames. All ports must use the
+    /// [P2P](crate::DelayMechanism::P2P) delay mechanism, and only take part
+    /// in synchronization while they are capable of doing so (asCapable, IEEE
+    /// 802.1AS-2020 section 11.2.2): their peer delay measurements succeed and
+    /// the link delay is below the [neighbor propagation delay
+    /// threshold](crate::PortDS::with_neighbor_prop_delay_thresh).
+    ///
+    /// Masters add the follow up information TLV to their Follow_Up messages,
+    /// and announce messages qualify without waiting for multiple of them to
+    /// arrive. Ports should have a [sync receipt
+    /// timeout](crate::PortDS::with_sync_receipt_timeout), see
+    /// [`PortDS::new_gptp`](crate::PortDS::new_gptp) for the default port
+    /// configuration.
+    Gptp,
+    /// The telecom profile for phase/time synchronization with full timing
+    /// support from the network of ITU-T G.8275.1
+    ///
+    /// Every node in the network is a PTP node, reached over Layer-2
+    /// multicast, either to the forwardable address 01-1B-19-00-00-00 or to
+    /// the non-forwardable address 01-80-C2-00-00-0E. Domain numbers are in
+    /// the range 24 to 43, 24 being the default. See
+    /// [`PortDS::new_g8275_1`](crate::PortDS::new_g8275_1) for the default
+    /// port configuration.
+    ///
+    /// The profile uses an alternate BMCA, which ignores priority 1 and
+    /// prefers masters with a better local priority (see
+    /// [`PortDS::with_local_priority`](crate::PortDS::with_local_priority))
+    /// among masters of equal quality. Ports that should never synchronize to
+    /// a master are configured with
+    /// [`PortDS::with_master_only`](crate::PortDS::with_master_only)
+    /// (notSlave). The clock class changes of a clock that loses its
+    /// reference are configured with
+    /// [`DefaultDS::with_holdover`](crate::DefaultDS::with_holdover).
+    G8275_1,
+    /// The telecom profile for phase/time synchronization with partial timing
+    /// support from the network of ITU-T G.8275.2
+    ///
+    /// PTP nodes talk to each other over unicast UDP (IPv4 or IPv6), and
+    /// negotiate the message rates they need (see
+    /// [`PortDS::with_unicast_negotiation`](crate::PortDS::with_unicast_negotiation)).
+    /// The network between them may contain routers and switches that are not
+    /// PTP aware, so only the [E2E](crate::DelayMechanism::E2E) delay
+    /// mechanism can be used. Domain numbers are in the range 44 to 63, 44
+    /// being the default. See
+    /// [`PortDS::new_g8275_2`](crate::PortDS::new_g8275_2) for the default
+    /// port configuration.
+    ///
+    /// Like [G.8275.1](Profile::G8275_1), the profile uses the alternate
+    /// BMCA with a local priority per port.
+    G8275_2,
+    /// The broadcast profile of SMPTE ST 2059-2
+    ///
+    /// Domain numbers are in the range 0 to 127, 127 being the default. See
+    /// [`PortDS::new_st2059_2`](crate::PortDS::new_st2059_2) for the default
+    /// port configuration. The grandmaster tells its slaves how to derive
+    /// time codes from the PTP time with the synchronization metadata TLV,
+    /// configured with
+    /// [`TimePropertiesDS::with_smpte_sync_metadata`](crate::TimePropertiesDS::with_smpte_sync_metadata).
+    St2059_2,
+    /// The power profile of IEEE C37.238-2017, for substations
+    ///
+    /// Like gPTP, the power profile runs over Layer-2 (ethernet) only, and all
+    /// ports use the [P2P](crate::DelayMechanism::P2P) delay mechanism. See
+    /// [`PortDS::new_c37_238`](crate::PortDS::new_c37_238) for the default
+    /// port configuration. The default domain number is 254.
+    ///
+    /// Announce messages carry the IEEE_C37_238 TLV, with the [grandmaster
+    /// id](crate::DefaultDS::with_grandmaster_id) of the grandmaster, and the
+    /// total [time inaccuracy](crate::DefaultDS::with_time_inaccuracy) of the
+    /// clocks between the grandmaster and the slave. Slaves find it in the
+    /// [time properties](crate::TimePropertiesDS::power_profile). Grandmasters
+    /// should advertise the local time with an [alternate
+    /// timescale](crate::TimePropertiesDS::with_alternate_time_offset).
+    C37_238,
+}
+
+/// The port configuration a profile allows
+struct PortRanges {
+    log_announce_interval: RangeInclusive<i8>,
+    announce_receipt_timeout: RangeInclusive<u8>,
+    log_sync_interval: RangeInclusive<i8>,
+    log_min_delay_req_interval: RangeInclusive<i8>,
+    log_min_p_delay_req_interval: RangeInclusive<i8>,
+    delay_mechanisms: &'static [DelayMechanism],
+    unicast_negotiation: bool,
+    unicast_grant_duration: RangeInclusive<u32>,
+}
+
+impl Profile {
+    /// The sdo id messages are sent with, if the profile prescribes one
+    pub(crate) fn sdo_id(&self) -> Option<SdoId> {
+        match self {
+            Profile::Default | Profile::St2059_2 | Profile::C37_238 => None,
+            Profile::Gptp => SdoId::new(0x100),
+            Profile::G8275_1 | Profile::G8275_2 => SdoId::new(0),
+        }
+    }
+
+    /// The priority 1 the instance advertises, if the profile prescribes one
+    pub(crate) fn priority_1(&self) -> Option<u8> {
+        match self {
+            Profile::Default | Profile::Gptp | Profile::St2059_2 | Profile::C37_238 => None,
+            Profile::G8275_1 | Profile::G8275_2 => Some(128),
+        }
+    }
+
+    /// The domain numbers the profile allows
+    pub(crate) fn domain_numbers(&self) -> RangeInclusive<u8> {
+        match self {
+            Profile::Default | Profile::Gptp | Profile::C37_238 => 0..=255,
+            Profile::G8275_1 => 24..=43,
+            Profile::G8275_2 => 44..=63,
+            Profile::St2059_2 => 0..=127,
+        }
+    }
+
+    /// Whether the alternate BMCA of ITU-T G.8275.1 (section 6.3) replaces the
+    /// dataset comparison of IEEE1588-2019
+    pub(crate) fn alternate_bmca(&self) -> bool {
+        match self {
+            Profile::Default | Profile::Gptp | Profile::St2059_2 | Profile::C37_238 => false,
+            Profile::G8275_1 | Profile::G8275_2 => true,
+        }
+    }
+
+    /// Whether foreign masters need to send multiple announce messages before
+    /// they qualify (IEEE1588-2019 section 9.3.2.5)
+    pub(crate) fn foreign_master_qualification(&self) -> bool {
+        match self {
+            Profile::Default
+            | Profile::G8275_1
+            | Profile::G8275_2
+            | Profile::St2059_2
+            | Profile::C37_238 => true,
+            Profile::Gptp => false,
+        }
+    }
+
+    /// The port configuration the profile allows
+    fn port_ranges(&self) -> PortRanges {
+        match self {
+            Profile::Default => PortRanges {
+                log_announce_interval: i8::MIN..=i8::MAX,
+                announce_receipt_timeout: 0..=u8::MAX,
+                log_sync_interval: i8::MIN..=i8::MAX,
+                log_min_delay_req_interval: i8::MIN..=i8::MAX,
+                log_min_p_delay_req_interval: i8::MIN..=i8::MAX,
+                delay_mechanisms: &[DelayMechanism::E2E, DelayMechanism::P2P],
+                unicast_negotiation: false,
+                unicast_grant_duration: 0..=u32::MAX,
+            },
+            Profile::Gptp => PortRanges {
+                delay_mechanisms: &[DelayMechanism::P2P],
+                ..Profile::Default.port_ranges()
+            },
+            // ITU-T G.8275.1 table A.8
+            Profile::G8275_1 => PortRanges {
+                log_announce_interval: -3..=-3,
+                announce_receipt_timeout: 3..=4,
+                log_sync_interval: -4..=-4,
+                log_min_delay_req_interval: -4..=-4,
+                delay_mechanisms: &[DelayMechanism::E2E],
+                ..Profile::Default.port_ranges()
+            },
+            // ITU-T G.8275.2 table A.7
+            Profile::G8275_2 => PortRanges {
+                log_announce_interval: -3..=0,
+                announce_receipt_timeout: 2..=10,
+                log_sync_interval: -7..=0,
+                log_min_delay_req_interval: -7..=0,
+                log_min_p_delay_req_interval: i8::MIN..=i8::MAX,
+                delay_mechanisms: &[DelayMechanism::E2E],
+                unicast_negotiation: true,
+                unicast_grant_duration: 60..=1000,
+            },
+            // SMPTE ST 2059-2 section 6.5
+            Profile::St2059_2 => PortRanges {
+                log_announce_interval: -3..=1,
+                announce_receipt_timeout: 2..=10,
+                log_sync_interval: -7..=-1,
+                log_min_delay_req_interval: -7..=4,
+                ..Profile::Default.port_ranges()
+            },
+            // IEEE C37.238-2017 section 6.4
+            Profile::C37_238 => PortRanges {
+                log_announce_interval: 0..=0,
+                announce_receipt_timeout: 3..=3,
+                log_sync_interval: 0..=0,
+                log_min_p_delay_req_interval: 0..=0,
+                delay_mechanisms: &[DelayMechanism::P2P],
+                ..Profile::Default.port_ranges()
+            },
+        }
+    }
+
+    /// Check that the instance configuration is allowed by the profile
+    ///
+    /// # Panics
+    ///
+    /// Panics when it is not, naming the offending attribute.
+    pub(crate) fn check_default_ds(&self, default_ds: &DefaultDS) {
+        if *self == Profile::C37_238 && !default_ds.slave_only {
+            assert!(
+                (3..=254).contains(&default_ds.grandmaster_id),
+                "grandmaster id {} is not allowed by profile {self:?}",
+                default_ds.grandmaster_id
+            );
+        }
+    }
+
+    /// Check that the configuration of a port is allowed by the profile
+    ///
+    /// # Panics
+    ///
+    /// Panics when it is not, naming the offending attribute.
+    pub(crate) fn check_port_ds(&self, port_ds: &PortDS) {
+        let ranges = self.port_ranges();
+        let number = port_ds.port_identity.port_number;
+
+        assert!(
+            ranges
+                .log_announce_interval
+                .contains(&port_ds.log_announce_interval()),
+            "log announce interval of port {number} is not allowed by profile {self:?}"
+        );
+        assert!(
+            ranges
+                .announce_receipt_timeout
+                .contains(&port_ds.announce_receipt_timeout()),
+            "announce receipt timeout of port {number} is not allowed by profile {self:?}"
+        );
+        assert!(
+            ranges
+                .log_sync_interval
+                .contains(&port_ds.log_sync_interval()),
+            "log sync interval of port {number} is not allowed by profile {self:?}"
+        );
+        if port_ds.delay_mechanism() == DelayMechanism::E2E {
+            assert!(
+                ranges
+                    .log_min_delay_req_interval
+                    .contains(&port_ds.min_delay_req_interval()),
+                "log min delay request interval of port {number} is not allowed by profile \
+                 {self:?}"
+            );
+        }
+        if port_ds.delay_mechanism() == DelayMechanism::P2P {
+            assert!(
+                ranges
+                    .log_min_p_delay_req_interval
+                    .contains(&port_ds.log_min_p_delay_req_interval()),
+                "log min peer delay request interval of port {number} is not allowed by profile \
+                 {self:?}"
+            );
+        }
+        assert!(
+            ranges.delay_mechanisms.contains(&port_ds.delay_mechanism()),
+            "delay mechanism of port {number} is not allowed by profile {self:?}"
+        );
+        if ranges.unicast_negotiation {
+            assert!(
+                port_ds.unicast_negotiation(),
+                "port {number} needs unicast negotiation for profile {self:?}"
+            );
+            assert!(
+                ranges
+                    .unicast_grant_duration
+                    .contains(&port_ds.unicast_grant_duration()),
+                "unicast grant duration of port {number} is not allowed by profile {self:?}"
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrayvec::ArrayVec;
+
+    use super::*;
+    use crate::datastructures::common::{
+        ClockIdentity, NetworkProtocol, PortAddress, PortIdentity,
+    };
+
+    const IDENTITY: PortIdentity = PortIdentity {
+        clock_identity: ClockIdentity([1; 8]),
+        port_number: 1,
+    };
+
+    #[test]
+    fn default_port_ds() {
+        Profile::Gptp.check_port_ds(&PortDS::new_gptp(IDENTITY));
+        Profile::G8275_1.check_port_ds(&PortDS::new_g8275_1(IDENTITY));
+        Profile::St2059_2.check_port_ds(&PortDS::new_st2059_2(IDENTITY));
+        Profile::C37_238.check_port_ds(&PortDS::new_c37_238(IDENTITY));
+
+        let master = PortAddress {
+            network_protocol: NetworkProtocol::UdpIPv4,
+            address: ArrayVec::from_iter([192, 168, 0, 25]),
+        };
+        Profile::G8275_2.check_port_ds(&PortDS::new_g8275_2(IDENTITY, &[master]));
+    }
+
+    #[test]
+    #[should_panic]
+    fn g8275_2_without_unicast_negotiation() {
+        let port_ds = PortDS::new(IDENTITY, -4, 0, 3, -4, DelayMechanism::E2E, 0);
+        Profile::G8275_2.check_port_ds(&port_ds);
+    }
+
+    #[test]
+    #[should_panic]
+    fn g8275_2_peer_delay() {
+        let port_ds = PortDS::new(IDENTITY, -4, 0, 3, -4, DelayMechanism::P2P, 0)
+            .with_unicast_negotiation(300, 0);
+        Profile::G8275_2.check_port_ds(&port_ds);
+    }
+
+    #[test]
+    #[should_panic]
+    fn c37_238_without_grandmaster_id() {
+        let default_ds = DefaultDS::new_ordinary_clock(
+            IDENTITY.clock_identity,
+            128,
+            128,
+            254,
+            false,
+            SdoId::default(),
+        )
+        .with_profile(Profile::C37_238);
+        Profile::C37_238.check_default_ds(&default_ds);
+    }
+}
@@ -1,19 +1,24 @@
 use core::{
-    cell::RefCell,
+    cell::{Cell, RefCell},
     future::Future,
     pin::{pin, Pin},
 };
 
+use arrayvec::ArrayVec;
 use futures::StreamExt;
 
 use crate::{
-    bmc::bmca::Bmca,
+    bmc::bmca::{BestAnnounceMessage, Bmca},
     clock::{Clock, Timer},
-    datastructures::datasets::{CurrentDS, DefaultDS, ParentDS, TimePropertiesDS},
+    datastructures::{
+        common::ClockQuality,
+        datasets::{CurrentDS, DefaultDS, DesiredState, ParentDS, PortDS, TimePropertiesDS},
+        messages::Message,
+    },
     filters::Filter,
     network::NetworkPort,
-    port::{Port, PortError, Ticker},
-    time::Duration,
+    port::{state::PortState, InstanceChange, Port, PortError, Ticker},
+    time::{Duration, Instant},
     utils::SignalContext,
 };
 
@@ -61,17 +66,54 @@ use crate::{
 ///
 /// instance.run(&TimerImpl).await;
 /// ```
-pub struct PtpInstance<P, C, F, const N: usize> {
-    default_ds: DefaultDS,
-    current_ds: CurrentDS,
-    parent_ds: ParentDS,
-    time_properties_ds: TimePropertiesDS,
-    ports: [Port<P>; N],
+///
+/// # Domains
+/// An instance can take part in several domains at once, each with its own
+/// datasets and BMCA, added with [`with_domain`](Self::with_domain). The
+/// domains share the network ports of the instance, and only one of them
+/// steers the local clock. The others are measured through their own filter,
+/// for example to compare redundant grandmasters, and their [current
+/// dataset](PtpInstance::current_ds) tells how far off they are. The instance takes part in
+/// at most as many domains as its [ports](Port::with_max_domains) have room
+/// for:
+///
+/// ```ignore
+/// let port = Port::new(port_ds, &mut network_runtime, interface)
+///     .await
+///     .with_max_domains::<2>();
+/// let mut instance = PtpInstance::new_ordinary_clock(
+///     default_ds,
+///     time_properties_ds,
+///     port,
+///     local_clock,
+///     BasicFilter::new(0.25),
+/// )
+/// .with_domain(
+///     backup_default_ds,
+///     backup_time_properties_ds,
+///     [backup_port_ds],
+///     BasicFilter::new(0.25),
+/// );
+/// ```
+pub struct PtpInstance<P, C, F, const N: usize, const D: usize = 1> {
+    domains: ArrayVec<Domain<F>, D>,
+    ports: [Port<P, D>; N],
     local_clock: RefCell<C>,
-    filter: RefCell<F>,
+    clock_domain: usize,
 }
 
-impl<P, C, F> PtpInstance<P, C, F, 1> {
+/// The datasets of a single domain of a [`PtpInstance`]
+pub(crate) struct Domain<F> {
+    pub(crate) default_ds: DefaultDS,
+    pub(crate) current_ds: Cell<CurrentDS>,
+    pub(crate) parent_ds: ParentDS,
+    pub(crate) time_properties_ds: TimePropertiesDS,
+    pub(crate) filter: RefCell<F>,
+    has_reference: bool,
+    holdover_start: Option<Instant>,
+}
+
+impl<P, C, F, const D: usize> PtpInstance<P, C, F, 1, D> {
     /// Create a new ordinary clock instance.
     ///
     /// This creates a PTP ordinary clock with a single port. Note that the port
@@ -79,7 +121,7 @@ impl<P, C, F> PtpInstance<P, C, F, 1> {
     pub fn new_ordinary_clock(
         default_ds: DefaultDS,
         time_properties_ds: TimePropertiesDS,
-        port: Port<P>,
+        port: Port<P, D>,
         local_clock: C,
         filter: F,
     ) -> Self {
@@ -87,80 +129,254 @@ impl<P, C, F> PtpInstance<P, C, F, 1> {
     }
 }
 
-impl<P, C, F, const N: usize> PtpInstance<P, C, F, N> {
+impl<P, C, F, const N: usize, const D: usize> PtpInstance<P, C, F, N, D> {
     /// Create a new boundary clock instance.
     ///
     /// This creates a PTP boundary clock. Multiple ports can be provided to
     /// handle multiple network interfaces. For each provided port, the port
     /// number needs to equal the index of the port in the array plus 1.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the configuration of the instance or a port is not allowed
+    /// by the [profile](crate::Profile) of the default dataset, or when the
+    /// instance uses [external port
+    /// configuration](DefaultDS::with_external_port_configuration) and a port
+    /// has no desired state.
     pub fn new_boundary_clock(
         default_ds: DefaultDS,
         time_properties_ds: TimePropertiesDS,
-        ports: [Port<P>; N],
+        ports: [Port<P, D>; N],
         local_clock: C,
         filter: F,
     ) -> Self {
         for (index, port) in ports.iter().enumerate() {
             assert_eq!(port.identity().port_number - 1, index as u16);
         }
-        PtpInstance {
-            default_ds,
-            current_ds: Default::default(),
-            parent_ds: Default::default(),
-            time_properties_ds,
+
+        let mut domains = ArrayVec::new();
+        domains.push(Domain::new(default_ds, time_properties_ds, filter));
+
+        let instance = PtpInstance {
+            domains,
             ports,
             local_clock: RefCell::new(local_clock),
-            filter: RefCell::new(filter),
+            clock_domain: 0,
+        };
+        instance.check_domain(0);
+        instance
+    }
+
+    /// Take part in another domain
+    ///
+    /// The domain has its own datasets and runs its own BMCA, but shares the
+    /// network ports of the instance. `port_datasets` configure the ports in
+    /// the new domain, in the same order as the ports of the instance. The
+    /// measurements of the domain go into its own `filter`, they only steer
+    /// the local clock when it is the [clock
+    /// domain](PtpInstance::with_clock_domain).
+    ///
+    /// # Panics
+    ///
+    /// Panics when the ports have no room for another domain, see
+    /// [`Port::with_max_domains`], when it already takes part in a domain with the same domain
+    /// number and sdo id, or when the configuration is not allowed, as with
+    /// [`new_boundary_clock`](PtpInstance::new_boundary_clock).
+    pub fn with_domain(
+        mut self,
+        default_ds: DefaultDS,
+        time_properties_ds: TimePropertiesDS,
+        port_datasets: [PortDS; N],
+        filter: F,
+    ) -> Self {
+        assert!(
+            self.domains.iter().all(|domain| {
+                domain.default_ds.domain_number != default_ds.domain_number
+                    || domain.default_ds.sdo_id != default_ds.sdo_id
+            }),
+            "already taking part in domain {} of sdo id {}",
+            default_ds.domain_number,
+            default_ds.sdo_id
+        );
+
+        self.domains
+            .try_push(Domain::new(default_ds, time_properties_ds, filter))
+            .expect("too many domains");
+        for (port, port_ds) in self.ports.iter_mut().zip(port_datasets) {
+            port.add_domain(port_ds);
+        }
+        self.check_domain(self.domains.len() - 1);
+        self
+    }
+
+    /// Steer the local clock with the time of the domain at `index`, the first
+    /// domain by default
+    ///
+    /// Domains are numbered in the order they were added, starting with 0 for
+    /// the domain the instance was created with.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the instance has no domain at `index`.
+    pub fn with_clock_domain(mut self, index: usize) -> Self {
+        assert!(index < self.domains.len(), "no domain at index {}", index);
+        self.clock_domain = index;
+        self
+    }
+
+    /// The current dataset of the domain at `index`, with the offset from the
+    /// master and the mean delay last measured in that domain
+    ///
+    /// Every domain is measured, also the ones that don't steer the local
+    /// clock. Use [`run_observed`](Self::run_observed) to follow the
+    /// measurements while the instance runs.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the instance has no domain at `index`.
+    pub fn current_ds(&self, index: usize) -> CurrentDS {
+        self.domains[index].current_ds.get()
+    }
+
+    /// The time properties dataset of the domain at `index`, as last received
+    /// from the grandmaster of that domain
+    ///
+    /// This holds what the grandmaster sends along with the time, such as its
+    /// [alternate timescales](TimePropertiesDS::alternate_time_offsets) and
+    /// [enhanced accuracy metrics](TimePropertiesDS::enhanced_accuracy_metrics).
+    /// Use [`run_observed`](Self::run_observed) to follow it while the
+    /// instance runs.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the instance has no domain at `index`.
+    pub fn time_properties_ds(&self, index: usize) -> &TimePropertiesDS {
+        &self.domains[index].time_properties_ds
+    }
+
+    fn check_domain(&self, index: usize) {
+        let default_ds = &self.domains[index].default_ds;
+        default_ds.profile.check_default_ds(default_ds);
+        for port in &self.ports {
+            let port_ds = port.domains()[index].port_ds();
+            default_ds.profile.check_port_ds(port_ds);
+            assert!(
+                !default_ds.external_port_configuration_enabled
+                    || port_ds.desired_state().is_some(),
+                "port {} has no desired state",
+                port.identity().port_number
+            );
         }
     }
 }
 
-impl<P: NetworkPort, C: Clock, F: Filter, const N: usize> PtpInstance<P, C, F, N> {
+impl<P: NetworkPort, C: Clock, F: Filter, const N: usize, const D: usize>
+    PtpInstance<P, C, F, N, D>
+{
     /// Run the PTP stack.
     ///
     /// This future needs to be awaited for the PTP protocol to be handled and
     /// the clock to be synchronized.
     pub async fn run(&mut self, timer: &impl Timer) -> ! {
+        self.run_observed(timer, |_| ()).await
+    }
+
+    /// Run the PTP stack, and hand the instance to `observe` after every run of
+    /// the BMCA
+    ///
+    /// The observer has read-only access to the instance, for example to
+    /// follow the [measurements](Self::current_ds) and the [time
+    /// properties](Self::time_properties_ds) of every domain.
+    pub async fn run_observed(&mut self, timer: &impl Timer, mut observe: impl FnMut(&Self)) -> ! {
         log::info!("Running!");
 
-        let interval = self
-            .ports
-            .iter()
-            .map(|port| port.announce_interval())
-            .max()
-            .expect("no ports");
-        let mut bmca_timeout = pin!(Ticker::new(|interval| timer.after(interval), interval));
+        let mut bmca_timeout = pin!(Ticker::new(
+            |interval| timer.after(interval),
+            self.bmca_interval()
+        ));
 
         let announce_receipt_timeouts = pin!(into_array::<_, N>(self.ports.iter().map(|port| {
-            Ticker::new(
-                |interval| timer.after(interval),
-                port.announce_receipt_interval(),
-            )
+            port.domains()
+                .iter()
+                .map(|domain_port| {
+                    Ticker::new(
+                        |interval| timer.after(interval),
+                        domain_port.announce_receipt_interval(),
+                    )
+                })
+                .collect::<ArrayVec<_, D>>()
         })));
         let sync_timeouts = pin!(into_array::<_, N>(self.ports.iter().map(|port| {
-            Ticker::new(|interval| timer.after(interval), port.sync_interval())
+            port.domains()
+                .iter()
+                .map(|domain_port| {
+                    Ticker::new(
+                        |interval| timer.after(interval),
+                        domain_port.sync_interval(),
+                    )
+                })
+                .collect::<ArrayVec<_, D>>()
         })));
         let announce_timeouts = pin!(into_array::<_, N>(self.ports.iter().map(|port| {
-            Ticker::new(|interval| timer.after(interval), port.announce_interval())
+            port.domains()
+                .iter()
+                .map(|domain_port| {
+                    Ticker::new(
+                        |interval| timer.after(interval),
+                        domain_port.announce_interval(),
+                    )
+                })
+                .collect::<ArrayVec<_, D>>()
+        })));
+        let pdelay_timeouts = pin!(into_array::<_, N>(self.ports.iter().map(|port| {
+            port.domains()
+                .iter()
+                .map(|domain_port| {
+                    Ticker::new(
+                        |interval| timer.after(interval),
+                        domain_port.min_p_delay_req_interval(),
+                    )
+                })
+                .collect::<ArrayVec<_, D>>()
+        })));
+
+        let unicast_timeouts = pin!(into_array::<_, N>(self.ports.iter().map(|port| {
+            port.domains()
+                .iter()
+                .map(|_| Ticker::new(|interval| timer.after(interval), Duration::from_secs(1)))
+                .collect::<ArrayVec<_, D>>()
         })));
 
         let mut pinned_announce_receipt_timeouts = into_array::<_, N>(unsafe {
             announce_receipt_timeouts
                 .get_unchecked_mut()
                 .iter_mut()
-                .map(|announce_receipt_timeout| Pin::new_unchecked(announce_receipt_timeout))
+                .map(|announce_receipt_timeouts| pin_each(announce_receipt_timeouts))
         });
         let mut pinned_sync_timeouts = into_array::<_, N>(unsafe {
             sync_timeouts
                 .get_unchecked_mut()
                 .iter_mut()
-                .map(|sync_timeout| Pin::new_unchecked(sync_timeout))
+                .map(|sync_timeouts| pin_each(sync_timeouts))
         });
         let mut pinned_announce_timeouts = into_array::<_, N>(unsafe {
             announce_timeouts
                 .get_unchecked_mut()
                 .iter_mut()
-                .map(|announce_timeout| Pin::new_unchecked(announce_timeout))
+                .map(|announce_timeouts| pin_each(announce_timeouts))
+        });
+        let mut pinned_pdelay_timeouts = into_array::<_, N>(unsafe {
+            pdelay_timeouts
+                .get_unchecked_mut()
+                .iter_mut()
+                .map(|pdelay_timeouts| pin_each(pdelay_timeouts))
+        });
+
+        let mut pinned_unicast_timeouts = into_array::<_, N>(unsafe {
+            unicast_timeouts
+                .get_unchecked_mut()
+                .iter_mut()
+                .map(|unicast_timeouts| pin_each(unicast_timeouts))
         });
 
         let mut stopcontexts = [(); N].map(|_| SignalContext::new());
@@ -178,22 +394,32 @@ impl<P: NetworkPort, C: Clock, F: Filter, const N: usize> PtpInstance<P, C, F, N
                 .zip(&mut pinned_announce_receipt_timeouts)
                 .zip(&mut pinned_sync_timeouts)
                 .zip(&mut pinned_announce_timeouts)
+                .zip(&mut pinned_pdelay_timeouts)
+                .zip(&mut pinned_unicast_timeouts)
                 .zip(signals.into_iter())
                 .map(
                     |(
-                        (((port, announce_receipt_timeout), sync_timeout), announce_timeout),
+                        (
+                            (
+                                (
+                                    ((port, announce_receipt_timeouts), sync_timeouts),
+                                    announce_timeouts,
+                                ),
+                                pdelay_timeouts,
+                            ),
+                            unicast_timeouts,
+                        ),
                         stop,
                     )| {
                         port.run_port(
                             &self.local_clock,
-                            &self.filter,
-                            announce_receipt_timeout,
-                            sync_timeout,
-                            announce_timeout,
-                            &self.default_ds,
-                            &self.time_properties_ds,
-                            &self.parent_ds,
-                            &self.current_ds,
+                            announce_receipt_timeouts,
+                            sync_timeouts,
+                            announce_timeouts,
+                            pdelay_timeouts,
+                            unicast_timeouts,
+                            &self.domains,
+                            self.clock_domain,
                             stop,
                         )
                     },
@@ -211,55 +437,324 @@ impl<P: NetworkPort, C: Clock, F: Filter, const N: usize> PtpInstance<P, C, F, N
             )
             .await;
 
+            self.handle_pending_management().await;
+
+            // Management messages may have changed the port intervals
+            bmca_timeout.set_interval(self.bmca_interval());
+            for (index, port) in self.ports.iter().enumerate() {
+                for (domain, domain_port) in port.domains().iter().enumerate() {
+                    pinned_announce_receipt_timeouts[index][domain]
+                        .set_interval(domain_port.announce_receipt_interval());
+                    pinned_sync_timeouts[index][domain].set_interval(domain_port.sync_interval());
+                    pinned_announce_timeouts[index][domain]
+                        .set_interval(domain_port.announce_interval());
+                    pinned_pdelay_timeouts[index][domain]
+                        .set_interval(domain_port.min_p_delay_req_interval());
+                }
+            }
+
             self.run_bmca(&mut pinned_announce_receipt_timeouts);
+            observe(self);
+        }
+    }
+
+    /// The BMCA runs once every announce interval of the slowest port
+    fn bmca_interval(&self) -> Duration {
+        self.ports
+            .iter()
+            .flat_map(|port| port.domains())
+            .map(|domain_port| domain_port.announce_interval())
+            .max()
+            .expect("no ports")
+    }
+
+    /// Answer the management messages for other ports than the one that
+    /// received them, apply the dataset changes requested through management
+    /// messages, and forward management messages with boundary hops left to
+    /// the other ports
+    async fn handle_pending_management(&mut self) {
+        // The addressed ports may request changes of their own, so they answer
+        // first
+        for index in 0..N {
+            for domain_index in 0..self.domains.len() {
+                let dispatches = self.ports[index]
+                    .domain_mut(domain_index)
+                    .take_management_dispatches();
+
+                for (message, source_address) in dispatches {
+                    // Port numbers are the index of the port plus 1
+                    let target = message.target_port_identity.port_number as usize;
+                    let Some(port) = target
+                        .checked_sub(1)
+                        .and_then(|target_index| self.ports.get_mut(target_index))
+                    else {
+                        log::warn!("Dropping management message for missing port {}", target);
+                        continue;
+                    };
+
+                    let domain = &self.domains[domain_index];
+                    let response = port.domain_mut(domain_index).respond_management(
+                        &message,
+                        &domain.default_ds,
+                        &domain.time_properties_ds,
+                        &domain.parent_ds,
+                        &domain.current_ds.get(),
+                        &Domain::other_domain_numbers::<D>(&self.domains, domain_index),
+                    );
+                    let response = match response {
+                        Ok(Some(response)) => response,
+                        Ok(None) => continue,
+                        Err(error) => {
+                            log::error!("{:?}", error);
+                            continue;
+                        }
+                    };
+
+                    // Failures to send already show up in the log
+                    let _ = self.ports[index]
+                        .send_management_response(domain_index, &response, source_address.as_ref())
+                        .await;
+                }
+            }
+        }
+
+        for index in 0..N {
+            for domain_index in 0..self.domains.len() {
+                let pending = self.ports[index]
+                    .domain_mut(domain_index)
+                    .take_pending_management();
+
+                for change in pending.instance_changes {
+                    // Another domain may have taken the domain number since the port
+                    // accepted the change
+                    if let InstanceChange::Domain(domain_number) = change {
+                        let sdo_id = self.domains[domain_index].default_ds.sdo_id;
+                        if self.domains.iter().enumerate().any(|(other, domain)| {
+                            other != domain_index
+                                && domain.default_ds.sdo_id == sdo_id
+                                && domain.default_ds.domain_number == domain_number
+                        }) {
+                            log::warn!("Dropping management change {:?}, in use", change);
+                            continue;
+                        }
+                    }
+
+                    log::info!("Applying management change {:?}", change);
+                    change.apply(&mut self.domains[domain_index].default_ds);
+                }
+
+                for message in pending.forwards {
+                    let data = match Message::Management(message).serialize_vec() {
+                        Ok(data) => data,
+                        Err(error) => {
+                            log::error!("{:?}", error);
+                            continue;
+                        }
+                    };
+
+                    for (other_index, port) in self.ports.iter_mut().enumerate() {
+                        if other_index == index {
+                            continue;
+                        }
+
+                        if let Err(error) = port.authenticated_port().send(&data).await {
+                            log::error!("failed to forward management message: {:?}", error);
+                        }
+                    }
+                }
+            }
         }
     }
 
     fn run_bmca<Fut: Future>(
         &mut self,
-        pinned_timeouts: &mut [Pin<&mut Ticker<Fut, impl FnMut(Duration) -> Fut>>],
+        pinned_timeouts: &mut [ArrayVec<Pin<&mut Ticker<Fut, impl FnMut(Duration) -> Fut>>, D>],
     ) {
-        log::debug!("Running BMCA");
-        let mut erbests = [None; N];
-
-        let current_time = self
+        let (current_time, clock_quality) = self
             .local_clock
             .try_borrow()
-            .map(|borrow| borrow.now())
+            .map(|borrow| (borrow.now(), borrow.quality()))
             .map_err(|_| PortError::ClockBusy)
-            .unwrap()
-            .into();
+            .unwrap();
+
+        for domain_index in 0..self.domains.len() {
+            self.run_domain_bmca(domain_index, current_time, clock_quality, pinned_timeouts);
+        }
+    }
+
+    fn run_domain_bmca<Fut: Future>(
+        &mut self,
+        domain_index: usize,
+        current_time: Instant,
+        clock_quality: ClockQuality,
+        pinned_timeouts: &mut [ArrayVec<Pin<&mut Ticker<Fut, impl FnMut(Duration) -> Fut>>, D>],
+    ) {
+        let domain = &mut self.domains[domain_index];
+        log::debug!(
+            "Running BMCA for domain {}",
+            domain.default_ds.domain_number
+        );
+        let mut erbests = [(); N].map(|_| None);
 
+        let has_slave_port = self
+            .ports
+            .iter()
+            .any(|port| matches!(port.domains()[domain_index].state(), PortState::Slave(_)));
+        domain.update_clock_quality(current_time, clock_quality, has_slave_port);
+
+        let current_time = current_time.into();
         for (index, port) in self.ports.iter_mut().enumerate() {
-            erbests[index] = port.best_local_announce_message(current_time);
+            erbests[index] = port
+                .domain_mut(domain_index)
+                .best_local_announce_message(current_time, &domain.default_ds);
+        }
+
+        if domain.default_ds.external_port_configuration_enabled {
+            self.apply_port_configuration(domain_index, erbests, pinned_timeouts);
+            return;
         }
 
         // TODO: What to do with `None`s?
-        let ebest = Bmca::find_best_announce_message(erbests.iter().flatten().cloned());
+        let ebest = Bmca::find_best_announce_message(
+            erbests.iter().flatten().cloned(),
+            domain.default_ds.profile,
+        );
 
         for (index, port) in self.ports.iter_mut().enumerate() {
+            let domain_port = port.domain_mut(domain_index);
             let recommended_state = Bmca::calculate_recommended_state(
-                &self.default_ds,
-                ebest,
-                erbests[index],
-                port.state(),
+                &domain.default_ds,
+                ebest.clone(),
+                erbests[index].clone(),
+                domain_port.state(),
             );
 
             log::debug!("Recommended state port {}: {:?}", index, recommended_state);
 
             if let Some(recommended_state) = recommended_state {
-                if let Err(error) = port.set_recommended_state(
+                if let Err(error) = domain_port.set_recommended_state(
                     recommended_state,
-                    &mut pinned_timeouts[index],
-                    &mut self.time_properties_ds,
-                    &mut self.current_ds,
-                    &mut self.parent_ds,
+                    &mut pinned_timeouts[index][domain_index],
+                    &mut domain.time_properties_ds,
+                    domain.current_ds.get_mut(),
+                    &mut domain.parent_ds,
                 ) {
                     log::error!("{:?}", error)
                 }
             }
         }
     }
+
+    /// Put every port in its desired state instead of running the state
+    /// decision algorithm (IEEE1588-2019 section 17.6)
+    fn apply_port_configuration<Fut: Future>(
+        &mut self,
+        domain_index: usize,
+        erbests: [Option<BestAnnounceMessage>; N],
+        pinned_timeouts: &mut [ArrayVec<Pin<&mut Ticker<Fut, impl FnMut(Duration) -> Fut>>, D>],
+    ) {
+        let domain = &mut self.domains[domain_index];
+        let has_slave_port = self.ports.iter().any(|port| {
+            port.domains()[domain_index].port_ds().desired_state() == Some(DesiredState::Slave)
+        });
+
+        for ((index, port), erbest) in self.ports.iter_mut().enumerate().zip(erbests) {
+            if let Err(error) = port.domain_mut(domain_index).set_configured_state(
+                erbest,
+                has_slave_port,
+                &domain.default_ds,
+                &mut pinned_timeouts[index][domain_index],
+                &mut domain.time_properties_ds,
+                domain.current_ds.get_mut(),
+                &mut domain.parent_ds,
+            ) {
+                log::error!("{:?}", error)
+            }
+        }
+    }
+}
+
+impl<F> Domain<F> {
+    /// The domain numbers of the other domains with the same sdo id as the
+    /// domain at `index`, which that domain can't move to
+    pub(crate) fn other_domain_numbers<const D: usize>(
+        domains: &[Self],
+        index: usize,
+    ) -> ArrayVec<u8, D> {
+        let sdo_id = domains[index].default_ds.sdo_id;
+        domains
+            .iter()
+            .enumerate()
+            .filter(|(other, domain)| *other != index && domain.default_ds.sdo_id == sdo_id)
+            .map(|(_, domain)| domain.default_ds.domain_number)
+            .collect()
+    }
+
+    fn new(default_ds: DefaultDS, time_properties_ds: TimePropertiesDS, filter: F) -> Self {
+        Domain {
+            default_ds,
+            current_ds: Default::default(),
+            parent_ds: Default::default(),
+            time_properties_ds,
+            filter: RefCell::new(filter),
+            has_reference: false,
+            holdover_start: None,
+        }
+    }
+
+    /// Advertise the quality of the local clock, or the holdover clock class
+    /// when the domain lost its time reference
+    ///
+    /// Without holdover, the domain keeps the clock quality of its default
+    /// dataset.
+    fn update_clock_quality(
+        &mut self,
+        current_time: Instant,
+        clock_quality: ClockQuality,
+        has_slave_port: bool,
+    ) {
+        let Some(holdover) = self.default_ds.holdover else {
+            return;
+        };
+
+        self.default_ds.clock_quality = clock_quality;
+
+        let has_reference = clock_quality.clock_class <= 127 || has_slave_port;
+
+        if has_reference {
+            self.holdover_start = None;
+        } else if self.has_reference {
+            log::info!("Lost time reference, going into holdover");
+            self.holdover_start = Some(current_time);
+        }
+        self.has_reference = has_reference;
+
+        if let Some(holdover_start) = self.holdover_start {
+            self.default_ds.clock_quality.clock_class =
+                if current_time - holdover_start < holdover.in_spec_duration {
+                    holdover.in_spec_class
+                } else {
+                    holdover.out_of_spec_class
+                };
+        }
+
+        // Slave only clocks never advertise themselves as a possible master
+        if self.default_ds.slave_only {
+            self.default_ds.clock_quality.clock_class = 255;
+        }
+    }
+}
+
+/// Pin every value in place
+///
+/// # Safety
+///
+/// The values must never be moved again.
+unsafe fn pin_each<T, const D: usize>(values: &mut ArrayVec<T, D>) -> ArrayVec<Pin<&mut T>, D> {
+    values
+        .iter_mut()
+        .map(|value| Pin::new_unchecked(value))
+        .collect()
 }
 
 fn into_array<T, const N: usize>(iter: impl IntoIterator<Item = T>) -> [T; N] {
@@ -268,3 +763,238 @@ fn into_array<T, const N: usize>(iter: impl IntoIterator<Item = T>) -> [T; N] {
     assert!(iter.next().is_none());
     arr
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        datastructures::{
+            common::{ClockIdentity, PortIdentity, TimeSource},
+            datasets::{DelayMechanism, Holdover},
+            messages::{
+                EnhancedAccuracyMetricsTlv, ManagementAction, ManagementId, ManagementTlv,
+                MessageBuilder, SdoId,
+            },
+        },
+        filters::basic::BasicFilter,
+        port::tests::{RecordingNetworkPort, RecordingRuntime, TestClock},
+    };
+
+    const CLOCK_IDENTITY: ClockIdentity = ClockIdentity([1, 2, 3, 4, 5, 6, 7, 8]);
+
+    fn default_ds(domain_number: u8) -> DefaultDS {
+        DefaultDS::new_ordinary_clock(
+            CLOCK_IDENTITY,
+            128,
+            128,
+            domain_number,
+            false,
+            SdoId::default(),
+        )
+    }
+
+    fn time_properties_ds() -> TimePropertiesDS {
+        TimePropertiesDS::new_arbitrary_time(false, false, TimeSource::InternalOscillator)
+    }
+
+    fn port_ds() -> PortDS {
+        PortDS::new(
+            PortIdentity {
+                clock_identity: CLOCK_IDENTITY,
+                port_number: 1,
+            },
+            1,
+            1,
+            3,
+            0,
+            DelayMechanism::E2E,
+            1,
+        )
+    }
+
+    fn setup<const D: usize>() -> PtpInstance<RecordingNetworkPort, (), (), 1, D> {
+        let port = embassy_futures::block_on(Port::new(
+            port_ds(),
+            &mut RecordingRuntime,
+            Instant::default(),
+        ));
+        PtpInstance::new_ordinary_clock(
+            default_ds(0),
+            time_properties_ds(),
+            port.with_max_domains(),
+            (),
+            (),
+        )
+    }
+
+    #[test]
+    fn test_with_domain() {
+        let instance = setup::<2>()
+            .with_domain(default_ds(1), time_properties_ds(), [port_ds()], ())
+            .with_clock_domain(1);
+
+        assert_eq!(instance.domains.len(), 2);
+        assert_eq!(instance.domains[1].default_ds.domain_number, 1);
+        assert_eq!(instance.ports[0].domains().len(), 2);
+        assert_eq!(instance.clock_domain, 1);
+    }
+
+    #[test]
+    fn test_domain_datasets() {
+        let mut instance =
+            setup::<2>().with_domain(default_ds(1), time_properties_ds(), [port_ds()], ());
+        let metrics = EnhancedAccuracyMetricsTlv {
+            bc_hop_count: 1,
+            ..Default::default()
+        };
+        instance.domains[1]
+            .time_properties_ds
+            .enhanced_accuracy_metrics = Some(metrics);
+        instance.domains[1].current_ds.get_mut().offset_from_master = Duration::from_micros(5);
+
+        // Every domain keeps what it received from its own master
+        assert_eq!(
+            instance.time_properties_ds(0).enhanced_accuracy_metrics(),
+            None
+        );
+        assert_eq!(
+            instance.time_properties_ds(1).enhanced_accuracy_metrics(),
+            Some(metrics)
+        );
+        assert_eq!(instance.current_ds(0).offset_from_master(), Duration::ZERO);
+        assert_eq!(
+            instance.current_ds(1).offset_from_master(),
+            Duration::from_micros(5)
+        );
+    }
+
+    #[test]
+    fn test_clock_quality() {
+        let locked = ClockQuality {
+            clock_class: 6,
+            ..Default::default()
+        };
+        let free_running = ClockQuality {
+            clock_class: 248,
+            ..Default::default()
+        };
+
+        // Without holdover, the configured clock quality stays
+        let mut domain = Domain::new(default_ds(0), time_properties_ds(), ());
+        domain.default_ds.clock_quality = locked;
+        domain.update_clock_quality(Instant::from_secs(0), free_running, false);
+        assert_eq!(domain.default_ds.clock_quality, locked);
+
+        let mut domain = Domain::new(
+            default_ds(0).with_holdover(Holdover {
+                in_spec_class: 7,
+                out_of_spec_class: 140,
+                in_spec_duration: Duration::from_secs(10),
+            }),
+            time_properties_ds(),
+            (),
+        );
+        domain.update_clock_quality(Instant::from_secs(0), locked, false);
+        assert_eq!(domain.default_ds.clock_quality, locked);
+        domain.update_clock_quality(Instant::from_secs(1), free_running, false);
+        assert_eq!(domain.default_ds.clock_quality.clock_class, 7);
+        domain.update_clock_quality(Instant::from_secs(12), free_running, false);
+        assert_eq!(domain.default_ds.clock_quality.clock_class, 140);
+    }
+
+    #[test]
+    fn test_management_for_other_port() {
+        let ports = [1, 2].map(|port_number| {
+            embassy_futures::block_on(Port::new(
+                PortDS::new(
+                    PortIdentity {
+                        clock_identity: CLOCK_IDENTITY,
+                        port_number,
+                    },
+                    1,
+                    1,
+                    3,
+                    0,
+                    DelayMechanism::E2E,
+                    1,
+                ),
+                &mut RecordingRuntime,
+                Instant::default(),
+            ))
+        });
+        let mut instance: PtpInstance<_, _, _, 2, 1> = PtpInstance::new_boundary_clock(
+            DefaultDS::new_boundary_clock(CLOCK_IDENTITY, 2, 128, 128, 0, SdoId::default()),
+            time_properties_ds(),
+            ports,
+            TestClock {
+                current_time: Instant::default(),
+            },
+            BasicFilter::new(0.25),
+        );
+
+        let other_port = PortIdentity {
+            clock_identity: CLOCK_IDENTITY,
+            port_number: 2,
+        };
+        let Message::Management(request) = MessageBuilder::new()
+            .source_port_identity(PortIdentity {
+                clock_identity: ClockIdentity([9; 8]),
+                port_number: 1,
+            })
+            .management_message(
+                other_port,
+                0,
+                0,
+                ManagementAction::GET,
+                ManagementTlv::Empty(ManagementId::PortDataSet),
+            )
+        else {
+            unreachable!()
+        };
+
+        let domain = &instance.domains[0];
+        embassy_futures::block_on(instance.ports[0].domain_mut(0).handle_management_message(
+            // Only the instance sends the response
+            &mut RecordingNetworkPort::default(),
+            request,
+            None,
+            &domain.default_ds,
+            &domain.time_properties_ds,
+            &domain.parent_ds,
+            &domain.current_ds.get(),
+            &[],
+        ))
+        .unwrap();
+        embassy_futures::block_on(instance.handle_pending_management());
+
+        // The addressed port answers through the port that received the request
+        assert!(instance.ports[1].network_port().normal.is_empty());
+        let data = instance.ports[0].network_port().normal.pop().unwrap();
+        let Message::Management(response) = Message::deserialize(&data).unwrap() else {
+            panic!("Unexpected message type");
+        };
+        assert_eq!(response.header.source_port_identity, other_port);
+        let ManagementTlv::PortDataSet(port_ds) = response.management_tlv else {
+            panic!("Unexpected management TLV");
+        };
+        assert_eq!(port_ds.port_identity, other_port);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_with_domain_full() {
+        setup::<1>().with_domain(default_ds(1), time_properties_ds(), [port_ds()], ());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_with_domain_twice() {
+        setup::<2>().with_domain(default_ds(0), time_properties_ds(), [port_ds()], ());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_with_clock_domain_missing() {
+        setup::<1>().with_clock_domain(1);
+    }
+}
@@ -0,0 +1,1059 @@
+use core::{
+    cell::RefCell,
+    mem::Discriminant,
+    pin::{pin, Pin},
+};
+
+use arrayvec::ArrayVec;
+use embassy_futures::select::{select, select_array, Either};
+use futures::StreamExt;
+
+use crate::{
+    clock::{Clock, Timer},
+    datastructures::{
+        common::{InstanceType, PortIdentity, TimeInterval},
+        datasets::DefaultDS,
+        messages::{
+            add_to_correction_field, DelayRespMessage, Message, PowerProfileTlv, MAX_DATA_LEN,
+        },
+    },
+    network::{NetworkPacket, NetworkPort},
+    port::{Port, PortError, Result, Ticker},
+    profile::Profile,
+    time::{Duration, Instant},
+};
+
+/// The number of residence times remembered per port while waiting for the
+/// matching Follow_Up or Delay_Resp message
+const MAX_RESIDENCE_TIMES: usize = 16;
+/// The number of recently forwarded messages remembered to drop duplicates,
+/// e.g. forwarded messages looping back into another port
+const MAX_FORWARDED_MESSAGES: usize = 32;
+
+/// A PTP transparent clock.
+///
+/// A transparent clock does not synchronize to a master, nor does it run the
+/// best master clock algorithm. Instead, it forwards all PTP messages received
+/// on one of its ports to all of its other ports, adding the time each event
+/// message spent inside the transparent clock (the residence time) to the
+/// correction field. For two-step event messages, the residence time is added
+/// to the Follow_Up or Delay_Resp message belonging to it instead.
+///
+/// An end to end transparent clock forwards the delay requests of slaves to
+/// their master. A peer to peer transparent clock instead measures the link
+/// delay on each of its ports, and adds the link delay of the port a Sync came
+/// in on to its correction. Delay requests and responses are dropped, and peer
+/// delay messages are never forwarded.
+///
+/// # Example
+/// Assuming we already have a network runtime and clock runtime, an end to end
+/// transparent clock with two ports can be run by creating the default
+/// dataset and the ports, then setting up the transparent clock:
+///
+/// ```ignore
+/// let default_ds = DefaultDS::new_e2e_transparent_clock(
+///     clock_identity,
+///     2,
+///     0,
+///     SdoId::new(0).unwrap(),
+/// );
+/// let port_1 = Port::new(port_1_ds, &mut network_runtime, interface_name_1).await;
+/// let port_2 = Port::new(port_2_ds, &mut network_runtime, interface_name_2).await;
+/// let mut transparent_clock =
+///     TransparentClock::new(default_ds, [port_1, port_2], local_clock);
+///
+/// transparent_clock.run(&TimerImpl).await;
+/// ```
+pub struct TransparentClock<P, C, const N: usize> {
+    default_ds: DefaultDS,
+    ports: [Port<P>; N],
+    local_clock: RefCell<C>,
+    residence_times: [ResidenceTimes; N],
+    forwarded_messages: ArrayVec<ForwardedMessage, MAX_FORWARDED_MESSAGES>,
+}
+
+impl<P, C, const N: usize> TransparentClock<P, C, N> {
+    /// Create a new transparent clock.
+    ///
+    /// The default dataset needs to be created for an end to end or peer to
+    /// peer transparent clock. For each provided port, the port number needs
+    /// to equal the index of the port in the array plus 1.
+    pub fn new(default_ds: DefaultDS, mut ports: [Port<P>; N], local_clock: C) -> Self {
+        assert!(matches!(
+            default_ds.instance_type,
+            InstanceType::E2ETransparentClock | InstanceType::P2PTransparentClock
+        ));
+        for (index, port) in ports.iter_mut().enumerate() {
+            assert_eq!(port.identity().port_number - 1, index as u16);
+            // Transparent clock ports don't run the BMCA, they only need to be
+            // initialized
+            port.initialize();
+        }
+        TransparentClock {
+            default_ds,
+            ports,
+            local_clock: RefCell::new(local_clock),
+            residence_times: [(); N].map(|_| ResidenceTimes::default()),
+            forwarded_messages: ArrayVec::new(),
+        }
+    }
+}
+
+impl<P: NetworkPort, C: Clock, const N: usize> TransparentClock<P, C, N> {
+    /// Run the transparent clock.
+    ///
+    /// This future needs to be awaited for messages to be forwarded between
+    /// the ports.
+    pub async fn run(&mut self, timer: &impl Timer) -> ! {
+        log::info!(
+            "Running transparent clock {:?}!",
+            self.default_ds.clock_identity
+        );
+
+        let pdelay_timeouts = pin!(self.ports.each_ref().map(|port| {
+            Ticker::new(
+                |interval| timer.after(interval),
+                port.min_p_delay_req_interval(),
+            )
+        }));
+        let mut pinned_pdelay_timeouts = unsafe {
+            pdelay_timeouts
+                .get_unchecked_mut()
+                .each_mut()
+                .map(|pdelay_timeout| Pin::new_unchecked(pdelay_timeout))
+        };
+
+        loop {
+            let event = {
+                let packets =
+                    select_array(self.ports.each_mut().map(|port| port.network_port().recv()));
+                let timeouts = select_array(
+                    pinned_pdelay_timeouts
+                        .each_mut()
+                        .map(|pdelay_timeout| pdelay_timeout.next()),
+                );
+                select(packets, timeouts).await
+            };
+
+            match event {
+                Either::First((Ok(packet), index)) => {
+                    log::trace!("Port {} message received: {:?}", index + 1, packet);
+                    if let Err(error) = self.handle_packet(index, packet).await {
+                        log::error!("{:?}", error);
+                    }
+                }
+                Either::First((Err(error), _)) => {
+                    log::error!("failed to parse packet {:?}", error)
+                }
+                Either::Second((_, index)) => {
+                    log::trace!("Port {} pdelay timeout", index + 1);
+                    if let Err(error) = self.ports[index]
+                        .send_pdelay_req(&self.local_clock, &self.default_ds)
+                        .await
+                    {
+                        log::error!("{:?}", error);
+                    }
+                }
+            }
+        }
+    }
+
+    fn is_peer_to_peer(&self) -> bool {
+        self.default_ds.instance_type == InstanceType::P2PTransparentClock
+    }
+
+    async fn handle_packet(&mut self, ingress: usize, mut packet: NetworkPacket) -> Result<()> {
+        // Residence times start at the reference plane of the ingress port
+        packet.timestamp -= self.ports[ingress].ingress_latency();
+
+        // Only authentic messages are processed (IEEE1588-2019 section 16.14.3),
+        // but they are forwarded with their AUTHENTICATION TLV
+        let mut verified = packet.clone();
+        if let Err(error) = self.ports[ingress].verify(&mut verified) {
+            log::debug!("Ignoring unauthenticated message: {:?}", error);
+            return Ok(());
+        }
+        let message = Message::deserialize(&verified.data)?;
+
+        if self.is_peer_to_peer() {
+            match message {
+                // Peer delay messages are for the link only
+                Message::PDelayReq(_) | Message::PDelayResp(_) | Message::PDelayRespFollowUp(_) => {
+                    return self.ports[ingress]
+                        .handle_peer_delay_message(message, packet.timestamp)
+                        .await;
+                }
+                Message::DelayReq(_) | Message::DelayResp(_) => {
+                    log::trace!("Dropping end to end delay message");
+                    return Ok(());
+                }
+                _ => (),
+            }
+        }
+
+        let forwarded_message = ForwardedMessage::new(&message);
+        if self.forwarded_messages.contains(&forwarded_message) {
+            log::trace!("Dropping already forwarded message {:?}", forwarded_message);
+            return Ok(());
+        }
+        if self.forwarded_messages.is_full() {
+            self.forwarded_messages.remove(0);
+        }
+        self.forwarded_messages.push(forwarded_message);
+
+        match message {
+            Message::Sync(_) | Message::DelayReq(_) => {
+                self.forward_event(ingress, message, &packet).await
+            }
+            Message::FollowUp(_) => self.forward_follow_up(ingress, message, &packet).await,
+            Message::DelayResp(delay_resp) => {
+                self.forward_delay_resp(ingress, delay_resp, &packet).await
+            }
+            Message::Announce(announce)
+                if self.default_ds.profile == Profile::C37_238
+                    && announce.power_profile.is_some() =>
+            {
+                self.forward_power_profile_announce(ingress, &packet).await;
+                Ok(())
+            }
+            _ => {
+                self.forward(ingress, message.is_event(), &packet.data)
+                    .await;
+                Ok(())
+            }
+        }
+    }
+
+    async fn forward_event(
+        &mut self,
+        ingress: usize,
+        message: Message,
+        packet: &NetworkPacket,
+    ) -> Result<()> {
+        let kind = ResidenceKind::of(&message);
+        let one_step = matches!(message, Message::Sync(_)) && !message.header().two_step_flag();
+
+        // A sync still needs to be corrected for the delay of the link it came in over
+        let link_delay = if self.is_peer_to_peer() {
+            self.ports[ingress].mean_link_delay()
+        } else {
+            Duration::ZERO
+        };
+
+        for (index, port) in self.ports.iter_mut().enumerate() {
+            if index == ingress {
+                continue;
+            }
+
+            if one_step {
+                // There is no follow up to carry the residence time, so it is
+                // estimated just before sending
+                let residence_time = current_time(&self.local_clock)? - packet.timestamp;
+                let data = with_correction(&packet.data, residence_time + link_delay);
+                if let Err(error) = port.latency_corrected_port().send_one_step(&data).await {
+                    log::error!("failed to forward message: {:?}", error);
+                }
+            } else {
+                let egress_time = match port
+                    .latency_corrected_port()
+                    .send_time_critical(&packet.data)
+                    .await
+                {
+                    Ok(Some(egress_time)) => egress_time,
+                    Ok(None) => current_time(&self.local_clock)?,
+                    Err(error) => {
+                        log::error!("failed to forward message: {:?}", error);
+                        continue;
+                    }
+                };
+
+                if let Some(kind) = kind {
+                    self.residence_times[index].insert(ResidenceTime {
+                        kind,
+                        source_port_identity: message.header().source_port_identity(),
+                        sequence_id: message.header().sequence_id(),
+                        residence_time: egress_time - packet.timestamp + link_delay,
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn forward_follow_up(
+        &mut self,
+        ingress: usize,
+        message: Message,
+        packet: &NetworkPacket,
+    ) -> Result<()> {
+        for (index, port) in self.ports.iter_mut().enumerate() {
+            if index == ingress {
+                continue;
+            }
+
+            // The residence time of the sync depends on the port it was sent from
+            let residence_time = self.residence_times[index].take(
+                ResidenceKind::Sync,
+                message.header().source_port_identity(),
+                message.header().sequence_id(),
+            );
+
+            let result = match residence_time {
+                Some(residence_time) => {
+                    let data = with_correction(&packet.data, residence_time);
+                    port.network_port().send(&data).await
+                }
+                None => {
+                    log::debug!("No residence time for follow up, forwarding as is");
+                    port.network_port().send(&packet.data).await
+                }
+            };
+
+            if let Err(error) = result {
+                log::error!("failed to forward message: {:?}", error);
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn forward_delay_resp(
+        &mut self,
+        ingress: usize,
+        delay_resp: DelayRespMessage,
+        packet: &NetworkPacket,
+    ) -> Result<()> {
+        // The delay request was sent towards the master through the port the
+        // response came in on
+        let residence_time = self.residence_times[ingress].take(
+            ResidenceKind::DelayReq,
+            delay_resp.requesting_port_identity(),
+            delay_resp.header().sequence_id(),
+        );
+
+        match residence_time {
+            Some(residence_time) => {
+                let data = with_correction(&packet.data, residence_time);
+                self.forward(ingress, false, &data).await;
+            }
+            None => {
+                log::debug!("No residence time for delay response, forwarding as is");
+                self.forward(ingress, false, &packet.data).await;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Forward an announce of a power profile domain, adding the time
+    /// inaccuracy of this clock to the total of its IEEE_C37_238 TLV
+    async fn forward_power_profile_announce(&mut self, ingress: usize, packet: &NetworkPacket) {
+        let mut data = packet.data.clone();
+        PowerProfileTlv::forward_in_place(&mut data, self.default_ds.time_inaccuracy);
+        self.forward(ingress, false, &data).await;
+    }
+
+    /// Forward data unchanged to all ports except the ingress port
+    async fn forward(&mut self, ingress: usize, time_critical: bool, data: &[u8]) {
+        for (index, port) in self.ports.iter_mut().enumerate() {
+            if index == ingress {
+                continue;
+            }
+
+            let result = if time_critical {
+                port.network_port()
+                    .send_time_critical(data)
+                    .await
+                    .map(|_| ())
+            } else {
+                port.network_port().send(data).await
+            };
+
+            if let Err(error) = result {
+                log::error!("failed to forward message: {:?}", error);
+            }
+        }
+    }
+}
+
+fn current_time(local_clock: &RefCell<impl Clock>) -> Result<Instant> {
+    local_clock
+        .try_borrow()
+        .map(|borrow| borrow.now())
+        .map_err(|_| PortError::ClockBusy)
+}
+
+/// The data of a message with `correction` added to its correction field,
+/// keeping its TLVs, like an AUTHENTICATION TLV, as they are
+fn with_correction(
+    data: &ArrayVec<u8, MAX_DATA_LEN>,
+    correction: Duration,
+) -> ArrayVec<u8, MAX_DATA_LEN> {
+    let mut data = data.clone();
+    add_to_correction_field(&mut data, TimeInterval::from(correction));
+    data
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ResidenceKind {
+    Sync,
+    DelayReq,
+}
+
+impl ResidenceKind {
+    fn of(message: &Message) -> Option<Self> {
+        match message {
+            Message::Sync(_) => Some(ResidenceKind::Sync),
+            Message::DelayReq(_) => Some(ResidenceKind::DelayReq),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ResidenceTime {
+    kind: ResidenceKind,
+    source_port_identity: PortIdentity,
+    sequence_id: u16,
+    residence_time: Duration,
+}
+
+/// Residence times of two-step event messages sent out over a single port
+#[derive(Debug, Default)]
+struct ResidenceTimes {
+    entries: ArrayVec<ResidenceTime, MAX_RESIDENCE_TIMES>,
+}
+
+impl ResidenceTimes {
+    fn insert(&mut self, entry: ResidenceTime) {
+        // Forget the oldest entry, its general message is unlikely to still show up
+        if self.entries.is_full() {
+            self.entries.remove(0);
+        }
+        self.entries.push(entry);
+    }
+
+    fn take(
+        &mut self,
+        kind: ResidenceKind,
+        source_port_identity: PortIdentity,
+        sequence_id: u16,
+    ) -> Option<Duration> {
+        let index = self.entries.iter().position(|entry| {
+            entry.kind == kind
+                && entry.source_port_identity == source_port_identity
+                && entry.sequence_id == sequence_id
+        })?;
+        Some(self.entries.remove(index).residence_time)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ForwardedMessage {
+    message_type: Discriminant<Message>,
+    domain_number: u8,
+    source_port_identity: PortIdentity,
+    sequence_id: u16,
+    /// A master answers the delay requests of all its slaves with the same
+    /// source port identity, and their sequence ids can coincide
+    requesting_port_identity: Option<PortIdentity>,
+}
+
+impl ForwardedMessage {
+    fn new(message: &Message) -> Self {
+        let requesting_port_identity = match message {
+            Message::DelayResp(delay_resp) => Some(delay_resp.requesting_port_identity()),
+            _ => None,
+        };
+
+        ForwardedMessage {
+            message_type: core::mem::discriminant(message),
+            domain_number: message.header().domain_number(),
+            source_port_identity: message.header().source_port_identity(),
+            sequence_id: message.header().sequence_id(),
+            requesting_port_identity,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use super::*;
+    use crate::{
+        datastructures::{
+            common::{ClockIdentity, ClockQuality, Timestamp},
+            datasets::{DelayMechanism, PortDS},
+            messages::{MessageBuilder, SdoId},
+        },
+        port::{
+            tests::{RecordingNetworkPort, RecordingRuntime, TestClock},
+            Authentication, SecurityAssociation,
+        },
+    };
+
+    fn setup(egress_times: [Instant; 3]) -> TransparentClock<RecordingNetworkPort, TestClock, 3> {
+        setup_with(DelayMechanism::E2E, |port_ds| port_ds, None, egress_times)
+    }
+
+    fn setup_with(
+        delay_mechanism: DelayMechanism,
+        configure_port: impl Fn(PortDS) -> PortDS,
+        security_association: Option<&SecurityAssociation>,
+        egress_times: [Instant; 3],
+    ) -> TransparentClock<RecordingNetworkPort, TestClock, 3> {
+        let clock_identity = ClockIdentity([1, 2, 3, 4, 5, 6, 7, 8]);
+        let default_ds = match delay_mechanism {
+            DelayMechanism::P2P => {
+                DefaultDS::new_p2p_transparent_clock(clock_identity, 3, 0, SdoId::default())
+            }
+            _ => DefaultDS::new_e2e_transparent_clock(clock_identity, 3, 0, SdoId::default()),
+        };
+
+        let mut port_number = 0;
+        let ports = egress_times.map(|egress_time| {
+            port_number += 1;
+            let port_ds = PortDS::new(
+                PortIdentity {
+                    clock_identity,
+                    port_number,
+                },
+                1,
+                1,
+                3,
+                0,
+                delay_mechanism,
+                1,
+            );
+            let port_ds = configure_port(port_ds);
+            let port =
+                embassy_futures::block_on(Port::new(port_ds, &mut RecordingRuntime, egress_time));
+            match security_association {
+                Some(security_association) => {
+                    port.with_security_association(security_association.clone())
+                }
+                None => port,
+            }
+        });
+
+        TransparentClock::new(
+            default_ds,
+            ports,
+            TestClock {
+                current_time: Instant::from_micros(1300),
+            },
+        )
+    }
+
+    fn packet(message: &Message, timestamp: Instant) -> NetworkPacket {
+        NetworkPacket {
+            data: message.serialize_vec().unwrap(),
+            timestamp,
+            source_address: None,
+        }
+    }
+
+    fn master() -> PortIdentity {
+        PortIdentity {
+            clock_identity: ClockIdentity([9; 8]),
+            port_number: 1,
+        }
+    }
+
+    fn slave() -> PortIdentity {
+        PortIdentity {
+            clock_identity: ClockIdentity([10; 8]),
+            port_number: 1,
+        }
+    }
+
+    #[test]
+    fn test_two_step_sync() {
+        let mut tc = setup([
+            Instant::from_micros(1000),
+            Instant::from_micros(1010),
+            Instant::from_micros(1020),
+        ]);
+
+        let sync = MessageBuilder::new()
+            .two_step_flag(true)
+            .source_port_identity(master())
+            .sequence_id(5)
+            .sync_message(Timestamp::default());
+        embassy_futures::block_on(tc.handle_packet(0, packet(&sync, Instant::from_micros(1000))))
+            .unwrap();
+
+        // Sent on both other ports, unchanged
+        assert_eq!(tc.ports[0].network_port().time.len(), 0);
+        for index in [1, 2] {
+            let sent = tc.ports[index].network_port().time.pop().unwrap();
+            assert_eq!(Message::deserialize(&sent).unwrap(), sync);
+        }
+
+        let follow_up = MessageBuilder::new()
+            .source_port_identity(master())
+            .sequence_id(5)
+            .correction_field(TimeInterval(2000.into()))
+            .follow_up_message(Instant::from_micros(900).into(), None);
+        embassy_futures::block_on(
+            tc.handle_packet(0, packet(&follow_up, Instant::from_micros(1100))),
+        )
+        .unwrap();
+
+        assert_eq!(tc.ports[0].network_port().normal.len(), 0);
+        for (index, correction) in [(1, 12000), (2, 22000)] {
+            let sent = tc.ports[index].network_port().normal.pop().unwrap();
+            let Message::FollowUp(forwarded) = Message::deserialize(&sent).unwrap() else {
+                panic!("Unexpected message type");
+            };
+            assert_eq!(
+                forwarded.header().correction_field(),
+                TimeInterval(correction.into())
+            );
+            assert_eq!(
+                forwarded.precise_origin_timestamp(),
+                Instant::from_micros(900).into()
+            );
+        }
+    }
+
+    #[test]
+    fn test_authenticated_follow_up() {
+        let security_association = SecurityAssociation::new(1, 7, b"secret");
+        let mut tc = setup_with(
+            DelayMechanism::E2E,
+            |port_ds| port_ds,
+            Some(&security_association),
+            [
+                Instant::from_micros(1000),
+                Instant::from_micros(1010),
+                Instant::from_micros(1020),
+            ],
+        );
+        let mut master_authentication = Authentication::new(security_association.clone());
+        let mut authenticated = |message: Message, timestamp| NetworkPacket {
+            data: master_authentication
+                .authenticate(&message.serialize_vec().unwrap())
+                .unwrap(),
+            timestamp,
+            source_address: None,
+        };
+
+        let sync = MessageBuilder::new()
+            .two_step_flag(true)
+            .source_port_identity(master())
+            .sequence_id(5)
+            .sync_message(Timestamp::default());
+
+        // Unauthenticated messages are dropped
+        embassy_futures::block_on(tc.handle_packet(0, packet(&sync, Instant::from_micros(1000))))
+            .unwrap();
+        for index in [1, 2] {
+            assert_eq!(tc.ports[index].network_port().time.len(), 0);
+        }
+
+        embassy_futures::block_on(
+            tc.handle_packet(0, authenticated(sync, Instant::from_micros(1000))),
+        )
+        .unwrap();
+        for index in [1, 2] {
+            assert_eq!(tc.ports[index].network_port().time.len(), 1);
+        }
+
+        let follow_up = MessageBuilder::new()
+            .source_port_identity(master())
+            .sequence_id(5)
+            .follow_up_message(Instant::from_micros(900).into(), None);
+        embassy_futures::block_on(
+            tc.handle_packet(0, authenticated(follow_up, Instant::from_micros(1100))),
+        )
+        .unwrap();
+
+        // The residence time is added without invalidating the AUTHENTICATION TLV
+        for (index, correction) in [(1, 10000), (2, 20000)] {
+            let sent = tc.ports[index].network_port().normal.pop().unwrap();
+            let mut received = NetworkPacket {
+                data: sent.as_slice().try_into().unwrap(),
+                timestamp: Instant::from_micros(1200),
+                source_address: None,
+            };
+            Authentication::new(security_association.clone())
+                .verify(&mut received)
+                .unwrap();
+            assert_eq!(
+                Message::deserialize(&received.data)
+                    .unwrap()
+                    .header()
+                    .correction_field(),
+                TimeInterval(correction.into())
+            );
+        }
+    }
+
+    #[test]
+    fn test_one_step_sync() {
+        let mut tc = setup([Instant::from_micros(0); 3]);
+
+        let sync = MessageBuilder::new()
+            .two_step_flag(false)
+            .source_port_identity(master())
+            .sequence_id(5)
+            .correction_field(TimeInterval(2000.into()))
+            .sync_message(Timestamp::default());
+        embassy_futures::block_on(tc.handle_packet(1, packet(&sync, Instant::from_micros(1250))))
+            .unwrap();
+
+        // Residence time is estimated from the local clock at 1300us
+        for index in [0, 2] {
+            let sent = tc.ports[index].network_port().time.pop().unwrap();
+            assert_eq!(
+                Message::deserialize(&sent)
+                    .unwrap()
+                    .header()
+                    .correction_field(),
+                TimeInterval(52000.into())
+            );
+        }
+        assert_eq!(tc.ports[1].network_port().time.len(), 0);
+    }
+
+    #[test]
+    fn test_latency_corrected() {
+        let mut tc = setup_with(
+            DelayMechanism::E2E,
+            |port_ds| {
+                port_ds
+                    .with_ingress_latency(Duration::from_micros(3))
+                    .with_egress_latency(Duration::from_micros(5))
+            },
+            None,
+            [
+                Instant::from_micros(1000),
+                Instant::from_micros(1010),
+                Instant::from_micros(1020),
+            ],
+        );
+
+        let sync = MessageBuilder::new()
+            .two_step_flag(true)
+            .source_port_identity(master())
+            .sequence_id(5)
+            .sync_message(Timestamp::default());
+        embassy_futures::block_on(tc.handle_packet(0, packet(&sync, Instant::from_micros(1000))))
+            .unwrap();
+
+        let follow_up = MessageBuilder::new()
+            .source_port_identity(master())
+            .sequence_id(5)
+            .follow_up_message(Instant::from_micros(900).into(), None);
+        embassy_futures::block_on(
+            tc.handle_packet(0, packet(&follow_up, Instant::from_micros(1100))),
+        )
+        .unwrap();
+
+        // From 997us at the ingress reference plane to 1015us and 1025us at the egress
+        // reference planes
+        for (index, correction) in [(1, 18000), (2, 28000)] {
+            let sent = tc.ports[index].network_port().normal.pop().unwrap();
+            assert_eq!(
+                Message::deserialize(&sent)
+                    .unwrap()
+                    .header()
+                    .correction_field(),
+                TimeInterval(correction.into())
+            );
+        }
+
+        // A one-step sync is estimated from the local clock at 1300us, plus the egress
+        // latency
+        let sync = MessageBuilder::new()
+            .two_step_flag(false)
+            .source_port_identity(master())
+            .sequence_id(6)
+            .sync_message(Timestamp::default());
+        embassy_futures::block_on(tc.handle_packet(1, packet(&sync, Instant::from_micros(1250))))
+            .unwrap();
+
+        for index in [0, 2] {
+            let sent = tc.ports[index].network_port().time.pop().unwrap();
+            assert_eq!(
+                Message::deserialize(&sent)
+                    .unwrap()
+                    .header()
+                    .correction_field(),
+                TimeInterval(58000.into())
+            );
+        }
+    }
+
+    #[test]
+    fn test_delay_req() {
+        let mut tc = setup([
+            Instant::from_micros(2030),
+            Instant::from_micros(2000),
+            Instant::from_micros(2040),
+        ]);
+
+        let delay_req = MessageBuilder::new()
+            .source_port_identity(slave())
+            .sequence_id(7)
+            .delay_req_message(Timestamp::default());
+        embassy_futures::block_on(
+            tc.handle_packet(1, packet(&delay_req, Instant::from_micros(2000))),
+        )
+        .unwrap();
+
+        assert_eq!(tc.ports[0].network_port().time.len(), 1);
+        assert_eq!(tc.ports[2].network_port().time.len(), 1);
+
+        // The master is behind port 1
+        let delay_resp = MessageBuilder::new()
+            .source_port_identity(master())
+            .sequence_id(7)
+            .delay_resp_message(Instant::from_micros(3000).into(), slave());
+        embassy_futures::block_on(
+            tc.handle_packet(0, packet(&delay_resp, Instant::from_micros(2100))),
+        )
+        .unwrap();
+
+        assert_eq!(tc.ports[0].network_port().normal.len(), 0);
+        for index in [1, 2] {
+            let sent = tc.ports[index].network_port().normal.pop().unwrap();
+            let Message::DelayResp(forwarded) = Message::deserialize(&sent).unwrap() else {
+                panic!("Unexpected message type");
+            };
+            assert_eq!(
+                forwarded.header().correction_field(),
+                TimeInterval(30000.into())
+            );
+            assert_eq!(forwarded.requesting_port_identity(), slave());
+        }
+    }
+
+    #[test]
+    fn test_delay_resp_same_sequence_id() {
+        let mut tc = setup([Instant::from_micros(0); 3]);
+
+        let other_slave = PortIdentity {
+            clock_identity: ClockIdentity([11; 8]),
+            port_number: 1,
+        };
+
+        // Slaves started together count their sequence ids in lockstep
+        for (index, requester) in [(1, slave()), (2, other_slave)] {
+            let delay_req = MessageBuilder::new()
+                .source_port_identity(requester)
+                .sequence_id(7)
+                .delay_req_message(Timestamp::default());
+            embassy_futures::block_on(
+                tc.handle_packet(index, packet(&delay_req, Instant::from_micros(0))),
+            )
+            .unwrap();
+        }
+
+        for requester in [slave(), other_slave] {
+            let delay_resp = MessageBuilder::new()
+                .source_port_identity(master())
+                .sequence_id(7)
+                .delay_resp_message(Instant::from_micros(3000).into(), requester);
+            embassy_futures::block_on(
+                tc.handle_packet(0, packet(&delay_resp, Instant::from_micros(100))),
+            )
+            .unwrap();
+        }
+
+        // Both responses reach both slaves
+        for index in [1, 2] {
+            let requesters: Vec<_> = tc.ports[index]
+                .network_port()
+                .normal
+                .iter()
+                .map(|sent| match Message::deserialize(sent).unwrap() {
+                    Message::DelayResp(delay_resp) => delay_resp.requesting_port_identity(),
+                    _ => panic!("Unexpected message type"),
+                })
+                .collect();
+            assert_eq!(requesters, [slave(), other_slave]);
+        }
+    }
+
+    #[test]
+    fn test_forward_general_once() {
+        let mut tc = setup([Instant::from_micros(0); 3]);
+
+        let announce = MessageBuilder::new()
+            .source_port_identity(master())
+            .sequence_id(3)
+            .announce_message(
+                Timestamp::default(),
+                37,
+                128,
+                ClockQuality::default(),
+                128,
+                master().clock_identity,
+                0,
+                crate::TimeSource::InternalOscillator,
+                Default::default(),
+                Default::default(),
+                None,
+                None,
+                None,
+            );
+        let announce_packet = packet(&announce, Instant::from_micros(10));
+        embassy_futures::block_on(tc.handle_packet(0, announce_packet.clone())).unwrap();
+
+        assert_eq!(tc.ports[0].network_port().normal.len(), 0);
+        assert_eq!(tc.ports[1].network_port().normal.len(), 1);
+        assert_eq!(tc.ports[2].network_port().normal.len(), 1);
+        assert_eq!(
+            tc.ports[1].network_port().normal[0].as_slice(),
+            announce_packet.data.as_slice()
+        );
+
+        // The forwarded message looping back in is not forwarded again
+        embassy_futures::block_on(tc.handle_packet(2, announce_packet)).unwrap();
+
+        assert_eq!(tc.ports[0].network_port().normal.len(), 0);
+        assert_eq!(tc.ports[1].network_port().normal.len(), 1);
+        assert_eq!(tc.ports[2].network_port().normal.len(), 1);
+    }
+
+    #[test]
+    fn test_forward_power_profile() {
+        let mut tc = setup_with(
+            DelayMechanism::P2P,
+            |port_ds| port_ds,
+            None,
+            [Instant::from_micros(0); 3],
+        );
+        tc.default_ds.profile = Profile::C37_238;
+        tc.default_ds.time_inaccuracy = 50;
+
+        let power_profile = PowerProfileTlv {
+            grandmaster_id: 3,
+            grandmaster_time_inaccuracy: 100,
+            total_time_inaccuracy: 200,
+        };
+        let announce = MessageBuilder::new()
+            .source_port_identity(master())
+            .sequence_id(3)
+            .announce_message(
+                Timestamp::default(),
+                37,
+                128,
+                ClockQuality::default(),
+                128,
+                master().clock_identity,
+                0,
+                crate::TimeSource::InternalOscillator,
+                Default::default(),
+                Default::default(),
+                None,
+                Some(power_profile),
+                None,
+            );
+        embassy_futures::block_on(tc.handle_packet(0, packet(&announce, Instant::from_micros(10))))
+            .unwrap();
+
+        // The inaccuracy of the transparent clock is added to the total
+        for port in 1..3 {
+            let forwarded = tc.ports[port].network_port().normal.pop().unwrap();
+            match Message::deserialize(&forwarded).unwrap() {
+                Message::Announce(announce) => assert_eq!(
+                    announce.power_profile,
+                    Some(PowerProfileTlv {
+                        total_time_inaccuracy: 250,
+                        ..power_profile
+                    })
+                ),
+                _ => panic!("Unexpected message type"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_peer_to_peer() {
+        let mut tc = setup_with(
+            DelayMechanism::P2P,
+            |port_ds| port_ds,
+            None,
+            [
+                Instant::from_micros(1000),
+                Instant::from_micros(1010),
+                Instant::from_micros(1020),
+            ],
+        );
+
+        // Measure the link delay of port 1
+        embassy_futures::block_on(tc.ports[0].send_pdelay_req(&tc.local_clock, &tc.default_ds))
+            .unwrap();
+        let Message::PDelayReq(pdelay_req) =
+            Message::deserialize(&tc.ports[0].network_port().time.pop().unwrap()).unwrap()
+        else {
+            panic!("Unexpected message type");
+        };
+        let pdelay_resp = MessageBuilder::new()
+            .two_step_flag(false)
+            .source_port_identity(master())
+            .sequence_id(pdelay_req.header().sequence_id())
+            .correction_field(TimeInterval(20000.into()))
+            .pdelay_resp_message(Timestamp::default(), tc.ports[0].identity());
+        embassy_futures::block_on(
+            tc.handle_packet(0, packet(&pdelay_resp, Instant::from_micros(1040))),
+        )
+        .unwrap();
+
+        // ((1040 - 1000) - 20) / 2 = 10
+        assert_eq!(tc.ports[0].mean_link_delay(), Duration::from_micros(10));
+        // Peer delay messages are not forwarded
+        for index in [1, 2] {
+            assert_eq!(tc.ports[index].network_port().time.len(), 0);
+            assert_eq!(tc.ports[index].network_port().normal.len(), 0);
+        }
+
+        let sync = MessageBuilder::new()
+            .two_step_flag(true)
+            .source_port_identity(master())
+            .sequence_id(5)
+            .sync_message(Timestamp::default());
+        embassy_futures::block_on(tc.handle_packet(0, packet(&sync, Instant::from_micros(1000))))
+            .unwrap();
+
+        let follow_up = MessageBuilder::new()
+            .source_port_identity(master())
+            .sequence_id(5)
+            .follow_up_message(Instant::from_micros(900).into(), None);
+        embassy_futures::block_on(
+            tc.handle_packet(0, packet(&follow_up, Instant::from_micros(1100))),
+        )
+        .unwrap();
+
+        // Corrected for both residence time and the link delay of port 1
+        for (index, correction) in [(1, 20000), (2, 30000)] {
+            assert_eq!(tc.ports[index].network_port().time.len(), 1);
+            let sent = tc.ports[index].network_port().normal.pop().unwrap();
+            assert_eq!(
+                Message::deserialize(&sent)
+                    .unwrap()
+                    .header()
+                    .correction_field(),
+                TimeInterval(correction.into())
+            );
+        }
+
+        // Delay requests are dropped
+        let delay_req = MessageBuilder::new()
+            .source_port_identity(slave())
+            .sequence_id(7)
+            .delay_req_message(Timestamp::default());
+        embassy_futures::block_on(
+            tc.handle_packet(1, packet(&delay_req, Instant::from_micros(2000))),
+        )
+        .unwrap();
+
+        assert_eq!(tc.ports[0].network_port().time.len(), 0);
+        assert_eq!(tc.ports[2].network_port().time.len(), 1);
+    }
+}